@@ -0,0 +1,282 @@
+use std::path::Path;
+
+use crate::utils::errors::{GPGError, GPGErrorType};
+use crate::utils::utils::{check_output_parent, find_overriding_arg, path_to_arg};
+
+// option taking a value that gpg accept more than once, every value was kept ( ex. one --recipient for each key )
+const REPEATABLE_OPTIONS: [&str; 5] = [
+    "--recipient",
+    "--hidden-recipient",
+    "--encrypt-to",
+    "--local-user",
+    "--weak-digest",
+];
+
+//*******************************************************
+
+//              RELATED TO ARG BUILDER
+
+//*******************************************************
+// a gpg argument without value that the crate know the meaning of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flag {
+    // --encrypt
+    Encrypt,
+    // --symmetric
+    Symmetric,
+    // --sign
+    Sign,
+    // --detach-sign
+    DetachSign,
+    // --clearsign
+    Clearsign,
+    // --decrypt
+    Decrypt,
+    // --verify
+    Verify,
+    // --list-keys
+    ListKeys,
+    // --list-secret-keys
+    ListSecretKeys,
+    // --list-sigs
+    ListSigs,
+    // --check-sigs
+    CheckSigs,
+    // --armor
+    Armor,
+    // --yes, assume yes on most question ( ex. to overwrite the output )
+    Yes,
+    // --no-symkey-cache
+    NoSymkeyCache,
+    // --show-session-key
+    ShowSessionKey,
+    // --with-keygrip
+    WithKeygrip,
+    // --fingerprint given twice, so the fingerprint of the subkeys were listed as well
+    SubkeyFingerprints,
+}
+
+impl Flag {
+    // the argument(s) passed to gpg for the flag
+    pub fn args(&self) -> Vec<String> {
+        let arg: &str = match self {
+            Flag::Encrypt => "--encrypt",
+            Flag::Symmetric => "--symmetric",
+            Flag::Sign => "--sign",
+            Flag::DetachSign => "--detach-sign",
+            Flag::Clearsign => "--clearsign",
+            Flag::Decrypt => "--decrypt",
+            Flag::Verify => "--verify",
+            Flag::ListKeys => "--list-keys",
+            Flag::ListSecretKeys => "--list-secret-keys",
+            Flag::ListSigs => "--list-sigs",
+            Flag::CheckSigs => "--check-sigs",
+            Flag::Armor => "--armor",
+            Flag::Yes => "--yes",
+            Flag::NoSymkeyCache => "--no-symkey-cache",
+            Flag::ShowSessionKey => "--show-session-key",
+            Flag::WithKeygrip => "--with-keygrip",
+            Flag::SubkeyFingerprints => return vec!["--fingerprint".to_string(), "--fingerprint".to_string()],
+        };
+        return vec![arg.to_string()];
+    }
+
+    // whether the flag was a command ( what gpg was run for ) rather than an option
+    pub fn is_command(&self) -> bool {
+        return matches!(
+            self,
+            Flag::Encrypt
+                | Flag::Symmetric
+                | Flag::Sign
+                | Flag::DetachSign
+                | Flag::Clearsign
+                | Flag::Decrypt
+                | Flag::Verify
+                | Flag::ListKeys
+                | Flag::ListSecretKeys
+                | Flag::ListSigs
+                | Flag::CheckSigs
+        );
+    }
+
+    // whether the command can be combined with the other command ( ex. --sign with --encrypt )
+    fn combines_with(&self, other: &Flag) -> bool {
+        let signing: [Flag; 3] = [Flag::Sign, Flag::DetachSign, Flag::Clearsign];
+        match (self, other) {
+            (Flag::DetachSign, Flag::Clearsign) | (Flag::Clearsign, Flag::DetachSign) => return false,
+            // a detached or cleartext signature can not be encrypted
+            (Flag::DetachSign | Flag::Clearsign, Flag::Encrypt | Flag::Symmetric) => return false,
+            (Flag::Encrypt | Flag::Symmetric, Flag::DetachSign | Flag::Clearsign) => return false,
+            (Flag::Encrypt | Flag::Symmetric | Flag::Sign | Flag::DetachSign | Flag::Clearsign, _) => {
+                return signing.contains(other) || matches!(other, Flag::Encrypt | Flag::Symmetric);
+            }
+            // decrypt, verify and the listings were run alone
+            _ => return false,
+        }
+    }
+}
+
+// assemble the arguments of a gpg command, instead of appending to a Vec<String> by hand:
+// a flag was only passed once, a later value of an option replaced the earlier one ( except the repeatable ones
+// like --recipient ), conflicting commands and extra arguments that would override what was set were rejected,
+// and the arguments were ordered as commands, flags, options, output, extra arguments then operands
+// ex) ArgBuilder::new().flag(Flag::Encrypt).recipient(fingerprint).flag(Flag::Armor).output(&path).build()
+#[derive(Debug, Default)]
+pub struct ArgBuilder {
+    flags: Vec<Flag>,
+    options: Vec<(String, String)>,
+    output: Option<String>,
+    extra_args: Vec<String>,
+    operands: Vec<String>,
+    // unsafe_extra_args: pass extra arguments that would override what was set anyway ( check GPG.unsafe_extra_args )
+    unsafe_extra_args: bool,
+    // create_output_dirs: create the parent directory of the output if it does not exist ( check GPG.create_output_dirs )
+    create_output_dirs: bool,
+    // the first invalid argument, returned by build so the methods can be chained
+    error: Option<GPGError>,
+}
+
+impl ArgBuilder {
+    pub fn new() -> ArgBuilder {
+        return ArgBuilder::default();
+    }
+
+    pub fn unsafe_extra_args(mut self, unsafe_extra_args: bool) -> ArgBuilder {
+        self.unsafe_extra_args = unsafe_extra_args;
+        return self;
+    }
+
+    pub fn create_output_dirs(mut self, create_output_dirs: bool) -> ArgBuilder {
+        self.create_output_dirs = create_output_dirs;
+        return self;
+    }
+
+    pub fn flag(mut self, flag: Flag) -> ArgBuilder {
+        if !self.flags.contains(&flag) {
+            self.flags.push(flag);
+        }
+        return self;
+    }
+
+    // an option with its value ( ex. --trust-model always )
+    pub fn option(mut self, name: &str, value: impl Into<String>) -> ArgBuilder {
+        // name: the long option, starting with --
+
+        let value: String = value.into();
+        if !name.starts_with("--") || name.len() <= 2 {
+            return self.fail(format!("{} is not a long option", name));
+        }
+        if REPEATABLE_OPTIONS.contains(&name) {
+            if !self.options.iter().any(|(n, v)| n == name && *v == value) {
+                self.options.push((name.to_string(), value));
+            }
+            return self;
+        }
+        match self.options.iter_mut().find(|(n, _)| n == name) {
+            Some(option) => option.1 = value,
+            None => self.options.push((name.to_string(), value)),
+        }
+        return self;
+    }
+
+    pub fn recipient(self, recipient: impl Into<String>) -> ArgBuilder {
+        return self.option("--recipient", recipient);
+    }
+
+    pub fn default_key(self, keyid: impl Into<String>) -> ArgBuilder {
+        return self.option("--default-key", keyid);
+    }
+
+    // where gpg write the output to, --yes was added if it already exist so gpg will not ask to overwrite it
+    pub fn output(mut self, output: &Path) -> ArgBuilder {
+        let output_arg: String = match path_to_arg(output) {
+            Ok(output_arg) => output_arg,
+            Err(e) => return self.fail_with(e),
+        };
+        if let Err(e) = check_output_parent(output, self.create_output_dirs) {
+            return self.fail_with(e);
+        }
+        if output.exists() {
+            self = self.flag(Flag::Yes);
+        }
+        self.output = Some(output_arg);
+        return self;
+    }
+
+    // arguments passed as they were, an argument that would override what was set ( ex. --output, --homedir )
+    // was rejected unless unsafe_extra_args was set
+    pub fn extra_args(mut self, extra_args: Vec<String>) -> ArgBuilder {
+        match check_overriding_args(extra_args, self.unsafe_extra_args) {
+            Ok(mut extra_args) => self.extra_args.append(&mut extra_args),
+            Err(e) => return self.fail_with(e),
+        }
+        return self;
+    }
+
+    // an argument after every option ( ex. a keyid to list, a file to verify )
+    pub fn operand(mut self, operand: impl Into<String>) -> ArgBuilder {
+        self.operands.push(operand.into());
+        return self;
+    }
+
+    pub fn build(self) -> Result<Vec<String>, GPGError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let commands: Vec<Flag> = self.flags.iter().filter(|f| f.is_command()).copied().collect();
+        for (i, command) in commands.iter().enumerate() {
+            if let Some(other) = commands[i + 1..].iter().find(|other| !command.combines_with(other)) {
+                return Err(GPGError::new(
+                    GPGErrorType::InvalidArgumentError(format!(
+                        "{} can not be used with {}",
+                        command.args()[0],
+                        other.args()[0]
+                    )),
+                    None,
+                ));
+            }
+        }
+        let mut args: Vec<String> = commands.iter().flat_map(|f| f.args()).collect();
+        args.extend(self.flags.iter().filter(|f| !f.is_command()).flat_map(|f| f.args()));
+        for (name, value) in self.options {
+            args.append(&mut vec![name, value]);
+        }
+        if let Some(output) = self.output {
+            args.append(&mut vec!["--output".to_string(), output]);
+        }
+        args.extend(self.extra_args);
+        args.extend(self.operands);
+        return Ok(args);
+    }
+
+    fn fail(self, message: String) -> ArgBuilder {
+        return self.fail_with(GPGError::new(GPGErrorType::InvalidArgumentError(message), None));
+    }
+
+    // only the first error was kept
+    fn fail_with(mut self, error: GPGError) -> ArgBuilder {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+        return self;
+    }
+}
+
+// reject argument that would override what the crate pass to gpg unless allowed
+pub(crate) fn check_overriding_args(extra_args: Vec<String>, allow: bool) -> Result<Vec<String>, GPGError> {
+    if allow {
+        return Ok(extra_args);
+    }
+    match find_overriding_arg(&extra_args) {
+        Some(arg) => {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!(
+                    "{} would override the argument set by crab-gnupg, set unsafe_extra_args to pass it anyway",
+                    arg
+                )),
+                None,
+            ));
+        }
+        None => return Ok(extra_args),
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    fs::OpenOptions,
+    io::{Read, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::runner::GpgRunner;
+use crate::status::split_status_line;
+use crate::utils::{
+    enums::{GpgExitStatus, Operation},
+    errors::{GPGError, GPGErrorType},
+    response::CmdResult,
+    secret::Passphrase,
+    utils::redact_args,
+};
+
+// status keywords reporting the fingerprint of a key used by the operation
+const FINGERPRINT_STATUS: [&str; 6] = [
+    "KEY_CONSIDERED",
+    "KEY_CREATED",
+    "SIG_CREATED",
+    "VALIDSIG",
+    "IMPORT_OK",
+    "DECRYPTION_KEY",
+];
+
+//*******************************************************
+
+//                RELATED TO AUDIT RECORD
+
+//*******************************************************
+// a record of one gpg ( or gpgv ) invocation
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    // when the command was started
+    pub time: DateTime<Local>,
+    pub operation: Operation,
+    // the full command line with the value of secret argument ( ex. --passphrase ) redacted,
+    // the passphrase written into stdin was never part of it
+    pub args: Vec<String>,
+    // fingerprints of the keys gpg reported to be used ( KEY_CONSIDERED, SIG_CREATED, VALIDSIG ... )
+    pub fingerprints: Vec<String>,
+    // whether the command succeeded, false if it could not be started
+    pub success: bool,
+    pub exit_status: GpgExitStatus,
+    // including the time a mutating command waited for the lock of the homedir
+    pub duration: Duration,
+    // the file gpg was told to write the output to ( --output / -o )
+    pub output: Option<String>,
+    // why the command could not be started, None if it was run
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    // a single line of tab separated field:
+    // time, operation, success, exit status, duration in milliseconds, fingerprints ( comma separated ), output, args
+    // a missing field will be written as -
+    pub fn to_line(&self) -> String {
+        let fingerprints: String = if self.fingerprints.is_empty() {
+            "-".to_string()
+        } else {
+            self.fingerprints.join(",")
+        };
+        let exit_status: String = match &self.exit_status {
+            GpgExitStatus::Success => "0".to_string(),
+            GpgExitStatus::BadSignature => "1".to_string(),
+            GpgExitStatus::Error(code) => code.to_string(),
+            GpgExitStatus::Unknown => "-".to_string(),
+        };
+        // the fields were separated by tab, it should not appear inside of one
+        let args: String = self.args.join(" ").replace(['\t', '\n'], " ");
+        return format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.time.to_rfc3339(),
+            self.operation,
+            self.success,
+            exit_status,
+            self.duration.as_millis(),
+            fingerprints,
+            self.output.clone().unwrap_or("-".to_string()).replace(['\t', '\n'], " "),
+            args
+        );
+    }
+}
+
+//*******************************************************
+
+//                 RELATED TO AUDIT SINK
+
+//*******************************************************
+// where the audit record of every gpg invocation will be sent to, set it on GPG.audit_sink
+pub trait AuditSink: Debug + Send + Sync {
+    // returning an Err will fail the operation with it, as the invocation could not be audited
+    // NOTE: gpg had already run, the output it wrote will not be removed
+    fn record(&self, record: &AuditRecord) -> Result<(), GPGError>;
+}
+
+// append the record as a line ( AuditRecord.to_line ) to a file, the file will be created if it does not exist
+#[derive(Debug)]
+pub struct FileAuditSink {
+    pub path: PathBuf,
+    // so lines from different threads were not interleaved
+    lock: Mutex<()>,
+}
+
+impl FileAuditSink {
+    pub fn new(path: PathBuf) -> FileAuditSink {
+        return FileAuditSink {
+            path: path,
+            lock: Mutex::new(()),
+        };
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, record: &AuditRecord) -> Result<(), GPGError> {
+        let _guard = self.lock.lock().unwrap();
+        let file = OpenOptions::new().create(true).append(true).open(&self.path);
+        let mut file = match file {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(GPGError::new(
+                    GPGErrorType::WriteFailError(format!("failed to open audit log {}: {}", self.path.display(), e)),
+                    None,
+                ))
+            }
+        };
+        if let Err(e) = writeln!(file, "{}", record.to_line()) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to write audit log {}: {}", self.path.display(), e)),
+                None,
+            ));
+        }
+        return Ok(());
+    }
+}
+
+pub type AuditCallback = Box<dyn Fn(&AuditRecord) -> Result<(), GPGError> + Send + Sync>;
+
+// pass the record to a callback ( ex. to send it to the logging system of the application )
+pub struct CallbackAuditSink {
+    callback: AuditCallback,
+}
+
+impl CallbackAuditSink {
+    pub fn new(callback: AuditCallback) -> CallbackAuditSink {
+        return CallbackAuditSink { callback: callback };
+    }
+}
+
+impl Debug for CallbackAuditSink {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        return write!(f, "CallbackAuditSink");
+    }
+}
+
+impl AuditSink for CallbackAuditSink {
+    fn record(&self, record: &AuditRecord) -> Result<(), GPGError> {
+        return (self.callback)(record);
+    }
+}
+
+//*******************************************************
+
+//                RELATED TO AUDIT RUNNER
+
+//*******************************************************
+// a runner sending a record of every command run by the runner it wraps to the sink
+// GPG wrap its runner with it when GPG.audit_sink was set
+#[derive(Debug, Clone)]
+pub struct AuditRunner {
+    pub runner: Arc<dyn GpgRunner>,
+    pub sink: Arc<dyn AuditSink>,
+}
+
+impl AuditRunner {
+    pub fn new(runner: Arc<dyn GpgRunner>, sink: Arc<dyn AuditSink>) -> AuditRunner {
+        return AuditRunner {
+            runner: runner,
+            sink: sink,
+        };
+    }
+
+    fn audit(
+        &self,
+        args: &[String],
+        time: DateTime<Local>,
+        started: Instant,
+        ops: Operation,
+        result: Result<CmdResult, GPGError>,
+    ) -> Result<CmdResult, GPGError> {
+        let record: AuditRecord = get_audit_record(args, time, started.elapsed(), ops, result.as_ref());
+        self.sink.record(&record)?;
+        return result;
+    }
+}
+
+impl GpgRunner for AuditRunner {
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: Option<Box<dyn Read + Send>>,
+        env: Option<HashMap<String, String>>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        let time: DateTime<Local> = Local::now();
+        let started: Instant = Instant::now();
+        let result: Result<CmdResult, GPGError> = self.runner.run(args.clone(), stdin, env, ops.clone());
+        return self.audit(&args, time, started, ops, result);
+    }
+
+    fn run_interactive(
+        &self,
+        args: Vec<String>,
+        stdin_prefix: Option<Passphrase>,
+        env: Option<HashMap<String, String>>,
+        responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        let time: DateTime<Local> = Local::now();
+        let started: Instant = Instant::now();
+        let result: Result<CmdResult, GPGError> =
+            self.runner.run_interactive(args.clone(), stdin_prefix, env, responder, ops.clone());
+        return self.audit(&args, time, started, ops, result);
+    }
+    fn with_timeout(&self, timeout: Duration) -> Option<Arc<dyn GpgRunner>> {
+        return self
+            .runner
+            .with_timeout(timeout)
+            .map(|runner| Arc::new(AuditRunner::new(runner, self.sink.clone())) as Arc<dyn GpgRunner>);
+    }
+}
+
+pub(crate) fn get_audit_record(
+    args: &[String],
+    time: DateTime<Local>,
+    duration: Duration,
+    ops: Operation,
+    result: Result<&CmdResult, &GPGError>,
+) -> AuditRecord {
+    let mut record: AuditRecord = AuditRecord {
+        time: time,
+        operation: ops,
+        args: redact_args(args),
+        fingerprints: vec![],
+        success: false,
+        exit_status: GpgExitStatus::Unknown,
+        duration: duration,
+        output: get_output_arg(args),
+        error: None,
+    };
+    match result {
+        Ok(result) => {
+            record.success = result.is_success();
+            record.exit_status = result.exit_status();
+            record.fingerprints = get_status_fingerprints(result);
+        }
+        Err(e) => record.error = Some(e.error_type.to_string()),
+    }
+    return record;
+}
+
+fn get_output_arg(args: &[String]) -> Option<String> {
+    let position: Option<usize> = args.iter().position(|arg| arg == "--output" || arg == "-o");
+    return position.and_then(|p| args.get(p + 1)).cloned();
+}
+
+fn get_status_fingerprints(result: &CmdResult) -> Vec<String> {
+    let mut fingerprints: Vec<String> = vec![];
+    let raw_data: String = result.raw_data.clone().unwrap_or_default();
+    for line in raw_data.lines() {
+        let fields = match split_status_line(line) {
+            Some((keyword, args)) if FINGERPRINT_STATUS.contains(&keyword) => args.split_whitespace(),
+            _ => continue,
+        };
+        for field in fields {
+            let is_fingerprint: bool =
+                (field.len() == 40 || field.len() == 64) && field.chars().all(|c| c.is_ascii_hexdigit());
+            if is_fingerprint && !fingerprints.iter().any(|f| f == field) {
+                fingerprints.push(field.to_string());
+            }
+        }
+    }
+    return fingerprints;
+}
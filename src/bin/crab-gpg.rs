@@ -0,0 +1,331 @@
+// a command line tool built on the crate's own API ( enabled with the cli feature ),
+// for scripts moving away from calling gpg directly, it exit with the same code as gpg would
+// ex) crab-gpg --homedir ~/.gnupg encrypt -r alice@example.com -o report.pdf.gpg report.pdf
+
+// the same lints as allowed in lib.rs
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::result_large_err)]
+
+use std::env;
+use std::fs::read_to_string;
+use std::io::{stdin, stdout, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use tempfile::NamedTempFile;
+
+use crab_gnupg::gnupg::{DecryptOption, EncryptOption, GPGBuilder, SignOption, GPG};
+use crab_gnupg::temp::create_temp_file_in;
+use crab_gnupg::utils::enums::{InputSource, EXIT_CODE_ERROR, EXIT_CODE_SUCCESS};
+use crab_gnupg::utils::errors::{GPGError, GPGErrorType};
+use crab_gnupg::utils::response::{CmdResult, ListKeyResult, VerifyResult};
+use crab_gnupg::utils::secret::Passphrase;
+
+const USAGE: &str = "usage: crab-gpg [--homedir DIR] [-a] <command> [options] [FILE]
+
+FILE was read from stdin if it was not provided or was -, the output was written to stdout unless -o was provided
+
+commands:
+  encrypt   -r RECIPIENT... [-c] [--passphrase-file FILE] [-o OUTPUT] [FILE]
+  decrypt   [--passphrase-file FILE] [-o OUTPUT] [FILE]
+  sign      [-u KEYID] [-b | --clearsign] [--passphrase-file FILE] [-o OUTPUT] [FILE]
+  verify    [--signature SIGNATURE] [FILE]
+  list      [-K]
+
+options:
+  --homedir DIR           the gpg homedir, GNUPGHOME or the default homedir if not provided
+  -a, --armor             write ascii armored output
+  -r, --recipient KEY     encrypt to the key ( keyid, fingerprint or email ), can be repeated
+  -c, --symmetric         encrypt with the passphrase ( along with the recipients, if any )
+  -u, --local-user KEYID  sign with the key instead of the default key
+  -b, --detach-sign       make a detached signature
+  --clearsign             make a cleartext signature
+  --passphrase-file FILE  read the passphrase from the first line of the file
+  --signature FILE        the detached signature to verify FILE against
+  -o, --output FILE       write the output to the file
+  -K, --secret            list the secret keys
+  -h, --help              show this message";
+
+// what was provided on the command line
+#[derive(Debug, Default)]
+struct Args {
+    homedir: Option<PathBuf>,
+    armor: bool,
+    command: String,
+    recipients: Vec<String>,
+    local_user: Option<String>,
+    symmetric: bool,
+    detach: bool,
+    clearsign: bool,
+    passphrase_file: Option<PathBuf>,
+    signature: Option<PathBuf>,
+    output: Option<PathBuf>,
+    secret: bool,
+    input: Option<PathBuf>,
+}
+
+fn main() {
+    let args: Args = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("crab-gpg: {}\n\n{}", message, USAGE);
+            exit(EXIT_CODE_ERROR);
+        }
+    };
+    if args.command == "help" {
+        println!("{}", USAGE);
+        exit(EXIT_CODE_SUCCESS);
+    }
+    exit(run(args));
+}
+
+// like gpg, the options can be provided before or after the command
+fn parse_args(argv: Vec<String>) -> Result<Args, String> {
+    let mut args: Args = Args::default();
+    let mut positionals: Vec<String> = Vec::new();
+    let mut argv = argv.into_iter();
+    while let Some(arg) = argv.next() {
+        let takes_value: bool = matches!(
+            arg.as_str(),
+            "--homedir" | "-r" | "--recipient" | "-u" | "--local-user" | "--passphrase-file" | "--signature" | "-o" | "--output"
+        );
+        let value: String = if takes_value {
+            match argv.next() {
+                Some(value) => value,
+                None => return Err(format!("{} requires a value", arg)),
+            }
+        } else {
+            String::new()
+        };
+        match arg.as_str() {
+            "-h" | "--help" => args.command = "help".to_string(),
+            "--homedir" => args.homedir = Some(PathBuf::from(value)),
+            "-a" | "--armor" => args.armor = true,
+            "-r" | "--recipient" => args.recipients.push(value),
+            "-u" | "--local-user" => args.local_user = Some(value),
+            "-c" | "--symmetric" => args.symmetric = true,
+            "-b" | "--detach-sign" => args.detach = true,
+            "--clearsign" => args.clearsign = true,
+            "--passphrase-file" => args.passphrase_file = Some(PathBuf::from(value)),
+            "--signature" => args.signature = Some(PathBuf::from(value)),
+            "-o" | "--output" => args.output = Some(PathBuf::from(value)),
+            "-K" | "--secret" => args.secret = true,
+            "-" => positionals.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ => positionals.push(arg),
+        }
+    }
+    if args.command == "help" {
+        return Ok(args);
+    }
+    let mut positionals = positionals.into_iter();
+    args.command = match positionals.next() {
+        Some(command) => command,
+        None => return Err("no command was provided".to_string()),
+    };
+    if !["encrypt", "decrypt", "sign", "verify", "list"].contains(&args.command.as_str()) {
+        return Err(format!("unknown command {}", args.command));
+    }
+    args.input = positionals.next().filter(|input| input != "-").map(PathBuf::from);
+    if let Some(extra) = positionals.next() {
+        return Err(format!("unexpected argument {}", extra));
+    }
+    if args.detach && args.clearsign {
+        return Err("--detach-sign and --clearsign can not be used together".to_string());
+    }
+    return Ok(args);
+}
+
+// run the command, return the exit code
+fn run(args: Args) -> i32 {
+    // the same homedir gpg would use
+    let homedir: Option<PathBuf> = args
+        .homedir
+        .clone()
+        .or(env::var_os("GNUPGHOME").map(PathBuf::from));
+    // every output was written to stdout or -o, so the output_dir was never needed
+    let mut builder: GPGBuilder = GPGBuilder::default().armor(args.armor).no_output_dir(true);
+    if let Some(homedir) = homedir {
+        builder = builder.homedir(homedir);
+    }
+    let gpg: GPG = match builder.build() {
+        Ok(gpg) => gpg,
+        Err(e) => return report_error(e),
+    };
+    if args.command == "list" {
+        return list(&gpg, args.secret);
+    }
+    if args.command == "verify" {
+        return match gpg.verify_file(input_source(&args.input), args.signature.clone(), None) {
+            Ok(result) => report_verify(&result),
+            Err(e) => report_error(e),
+        };
+    }
+    let passphrase: Option<Passphrase> = match &args.passphrase_file {
+        Some(path) => match read_to_string(path) {
+            Ok(content) => Some(Passphrase::from(content.lines().next().unwrap_or(""))),
+            Err(e) => {
+                eprintln!("crab-gpg: failed to read the passphrase file: {}", e);
+                return EXIT_CODE_ERROR;
+            }
+        },
+        None => None,
+    };
+    // the output was written to a temporary file next to -o and only renamed over it once the command succeeded,
+    // so a failing command leave the existing file as it was
+    let mut output_file: Option<NamedTempFile> = match &args.output {
+        Some(output) => {
+            let output_dir: &Path = match output.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            match create_temp_file_in(output_dir, "output") {
+                Ok(file) => Some(file),
+                Err(e) => return report_error(e),
+            }
+        }
+        None => None,
+    };
+    let mut writer: Box<dyn Write + '_> = match output_file.as_mut() {
+        Some(file) => Box::new(file),
+        None => Box::new(stdout().lock()),
+    };
+    let input: InputSource = input_source(&args.input);
+    let result: Result<CmdResult, GPGError> = match args.command.as_str() {
+        "encrypt" => match encrypt_option(input, &args, passphrase) {
+            Ok(option) => gpg.encrypt_to_writer(option, &mut writer),
+            Err(e) => Err(e),
+        },
+        "decrypt" => gpg.decrypt_to_writer(decrypt_option(input, passphrase), &mut writer),
+        _ => gpg.sign_to_writer(sign_option(input, &args, passphrase), &mut writer),
+    };
+    let result: Result<CmdResult, GPGError> = match writer.flush() {
+        Ok(_) => result,
+        Err(e) => Err(GPGError::new(
+            GPGErrorType::WriteFailError(format!("failed to write the output: {}", e)),
+            None,
+        )),
+    };
+    drop(writer);
+    // the temporary file was removed once dropped on failure, so no partial output was left behind
+    if let Err(e) = result {
+        return report_error(e);
+    }
+    if let (Some(file), Some(output)) = (output_file, &args.output) {
+        if let Err(e) = file.persist(output) {
+            eprintln!("crab-gpg: failed to write {}: {}", output.display(), e.error);
+            return EXIT_CODE_ERROR;
+        }
+    }
+    return EXIT_CODE_SUCCESS;
+}
+
+fn input_source(input: &Option<PathBuf>) -> InputSource {
+    return match input {
+        Some(path) => InputSource::Path(path.clone()),
+        None => InputSource::Reader(Box::new(stdin())),
+    };
+}
+
+fn encrypt_option(input: InputSource, args: &Args, passphrase: Option<Passphrase>) -> Result<EncryptOption, GPGError> {
+    if !args.symmetric {
+        if args.recipients.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("at least one recipient or --symmetric must be provided".to_string()),
+                None,
+            ));
+        }
+        return Ok(EncryptOption::default(input, args.recipients.clone(), None));
+    }
+    let passphrase: Passphrase = match passphrase {
+        Some(passphrase) => passphrase,
+        None => {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("--symmetric requires --passphrase-file".to_string()),
+                None,
+            ))
+        }
+    };
+    if args.recipients.is_empty() {
+        return Ok(EncryptOption::with_symmetric(input, None, passphrase, None));
+    }
+    return Ok(EncryptOption::with_key_and_symmetric(
+        input,
+        Some(args.recipients.clone()),
+        None,
+        passphrase,
+        None,
+    ));
+}
+
+fn decrypt_option(input: InputSource, passphrase: Option<Passphrase>) -> DecryptOption {
+    // passphrase: the passphrase of the secret key ( or the passphrase it was symmetrically encrypted with )
+
+    return DecryptOption {
+        input: input,
+        recipient: None,
+        always_trust: true,
+        passphrase: passphrase,
+        key_passphrase: None,
+        output: None,
+        extra_args: None,
+        strict: false,
+        show_session_key: false,
+        session_key: None,
+    };
+}
+
+fn sign_option(input: InputSource, args: &Args, key_passphrase: Option<Passphrase>) -> SignOption {
+    return SignOption {
+        input: input,
+        keyid: args.local_user.clone(),
+        key_passphrase: key_passphrase,
+        clearsign: args.clearsign,
+        detach: args.detach,
+        output: None,
+        extra_args: None,
+        reject_unusable_keys: false,
+    };
+}
+
+fn list(gpg: &GPG, secret: bool) -> i32 {
+    let keys: Vec<ListKeyResult> = match gpg.list_keys(secret, None, false) {
+        Ok(keys) => keys,
+        Err(e) => return report_error(e),
+    };
+    let prefix: (&str, &str) = if secret { ("sec", "ssb") } else { ("pub", "sub") };
+    for key in keys {
+        println!("{}  {} [{}]", prefix.0, key.fingerprint, key.cap);
+        for uid in &key.uids {
+            println!("uid  {}", uid);
+        }
+        for subkey in &key.subkeys {
+            println!("{}  {} [{}]", prefix.1, subkey.fingerprint, subkey.cap);
+        }
+    }
+    return EXIT_CODE_SUCCESS;
+}
+
+fn report_verify(result: &VerifyResult) -> i32 {
+    let username: String = result.username.clone().unwrap_or_default();
+    if result.valid {
+        eprintln!(
+            "crab-gpg: Good signature from \"{}\" ( {} )",
+            username,
+            result.fingerprint.clone().unwrap_or_default()
+        );
+    } else if result.key_id.is_some() {
+        eprintln!(
+            "crab-gpg: BAD signature from \"{}\" ( {} )",
+            username,
+            result.key_id.clone().unwrap_or_default()
+        );
+    } else {
+        eprintln!("crab-gpg: the signature could not be checked");
+    }
+    return result.exit_code();
+}
+
+fn report_error(error: GPGError) -> i32 {
+    eprintln!("crab-gpg: {}", error.error_type);
+    return error.exit_code();
+}
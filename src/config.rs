@@ -0,0 +1,326 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use tempfile::NamedTempFile;
+
+use crate::temp::create_temp_file_in;
+use crate::utils::errors::{GPGError, GPGErrorType};
+use crate::utils::utils::append_extension;
+
+//*******************************************************
+
+//              RELATED TO GPG CONF
+
+//*******************************************************
+// a struct to read and write the gpg.conf in a gpg homedir
+// comments and the order of the options in the file will be kept as it is
+#[derive(Debug, Clone)]
+pub struct GpgConf {
+    // path: path to the gpg.conf
+    pub path: PathBuf,
+    // lines: content of the file, line by line
+    lines: Vec<String>,
+}
+
+impl GpgConf {
+    // read the gpg.conf of the homedir, an empty config will be returned if the file does not exist yet
+    pub fn open(homedir: impl AsRef<Path>) -> Result<GpgConf, GPGError> {
+        // homedir: the gpg homedir where the gpg.conf was at
+
+        let path: PathBuf = homedir.as_ref().join("gpg.conf");
+        let lines: Vec<String> = read_conf_lines(&path)?;
+        return Ok(GpgConf {
+            path: path,
+            lines: lines,
+        });
+    }
+
+    // retrieve the value of the option, empty string for option without value ( ex. no-greeting )
+    // if the option appear more than once, the last one will be returned as it is the one gpg will use
+    pub fn get(&self, option: &str) -> Option<String> {
+        return get_conf_values(&self.lines, option).pop();
+    }
+
+    // retrieve all the values of an option that can be repeated ( ex. keyserver )
+    pub fn get_all(&self, option: &str) -> Vec<String> {
+        return get_conf_values(&self.lines, option);
+    }
+
+    // set the option, replacing the existing one(s), value None for option without value
+    pub fn set(&mut self, option: &str, value: Option<String>) -> Result<(), GPGError> {
+        return set_conf_value(&mut self.lines, option, value);
+    }
+
+    // remove all occurrence of the option, return false if the option was not set
+    pub fn remove(&mut self, option: &str) -> bool {
+        return remove_conf_value(&mut self.lines, option);
+    }
+
+    // write the changes to gpg.conf, the previous gpg.conf will be kept as gpg.conf.bak
+    pub fn save(&self) -> Result<(), GPGError> {
+        return write_conf_atomic(&self.path, &self.lines);
+    }
+}
+
+//*******************************************************
+
+//            RELATED TO GPG AGENT CONF
+
+//*******************************************************
+// a struct to read and write the gpg-agent.conf in a gpg homedir
+// comments and the order of the options in the file will be kept as it is
+#[derive(Debug, Clone)]
+pub struct AgentConf {
+    // homedir: the gpg homedir that the agent was serving
+    pub homedir: PathBuf,
+    // path: path to the gpg-agent.conf
+    pub path: PathBuf,
+    // lines: content of the file, line by line
+    lines: Vec<String>,
+}
+
+impl AgentConf {
+    // read the gpg-agent.conf of the homedir, an empty config will be returned if the file does not exist yet
+    pub fn open(homedir: impl AsRef<Path>) -> Result<AgentConf, GPGError> {
+        // homedir: the gpg homedir where the gpg-agent.conf was at
+
+        let homedir: PathBuf = homedir.as_ref().to_path_buf();
+        let path: PathBuf = homedir.join("gpg-agent.conf");
+        let lines: Vec<String> = read_conf_lines(&path)?;
+        return Ok(AgentConf {
+            homedir: homedir,
+            path: path,
+            lines: lines,
+        });
+    }
+
+    // retrieve the value of the option, empty string for option without value ( ex. allow-preset-passphrase )
+    pub fn get(&self, option: &str) -> Option<String> {
+        return get_conf_values(&self.lines, option).pop();
+    }
+
+    // set the option, replacing the existing one(s), value None for option without value
+    pub fn set(&mut self, option: &str, value: Option<String>) -> Result<(), GPGError> {
+        return set_conf_value(&mut self.lines, option, value);
+    }
+
+    // remove all occurrence of the option, return false if the option was not set
+    pub fn remove(&mut self, option: &str) -> bool {
+        return remove_conf_value(&mut self.lines, option);
+    }
+
+    // how long ( in seconds ) a passphrase was cached since it was last used
+    pub fn set_default_cache_ttl(&mut self, seconds: u64) {
+        let _ = set_conf_value(&mut self.lines, "default-cache-ttl", Some(seconds.to_string()));
+    }
+
+    // how long ( in seconds ) a passphrase was cached at most
+    pub fn set_max_cache_ttl(&mut self, seconds: u64) {
+        let _ = set_conf_value(&mut self.lines, "max-cache-ttl", Some(seconds.to_string()));
+    }
+
+    // allow passphrase to be preset into the agent with gpg-preset-passphrase
+    pub fn set_allow_preset_passphrase(&mut self, allow: bool) {
+        if allow {
+            let _ = set_conf_value(&mut self.lines, "allow-preset-passphrase", None);
+        } else {
+            remove_conf_value(&mut self.lines, "allow-preset-passphrase");
+        }
+    }
+
+    // let the agent serve the keys listed in sshcontrol over its ssh socket, as an ssh-agent
+    // NOTE: the running agent will only open the ssh socket after restart()
+    pub fn set_enable_ssh_support(&mut self, enable: bool) {
+        if enable {
+            let _ = set_conf_value(&mut self.lines, "enable-ssh-support", None);
+        } else {
+            remove_conf_value(&mut self.lines, "enable-ssh-support");
+        }
+    }
+
+    // pinentry program for the agent to use, None to use the default pinentry
+    pub fn set_pinentry_program(&mut self, program: Option<String>) -> Result<(), GPGError> {
+        if program.is_none() {
+            remove_conf_value(&mut self.lines, "pinentry-program");
+            return Ok(());
+        }
+        return set_conf_value(&mut self.lines, "pinentry-program", program);
+    }
+
+    // write the changes to gpg-agent.conf, the previous gpg-agent.conf will be kept as gpg-agent.conf.bak
+    // NOTE: the running agent will only pick up the changes after reload()
+    pub fn save(&self) -> Result<(), GPGError> {
+        return write_conf_atomic(&self.path, &self.lines);
+    }
+
+    // ask the running agent of the homedir to reload its configuration
+    pub fn reload(&self) -> Result<(), GPGError> {
+        return run_gpgconf(&self.homedir, &["--reload", "gpg-agent"]);
+    }
+
+    // stop the running agent of the homedir and start it again, for the option only read on start ( ex. enable-ssh-support )
+    pub fn restart(&self) -> Result<(), GPGError> {
+        run_gpgconf(&self.homedir, &["--kill", "gpg-agent"])?;
+        return run_gpgconf(&self.homedir, &["--launch", "gpg-agent"]);
+    }
+}
+
+pub(crate) fn run_gpgconf(homedir: &Path, args: &[&str]) -> Result<(), GPGError> {
+    let output: Result<Output, std::io::Error> = Command::new("gpgconf")
+        .arg("--homedir")
+        .arg(homedir)
+        .args(args)
+        .output();
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                return Ok(());
+            }
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError(
+                    String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                ),
+                None,
+            ));
+        }
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::FailedToStartProcess(e.to_string()),
+                None,
+            ));
+        }
+    }
+}
+
+//*******************************************************
+
+//          SHARED BY THE CONF FILE HELPERS
+
+//*******************************************************
+fn read_conf_lines(path: &Path) -> Result<Vec<String>, GPGError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    match std::fs::read_to_string(path) {
+        Ok(content) => return Ok(content.lines().map(|l| l.to_string()).collect()),
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::ReadFailError(e.to_string()),
+                None,
+            ));
+        }
+    }
+}
+
+// split a line into the option and its value, None for empty line or comment
+fn parse_conf_line(line: &str) -> Option<(String, String)> {
+    let line: &str = line.trim();
+    if line.is_empty() || line.starts_with("#") {
+        return None;
+    }
+    let mut parts = line.splitn(2, char::is_whitespace);
+    let option: String = parts.next().unwrap_or("").to_string();
+    let value: String = parts.next().unwrap_or("").trim().to_string();
+    return Some((option, value));
+}
+
+fn get_conf_values(lines: &[String], option: &str) -> Vec<String> {
+    return lines
+        .iter()
+        .filter_map(|l| parse_conf_line(l))
+        .filter(|(o, _)| o == option)
+        .map(|(_, v)| v)
+        .collect();
+}
+
+fn set_conf_value(lines: &mut Vec<String>, option: &str, value: Option<String>) -> Result<(), GPGError> {
+    if option.is_empty()
+        || option.starts_with("#")
+        || option.contains(char::is_whitespace)
+    {
+        return Err(GPGError::new(
+            GPGErrorType::InvalidArgumentError(format!("option invalid: {}", option)),
+            None,
+        ));
+    }
+    let value: String = value.unwrap_or("".to_string());
+    if value.contains("\n") || value.contains("\r") {
+        return Err(GPGError::new(
+            GPGErrorType::InvalidArgumentError(format!("value invalid for option {}", option)),
+            None,
+        ));
+    }
+    let line: String = if value.trim().is_empty() {
+        option.to_string()
+    } else {
+        format!("{} {}", option, value.trim())
+    };
+
+    // the first occurrence will be replaced to keep the option at the same place
+    let position: Option<usize> = lines
+        .iter()
+        .position(|l| parse_conf_line(l).is_some_and(|(o, _)| o == option));
+    remove_conf_value(lines, option);
+    match position {
+        Some(position) => lines.insert(position, line),
+        None => lines.push(line),
+    }
+    return Ok(());
+}
+
+fn remove_conf_value(lines: &mut Vec<String>, option: &str) -> bool {
+    let len: usize = lines.len();
+    lines.retain(|l| match parse_conf_line(l) {
+        Some((o, _)) => o != option,
+        None => true,
+    });
+    return lines.len() != len;
+}
+
+fn write_conf_atomic(path: &Path, lines: &[String]) -> Result<(), GPGError> {
+    // write to a temporary file next to the conf and rename it over the conf,
+    // so gpg will never read a partially written conf
+    let mut content: String = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    let conf_dir: &Path = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    // the temporary file was only readable and writable by the owner ( 0600 ),
+    // an existing conf keep its own permissions once the temporary file was renamed over it
+    let mut tmp_file: NamedTempFile = create_temp_file_in(conf_dir, "conf")?;
+    if let Err(e) = tmp_file.write_all(content.as_bytes()) {
+        return Err(GPGError::new(
+            GPGErrorType::WriteFailError(e.to_string()),
+            None,
+        ));
+    }
+    if path.exists() {
+        let permissions: Result<(), std::io::Error> = std::fs::metadata(path)
+            .and_then(|metadata| tmp_file.as_file().set_permissions(metadata.permissions()));
+        if let Err(e) = permissions {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(e.to_string()),
+                None,
+            ));
+        }
+        if let Err(e) = std::fs::copy(path, append_extension(path, "bak")) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(e.to_string()),
+                None,
+            ));
+        }
+    }
+    if let Err(e) = tmp_file.persist(path) {
+        return Err(GPGError::new(
+            GPGErrorType::WriteFailError(e.error.to_string()),
+            None,
+        ));
+    }
+    return Ok(());
+}
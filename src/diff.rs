@@ -0,0 +1,149 @@
+use crate::utils::response::{ListKeyResult, Subkey};
+use crate::utils::utils::decode_list_key_listing;
+
+//*******************************************************
+
+//              RELATED TO KEYRING DIFF
+
+//*******************************************************
+// what was different between two key listings ( ex. of two homedirs, or a homedir and its snapshot )
+// keys were matched by the fingerprint of the primary key
+#[derive(Debug, Clone)]
+pub struct KeyringDiff {
+    // added: keys only in the later listing
+    pub added: Vec<ListKeyResult>,
+    // removed: keys only in the earlier listing
+    pub removed: Vec<ListKeyResult>,
+    // changed: keys in both listings that were different
+    pub changed: Vec<KeyChange>,
+}
+
+impl KeyringDiff {
+    // whether the two listings had the same keys without any change
+    pub fn is_empty(&self) -> bool {
+        return self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty();
+    }
+}
+
+// how a key in both listings was different
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyChange {
+    // fingerprint: fingerprint of the primary key
+    pub fingerprint: String,
+    // uids: uids of the key in the later listing, to tell the owner
+    pub uids: Vec<String>,
+    // added_uids: uids only in the later listing
+    pub added_uids: Vec<String>,
+    // removed_uids: uids only in the earlier listing ( ex. a uid deleted or revoked and cleaned )
+    pub removed_uids: Vec<String>,
+    // added_subkeys: fingerprint of the subkeys only in the later listing
+    pub added_subkeys: Vec<String>,
+    // removed_subkeys: fingerprint of the subkeys only in the earlier listing
+    pub removed_subkeys: Vec<String>,
+    // revoked: whether the key was revoked since the earlier listing
+    pub revoked: bool,
+    // revoked_subkeys: fingerprint of the subkeys revoked since the earlier listing
+    pub revoked_subkeys: Vec<String>,
+    // expiry_changes: the primary key and subkeys with their expiry changed
+    pub expiry_changes: Vec<ExpiryChange>,
+}
+
+// the expiry of a primary key or subkey changed between the two listings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiryChange {
+    // fingerprint: fingerprint of the primary key or subkey
+    pub fingerprint: String,
+    // before: expiry time in seconds since epoch in the earlier listing, None if it did not expire
+    pub before: Option<i64>,
+    // after: expiry time in seconds since epoch in the later listing, None if it does not expire
+    pub after: Option<i64>,
+}
+
+// compare two key listings ( ex. list_keys of two GPG ), before was the earlier or the reference listing
+pub fn diff_keyrings(before: &[ListKeyResult], after: &[ListKeyResult]) -> KeyringDiff {
+    let mut diff: KeyringDiff = KeyringDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    for key in after {
+        match before.iter().find(|k| k.fingerprint == key.fingerprint) {
+            Some(previous) => {
+                if let Some(change) = diff_key(previous, key) {
+                    diff.changed.push(change);
+                }
+            }
+            None => diff.added.push(key.clone()),
+        }
+    }
+    for key in before {
+        if !after.iter().any(|k| k.fingerprint == key.fingerprint) {
+            diff.removed.push(key.clone());
+        }
+    }
+    return diff;
+}
+
+// the keys of a snapshot, a --with-colons key listing saved earlier ( check GPG.keyring_snapshot )
+pub fn parse_snapshot(snapshot: &str) -> Vec<ListKeyResult> {
+    return decode_list_key_listing(snapshot);
+}
+
+fn diff_key(before: &ListKeyResult, after: &ListKeyResult) -> Option<KeyChange> {
+    let find_subkey = |subkeys: &[Subkey], fingerprint: &str| -> Option<Subkey> {
+        return subkeys.iter().find(|s| s.fingerprint == fingerprint).cloned();
+    };
+    let mut change: KeyChange = KeyChange {
+        fingerprint: after.fingerprint.clone(),
+        uids: after.uids.clone(),
+        added_uids: after.uids.iter().filter(|uid| !before.uids.contains(uid)).cloned().collect(),
+        removed_uids: before.uids.iter().filter(|uid| !after.uids.contains(uid)).cloned().collect(),
+        added_subkeys: Vec::new(),
+        removed_subkeys: Vec::new(),
+        revoked: after.is_revoked && !before.is_revoked,
+        revoked_subkeys: Vec::new(),
+        expiry_changes: Vec::new(),
+    };
+    if before.expiry_timestamp != after.expiry_timestamp {
+        change.expiry_changes.push(ExpiryChange {
+            fingerprint: after.fingerprint.clone(),
+            before: before.expiry_timestamp,
+            after: after.expiry_timestamp,
+        });
+    }
+    for subkey in &after.subkeys {
+        let previous: Subkey = match find_subkey(&before.subkeys, &subkey.fingerprint) {
+            Some(previous) => previous,
+            None => {
+                change.added_subkeys.push(subkey.fingerprint.clone());
+                continue;
+            }
+        };
+        if subkey.is_revoked && !previous.is_revoked {
+            change.revoked_subkeys.push(subkey.fingerprint.clone());
+        }
+        if subkey.expiry_timestamp != previous.expiry_timestamp {
+            change.expiry_changes.push(ExpiryChange {
+                fingerprint: subkey.fingerprint.clone(),
+                before: previous.expiry_timestamp,
+                after: subkey.expiry_timestamp,
+            });
+        }
+    }
+    for subkey in &before.subkeys {
+        if find_subkey(&after.subkeys, &subkey.fingerprint).is_none() {
+            change.removed_subkeys.push(subkey.fingerprint.clone());
+        }
+    }
+    let unchanged: bool = change.added_uids.is_empty()
+        && change.removed_uids.is_empty()
+        && change.added_subkeys.is_empty()
+        && change.removed_subkeys.is_empty()
+        && !change.revoked
+        && change.revoked_subkeys.is_empty()
+        && change.expiry_changes.is_empty();
+    if unchanged {
+        return None;
+    }
+    return Some(change);
+}
@@ -1,1179 +1,5619 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt::{Display, Formatter};
+use std::path::{Component, Path, PathBuf};
 use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::process::{ChildStdin, ChildStdout};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use chrono::Local;
-
-use crate::process::handle_cmd_io;
-use crate::utils::enums::{Operation, TrustLevel};
-use crate::utils::utils::get_file_obj;
+use tempfile::{NamedTempFile, TempDir};
+
+use crate::audit::{AuditRunner, AuditSink};
+use crate::config::{AgentConf, GpgConf};
+use crate::diff::{diff_keyrings, parse_snapshot, KeyringDiff};
+use crate::metrics::{MetricsRecorder, MetricsRunner};
+use crate::pipeline::{PipelineOperation, PipelineSpec};
+use crate::args::{check_overriding_args, ArgBuilder, Flag};
+use crate::process::{
+    handle_card_cmd_interactive, handle_cmd_interactive, handle_cmd_io, handle_cmd_io_with_provider, handle_cmd_pipeline,
+    handle_cmd_stream, handle_gpgv_cmd_io, CmdStream,
+};
+use crate::runner::{GpgRunner, HomedirGuardRunner, LocalProcessRecorder, ProcessRunner};
+use crate::status::{GpgPrompt, PromptAnswer, StatusLine};
+use crate::temp::{create_temp_dir, create_temp_file, create_temp_file_in};
+#[cfg(feature = "snapshot")]
+use crate::temp::create_temp_dir_in;
+use crate::utils::enums::{CaptureMode, CardSlot, CipherAlgo, CompressAlgo, DigestAlgo, EditKeyCommand, GpgExitStatus, InputSource, KeyKind, Operation, OutputNaming, ShredMode, TrustLevel, TrustModel, VerifyBackend, Verbosity};
 use crate::utils::{
-    errors::{GPGError, GPGErrorType},
-    response::{CmdResult, ListKeyResult},
+    errors::{GPGError, GPGErrorType, UnresolvedRecipient},
+    secret::{Passphrase, PassphraseProvider},
+    response::{
+        AgentResponse, ArmoredKey, CmdResult, DaneRecord, DumpedKey, ExpiringKey, GPGCapabilities, GpgConfig, GpgDirs, GpgInstallation, GpgSockets, HealthCheck, HealthReport, ImportDryRunResult, KeyringDump, KeyAttribute, LegacyMigrationResult, MaintenanceResult, OutputCleanupResult, OutputInfo, GpgLock, ListKeyIter, ListKeyResult, SshKey,
+        DirectoryVerifyResult, ManifestVerifyResult, PermissionCheckResult, SelectedKey, Subkey, VerifyResult,
+    },
     utils::{
-        check_is_dir, decode_list_key_result, get_file_extension, get_gpg_version,
-        get_or_create_gpg_homedir, get_or_create_gpg_output_dir, is_passphrase_valid,
-        set_output_without_confirmation,
+        check_homedir_permissions, check_is_dir, check_output_parent, fix_homedir_permissions, decode_attribute_result, decode_dane_records, decode_list_config_result, decode_list_key_result, decode_verify_result, filter_ownertrust,
+        find_conventional_sig, get_conventional_sig_path, get_file_extension, get_gpg_capabilities, get_imported_keys,
+        get_decrypted_file_name, get_encrypted_file_name, get_input_file_name, get_unique_path,
+        is_armored_file, get_gpg_dirs, path_to_arg, append_extension, DETACHED_SIG_EXTENSIONS,
+        check_version_requirement, get_gpg_version, get_gpg_output_dir, get_or_create_gpg_homedir, get_or_create_gpg_output_dir, MIN_GPG_VERSION,
+        find_gpg_installations, select_gpg_installation, get_agent_version, run_agent_command,
+        parse_version_requirement, DEFAULT_HOMEDIR_LOCK_TIMEOUT, find_gpg_locks, lock_homedir, remove_stale_gpg_locks,
+        find_managed_outputs, remove_managed_outputs,
+        is_passphrase_valid, reject_weak_crypto, set_output_without_confirmation, shred_file, split_git_signature, take_spilled_output,
     },
 };
 
+// the range of --s2k-count gpg accept, a count out of it will be rounded silently by gpg
+pub const S2K_COUNT_MIN: u32 = 1024;
+pub const S2K_COUNT_MAX: u32 = 65011712;
+
+// gpg will ask for a confirmation when the photo id is larger than this (in bytes)
+const PHOTO_SIZE_WARNING: u64 = 6144;
+
+// the key material of the homedir archived by GPG::snapshot, the configuration and sockets were left out
+#[cfg(feature = "snapshot")]
+const SNAPSHOT_ENTRIES: [&str; 7] = [
+    "pubring.kbx",
+    "pubring.gpg",
+    "secring.gpg",
+    "trustdb.gpg",
+    "private-keys-v1.d",
+    "openpgp-revocs.d",
+    "sshcontrol",
+];
+
+// the data of an exported public key, binary or armored
+pub type KeyBytes = Vec<u8>;
+
+// version and capabilities of gpg retrieved from --list-config, cached per gpg binary and homedir by GPG::init
+#[derive(Debug, Clone)]
+struct GpgProbe {
+    version: f32,
+    full_version: String,
+    capabilities: GPGCapabilities,
+}
+
+// services creating a GPG object per request would otherwise spawn gpg --list-config every time
+// keyed by ( the gpg binary, the homedir ), the binary was "gpg" for gpg found on PATH
+type VersionCache = HashMap<(PathBuf, PathBuf), GpgProbe>;
+
+static VERSION_CACHE: OnceLock<Mutex<VersionCache>> = OnceLock::new();
+
+fn get_version_cache() -> &'static Mutex<VersionCache> {
+    return VERSION_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+}
+
+// result of list_keys cached for GPG.list_keys_cache_ttl, keyed by the arguments gpg was run with
+#[derive(Debug, Clone)]
+struct CachedKeys {
+    listed_at: Instant,
+    keys: Vec<ListKeyResult>,
+}
+
+type KeyCache = HashMap<PathBuf, HashMap<String, CachedKeys>>;
+
+// shared by every GPG on the same homedir, so a mutating operation of one invalidate the listing cached by the others
+static KEY_CACHE: OnceLock<Mutex<KeyCache>> = OnceLock::new();
+
+fn get_key_cache() -> &'static Mutex<KeyCache> {
+    return KEY_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+}
+
+// remove the listing cached for the homedir, or for every homedir if None, return false if nothing was cached
+pub(crate) fn invalidate_key_cache(homedir: Option<&Path>) -> bool {
+    let mut cache = get_key_cache().lock().unwrap();
+    match homedir {
+        Some(homedir) => return cache.remove(homedir).is_some_and(|keys| !keys.is_empty()),
+        None => {
+            let cleared: bool = cache.values().any(|keys| !keys.is_empty());
+            cache.clear();
+            return cleared;
+        }
+    }
+}
+
+// the top level entries of a snapshot archive, only the key material GPG::snapshot archived was accepted,
+// so a crafted archive can not write elsewhere in the homedir
+#[cfg(feature = "snapshot")]
+fn snapshot_entries(archive: &[u8]) -> Result<Vec<&'static str>, GPGError> {
+    let invalid = |reason: String| -> GPGError {
+        return GPGError::new(GPGErrorType::ReadFailError(format!("invalid snapshot: {}", reason)), None);
+    };
+    let mut reader: tar::Archive<&[u8]> = tar::Archive::new(archive);
+    let archived: tar::Entries<&[u8]> = match reader.entries() {
+        Ok(archived) => archived,
+        Err(e) => return Err(invalid(e.to_string())),
+    };
+    let mut entries: Vec<&'static str> = Vec::new();
+    for entry in archived {
+        let entry: tar::Entry<&[u8]> = match entry {
+            Ok(entry) => entry,
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        let path: PathBuf = match entry.path() {
+            Ok(path) => path.into_owned(),
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        match SNAPSHOT_ENTRIES.iter().find(|e| path.starts_with(e)) {
+            Some(e) => {
+                if !entries.contains(e) {
+                    entries.push(e);
+                }
+            }
+            None => return Err(invalid(format!("unexpected entry {}", path.display()))),
+        }
+    }
+    if entries.is_empty() {
+        return Err(invalid("no key material in the snapshot".to_string()));
+    }
+    return Ok(entries);
+}
+
+// rename the entry unpacked from a snapshot into the homedir, the entry it replace was moved aside first
+// return where the replaced entry was moved to, None if there was nothing to replace
+#[cfg(feature = "snapshot")]
+fn swap_snapshot_entry(unpacked: &Path, path: &Path, aside: &Path) -> std::io::Result<Option<PathBuf>> {
+    let replaced: bool = path.symlink_metadata().is_ok();
+    if replaced {
+        std::fs::rename(path, aside)?;
+    }
+    if let Err(e) = std::fs::rename(unpacked, path) {
+        if replaced {
+            let _ = std::fs::rename(aside, path);
+        }
+        return Err(e);
+    }
+    return Ok(if replaced { Some(aside.to_path_buf()) } else { None });
+}
+
+// put back the entries replaced by swap_snapshot_entry, the last swapped first
+#[cfg(feature = "snapshot")]
+fn rollback_snapshot_entries(swapped: &[(PathBuf, Option<PathBuf>)]) {
+    for (path, aside) in swapped.iter().rev() {
+        let _ = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        if let Some(aside) = aside {
+            let _ = std::fs::rename(aside, path);
+        }
+    }
+}
+
+fn probe_gpg(runner: &dyn GpgRunner, homedir: String) -> Result<GpgProbe, GPGError> {
+    let result = handle_cmd_io(
+        runner,
+        Some(vec![
+            "--list-config".to_string(),
+            "--with-colons".to_string(),
+        ]),
+        None,
+        0.0,
+        homedir,
+        None,
+        None,
+        None,
+        Operation::Verify,
+    );
+    match result {
+        Ok(result) => {
+            let version: (f32, String) = get_gpg_version(&result);
+            return Ok(GpgProbe {
+                version: version.0,
+                full_version: version.1,
+                capabilities: get_gpg_capabilities(&result),
+            });
+        }
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
 // a struct to represent a GPG object
 //*******************************************************
 
 //                 RELATED TO GPG
 
 //*******************************************************
+// GPG is a snapshot of the settings, every operation take &self, so it can be cloned or shared across threads
+// ( ex. in the state of a web server ) without a mutex. What need to be shared between operations ( the version
+// and key listing cache, the lock of the homedir ) was kept outside of it, behind its own lock
 #[derive(Debug, Clone)]
 pub struct GPG {
     // a path to a directory where the local key were at
-    pub homedir: String,
+    pub homedir: PathBuf,
     // a path to a directory where the output files from gpg will save to
-    pub output_dir: String,
+    pub output_dir: PathBuf,
     // a haspmap of env variables that would be passed to process
     pub env: Option<HashMap<String, String>>,
     // a list of name of keyring files to use. If provided, the default keyring will be ignored.
     pub keyrings: Option<Vec<String>>,
     // a list of name of secret keyring files to use.
     pub secret_keyring: Option<Vec<String>>,
-    // additional arguments to be passed to gpg
+    // additional arguments to be passed to gpg, argument overriding what the crate pass to gpg will be rejected
+    // ( ex. --output, --homedir ) unless unsafe_extra_args was set
     pub options: Option<Vec<String>>,
+    // common options to be passed to gpg, check GpgOptions
+    pub common_options: GpgOptions,
+    // consulted for the passphrase of a secret key when it was not provided to the operation, check PassphraseProvider
+    pub passphrase_provider: Option<Arc<dyn PassphraseProvider>>,
+    // how many more times the passphrase provider will be consulted if gpg rejected the passphrase it returned,
+    // like gpg asking again for the passphrase interactively ( 0 by default )
+    pub passphrase_retries: u32,
+    // the key to sign with when the signing operation ( sign, encrypt with sign ) did not provide one,
+    // set it with set_default_key ( None by default, gpg will use its own default key )
+    pub default_key: Option<String>,
+    // where the record of every gpg command run will be sent to, check AuditSink
+    // NOTE: the gpgconf related method were not audited as they do not run gpg
+    pub audit_sink: Option<Arc<dyn AuditSink>>,
+    // receive the timing and outcome of every gpg command run, check MetricsRecorder
+    pub metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+    // how long a mutating operation ( import, trust, gen_key ... ) will wait for the lock of the homedir held by
+    // another GPG ( in this or another process ), None to not lock the homedir ( 30 seconds by default )
+    pub homedir_lock_timeout: Option<Duration>,
+    // for verification only service on read only filesystem or shared homedir, gpg will not update the trustdb
+    // or lock the homedir, and mutating operation ( import, trust, gen_key ... ) will fail with ReadOnlyError
+    pub read_only: bool,
+    // how long the result of list_keys ( also used to resolve the recipients of encrypt ... ) will be cached for,
+    // None to not cache ( by default ). The cache was shared by GPG on the same homedir and invalidated by their
+    // mutating operation ( import, delete, gen_key ... ), use clear_key_cache if the keyring was changed elsewhere
+    pub list_keys_cache_ttl: Option<Duration>,
+    // whether to pass options and extra_args to gpg without checking, the caller is responsible for
+    // not redirecting the output or status of gpg that the crate rely on
+    pub unsafe_extra_args: bool,
+    // whether to create the missing parent directory of an output path provided,
+    // an OutputDirError will be returned for the missing directory otherwise
+    pub create_output_dirs: bool,
+    // for deployment on read only filesystem, the output_dir will never be created nor written to, an operation
+    // without an output provided will fail with OutputDirError ( use the *_to_writer method or provide the output ).
+    // Set it with GPGBuilder.no_output_dir, as GPG::init already created the output_dir
+    pub no_output_dir: bool,
+    // how the output of encrypt, decrypt and sign will be named when the option did not provide one,
+    // check OutputNaming ( OutputNaming::Timestamp by default )
+    pub output_naming: OutputNaming,
+    // the sub directory of the output_dir the output of each operation will be written to when the operation
+    // did not provide one, check OutputLayout ( OutputLayout::default() by default )
+    pub output_layout: OutputLayout,
+    // how long and how much the output the crate created in the output_dir will be kept for when clean_output_dir
+    // was called, check OutputRetention ( None by default )
+    pub output_retention: Option<OutputRetention>,
+    // the digest algorithm to compute the checksum of the output file with ( ex. DigestAlgo::Sha256 ), set in
+    // output_info of the CmdResult, None to not compute it ( by default ) as it take another gpg run to read the file
+    pub output_checksum: Option<DigestAlgo>,
+    // the permission of the homedir checked when initializing, gpg will warn about unsafe permission
+    // if it was not secure, use fix_permissions to fix it
+    pub permission_check: PermissionCheckResult,
+    // how much gpg will log, the log was captured into debug_log of the CmdResult, check Verbosity
+    // ( Verbosity::Normal by default )
+    pub verbosity: Verbosity,
     // a boolean to indicate if the output should be armored
     pub armor: bool,
     // the major minor version of gpg, should only be set by system, user should not set this ex) 2.4
     pub version: f32,
     // the full version of gpg, should only be set by system, user should not set this ex) 2.4.6
     pub full_version: String,
+    // algorithms supported by gpg, retrieved from --list-config when initializing
+    capabilities: GPGCapabilities,
+    // to run the gpg command, a local gpg process will be spawned unless initialized with init_with_runner
+    runner: Arc<dyn GpgRunner>,
+    // the gpg binary run by the default runner ( gpg found on PATH unless chosen with GPGBuilder ), spawned as well
+    // by the operation streaming from a local gpg process ( iter_keys, run_pipeline ... ), None for a custom runner
+    gpg_program: Option<PathBuf>,
 }
 
+// a field that is not Send + Sync ( ex. Rc, RefCell ) will fail the build here instead of in the application
+const _: fn() = || {
+    fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+    assert_shareable::<GPG>();
+    assert_shareable::<GPGBuilder>();
+};
+
 impl GPG {
     // initialize a GPG object with a homedir and an output_dir or none (system set homedir and output dir)
+    // NOTE: the version and capabilities of gpg retrieved for a homedir will be cached for the whole process,
+    //       call clear_version_cache after gpg was upgraded to retrieve them again
     pub fn init(
-        homedir: Option<String>,
-        output_dir: Option<String>,
+        homedir: Option<PathBuf>,
+        output_dir: Option<PathBuf>,
         armor: bool,
     ) -> Result<GPG, GPGError> {
         // homedir: a path to a directory where the local key were at
         // output_dir: a path to a directory where the output files from gpg will save to
         // a boolean to indicate if the output should be armored
 
-        let h_d: String = get_or_create_gpg_homedir(homedir.unwrap_or(String::new()));
-        let o_d: String = get_or_create_gpg_output_dir(output_dir.unwrap_or(String::new()));
-
-        let result = handle_cmd_io(
-            Some(vec![
-                "--list-config".to_string(),
-                "--with-colons".to_string(),
-            ]),
-            None,
-            0.0,
-            h_d.clone(),
-            None,
-            None,
-            None,
-            None,
-            None,
+        return GPG::init_gpg(
+            homedir,
+            output_dir,
+            armor,
+            Arc::new(ProcessRunner::default()),
+            Some(PathBuf::from("gpg")),
             false,
-            false,
-            Operation::Verify,
         );
+    }
 
-        match result {
-            Ok(result) => {
-                let version: (f32, String) = get_gpg_version(&result);
-                return Ok(GPG {
-                    homedir: h_d,
-                    output_dir: o_d,
-                    env: None,
-                    keyrings: None,
-                    secret_keyring: None,
-                    options: None,
-                    armor: armor,
-                    version: version.0,
-                    full_version: version.1,
-                });
+    // initialize a GPG object that hand every gpg command to the runner instead of spawning gpg itself
+    // NOTE: the gpgconf related method ( dirs, kill_agent ... ) will still run locally, iter_keys collect the whole
+    //       listing through the runner first, and the operation streaming from a local gpg process ( run_pipeline,
+    //       rekey, reencrypt_symmetric, import_from_homedir ) will fail with FailedToStartProcess
+    pub fn init_with_runner(
+        homedir: Option<PathBuf>,
+        output_dir: Option<PathBuf>,
+        armor: bool,
+        runner: Arc<dyn GpgRunner>,
+    ) -> Result<GPG, GPGError> {
+        // homedir: a path to a directory where the local key were at
+        // output_dir: a path to a directory where the output files from gpg will save to
+        // a boolean to indicate if the output should be armored
+        // runner: to run the gpg command, ProcessRunner will spawn a local gpg process
+
+        // the version retrieved through a custom runner was not cached as the runner may not be running the local gpg
+        return GPG::init_gpg(homedir, output_dir, armor, runner, None, false);
+    }
+
+    // every gpg binary ( gpg, gpg2, gpg1 ) found on PATH and the common install locations, the newest version first
+    // for system where several were installed ( ex. gpg 1.4 and 2.4 ), choose one with GPGBuilder.select_gpg
+    pub fn find_installations() -> Vec<GpgInstallation> {
+        return find_gpg_installations();
+    }
+
+    // remove the cached version and capabilities of every gpg binary for the homedir, or for every homedir if None
+    // return false if nothing was cached
+    pub fn clear_version_cache(homedir: Option<PathBuf>) -> bool {
+        // homedir: the homedir of the GPG object ( GPG.homedir )
+
+        let mut cache = get_version_cache().lock().unwrap();
+        match homedir {
+            Some(homedir) => {
+                let cached: usize = cache.len();
+                cache.retain(|(_, h_d), _| *h_d != homedir);
+                return cache.len() < cached;
             }
-            Err(e) => {
-                return Err(e);
+            None => {
+                let cleared: bool = !cache.is_empty();
+                cache.clear();
+                return cleared;
             }
         }
     }
 
-    //#######################################################
+    // remove the cached listing of list_keys for the homedir, or for every homedir if None
+    // the cache was invalidated by the mutating operation of GPG, this is for keyring changed outside of it
+    // return false if nothing was cached
+    pub fn clear_key_cache(homedir: Option<PathBuf>) -> bool {
+        // homedir: the homedir of the GPG object ( GPG.homedir )
 
-    //    FUNCTION BELOW RELATED TO GPG VARIOUS OPERATIONS
+        return invalidate_key_cache(homedir.as_deref());
+    }
 
-    //#######################################################
+    fn init_gpg(
+        homedir: Option<PathBuf>,
+        output_dir: Option<PathBuf>,
+        armor: bool,
+        runner: Arc<dyn GpgRunner>,
+        gpg_program: Option<PathBuf>,
+        no_output_dir: bool,
+    ) -> Result<GPG, GPGError> {
+        // gpg_program: the gpg binary run by the default runner, the version was cached for it,
+        //              None for a custom runner which may not be running the local gpg ( not cached )
+        // no_output_dir: the output_dir will not be created, check GPG.no_output_dir
 
-    //*******************************************************
+        // both will be passed to gpg as argument, checked before any directory was created
+        for dir in homedir.iter().chain(output_dir.iter()) {
+            path_to_arg(dir)?;
+        }
+        let h_d: PathBuf = get_or_create_gpg_homedir(homedir);
+        let o_d: PathBuf = if no_output_dir {
+            get_gpg_output_dir(output_dir)
+        } else {
+            get_or_create_gpg_output_dir(output_dir)
+        };
 
-    //                   GENERATE KEY
+        let cache_key: Option<(PathBuf, PathBuf)> = gpg_program.clone().map(|program| (program, h_d.clone()));
+        let cached: Option<GpgProbe> = match &cache_key {
+            Some(key) => get_version_cache().lock().unwrap().get(key).cloned(),
+            None => None,
+        };
+        let probe: GpgProbe = match cached {
+            Some(probe) => probe,
+            None => {
+                let probe: GpgProbe = probe_gpg(runner.as_ref(), h_d.to_string_lossy().to_string())?;
+                if let Some(key) = cache_key {
+                    get_version_cache().lock().unwrap().insert(key, probe.clone());
+                }
+                probe
+            }
+        };
+        // an unknown version ( 0.0 ) was not rejected, as a custom runner may not report it
+        if probe.version > 0.0 && probe.version < MIN_GPG_VERSION {
+            return Err(GPGError::new(
+                GPGErrorType::UnsupportedVersion(format!(
+                    "gpg {} is not supported, gpg {} or later is required",
+                    probe.full_version, MIN_GPG_VERSION
+                )),
+                None,
+            ));
+        }
+        // gpg will warn about unsafe permission of an existing homedir, it will only be fixed if asked to
+        let permission_check: PermissionCheckResult = check_homedir_permissions(&h_d);
+        return Ok(GPG {
+            homedir: h_d,
+            output_dir: o_d,
+            env: None,
+            keyrings: None,
+            secret_keyring: None,
+            options: None,
+            common_options: GpgOptions::default(),
+            passphrase_provider: None,
+            passphrase_retries: 0,
+            default_key: None,
+            audit_sink: None,
+            metrics_recorder: None,
+            homedir_lock_timeout: Some(DEFAULT_HOMEDIR_LOCK_TIMEOUT),
+            read_only: false,
+            list_keys_cache_ttl: None,
+            unsafe_extra_args: false,
+            create_output_dirs: false,
+            no_output_dir: no_output_dir,
+            output_naming: OutputNaming::Timestamp,
+            output_layout: OutputLayout::default(),
+            output_retention: None,
+            output_checksum: None,
+            permission_check: permission_check,
+            verbosity: Verbosity::Normal,
+            armor: armor,
+            version: probe.version,
+            full_version: probe.full_version,
+            capabilities: probe.capabilities,
+            runner: runner,
+            gpg_program: gpg_program,
+        });
+    }
 
-    //*******************************************************
-    pub fn gen_key(
-        &self,
-        key_passphrase: Option<String>,
-        args: Option<HashMap<String, String>>,
-    ) -> Result<CmdResult, GPGError> {
-        // passphrase: a passphrase for the key ( was used to protect the private key and will be needed during operation like decrypt )
-        // args: a hashmap of arguments to generate the type of key, if not provided, it will generate a default key of type RSA with key length of 2048
+    // initialize a GPG object with the default homedir reported by gpgconf instead of the one set by system
+    pub fn init_with_gpgconf(output_dir: Option<PathBuf>, armor: bool) -> Result<GPG, GPGError> {
+        // output_dir: a path to a directory where the output files from gpg will save to
+        // a boolean to indicate if the output should be armored
 
-        let k_p = key_passphrase.clone();
-        if k_p.is_some() {
-            if !is_passphrase_valid(k_p.as_ref().unwrap()) {
-                return Err(GPGError::new(
-                    GPGErrorType::PassphraseError("key passphrase invalid".to_string()),
-                    None,
-                ));
-            }
+        // gpgconf take GNUPGHOME and the platform specific layout into account
+        let dirs: GpgDirs = get_gpg_dirs(None)?;
+        if dirs.homedir.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::HomedirError("gpgconf did not report a homedir".to_string()),
+                None,
+            ));
         }
-        let input: String = self.gen_key_input(args, key_passphrase.clone());
-        let args: Vec<String> = vec!["--gen-key".to_string()];
-        let result: Result<CmdResult, GPGError> = handle_cmd_io(
-            Some(args),
-            key_passphrase,
-            self.version,
-            self.homedir.clone(),
-            self.options.clone(),
-            self.env.clone(),
-            None,
-            None,
-            Some(input.as_bytes().to_vec()),
-            true,
-            false,
-            Operation::GenerateKey,
-        );
-        return result;
+        return GPG::init(Some(PathBuf::from(dirs.homedir)), output_dir, armor);
     }
 
-    fn gen_key_input(
-        &self,
-        args: Option<HashMap<String, String>>,
-        passphrase: Option<String>,
-    ) -> String {
-        // generate the input we need to pass to gpg to generate a key
+    // retrieve the directories and sockets used by gpg for the homedir
+    pub fn dirs(&self) -> Result<GpgDirs, GPGError> {
+        return get_gpg_dirs(Some(self.homedir.clone()));
+    }
 
-        //******************* EXAMPLE ************************
-        // Key-Type: DSA
-        // Key-Length: 1024
-        // Subkey-Type: ELG-E
-        // Subkey-Length: 1024
-        // Name-Real: Joe Tester
-        // Name-Comment: with stupid passphrase
-        // Name-Email: joe@foo.bar
-        // Expire-Date: 0
-        // %no-protection
-        // %commit
-        //*****************************************************
+    // retrieve the Assuan sockets of gpg-agent, dirmngr and keyboxd for the homedir
+    // ex) the agent_ssh socket can be used as SSH_AUTH_SOCK when enable-ssh-support was set in gpg-agent.conf
+    pub fn sockets(&self) -> Result<GpgSockets, GPGError> {
+        let dirs: GpgDirs = self.dirs()?;
+        if dirs.agent_socket.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError("gpgconf did not report the socket of gpg-agent".to_string()),
+                None,
+            ));
+        }
+        return Ok(GpgSockets::from_dirs(&dirs));
+    }
 
-        let mut params: HashMap<String, String> = HashMap::new();
-        if args.is_some() {
-            for (key, value) in args.unwrap().iter() {
-                params.insert(key.replace("_", "-").to_string(), value.trim().to_string());
+    // remove the group and other permission of the homedir and the keyring files in it
+    // so gpg will not warn about unsafe permission, the homedir was only checked when initializing
+    // NOTE: this update permission_check, call it before the GPG was shared
+    pub fn fix_permissions(&mut self) -> Result<PermissionCheckResult, GPGError> {
+        match fix_homedir_permissions(&self.homedir) {
+            Ok(result) => {
+                self.permission_check = result.clone();
+                return Ok(result);
             }
+            Err(e) => return Err(e),
         }
-        params
-            .entry("Key-Type".to_string())
-            .or_insert("RSA".to_string());
-        if !params.contains_key("Key-Curve") {
-            params
-                .entry("Key-Length".to_string())
-                .or_insert("2048".to_string());
-        }
-        params
-            .entry("Expire-Date".to_string())
-            .or_insert("0".to_string());
-        params
-            .entry("Name-Real".to_string())
-            .or_insert("AutoGenerated Key".to_string());
-        let logname = env::var("LOGNAME")
-            .or_else(|_| env::var("USERNAME"))
-            .unwrap_or_else(|_| "unspecified".to_string());
-        let hostname = hostname::get().unwrap_or_else(|_| "unknown".into());
-        params.entry("Name-Email".to_string()).or_insert(format!(
-            "{}@{}",
-            logname,
-            hostname.to_string_lossy()
-        ));
-        let mut input: String = format!("Key-Type: {}\n", params.remove("Key-Type").unwrap());
-        for (key, value) in params.iter() {
-            input.push_str(&format!("{}: {}\n", key, value));
-        }
-        if passphrase.is_none() {
-            input.push_str("%no-protection\n");
-        }
-        input.push_str("%commit\n");
-        return input;
     }
 
-    //*******************************************************
-
-    //                     LIST KEY
-
-    //*******************************************************
-    pub fn list_keys(
-        &self,
-        secret: bool,
-        keys: Option<Vec<String>>,
-        signature: bool,
-    ) -> Result<Vec<ListKeyResult>, GPGError> {
-        // secret: if true, list secret keys
-        // keys: list of keyid(s) to match
-        // sigs: if true, include signatures
+    // the lock file(s) gpg created in the homedir, a mutating operation will wait for them to be released
+    // and return a HomedirLocked if one was stale or still held after GPG.homedir_lock_timeout
+    pub fn locks(&self) -> Vec<GpgLock> {
+        return find_gpg_locks(&self.homedir);
+    }
 
-        let mut mode: String = "keys".to_string();
-        if secret {
-            mode = "secret-keys".to_string();
-        } else if signature {
-            mode = "sigs".to_string();
+    // remove the stale lock file(s) ( created on this host by a gpg process no longer running, ex. after a crash )
+    // return the path of the removed lock file(s), a lock held by a running process or another host was kept
+    pub fn recover_locks(&self) -> Result<Vec<PathBuf>, GPGError> {
+        if self.read_only {
+            return Err(GPGError::new(
+                GPGErrorType::ReadOnlyError("recovering locks is not allowed as GPG was set to read only".to_string()),
+                None,
+            ));
         }
+        // only removed while no GPG on the homedir was running a mutating operation
+        let _lock: Option<File> = match self.homedir_lock_timeout {
+            Some(timeout) => match lock_homedir(&self.homedir, timeout) {
+                Ok(lock) => Some(lock),
+                Err(e) => return Err(e),
+            },
+            None => None,
+        };
+        return remove_stale_gpg_locks(&self.homedir);
+    }
 
-        let mut args: Vec<String> = vec![
-            format!("--list-{}", mode),
-            "--fingerprint".to_string(),
-            "--fingerprint".to_string(),
-        ]; // duplicate --fingerprint to get the subkeys FP as well
+    // housekeeping of the homedir for a long-running service: remove the stale lock file(s) ( check recover_locks ),
+    // let gpg do its maintenance of the keybox ( --rebuild-keydb-caches ) and check the trustdb
+    // NOTE: gpg only compress the keybox ( drop the space of the deleted keys ) when its own maintenance was due,
+    //       at most once every few hours, compare keybox_size_before and keybox_size_after to tell
+    pub fn maintain(&self) -> Result<MaintenanceResult, GPGError> {
+        let removed_locks: Vec<PathBuf> = self.recover_locks()?;
+
+        let keybox: PathBuf = self.homedir.join("pubring.kbx");
+        let keybox_size_before: Option<u64> = std::fs::metadata(&keybox).ok().map(|m| m.len());
+        let mut results: Vec<CmdResult> = Vec::new();
+        for arg in ["--rebuild-keydb-caches", "--check-trustdb"] {
+            let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+            let result: CmdResult = handle_cmd_io(
+                self.runner().as_ref(),
+                Some(vec![arg.to_string()]),
+                None,
+                self.version,
+                self.homedir_arg(),
+                cmd_options,
+                self.env.clone(),
+                None,
+                Operation::Maintain,
+            )?;
+            if !result.is_success() {
+                return Err(GPGError::new(
+                    GPGErrorType::GPGProcessError(format!("gpg {} failed", arg)),
+                    Some(result),
+                ));
+            }
+            results.push(result);
+        }
+        return Ok(MaintenanceResult {
+            removed_locks: removed_locks,
+            keybox_size_before: keybox_size_before,
+            keybox_size_after: std::fs::metadata(&keybox).ok().map(|m| m.len()),
+            trustdb: results.pop().unwrap(),
+        });
+    }
 
-        if self.version >= 2.1 {
-            args.push("--with-keygrip".to_string());
+    // apply the retention policy ( output_retention ) to the output the crate created in the output_dir,
+    // for a long-running service where the timestamped output would pile up otherwise, check OutputRetention
+    // nothing will be removed if no_output_dir was set, as the output_dir was never written to
+    pub fn clean_output_dir(&self) -> Result<OutputCleanupResult, GPGError> {
+        let retention: &OutputRetention = match &self.output_retention {
+            Some(retention) => retention,
+            None => {
+                return Err(GPGError::new(
+                    GPGErrorType::InvalidArgumentError("output_retention was not set".to_string()),
+                    None,
+                ));
+            }
+        };
+        if self.no_output_dir {
+            return Ok(OutputCleanupResult {
+                removed: Vec::new(),
+                freed_bytes: 0,
+                kept: 0,
+                kept_bytes: 0,
+            });
         }
-        if keys.is_some() {
-            args.append(&mut keys.unwrap());
+        // the output_dir and the sub directories the output was written to, an invalid sub directory was never used
+        let layout: [&Option<PathBuf>; 6] = [
+            &self.output_layout.encrypted,
+            &self.output_layout.decrypted,
+            &self.output_layout.signatures,
+            &self.output_layout.exported_public_keys,
+            &self.output_layout.exported_secret_keys,
+            &self.output_layout.backups,
+        ];
+        let mut dirs: Vec<PathBuf> = vec![self.output_dir.clone()];
+        for sub_dir in layout.into_iter().flatten() {
+            let inside: bool = sub_dir.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+            let dir: PathBuf = self.output_dir.join(sub_dir);
+            if inside && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
         }
-        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+        return remove_managed_outputs(find_managed_outputs(&dirs), retention.max_age, retention.max_total_size);
+    }
+
+    // check whether gpg was ready to serve the operations, for the readiness probe of a long-running service:
+    // gpg could be run, the homedir was writable, the agent responded and the keyring could be listed
+    // every check was run even if one failed, check HealthReport
+    // NOTE: the agent was checked with the local gpg-connect-agent even if initialized with init_with_runner,
+    //       and will be started if it was not running
+    pub fn health_check(&self) -> HealthReport {
+        let binary: HealthCheck = HealthCheck::run(|| {
+            let result: CmdResult = match self.run_health_command(vec!["--version".to_string()], Operation::NotSet) {
+                Ok(result) => result,
+                Err(e) => return Err(e),
+            };
+            // ex) gpg (GnuPG) 2.4.6
+            let raw_data: String = result.raw_data.unwrap_or_default();
+            let version: &str = raw_data.lines().find(|line| line.starts_with("gpg (")).unwrap_or(&self.full_version);
+            return Ok(version.to_string());
+        });
+        let homedir: HealthCheck = HealthCheck::run(|| {
+            if self.read_only {
+                return Ok("not checked as GPG was read only".to_string());
+            }
+            match create_temp_file_in(&self.homedir, "health") {
+                Ok(_) => return Ok(format!("{} is writable", self.homedir.display())),
+                Err(e) => return Err(e),
+            }
+        });
+        let agent: HealthCheck = HealthCheck::run(|| {
+            if self.version < 2.0 {
+                return Ok(format!("gpg {} does not use gpg-agent", self.full_version));
+            }
+            match get_agent_version(&self.homedir) {
+                Ok(version) => return Ok(format!("gpg-agent {}", version)),
+                Err(e) => return Err(e),
+            }
+        });
+        // listed without the cache, as the cached listing does not tell the keyring could still be opened
+        let keyring: HealthCheck = HealthCheck::run(|| {
+            let args: Vec<String> = self.gen_list_keys_args(false, None, false);
+            match self.run_health_command(args, Operation::ListKey) {
+                Ok(result) => return Ok(format!("{} key(s)", decode_list_key_result(result).len())),
+                Err(e) => return Err(e),
+            }
+        });
+        return HealthReport::new(binary, homedir, agent, keyring);
+    }
+
+    fn run_health_command(&self, args: Vec<String>, ops: Operation) -> Result<CmdResult, GPGError> {
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        return handle_cmd_io(
+            self.runner().as_ref(),
             Some(args),
             None,
             self.version,
-            self.homedir.clone(),
-            self.options.clone(),
+            self.homedir_arg(),
+            cmd_options,
             self.env.clone(),
             None,
-            None,
-            None,
-            false,
-            false,
-            Operation::ListKey,
+            ops,
         );
-        match result {
-            Ok(result) => {
-                return Ok(decode_list_key_result(result));
-            }
-            Err(e) => {
-                return Err(e);
-            }
+    }
+
+    // send a single Assuan command to the gpg-agent of the homedir through gpg-connect-agent,
+    // ex) "getinfo version", "keyinfo --list", "clear_passphrase --mode=normal <keygrip>"
+    // an ERR answered by the agent was not an error, check ok and error of AgentResponse
+    // NOTE: run with the local gpg-connect-agent even if initialized with init_with_runner,
+    //       and the agent will be started if it was not running
+    pub fn agent_command(&self, command: &str) -> Result<AgentResponse, GPGError> {
+        // command: the Assuan command with its arguments, a gpg-connect-agent meta command ( /... ) was not allowed
+
+        self.require_version(2.0, "gpg-agent command")?;
+        let command: &str = command.trim();
+        if command.is_empty() || !is_passphrase_valid(command) || command.starts_with("/") {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!(
+                    "agent command [{}] must be a single line Assuan command", command.escape_debug()
+                )),
+                None,
+            ));
         }
+        return run_agent_command(&self.homedir, command);
     }
 
-    //*******************************************************
+    // turn enable-ssh-support on or off in the gpg-agent.conf of the homedir and restart the agent,
+    // once enabled the agent serve the keys listed in sshcontrol on the agent_ssh socket ( check sockets() )
+    // NOTE: the agent was restarted with the local gpgconf even if initialized with init_with_runner,
+    //       so every passphrase it cached was forgotten
+    pub fn set_ssh_support(&self, enable: bool) -> Result<(), GPGError> {
+        // enable: true to serve the keys over ssh, false to stop
+
+        if self.read_only {
+            return Err(GPGError::new(
+                GPGErrorType::ReadOnlyError("changing gpg-agent.conf is not allowed as GPG was set to read only".to_string()),
+                None,
+            ));
+        }
+        self.require_version(2.0, "ssh support of gpg-agent")?;
+        let mut agent_conf: AgentConf = self.agent_conf()?;
+        agent_conf.set_enable_ssh_support(enable);
+        agent_conf.save()?;
+        return agent_conf.restart();
+    }
+
+    // list the keys the agent of the homedir serve over ssh, with the gpg key each of them belong to
+    pub fn list_ssh_keys(&self) -> Result<Vec<SshKey>, GPGError> {
+        self.require_version(2.1, "listing ssh keys of gpg-agent")?;
+        let response: AgentResponse = self.agent_command("keyinfo --ssh-list --ssh-fpr=sha256")?;
+        if !response.ok {
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError(response.error.unwrap_or_default()),
+                None,
+            ));
+        }
+        let mut ssh_keys: Vec<SshKey> = response.status.iter().filter_map(|line| SshKey::from_keyinfo(line)).collect();
+        if ssh_keys.is_empty() {
+            return Ok(ssh_keys);
+        }
+        // the keygrip of the primary key or a subkey ( usually the authentication subkey )
+        let keys: Vec<ListKeyResult> = self.list_keys(false, None, false)?;
+        for ssh_key in ssh_keys.iter_mut() {
+            ssh_key.fingerprint = keys.iter().find_map(|key| {
+                if key.keygrip == ssh_key.keygrip {
+                    return Some(key.fingerprint.clone());
+                }
+                return key.subkeys.iter().find(|sub| sub.keygrip == ssh_key.keygrip).map(|sub| sub.fingerprint.clone());
+            });
+        }
+        return Ok(ssh_keys);
+    }
+
+    // retrieve the algorithms supported by the installed gpg
+    pub fn capabilities(&self) -> GPGCapabilities {
+        return self.capabilities.clone();
+    }
+
+    // retrieve the full configuration gpg report for the homedir ( algorithms with their id, curves, groups ... )
+    // unlike capabilities(), it was read from gpg on every call, so a group added to gpg.conf will be included
+    pub fn config(&self) -> Result<GpgConfig, GPGError> {
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(vec![
+                "--list-config".to_string(),
+                "--with-colons".to_string(),
+            ]),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::NotSet,
+        );
+        match result {
+            Ok(result) => return Ok(decode_list_config_result(&result)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // read the gpg.conf of the homedir, changes will only be written after calling save()
+    pub fn gpg_conf(&self) -> Result<GpgConf, GPGError> {
+        return GpgConf::open(self.homedir.clone());
+    }
+
+    // read the gpg-agent.conf of the homedir, changes will only be written after calling save()
+    pub fn agent_conf(&self) -> Result<AgentConf, GPGError> {
+        return AgentConf::open(self.homedir.clone());
+    }
+
+    // set the key to sign with when the signing operation did not provide one ( ex. the identity of the service ),
+    // an InvalidArgumentError will be returned if it was not a secret key in the homedir
+    pub fn set_default_key(&mut self, fingerprint: String) -> Result<(), GPGError> {
+        // fingerprint: fingerprint ( or keyid ) of the secret key to sign with
+
+        // gpg fail the listing instead of returning no key for a key not found
+        let keys: Vec<ListKeyResult> = self.list_keys(true, None, false)?;
+        if fingerprint.is_empty() || !keys.iter().any(|k| k.fingerprint.ends_with(&fingerprint.to_uppercase())) {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!("no secret key found for {}", fingerprint)),
+                None,
+            ));
+        }
+        self.default_key = Some(fingerprint);
+        return Ok(());
+    }
+
+    // a GPG for a one-off operation with the overrides applied, every other setting was kept from this GPG,
+    // ex) gpg.with_overrides(overrides)?.verify_file(...)
+    // gpg was not probed again as the same gpg will be run
+    pub fn with_overrides(&self, overrides: OperationOverrides) -> Result<GPG, GPGError> {
+        // overrides: what to override for the operation, check OperationOverrides
+
+        let mut gpg: GPG = self.clone();
+        if let Some(homedir) = overrides.homedir {
+            path_to_arg(&homedir)?;
+            gpg.homedir = get_or_create_gpg_homedir(Some(homedir));
+            gpg.permission_check = check_homedir_permissions(&gpg.homedir);
+        }
+        if let Some(env) = overrides.env {
+            gpg.env.get_or_insert_with(HashMap::new).extend(env);
+        }
+        if let Some(mut options) = overrides.options {
+            // checked with the options of the GPG when the operation was run
+            gpg.options.get_or_insert_with(Vec::new).append(&mut options);
+        }
+        if let Some(verbosity) = overrides.verbosity {
+            gpg.verbosity = verbosity;
+        }
+        if let Some(timeout) = overrides.timeout {
+            gpg.runner = match self.runner.with_timeout(timeout) {
+                Some(runner) => runner,
+                None => {
+                    return Err(GPGError::new(
+                        GPGErrorType::InvalidArgumentError(
+                            "timeout is not supported by the runner of the GPG".to_string(),
+                        ),
+                        None,
+                    ))
+                }
+            };
+        }
+        return Ok(gpg);
+    }
+
+    // the homedir to be passed to gpg, it was checked to be valid unicode when initializing
+    fn homedir_arg(&self) -> String {
+        return self.homedir.to_string_lossy().to_string();
+    }
+
+    // the gpg binary to spawn for the operation streaming from a local gpg process, the one run by the runner
+    // refused for a custom runner, as it may not be running the local gpg ( ex. over ssh )
+    fn local_gpg_program(&self, ops: &Operation) -> Result<PathBuf, GPGError> {
+        match &self.gpg_program {
+            Some(gpg_program) => return Ok(gpg_program.clone()),
+            None => {
+                return Err(GPGError::new(
+                    GPGErrorType::FailedToStartProcess(format!(
+                        "streaming from a local gpg process is not supported by a custom runner: {}",
+                        ops
+                    )),
+                    None,
+                ))
+            }
+        }
+    }
+
+    // to record the gpg process spawned for the operation streaming from a local gpg process, started now
+    // to the audit sink and metrics recorder, as the runner would for the command run by it
+    fn local_recorder(&self) -> LocalProcessRecorder {
+        return LocalProcessRecorder::new(self.audit_sink.clone(), self.metrics_recorder.clone());
+    }
+
+    // the runner to run the gpg command with, mutating operation will be refused if read only or lock the homedir
+    // and invalidate the cached listing,
+    // every command will be recorded to the audit sink and reported to the metrics recorder if they were set
+    fn runner(&self) -> Arc<dyn GpgRunner> {
+        let mut runner: Arc<dyn GpgRunner> = self.runner.clone();
+        // the guard was wrapped by the audit and metrics runner, so a command refused as read only or failing
+        // to get the lock was still recorded, and the duration recorded include the time waiting for the lock
+        runner = Arc::new(HomedirGuardRunner {
+            runner: runner,
+            homedir: self.homedir.clone(),
+            lock_timeout: self.homedir_lock_timeout,
+            read_only: self.read_only,
+        });
+        if let Some(sink) = &self.audit_sink {
+            runner = Arc::new(AuditRunner::new(runner, sink.clone()));
+        }
+        // a command that failed to be audited was reported as failed
+        if let Some(recorder) = &self.metrics_recorder {
+            runner = Arc::new(MetricsRunner::new(runner, recorder.clone()));
+        }
+        return runner;
+    }
+
+    // the common options followed by the options set by user, to be passed to every gpg command
+    fn cmd_options(&self) -> Result<Option<Vec<String>>, GPGError> {
+        let tofu: bool = self.common_options.trust_model == Some(TrustModel::Tofu)
+            || self.common_options.trust_model == Some(TrustModel::TofuPgp);
+        if tofu {
+            self.require_version(2.1, "tofu trust model")?;
+        }
+        if self.common_options.weak_digests.is_some() {
+            self.require_version(2.1, "weak digest")?;
+        }
+        let mut options: Vec<String> = self.common_options.gen_options_args();
+        match self.verbosity.gen_args() {
+            Ok(mut verbosity) => options.append(&mut verbosity),
+            Err(e) => return Err(e),
+        }
+        // gpg should not try to update the trustdb or create lock file in the homedir
+        if self.read_only {
+            options.append(&mut vec!["--no-auto-check-trustdb".to_string(), "--lock-never".to_string()]);
+        }
+        if let Some(user_options) = &self.options {
+            match self.check_extra_args(user_options.clone()) {
+                Ok(mut user_options) => options.append(&mut user_options),
+                Err(e) => return Err(e),
+            }
+        }
+        if options.is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some(options));
+    }
+
+    // run the operation with its output written to a temporary file, then copy it into the writer
+    // the temporary file will be removed once copied, or if the operation failed
+    fn write_output_to(
+        &self,
+        writer: &mut impl Write,
+        operation: impl FnOnce(PathBuf) -> Result<CmdResult, GPGError>,
+    ) -> Result<CmdResult, GPGError> {
+        // operation: the operation to run with the path of the temporary file as its output
+
+        let output_dir: TempDir = create_temp_dir("output")?;
+        let output: PathBuf = output_dir.path().join("output");
+        let mut result: CmdResult = operation(output.clone())?;
+        // the temporary file will be removed, what was written was in the writer
+        result.output_info = None;
+        let mut file: File = match File::open(&output) {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(GPGError::new(
+                    GPGErrorType::ReadFailError(format!("failed to read the output: {}", e)),
+                    Some(result),
+                ));
+            }
+        };
+        if let Err(e) = std::io::copy(&mut file, writer).and_then(|_| writer.flush()) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to write the output to the writer: {}", e)),
+                Some(result),
+            ));
+        }
+        return Ok(result);
+    }
+
+    // set the size, creation time and checksum ( if output_checksum was set ) of the file gpg wrote its output to
+    // ( --output of the command ) as output_info of the result
+    fn add_output_info(&self, result: Result<CmdResult, GPGError>) -> Result<CmdResult, GPGError> {
+        let mut result: CmdResult = result?;
+        let output: PathBuf = match result.args.windows(2).find(|w| w[0] == "--output" && w[1] != "-") {
+            Some(w) => PathBuf::from(&w[1]),
+            None => return Ok(result),
+        };
+        let mut output_info: OutputInfo = match OutputInfo::from_path(&output) {
+            Some(output_info) => output_info,
+            None => return Ok(result),
+        };
+        if let Some(algo) = &self.output_checksum {
+            match self.gen_file_digest(&output, &algo.value(), result.operation.clone()) {
+                Ok(checksum) => {
+                    output_info.checksum = Some(checksum);
+                    output_info.checksum_algo = Some(algo.clone());
+                }
+                Err(e) => return Err(GPGError::new(e.error_type, Some(result))),
+            }
+        }
+        result.output_info = Some(output_info);
+        return Ok(result);
+    }
+
+    // the output when the option did not provide one, check OutputNaming
+    fn get_default_output(
+        &self,
+        sub_dir: Option<&Path>,
+        timestamp_name: String,
+        input_name: String,
+    ) -> Result<PathBuf, GPGError> {
+        // sub_dir: the sub directory of the output_dir for the operation, check OutputLayout
+        // timestamp_name: the name for OutputNaming::Timestamp
+        // input_name: the name derived from the input for OutputNaming::FromInput
+
+        let output_dir: PathBuf = self.get_output_dir(sub_dir)?;
+        match self.output_naming {
+            OutputNaming::Timestamp => return Ok(output_dir.join(timestamp_name)),
+            OutputNaming::FromInput => return Ok(get_unique_path(&output_dir, &input_name)),
+        }
+    }
+
+    // the output_dir ( or the sub directory of it, created if it does not exist ) to write an output to
+    // when the operation did not provide one, refused if no_output_dir was set
+    fn get_output_dir(&self, sub_dir: Option<&Path>) -> Result<PathBuf, GPGError> {
+        if self.no_output_dir {
+            return Err(GPGError::new(
+                GPGErrorType::OutputDirError(
+                    "no_output_dir was set, provide an output or write the output to a writer".to_string(),
+                ),
+                None,
+            ));
+        }
+        let output_dir: PathBuf = match sub_dir {
+            Some(sub_dir) => {
+                // the output of an operation should not end up outside of the output_dir
+                let inside: bool = sub_dir.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+                if !inside {
+                    return Err(GPGError::new(
+                        GPGErrorType::OutputDirError(format!(
+                            "{} is not a relative path inside the output_dir",
+                            sub_dir.display()
+                        )),
+                        None,
+                    ));
+                }
+                self.output_dir.join(sub_dir)
+            }
+            None => return Ok(self.output_dir.clone()),
+        };
+        if !check_is_dir(&output_dir) {
+            if let Err(e) = std::fs::create_dir_all(&output_dir) {
+                return Err(GPGError::new(
+                    GPGErrorType::OutputDirError(format!("failed to create {}: {}", output_dir.display(), e)),
+                    None,
+                ));
+            }
+        }
+        return Ok(output_dir);
+    }
+
+    // reject argument that would override what the crate pass to gpg unless unsafe_extra_args was set
+    fn check_extra_args(&self, extra_args: Vec<String>) -> Result<Vec<String>, GPGError> {
+        return check_overriding_args(extra_args, self.unsafe_extra_args);
+    }
+
+    // to assemble the arguments of an operation, with the extra arguments and output checked as set in GPG
+    fn arg_builder(&self) -> ArgBuilder {
+        return ArgBuilder::new()
+            .unsafe_extra_args(self.unsafe_extra_args)
+            .create_output_dirs(self.create_output_dirs);
+    }
+
+    // fail before running gpg if the feature was not available in the gpg found ( ex. gpg 1.4 )
+    fn require_version(&self, version: f32, feature: &str) -> Result<(), GPGError> {
+        // version: the oldest gpg ( major.minor ) with the feature
+        // feature: what is not available, for the error message
+
+        if self.version >= version {
+            return Ok(());
+        }
+        return Err(GPGError::new(
+            GPGErrorType::UnsupportedVersion(format!(
+                "{} requires gpg {} or later, gpg {} was found",
+                feature, version, self.full_version
+            )),
+            None,
+        ));
+    }
+
+    fn check_pubkey_algo(&self, algo: &str) -> Result<(), GPGError> {
+        // algo can be a Key-Type for gen_key ( ex. RSA, ELG-E ) or an algo for add_subkey ( ex. rsa2048, ed25519 )
+        let a: &str = algo.split("/").next().unwrap_or("").trim();
+        let lower: String = a.to_lowercase();
+        if lower.is_empty()
+            || lower == "default"
+            || lower == "future-default"
+            || lower.chars().all(|c| c.is_ascii_digit())
+        {
+            return Ok(());
+        }
+        // key length after the algorithm name was not part of the name ex) rsa2048
+        let name: &str = lower.trim_end_matches(|c: char| c.is_ascii_digit());
+        let supported: bool = match name {
+            "rsa" | "dsa" | "ecdsa" | "eddsa" | "ecdh" => self.capabilities.supports_pubkey(name),
+            "elg" | "elg-e" => self.capabilities.supports_pubkey("ELG"),
+            _ => self.capabilities.supports_curve(a),
+        };
+        if !supported {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!(
+                    "public key algorithm {} is not supported by gpg {}",
+                    algo, self.full_version
+                )),
+                None,
+            ));
+        }
+        return Ok(());
+    }
+
+    //#######################################################
+
+    //    FUNCTION BELOW RELATED TO GPG VARIOUS OPERATIONS
+
+    //#######################################################
+
+    //*******************************************************
+
+    //                   GENERATE KEY
+
+    //*******************************************************
+    pub fn gen_key(
+        &self,
+        key_passphrase: Option<Passphrase>,
+        args: Option<HashMap<String, String>>,
+    ) -> Result<CmdResult, GPGError> {
+        // passphrase: a passphrase for the key ( was used to protect the private key and will be needed during operation like decrypt )
+        // args: a hashmap of arguments to generate the type of key, if not provided, it will generate a default key of type RSA with key length of 2048
+
+        let k_p = key_passphrase.clone();
+        if k_p.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("key passphrase invalid".to_string()),
+                None,
+            ));
+        }
+        if let Some(args) = &args {
+            for (key, value) in args.iter() {
+                let key: String = key.replace("_", "-");
+                let result: Result<(), GPGError> = if key == "Key-Type" || key == "Subkey-Type" {
+                    self.check_pubkey_algo(value)
+                } else if (key == "Key-Curve" || key == "Subkey-Curve")
+                    && !self.capabilities.supports_curve(value)
+                {
+                    Err(GPGError::new(
+                        GPGErrorType::InvalidArgumentError(format!(
+                            "curve {} is not supported by gpg {}",
+                            value, self.full_version
+                        )),
+                        None,
+                    ))
+                } else {
+                    Ok(())
+                };
+                result?;
+            }
+        }
+        let input: String = self.gen_key_input(args, key_passphrase.clone());
+        let args: Vec<String> = vec!["--gen-key".to_string()];
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            key_passphrase,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Bytes(input.as_bytes().to_vec())),
+            Operation::GenerateKey,
+        );
+        return result;
+    }
+
+    fn gen_key_input(
+        &self,
+        args: Option<HashMap<String, String>>,
+        passphrase: Option<Passphrase>,
+    ) -> String {
+        // generate the input we need to pass to gpg to generate a key
+
+        //******************* EXAMPLE ************************
+        // Key-Type: DSA
+        // Key-Length: 1024
+        // Subkey-Type: ELG-E
+        // Subkey-Length: 1024
+        // Name-Real: Joe Tester
+        // Name-Comment: with stupid passphrase
+        // Name-Email: joe@foo.bar
+        // Expire-Date: 0
+        // %no-protection
+        // %commit
+        //*****************************************************
+
+        let mut params: HashMap<String, String> = HashMap::new();
+        if let Some(args) = args {
+            for (key, value) in args.iter() {
+                params.insert(key.replace("_", "-").to_string(), value.trim().to_string());
+            }
+        }
+        params
+            .entry("Key-Type".to_string())
+            .or_insert("RSA".to_string());
+        if !params.contains_key("Key-Curve") {
+            params
+                .entry("Key-Length".to_string())
+                .or_insert("2048".to_string());
+        }
+        params
+            .entry("Expire-Date".to_string())
+            .or_insert("0".to_string());
+        params
+            .entry("Name-Real".to_string())
+            .or_insert("AutoGenerated Key".to_string());
+        let logname = env::var("LOGNAME")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| "unspecified".to_string());
+        let hostname = hostname::get().unwrap_or_else(|_| "unknown".into());
+        params.entry("Name-Email".to_string()).or_insert(format!(
+            "{}@{}",
+            logname,
+            hostname.to_string_lossy()
+        ));
+        let mut input: String = format!("Key-Type: {}\n", params.remove("Key-Type").unwrap());
+        for (key, value) in params.iter() {
+            input.push_str(&format!("{}: {}\n", key, value));
+        }
+        if passphrase.is_none() {
+            input.push_str("%no-protection\n");
+        }
+        input.push_str("%commit\n");
+        return input;
+    }
+
+    //*******************************************************
+
+    //                     LIST KEY
+
+    //*******************************************************
+    pub fn list_keys(
+        &self,
+        secret: bool,
+        keys: Option<Vec<String>>,
+        signature: bool,
+    ) -> Result<Vec<ListKeyResult>, GPGError> {
+        // secret: if true, list secret keys
+        // keys: list of keyid(s) to match
+        // sigs: if true, include signatures
+
+        let args: Vec<String> = self.gen_list_keys_args(secret, keys, signature);
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let cache_key: String = format!("{:?} {:?}", cmd_options, args);
+        if let Some(ttl) = self.list_keys_cache_ttl {
+            if let Some(keys) = self.get_cached_keys(&cache_key, ttl) {
+                return Ok(keys);
+            }
+        }
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::ListKey,
+        );
+        match result {
+            Ok(result) => {
+                let keys: Vec<ListKeyResult> = decode_list_key_result(result);
+                if self.list_keys_cache_ttl.is_some() {
+                    let cached: CachedKeys = CachedKeys {
+                        listed_at: Instant::now(),
+                        keys: keys.clone(),
+                    };
+                    let mut cache = get_key_cache().lock().unwrap();
+                    cache.entry(self.homedir.clone()).or_default().insert(cache_key, cached);
+                }
+                return Ok(keys);
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+
+    // the listing cached for the same arguments if it was listed within ttl
+    fn get_cached_keys(&self, cache_key: &str, ttl: Duration) -> Option<Vec<ListKeyResult>> {
+        let mut cache = get_key_cache().lock().unwrap();
+        let cached: &mut HashMap<String, CachedKeys> = cache.get_mut(&self.homedir)?;
+        match cached.get(cache_key) {
+            Some(keys) if keys.listed_at.elapsed() < ttl => return Some(keys.keys.clone()),
+            Some(_) => {
+                cached.remove(cache_key);
+                return None;
+            }
+            None => return None,
+        }
+    }
+
+    pub fn iter_keys(
+        &self,
+        secret: bool,
+        keys: Option<Vec<String>>,
+        signature: bool,
+    ) -> Result<ListKeyIter, GPGError> {
+        // secret: if true, list secret keys
+        // keys: list of keyid(s) to match
+        // sigs: if true, include signatures
+
+        // same as list_keys, but each key will be parsed as gpg is listing it
+        // instead of collecting the whole listing first
+        // a custom runner may not be running the local gpg, the whole listing was collected through it instead
+        if self.gpg_program.is_none() {
+            return Ok(ListKeyIter::collected(self.list_keys(secret, keys, signature)?));
+        }
+        let args: Vec<String> = self.gen_list_keys_args(secret, keys, signature);
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let recorder: LocalProcessRecorder = self.local_recorder();
+        let result: Result<CmdStream, GPGError> = handle_cmd_stream(
+            &self.local_gpg_program(&Operation::ListKey)?,
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Operation::ListKey,
+        );
+        match result {
+            Ok(stream) => {
+                return Ok(ListKeyIter::new(stream, recorder));
+            }
+            Err(e) => {
+                return Err(recorder.record_error(Operation::ListKey, e));
+            }
+        }
+    }
+
+    pub fn check_signatures(&self, keys: Option<Vec<String>>) -> Result<Vec<ListKeyResult>, GPGError> {
+        // keys: list of keyid(s) to match
+
+        // same as list_keys with signature, but each signature will be verified by gpg
+        // and the result will be in the validity of the signature
+        let args: Vec<String> = self.gen_list_mode_args(Flag::CheckSigs, keys);
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::ListKey,
+        );
+        match result {
+            Ok(result) => {
+                return Ok(decode_list_key_result(result));
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+
+    fn gen_list_keys_args(
+        &self,
+        secret: bool,
+        keys: Option<Vec<String>>,
+        signature: bool,
+    ) -> Vec<String> {
+        let mut mode: Flag = Flag::ListKeys;
+        if secret {
+            mode = Flag::ListSecretKeys;
+        } else if signature {
+            mode = Flag::ListSigs;
+        }
+        return self.gen_list_mode_args(mode, keys);
+    }
+
+    fn gen_list_mode_args(&self, command: Flag, keys: Option<Vec<String>>) -> Vec<String> {
+        let mut builder: ArgBuilder = ArgBuilder::new().flag(command).flag(Flag::SubkeyFingerprints);
+        if self.version >= 2.1 {
+            builder = builder.flag(Flag::WithKeygrip);
+        }
+        for key in keys.unwrap_or_default() {
+            builder = builder.operand(key);
+        }
+        // a listing command alone with known flags was always valid
+        return builder.build().unwrap_or_default();
+    }
+
+    //*******************************************************
+
+    //                     FIND KEY
+
+    //*******************************************************
+    pub fn find_keys(&self, key_query: KeyQuery) -> Result<Vec<ListKeyResult>, GPGError> {
+        // key_query: the conditions that the returned key(s) must all satisfy
+
+        let keys: Vec<ListKeyResult> = self.list_keys(false, None, false)?;
+        let mut secret_fingerprints: Vec<String> = Vec::new();
+        if key_query.secret_available {
+            secret_fingerprints = match self.list_keys(true, None, false) {
+                Ok(keys) => keys.iter().map(|k| k.fingerprint.clone()).collect(),
+                Err(e) => return Err(e),
+            };
+        }
+        let now: i64 = Local::now().timestamp();
+        return Ok(keys
+            .into_iter()
+            .filter(|key| key_query.is_match(key, &secret_fingerprints, now))
+            .collect());
+    }
+
+    // the key ( or subkey ) to encrypt to for the email, a NoUsableKeyError will be returned if no key of the email
+    // can be used for encryption, and an AmbiguousKeyError listing the candidates if more than one key can
+    pub fn encryption_key_for(&self, email: String) -> Result<SelectedKey, GPGError> {
+        // email: email address of a uid of the key ( case insensitive )
+
+        return self.select_key_for(KeyQuery::for_encryption(email), 'e');
+    }
+
+    // the key ( or subkey ) to sign with for the email, only the key with the secret key in the keyring was considered
+    // a NoUsableKeyError or AmbiguousKeyError will be returned like encryption_key_for
+    pub fn signing_key_for(&self, email: String) -> Result<SelectedKey, GPGError> {
+        // email: email address of a uid of the key ( case insensitive )
+
+        return self.select_key_for(KeyQuery::for_signing(email), 's');
+    }
+
+    // encrypt to the key of every email, each email must resolve to exactly one key that can be used for encryption
+    // ( like encryption_key_for ), nothing will be encrypted and an UnresolvedRecipients listing every email that
+    // did not resolve will be returned otherwise
+    pub fn encrypt_for_emails(
+        &self,
+        data: InputSource,
+        emails: Vec<String>,
+        options: EmailEncryptOption,
+    ) -> Result<CmdResult, GPGError> {
+        // data: the data to be encrypted, check InputSource
+        // emails: email address of the recipients ( case insensitive )
+        // options: whether to locate the key of an unresolved email and where to write the output, check EmailEncryptOption
+
+        if emails.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("at least one email must be provided".to_string()),
+                None,
+            ));
+        }
+        let mut recipients: Vec<String> = Vec::new();
+        let mut unresolved: Vec<UnresolvedRecipient> = Vec::new();
+        let mut resolved_emails: Vec<String> = Vec::new();
+        for email in emails {
+            if resolved_emails.iter().any(|e| e.eq_ignore_ascii_case(&email)) {
+                continue;
+            }
+            resolved_emails.push(email.clone());
+            let mut selected: Vec<SelectedKey> = self.usable_keys_for(KeyQuery::for_encryption(email.clone()), 'e')?;
+            if selected.is_empty() && options.locate {
+                // a failure to locate was the same as not finding the key, the email will be reported as unresolved
+                if self.locate_external_key(&email, options.locate_mechanisms.clone()).is_ok() {
+                    selected = self.usable_keys_for(KeyQuery::for_encryption(email.clone()), 'e')?;
+                }
+            }
+            if selected.len() == 1 {
+                // gpg will pick the same subkey from the primary key
+                recipients.push(selected.remove(0).key.fingerprint);
+            } else {
+                unresolved.push(UnresolvedRecipient {
+                    email: email,
+                    candidates: selected.into_iter().map(|s| s.key.fingerprint).collect(),
+                });
+            }
+        }
+        if !unresolved.is_empty() {
+            return Err(GPGError::new(GPGErrorType::UnresolvedRecipients(unresolved), None));
+        }
+        return self.encrypt(EncryptOption::default(data, recipients, options.output));
+    }
+
+    // retrieve the key of the email with the auto-key-locate mechanisms ( ex. wkd ) into the keyring
+    fn locate_external_key(&self, email: &str, mechanisms: Option<Vec<String>>) -> Result<CmdResult, GPGError> {
+        // email: email address to locate the key of
+        // mechanisms: the auto-key-locate mechanisms to try in order, gpg's configured mechanisms if not provided
+
+        self.require_version(2.2, "locating external keys")?;
+        let mut args: Vec<String> = Vec::new();
+        if let Some(mechanisms) = mechanisms {
+            args.append(&mut vec!["--auto-key-locate".to_string(), mechanisms.join(",")]);
+        }
+        args.append(&mut vec!["--locate-external-keys".to_string(), email.to_string()]);
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        return handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::ImportKey,
+        );
+    }
+
+    fn select_key_for(&self, key_query: KeyQuery, capability: char) -> Result<SelectedKey, GPGError> {
+        // capability: the capability ( lower case ) the key or subkey itself must have, e = encrypt, s = sign
+
+        let email: String = key_query.email.clone().unwrap_or_default();
+        let mut selected: Vec<SelectedKey> = self.usable_keys_for(key_query, capability)?;
+        let purpose: &str = if capability == 'e' { "encryption" } else { "signing" };
+        if selected.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::NoUsableKeyError(format!("no key of {} can be used for {}", email, purpose)),
+                None,
+            ));
+        }
+        if selected.len() > 1 {
+            let candidates: Vec<String> = selected
+                .iter()
+                .map(|s| format!("{} ( {} )", s.key.fingerprint, s.key.uids.join(", ")))
+                .collect();
+            return Err(GPGError::new(
+                GPGErrorType::AmbiguousKeyError(format!(
+                    "{} keys of {} can be used for {}: {}",
+                    selected.len(),
+                    email,
+                    purpose,
+                    candidates.join("; ")
+                )),
+                None,
+            ));
+        }
+        return Ok(selected.remove(0));
+    }
+
+    // every key ( or subkey ) matching the query that can be used, one for each key
+    fn usable_keys_for(&self, key_query: KeyQuery, capability: char) -> Result<Vec<SelectedKey>, GPGError> {
+        // capability: the capability ( lower case ) the key or subkey itself must have, e = encrypt, s = sign
+
+        let keys: Vec<ListKeyResult> = self.find_keys(key_query.clone())?;
+        // a secret subkey may be missing ( ex. exported with only the primary secret key )
+        let mut secret_fingerprints: Vec<String> = Vec::new();
+        if key_query.secret_available {
+            secret_fingerprints = match self.list_keys(true, None, false) {
+                Ok(keys) => keys
+                    .iter()
+                    .flat_map(|k| std::iter::once(k.fingerprint.clone()).chain(k.subkeys.iter().map(|s| s.fingerprint.clone())))
+                    .collect(),
+                Err(e) => return Err(e),
+            };
+        }
+        let now: i64 = Local::now().timestamp();
+        let mut selected: Vec<SelectedKey> = Vec::new();
+        for key in keys {
+            let is_usable = |fingerprint: &str| -> bool {
+                return !key_query.secret_available || secret_fingerprints.iter().any(|f| f == fingerprint);
+            };
+            // like gpg, the newest usable subkey was preferred over the primary key
+            let subkey: Option<&Subkey> = key
+                .subkeys
+                .iter()
+                .filter(|s| s.cap.contains(capability) && !s.is_revoked && !s.is_expired && !s.is_disabled)
+                .filter(|s| match s.expiry_timestamp {
+                    Some(expires) => expires > now,
+                    None => true,
+                })
+                .filter(|s| is_usable(&s.fingerprint))
+                .max_by_key(|s| s.date.parse::<i64>().unwrap_or(0));
+            if let Some(subkey) = subkey {
+                selected.push(SelectedKey {
+                    fingerprint: subkey.fingerprint.clone(),
+                    is_subkey: true,
+                    key: key.clone(),
+                });
+            } else if key.cap.contains(capability) && !key.is_disabled && is_usable(&key.fingerprint) {
+                selected.push(SelectedKey {
+                    fingerprint: key.fingerprint.clone(),
+                    is_subkey: false,
+                    key: key.clone(),
+                });
+            }
+        }
+        return Ok(selected);
+    }
+
+    // the primary keys and subkeys in the keyring expiring within the window or already expired, the earliest first,
+    // for alerting before a key expire, a revoked key or subkey was not included as it will not be used anyway
+    pub fn expiring_keys(&self, within: Duration) -> Result<Vec<ExpiringKey>, GPGError> {
+        // within: how soon from now the key will expire to be reported
+
+        let keys: Vec<ListKeyResult> = self.list_keys(false, None, false)?;
+        let now: i64 = Local::now().timestamp();
+        let deadline: i64 = now.saturating_add(i64::try_from(within.as_secs()).unwrap_or(i64::MAX));
+        let mut expiring: Vec<ExpiringKey> = Vec::new();
+        for key in keys {
+            if key.is_revoked {
+                continue;
+            }
+            let primary = std::iter::once((&key.fingerprint, &key.cap, key.expiry_timestamp, false));
+            let subkeys = key
+                .subkeys
+                .iter()
+                .filter(|sub| !sub.is_revoked)
+                .map(|sub| (&sub.fingerprint, &sub.cap, sub.expiry_timestamp, true));
+            for (fingerprint, cap, expiry_timestamp, is_subkey) in primary.chain(subkeys) {
+                let expiry_timestamp: i64 = match expiry_timestamp {
+                    Some(expiry_timestamp) if expiry_timestamp <= deadline => expiry_timestamp,
+                    _ => continue,
+                };
+                expiring.push(ExpiringKey {
+                    fingerprint: fingerprint.clone(),
+                    primary_fingerprint: key.fingerprint.clone(),
+                    is_subkey: is_subkey,
+                    uids: key.uids.clone(),
+                    cap: cap.clone(),
+                    expiry_timestamp: expiry_timestamp,
+                    is_expired: expiry_timestamp <= now,
+                });
+            }
+        }
+        expiring.sort_by_key(|key| key.expiry_timestamp);
+        return Ok(expiring);
+    }
+
+    // the --with-colons listing of the public keys in the keyring, to be saved and compared later with diff_keyring
+    pub fn keyring_snapshot(&self) -> Result<String, GPGError> {
+        let args: Vec<String> = self.gen_list_keys_args(false, None, false);
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: CmdResult = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::ListKey,
+        )?;
+        match take_spilled_output(&result) {
+            Some(output) => return Ok(output),
+            None => return Ok(result.raw_data.unwrap_or_default()),
+        }
+    }
+
+    // compare the keyring with a snapshot taken earlier by keyring_snapshot ( ex. on another host of the fleet ),
+    // the snapshot was the earlier listing, so a key only in the keyring was reported as added
+    pub fn diff_keyring(&self, snapshot: &str) -> Result<KeyringDiff, GPGError> {
+        // snapshot: the --with-colons listing returned by keyring_snapshot
+
+        let keys: Vec<ListKeyResult> = self.list_keys(false, None, false)?;
+        return Ok(diff_keyrings(&parse_snapshot(snapshot), &keys));
+    }
+
+    // every key of the keyring with its uids, subkeys, signatures, ownertrust and whether the secret key was available,
+    // for a backup inventory or an external policy engine, serializable with the serde feature
+    pub fn dump_keyring(&self) -> Result<KeyringDump, GPGError> {
+        let keys: Vec<ListKeyResult> = self.list_keys(false, None, true)?;
+        let secret_fingerprints: Vec<String> = match self.list_keys(true, None, false) {
+            Ok(secret_keys) => secret_keys.into_iter().map(|key| key.fingerprint).collect(),
+            Err(e) => return Err(e),
+        };
+        let keys: Vec<DumpedKey> = keys
+            .into_iter()
+            .map(|key| DumpedKey {
+                has_secret: secret_fingerprints.contains(&key.fingerprint),
+                ownertrust: key.ownertrust.chars().next().and_then(TrustLevel::from_colon_char),
+                key: key,
+            })
+            .collect();
+        return Ok(KeyringDump {
+            homedir: self.homedir.clone(),
+            gpg_version: self.full_version.clone(),
+            created_at: Local::now().timestamp(),
+            keys: keys,
+        });
+    }
+
+    // archive the key material of the homedir ( keyring, secret keys, trustdb, revocation certificates and sshcontrol )
+    // as a tar, symmetrically encrypted with the passphrase by gpg, restore it with restore_snapshot
+    #[cfg(feature = "snapshot")]
+    pub fn snapshot(&self, output: PathBuf, passphrase: Passphrase) -> Result<CmdResult, GPGError> {
+        // output: path that the encrypted snapshot will be written to
+        // passphrase: the passphrase to encrypt the snapshot with, needed to restore it
+
+        let archive: Vec<u8> = {
+            // archived while no GPG on the homedir was running a mutating operation
+            let _lock: Option<File> = match self.homedir_lock_timeout {
+                Some(timeout) => match lock_homedir(&self.homedir, timeout) {
+                    Ok(lock) => Some(lock),
+                    Err(e) => return Err(e),
+                },
+                None => None,
+            };
+            let mut builder: tar::Builder<Vec<u8>> = tar::Builder::new(Vec::new());
+            builder.follow_symlinks(false);
+            for entry in SNAPSHOT_ENTRIES {
+                let path: PathBuf = self.homedir.join(entry);
+                let archived: std::io::Result<()> = if path.is_dir() {
+                    builder.append_dir_all(entry, &path)
+                } else if path.is_file() {
+                    builder.append_path_with_name(&path, entry)
+                } else {
+                    continue;
+                };
+                if let Err(e) = archived {
+                    return Err(GPGError::new(
+                        GPGErrorType::ReadFailError(format!("failed to archive {}: {}", path.display(), e)),
+                        None,
+                    ));
+                }
+            }
+            match builder.into_inner() {
+                Ok(archive) => archive,
+                Err(e) => {
+                    return Err(GPGError::new(
+                        GPGErrorType::WriteFailError(format!("failed to archive the homedir: {}", e)),
+                        None,
+                    ));
+                }
+            }
+        };
+
+        let result: CmdResult = self.encrypt(EncryptOption::with_symmetric(
+            InputSource::Bytes(archive),
+            None,
+            passphrase,
+            Some(output),
+        ))?;
+        if !result.is_success() {
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError("failed to encrypt the snapshot".to_string()),
+                Some(result),
+            ));
+        }
+        return Ok(result);
+    }
+
+    // restore the key material from a snapshot created by snapshot, an entry in the snapshot replace the one in the homedir
+    // while the one not in the snapshot was kept, return the path of the restored entries
+    #[cfg(feature = "snapshot")]
+    pub fn restore_snapshot(&self, input: PathBuf, passphrase: Passphrase) -> Result<Vec<PathBuf>, GPGError> {
+        // input: path to the encrypted snapshot
+        // passphrase: the passphrase the snapshot was encrypted with
+
+        if self.read_only {
+            return Err(GPGError::new(
+                GPGErrorType::ReadOnlyError("restoring a snapshot is not allowed as GPG was set to read only".to_string()),
+                None,
+            ));
+        }
+        let mut archive: Vec<u8> = Vec::new();
+        match self.decrypt_to_writer(
+            DecryptOption::with_symmetric(InputSource::Path(input), passphrase, None),
+            &mut archive,
+        ) {
+            Ok(result) => {
+                if !result.is_success() {
+                    return Err(GPGError::new(
+                        GPGErrorType::GPGProcessError("failed to decrypt the snapshot".to_string()),
+                        Some(result),
+                    ));
+                }
+            }
+            Err(e) => return Err(e),
+        }
+
+        let entries: Vec<&str> = snapshot_entries(&archive)?;
+
+        let _lock: Option<File> = match self.homedir_lock_timeout {
+            Some(timeout) => match lock_homedir(&self.homedir, timeout) {
+                Ok(lock) => Some(lock),
+                Err(e) => return Err(e),
+            },
+            None => None,
+        };
+        // unpacked in the homedir ( the same file system ) before anything was replaced, so the keyring was left as it was
+        // if the snapshot failed to unpack, then each entry was swapped in with a rename and put back if one failed
+        let staging: TempDir = create_temp_dir_in(&self.homedir, "restore")?;
+        let unpacked: PathBuf = staging.path().join("snapshot");
+        let replaced: PathBuf = staging.path().join("replaced");
+        let mut reader: tar::Archive<&[u8]> = tar::Archive::new(archive.as_slice());
+        reader.set_preserve_permissions(true);
+        if let Err(e) = reader.unpack(&unpacked) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to restore the snapshot: {}", e)),
+                None,
+            ));
+        }
+        if let Err(e) = std::fs::create_dir(&replaced) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to restore the snapshot: {}", e)),
+                None,
+            ));
+        }
+        let mut swapped: Vec<(PathBuf, Option<PathBuf>)> = Vec::new();
+        for entry in &entries {
+            let path: PathBuf = self.homedir.join(entry);
+            match swap_snapshot_entry(&unpacked.join(entry), &path, &replaced.join(entry)) {
+                Ok(aside) => swapped.push((path, aside)),
+                Err(e) => {
+                    rollback_snapshot_entries(&swapped);
+                    return Err(GPGError::new(
+                        GPGErrorType::WriteFailError(format!("failed to replace {}: {}", path.display(), e)),
+                        None,
+                    ));
+                }
+            }
+        }
+        // the replaced entries were removed with the staging directory
+        drop(staging);
+        let restored: Vec<PathBuf> = swapped.into_iter().map(|(path, _)| path).collect();
+        invalidate_key_cache(Some(&self.homedir));
+        return Ok(restored);
+    }
+
+    fn check_keys_usable(&self, keyids: Vec<String>) -> Result<(), GPGError> {
+        // keyid(s) that was not found will be left for gpg to report
+        for keyid in keyids {
+            let keys: Vec<ListKeyResult> = self.list_keys(false, Some(vec![keyid.clone()]), false)?;
+            for key in keys {
+                let mut state: Vec<&str> = Vec::new();
+                if key.is_revoked {
+                    state.push("revoked");
+                }
+                if key.is_expired {
+                    state.push("expired");
+                }
+                if key.is_disabled {
+                    state.push("disabled");
+                }
+                if !state.is_empty() {
+                    return Err(GPGError::new(
+                        GPGErrorType::KeyUnusableError(format!(
+                            "key {} was {}",
+                            keyid,
+                            state.join(" and ")
+                        )),
+                        None,
+                    ));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    //*******************************************************
 
     //                   DELETE KEY
 
     //*******************************************************
-    pub fn delete_keys(
-        &self,
-        mut fingerprints: Vec<String>,
-        is_secret: bool,
-        is_subkey: bool,
-        passphrase: Option<String>,
-    ) -> Result<CmdResult, GPGError> {
-        // fingerprints: list of fingerprints to delete
-        // is_secret: if true, delete secret keys only 
-        // is_subkey: if true, only delete subkeys
-        // passphrase: passphrase for passphrase protected secret keys
+    pub fn delete_keys(
+        &self,
+        mut fingerprints: Vec<String>,
+        is_secret: bool,
+        is_subkey: bool,
+        passphrase: Option<Passphrase>,
+    ) -> Result<CmdResult, GPGError> {
+        // fingerprints: list of fingerprints to delete
+        // is_secret: if true, delete secret keys only 
+        // is_subkey: if true, only delete subkeys
+        // passphrase: passphrase for passphrase protected secret keys
+
+        // NOTE: delete both public and secret key by default
+        // NOTE: If the fingerprint is for subkeys, but is_subkey was not set to true, it will remove the parent key also
+
+        let mut mode:String = "secret-and-public-key".to_string();
+        if is_secret {
+            mode = "secret-keys".to_string();
+        }
+        let mut args: Vec<String> = vec![
+            "--yes".to_string(),
+            format!("--delete-{}", mode),
+        ];
+
+        if is_subkey{
+            let mut subkey_fingerprints: Vec<String> = Vec::new();
+            for f_p in fingerprints{
+                subkey_fingerprints.push(format!("{}!", f_p));
+            }
+            args.append(&mut subkey_fingerprints);
+        } else {
+            args.append(&mut fingerprints);
+        }
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result:Result<CmdResult, GPGError> = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::DeleteKey,
+        );
+
+        return result;
+    }
+
+    //*******************************************************
+
+    //                   ADD SUBKEY
+
+    //*******************************************************
+    pub fn add_subkey(
+        &self,
+        fingerprint: String,
+        passphrase: Option<Passphrase>,
+        algo: String,
+        usage: String,
+        expire: String // ISO format YYYY-MM-DD or "-" for no expiration
+    ) -> Result<CmdResult, GPGError> {
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+
+        match self.require_version(2.1, "add_subkey") {
+            Ok(_) => {}
+            Err(e) => return Err(e),
+        }
+        match self.check_pubkey_algo(&algo) {
+            Ok(_) => {}
+            Err(e) => return Err(e),
+        }
+
+        let args:Vec<String> =vec!["--quick-add-key".to_string(), fingerprint, algo, usage, expire]; 
+
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::AddSubKey,
+        );
+
+        return result;
+
+    }
+
+    //*******************************************************
+
+    //                   REVOKE KEY
+
+    //*******************************************************
+    pub fn revoke_key(
+        &self,
+        keyid: String,
+        passphrase: Option<Passphrase>,
+        reason_code:u8,
+        revoke_desc: Option<String>,
+        is_subkey: bool,
+    ) -> Result<CmdResult, GPGError> {
+        let mut args:Vec<String> = vec![];
+        let mut desc:String = "".to_string();
+
+        if let Some(revoke_desc) = revoke_desc {
+            desc = revoke_desc;
+        }
+
+        if !(0..=3).contains(&reason_code){
+            // 0 = No reason specified
+            // 1 = Key has been compromised
+            // 2 = Key is superseded
+            // 3 = Key is no longer used
+            return Err(GPGError::new(
+                GPGErrorType::InvalidReasonCode("Please choose between 0~3 as a reason code for revoking a key".to_string()),
+                None,
+            ));
+        }
+
+        let mut byte_input:Vec<u8> = format!("revkey\ny\n{}\n{}\ny\nsave\n", reason_code, desc).as_bytes().to_vec();
+
+        if is_subkey {
+            let sequence: Result<u8, GPGError> = self.get_subkey_position(keyid.clone());
+            match sequence {
+                Ok(sequence) => {
+                    let selected_key = format!("key {}", sequence);
+                    byte_input = format!("{}\nrevkey\ny\n{}\n{}\ny\nsave\n", selected_key, reason_code, desc).as_bytes().to_vec();
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        args.append(&mut vec!["--command-fd".to_string(), "0".to_string(), "--edit-key".to_string(), keyid]);
+
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Bytes(byte_input)),
+            Operation::RevokeKey,
+        );
+
+        return result;
+    }
+
+    fn get_subkey_position(
+        &self,
+        keyid: String,
+    ) -> Result<u8, GPGError> {
+        let key_list: Result<Vec<ListKeyResult>, GPGError> = self.list_keys(false, Some(vec![keyid.clone()]), false);
+        match key_list {
+            Ok(key_list) => {
+                if !key_list[0].subkeys.is_empty() {
+                    let position: u8 = key_list[0].subkeys.iter().position(|x| x.keyid == keyid).unwrap() as u8;
+                    return Ok(position+1);
+                }
+                return Err(GPGError::new(
+                    GPGErrorType::KeyNotSubkey("keyid provided is not a subkey".to_string()),
+                    None,
+                ));
+            },
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+
+    //*******************************************************
+
+    //                     EDIT KEY
+
+    //*******************************************************
+    pub fn edit_key(
+        &self,
+        fingerprint: String,
+        passphrase: Option<Passphrase>,
+        script: Vec<EditKeyCommand>,
+        extra_args: Option<Vec<String>>,
+    ) -> Result<CmdResult, GPGError> {
+        // fingerprint: fingerprint of the key to edit
+        // passphrase: passphrase of the key if it was passphrase protected
+        // script: list of command(s) and answer(s) to be fed to gpg in sequence
+        // extra_args: extra arguments to pass to gpg ( ex. --default-key for signing related command )
+
+        //*****************************************************************************************
+        //    NOTE: Each item in the script was only fed to gpg when gpg ask for the matching
+        //          kind of input ( command at the edit-key prompt, a line answer, or a y/N ).
+        //          If gpg ask for something else, the editing will be stopped without saving
+        //          and an UnexpectedPromptError will be returned.
+        //          If the script ends at the edit-key prompt, gpg will quit without saving.
+        //******************************************************************************************
+
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+        for command in script.iter() {
+            if !is_passphrase_valid(&command.value()) {
+                return Err(GPGError::new(
+                    GPGErrorType::InvalidArgumentError(format!(
+                        "edit key command invalid: {:?}",
+                        command
+                    )),
+                    None,
+                ));
+            }
+        }
+
+        let mut args: Vec<String> = vec![];
+        if let Some(extra_args) = extra_args {
+            match self.check_extra_args(extra_args) {
+                Ok(mut extra_args) => args.append(&mut extra_args),
+                Err(e) => return Err(e),
+            }
+        }
+        args.append(&mut vec!["--edit-key".to_string(), fingerprint]);
+
+        let mut script = script.into_iter();
+        let mut unexpected_prompt: Option<String> = None;
+        let result: Result<CmdResult, GPGError> = {
+            let mut responder = |prompt_type: &str, keyword: &str| -> Option<String> {
+                match script.next() {
+                    Some(command) => {
+                        if command.is_expected(prompt_type, keyword) {
+                            return Some(command.value());
+                        }
+                        unexpected_prompt = Some(format!(
+                            "gpg asked for {} {} but the next item in script is {:?}",
+                            prompt_type, keyword, command
+                        ));
+                        return None;
+                    }
+                    None => {
+                        if keyword != "keyedit.prompt" {
+                            unexpected_prompt = Some(format!(
+                                "gpg asked for {} {} but the script has ended",
+                                prompt_type, keyword
+                            ));
+                        }
+                        return None;
+                    }
+                }
+            };
+            let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+            handle_cmd_interactive(
+                self.runner().as_ref(),
+                Some(args),
+                passphrase,
+                self.version,
+                self.homedir_arg(),
+                cmd_options,
+                self.env.clone(),
+                &mut responder,
+                Operation::EditKey,
+            )
+        };
+
+        if let Some(unexpected_prompt) = unexpected_prompt {
+            let cmd_result: Option<CmdResult> = match result {
+                Ok(result) => Some(result),
+                Err(e) => e.cmd_result,
+            };
+            return Err(GPGError::new(
+                GPGErrorType::UnexpectedPromptError(unexpected_prompt),
+                cmd_result,
+            ));
+        }
+        return result;
+    }
+
+    //*******************************************************
+
+    //                  INTERACTIVE COMMAND
+
+    //*******************************************************
+    pub fn interact(
+        &self,
+        args: Vec<String>,
+        passphrase: Option<Passphrase>,
+        responder: &mut dyn FnMut(&GpgPrompt) -> PromptAnswer,
+    ) -> Result<CmdResult, GPGError> {
+        // args: the gpg command to run ( ex. ["--edit-key", "<FINGERPRINT>"] ), checked like extra_args
+        // passphrase: passphrase of the key, None to have every passphrase ( or PIN ) gpg asks for passed to
+        //             the responder as a Hidden passphrase.enter prompt
+        // responder: receive every question gpg asked through --command-fd and return the answer,
+        //            PromptAnswer::Cancel will stop the operation
+
+        //*****************************************************************************************
+        //    NOTE: For operation that still ask questions in batch mode ( ex. card operation or
+        //          edit-key commands not covered by the other functions ). The operation was
+        //          treated as one that change the homedir.
+        //          An answer that is not a single line will stop the operation with an
+        //          InvalidArgumentError, as it would be read by gpg as more than one answer.
+        //******************************************************************************************
+
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+        let args: Vec<String> = self.check_extra_args(args)?;
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+
+        let mut invalid_answer: Option<String> = None;
+        let result: Result<CmdResult, GPGError> = {
+            let mut string_responder = |prompt_type: &str, keyword: &str| -> Option<String> {
+                let prompt: GpgPrompt = GpgPrompt::from_status(&StatusLine::from_parts(prompt_type, keyword))?;
+                let answer: String = match responder(&prompt) {
+                    PromptAnswer::Yes => "y".to_string(),
+                    PromptAnswer::No => "n".to_string(),
+                    PromptAnswer::Line(line) => line,
+                    PromptAnswer::Hidden(hidden) => hidden.expose().to_string(),
+                    PromptAnswer::Cancel => return None,
+                };
+                if !is_passphrase_valid(&answer) {
+                    invalid_answer = Some(format!("answer to {} must be a single line", prompt.keyword));
+                    return None;
+                }
+                return Some(answer);
+            };
+            match passphrase {
+                Some(passphrase) => handle_cmd_interactive(
+                    self.runner().as_ref(),
+                    Some(args),
+                    Some(passphrase),
+                    self.version,
+                    self.homedir_arg(),
+                    cmd_options,
+                    self.env.clone(),
+                    &mut string_responder,
+                    Operation::Interactive,
+                ),
+                None => handle_card_cmd_interactive(
+                    self.runner().as_ref(),
+                    Some(args),
+                    self.version,
+                    self.homedir_arg(),
+                    cmd_options,
+                    self.env.clone(),
+                    &mut string_responder,
+                    Operation::Interactive,
+                ),
+            }
+        };
+        if let Some(invalid_answer) = invalid_answer {
+            let cmd_result: Option<CmdResult> = match result {
+                Ok(result) => Some(result),
+                Err(e) => e.cmd_result,
+            };
+            return Err(GPGError::new(GPGErrorType::InvalidArgumentError(invalid_answer), cmd_result));
+        }
+        return result;
+    }
+
+    //*******************************************************
+
+    //                    KEY TO CARD
+
+    //*******************************************************
+    pub fn key_to_card(
+        &self,
+        fingerprint: String,
+        subkey_index: Option<u32>,
+        slot: CardSlot,
+        passphrase: Option<Passphrase>,
+        admin_pin: Passphrase,
+        replace: bool,
+    ) -> Result<CmdResult, GPGError> {
+        // fingerprint: fingerprint of the key
+        // subkey_index: index of the subkey to move ( starting from 1 as listed in edit-key ), None to move the primary key
+        // slot: the slot on the card to store the key, check CardSlot
+        // passphrase: passphrase of the key if it was passphrase protected
+        // admin_pin: admin PIN of the card
+        // replace: whether to replace the key that was already in the slot
+
+        //*****************************************************************************************
+        //    NOTE: The secret key will be replaced by a stub pointing to the card once saved,
+        //          backup the secret key before moving it if a copy is needed.
+        //******************************************************************************************
+
+        let mut pins: Vec<Passphrase> = vec![];
+        if let Some(passphrase) = passphrase {
+            pins.push(passphrase);
+        }
+        pins.push(admin_pin);
+        for pin in pins.iter() {
+            if !is_passphrase_valid(pin.expose()) {
+                return Err(GPGError::new(
+                    GPGErrorType::PassphraseError("passphrase or PIN invalid".to_string()),
+                    None,
+                ));
+            }
+        }
+
+        let mut commands: Vec<String> = vec![];
+        if let Some(subkey_index) = subkey_index {
+            commands.push(format!("key {}", subkey_index));
+        }
+        commands.push("keytocard".to_string());
+        let mut commands = commands.into_iter();
+        let mut pins = pins.into_iter();
+        let mut slot_selected: bool = false;
+        let mut unexpected_prompt: Option<String> = None;
+        let result: Result<CmdResult, GPGError> = {
+            let mut responder = |prompt_type: &str, keyword: &str| -> Option<String> {
+                match (prompt_type, keyword) {
+                    ("GET_LINE", "keyedit.prompt") => {
+                        let command: Option<String> = commands.next();
+                        if command.is_some() {
+                            return command;
+                        }
+                        if slot_selected {
+                            return Some("save".to_string());
+                        }
+                        // gpg went back to the prompt without asking for the slot ( ex. no card available )
+                        return None;
+                    }
+                    ("GET_BOOL", "keyedit.keytocard.use_primary") => {
+                        return Some("y".to_string());
+                    }
+                    ("GET_LINE", "cardedit.genkeys.storekeytype") => {
+                        slot_selected = true;
+                        return Some(slot.value());
+                    }
+                    ("GET_BOOL", "cardedit.genkeys.replace_key") => {
+                        if !replace {
+                            slot_selected = false;
+                            unexpected_prompt = Some("slot on the card already has a key".to_string());
+                            return None;
+                        }
+                        return Some("y".to_string());
+                    }
+                    ("GET_HIDDEN", "passphrase.enter") => {
+                        let pin: Option<Passphrase> = pins.next();
+                        if pin.is_none() {
+                            unexpected_prompt = Some("gpg asked for more passphrase or PIN than provided".to_string());
+                            return None;
+                        }
+                        return Some(pin.unwrap().expose().to_string());
+                    }
+                    ("GET_BOOL", "keyedit.save.okay") => {
+                        return Some("y".to_string());
+                    }
+                    _ => {
+                        unexpected_prompt = Some(format!("gpg asked for {} {}", prompt_type, keyword));
+                        return None;
+                    }
+                }
+            };
+            let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+            handle_card_cmd_interactive(
+                self.runner().as_ref(),
+                Some(vec!["--edit-key".to_string(), fingerprint]),
+                self.version,
+                self.homedir_arg(),
+                cmd_options,
+                self.env.clone(),
+                &mut responder,
+                Operation::KeyToCard,
+            )
+        };
+        if !slot_selected && unexpected_prompt.is_none() {
+            unexpected_prompt = Some("gpg did not ask for the slot to store the key".to_string());
+        }
+        return handle_card_result(result, unexpected_prompt);
+    }
+
+    //*******************************************************
+
+    //                 CARD GENERATE KEY
+
+    //*******************************************************
+    pub fn card_generate_key(&self, card_option: CardGenerateOption) -> Result<CmdResult, GPGError> {
+        // card_option: option for generating keys on the card, check CardGenerateOption
+
+        //*****************************************************************************************
+        //    NOTE: The keys ( signature, encryption and authentication ) will be generated on the
+        //          card and could not be exported, the public key and the stubs of the secret key
+        //          will be added to the homedir.
+        //******************************************************************************************
+
+        for pin in [&card_option.admin_pin, &card_option.user_pin] {
+            if !is_passphrase_valid(pin.expose()) {
+                return Err(GPGError::new(
+                    GPGErrorType::PassphraseError("PIN invalid".to_string()),
+                    None,
+                ));
+            }
+        }
+        let mut answers: Vec<String> = vec![
+            card_option.name.clone(),
+            card_option.email.clone(),
+            card_option.expire.clone(),
+        ];
+        if card_option.comment.is_some() {
+            answers.push(card_option.comment.clone().unwrap());
+        }
+        for answer in answers.iter() {
+            if !is_passphrase_valid(answer) {
+                return Err(GPGError::new(
+                    GPGErrorType::InvalidArgumentError(format!(
+                        "card generate option invalid: {}",
+                        answer
+                    )),
+                    None,
+                ));
+            }
+        }
+
+        // gpg ask for the PINs one after another with the same prompt, the order depends on
+        // whether the signature PIN was forced: gpg need the admin PIN to switch it off first,
+        // otherwise the user PIN was checked first and the admin PIN was asked when generating
+        let pin_forced: bool = match self.is_card_pin_forced() {
+            Ok(pin_forced) => pin_forced,
+            Err(e) => return handle_card_result(Err(e), None),
+        };
+        let mut pins: Vec<Passphrase> = vec![card_option.user_pin.clone(), card_option.admin_pin.clone()];
+        if pin_forced {
+            pins.reverse();
+        }
+
+        let mut commands = vec!["admin".to_string(), "generate".to_string()].into_iter();
+        let mut pins = pins.into_iter();
+        let mut generate_started: bool = false;
+        let mut unexpected_prompt: Option<String> = None;
+        let result: Result<CmdResult, GPGError> = {
+            let mut responder = |prompt_type: &str, keyword: &str| -> Option<String> {
+                match (prompt_type, keyword) {
+                    ("GET_LINE", "cardedit.prompt") => {
+                        let command: Option<String> = commands.next();
+                        if command.is_some() {
+                            return command;
+                        }
+                        return Some("quit".to_string());
+                    }
+                    ("GET_BOOL", "cardedit.genkeys.backup_enc") => {
+                        generate_started = true;
+                        return Some("n".to_string());
+                    }
+                    ("GET_BOOL", "cardedit.genkeys.replace_keys") => {
+                        if !card_option.replace {
+                            unexpected_prompt = Some("the card already has keys".to_string());
+                            return None;
+                        }
+                        return Some("y".to_string());
+                    }
+                    ("GET_HIDDEN", "passphrase.enter") => {
+                        // the user PIN was asked again for the self signatures if the card does not cache it
+                        return Some(pins.next().unwrap_or(card_option.user_pin.clone()).expose().to_string());
+                    }
+                    ("GET_LINE", "keygen.valid") => {
+                        return Some(card_option.expire.clone());
+                    }
+                    ("GET_BOOL", "keygen.valid.okay") => {
+                        return Some("y".to_string());
+                    }
+                    ("GET_LINE", "keygen.name") => {
+                        return Some(card_option.name.clone());
+                    }
+                    ("GET_LINE", "keygen.email") => {
+                        return Some(card_option.email.clone());
+                    }
+                    ("GET_LINE", "keygen.comment") => {
+                        return Some(card_option.comment.clone().unwrap_or("".to_string()));
+                    }
+                    ("GET_LINE", "keygen.userid.cmd") => {
+                        return Some("O".to_string());
+                    }
+                    _ => {
+                        unexpected_prompt = Some(format!("gpg asked for {} {}", prompt_type, keyword));
+                        return None;
+                    }
+                }
+            };
+            let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+            handle_card_cmd_interactive(
+                self.runner().as_ref(),
+                Some(vec!["--card-edit".to_string()]),
+                self.version,
+                self.homedir_arg(),
+                cmd_options,
+                self.env.clone(),
+                &mut responder,
+                Operation::CardGenerateKey,
+            )
+        };
+        if unexpected_prompt.is_none() {
+            let key_created: bool = match &result {
+                Ok(result) => result.raw_data.as_ref().is_some_and(|r| r.contains("[GNUPG:] KEY_CREATED")),
+                Err(_) => true, // let the error from gpg to be returned
+            };
+            if !generate_started || !key_created {
+                unexpected_prompt = Some("gpg did not generate the keys on the card".to_string());
+            }
+        }
+        return handle_card_result(result, unexpected_prompt);
+    }
+
+    // check if the card require the signature PIN for every signature ( forcesig )
+    fn is_card_pin_forced(&self) -> Result<bool, GPGError> {
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(vec!["--card-status".to_string()]),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::CardGenerateKey,
+        );
+        match result {
+            Ok(result) => {
+                let raw_data: String = result.raw_data.unwrap_or("".to_string());
+                return Ok(raw_data.lines().any(|l| l.starts_with("forcepin:1:")));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    //*******************************************************
+
+    //                  SET PRIMARY UID
+
+    //*******************************************************
+    pub fn set_primary_uid(
+        &self,
+        fingerprint: String,
+        uid: String,
+        passphrase: Option<Passphrase>,
+    ) -> Result<CmdResult, GPGError> {
+        // fingerprint: fingerprint of the key
+        // uid: the user id to be set as primary ex) "Name <email@example.com>"
+        // passphrase: passphrase of the key if it was passphrase protected
+
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+
+        let uid_hash: Result<String, GPGError> = self.get_uid_hash(fingerprint.clone(), uid);
+        let uid_hash: String = match uid_hash {
+            Ok(uid_hash) => uid_hash,
+            Err(e) => {
+                return Err(e);
+            }
+        };
+
+        // select the uid by its hash as the index of uid in edit-key
+        // was not guaranteed to be the same as the order in the listing
+        let byte_input: Vec<u8> = format!("uid {}\nprimary\nsave\n", uid_hash)
+            .as_bytes()
+            .to_vec();
+
+        let args: Vec<String> = vec![
+            "--command-fd".to_string(),
+            "0".to_string(),
+            "--edit-key".to_string(),
+            fingerprint,
+        ];
+
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Bytes(byte_input)),
+            Operation::SetPrimaryUid,
+        );
+
+        return result;
+    }
+
+    //*******************************************************
+
+    //                SET KEY PREFERENCES
+
+    //*******************************************************
+    pub fn set_key_preferences(
+        &self,
+        fingerprint: String,
+        preferences: KeyPreferences,
+        passphrase: Option<Passphrase>,
+    ) -> Result<CmdResult, GPGError> {
+        // fingerprint: fingerprint of the key
+        // preferences: the algorithms to be advertised by every uid of the key, check KeyPreferences
+        // passphrase: passphrase of the key if it was passphrase protected
+
+        // gpg confirm before updating the preferences of every uid
+        let script: Vec<EditKeyCommand> = vec![
+            EditKeyCommand::Command(format!("setpref {}", preferences)),
+            EditKeyCommand::Yes,
+            EditKeyCommand::Save,
+        ];
+        return self.edit_key(fingerprint, passphrase, script, None);
+    }
+
+    fn get_uid_hash(
+        &self,
+        keyid: String,
+        uid: String,
+    ) -> Result<String, GPGError> {
+        let key_list: Result<Vec<ListKeyResult>, GPGError> = self.list_keys(false, Some(vec![keyid]), false);
+        match key_list {
+            Ok(key_list) => {
+                if !key_list.is_empty() {
+                    let position: Option<usize> = key_list[0].uids.iter().position(|x| *x == uid);
+                    if let Some(position) = position {
+                        let uid_hash: String = key_list[0].uid_hashes[position].clone();
+                        if !uid_hash.is_empty() {
+                            return Ok(uid_hash);
+                        }
+                    }
+                }
+                return Err(GPGError::new(
+                    GPGErrorType::UidNotFound(format!("uid {} not found in key", uid)),
+                    None,
+                ));
+            },
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+
+    //*******************************************************
+
+    //                    PHOTO ID
+
+    //*******************************************************
+    pub fn add_photo(
+        &self,
+        keyid: String,
+        passphrase: Option<Passphrase>,
+        photo_path: impl AsRef<Path>,
+    ) -> Result<CmdResult, GPGError> {
+        // keyid: keyid of the key that the photo id will be attached to
+        // passphrase: passphrase of the key if it was passphrase protected
+        // photo_path: path to a JPEG image to be used as photo id
+
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+
+        let photo_path: &Path = photo_path.as_ref();
+        let photo_size: u64 = match std::fs::metadata(photo_path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => {
+                return Err(GPGError::new(
+                    GPGErrorType::FileNotFoundError("File do not exist".to_string()),
+                    None,
+                ));
+            }
+        };
+        // the path was answered to gpg through stdin, so it has to be a single line of UTF-8
+        let photo_path: String = path_to_arg(photo_path)?;
+        if photo_path.contains("\n") || photo_path.contains("\r") {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("photo path invalid".to_string()),
+                None,
+            ));
+        }
+
+        // gpg will ask for confirmation if the JPEG was larger than 6144 bytes
+        let mut byte_input: String = format!("addphoto\n{}\n", photo_path);
+        if photo_size > PHOTO_SIZE_WARNING {
+            byte_input.push_str("y\n");
+        }
+        byte_input.push_str("save\n");
+
+        let args: Vec<String> = vec![
+            "--command-fd".to_string(),
+            "0".to_string(),
+            "--edit-key".to_string(),
+            keyid,
+        ];
+
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Bytes(byte_input.as_bytes().to_vec())),
+            Operation::AddPhoto,
+        );
+
+        return result;
+    }
+
+    pub fn get_photos(&self, keyid: String) -> Result<Vec<KeyAttribute>, GPGError> {
+        // keyid: keyid of the key to retrieve the photo id(s) from
+
+        // gpg write the attribute data to a separate file while the listing goes to stdout
+        // the directory holding it will be removed once dropped
+        let attribute_dir: TempDir = create_temp_dir("attribute")?;
+        let attribute_file: PathBuf = attribute_dir.path().join("attribute");
+
+        let attribute_arg: String = path_to_arg(&attribute_file)?;
+        let args: Vec<String> = vec![
+            "--attribute-file".to_string(),
+            attribute_arg,
+            "--list-keys".to_string(),
+            keyid,
+        ];
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::ListKey,
+        );
+
+        let attribute_data: Vec<u8> = std::fs::read(&attribute_file).unwrap_or_default();
+        drop(attribute_dir);
+
+        match result {
+            Ok(result) => {
+                return Ok(decode_attribute_result(&result, &attribute_data));
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+
+    //*******************************************************
+
+    //                   IMPORT KEY
+
+    //*******************************************************
+    pub fn import_key(
+        &self,
+        input: InputSource,
+        merge_only: bool,
+        extra_args: Option<Vec<String>>,
+        import_option: Option<ImportOption>,
+    ) -> Result<CmdResult, GPGError> {
+        // input: the key(s) to import, check InputSource
+        // merge_only: if true, only merge new signatures, user-IDs, subkeys etc to existing keys
+        // extra_args: additional args provided for importing keys
+        // import_option: import options and filters to apply, if not provided, keys will be imported as it is
+
+        let mut args: Vec<String> = Vec::new();
+        if let Some(import_option) = import_option {
+            match self.gen_import_option_args(import_option) {
+                Ok(mut import_args) => args.append(&mut import_args),
+                Err(e) => return Err(e),
+            }
+        }
+        if let Some(extra_args) = extra_args {
+            match self.check_extra_args(extra_args) {
+                Ok(mut extra_args) => args.append(&mut extra_args),
+                Err(e) => return Err(e),
+            }
+        }
+
+        match input.read_all() {
+            Ok(buffer) => {
+                let result: Result<CmdResult, GPGError> = self.import_key_file_buffer(
+                    buffer, 
+                    merge_only, 
+                    Some(args));
+                match result {
+                    Ok(result) => {
+                        return Ok(result);
+                    }
+                    Err(e) => {
+                        return Err(e);
+                    }
+                }
+            }
+            Err(e)=>{
+                return Err(e);
+            }
+        }
+    }
+
+    pub fn import_key_dry_run(
+        &self,
+        input: InputSource,
+        import_option: Option<ImportOption>,
+    ) -> Result<Vec<ImportDryRunResult>, GPGError> {
+        // input: the key(s) to import, check InputSource
+        // import_option: import options and filters to apply, if not provided, keys will be imported as it is
+
+        // nothing will be written to the keyring, the key(s) will only be listed and compared
+        // with the keyring to show what will be added if the import was done
+        let mut import_option: ImportOption = import_option.unwrap_or_default();
+        import_option.import_show = true;
+        let mut args: Vec<String> = vec!["--dry-run".to_string()];
+        match self.gen_import_option_args(import_option) {
+            Ok(mut import_args) => args.append(&mut import_args),
+            Err(e) => return Err(e),
+        }
+
+        let buffer: Vec<u8> = input.read_all()?;
+        let result: CmdResult = self.import_key_file_buffer(buffer, false, Some(args))?;
+        let keys: Vec<ListKeyResult> = decode_list_key_result(result)
+            .into_iter()
+            .filter(|k| !k.fingerprint.is_empty())
+            .collect();
+
+        let existing_public_keys: Vec<ListKeyResult> = self.list_keys(false, None, false)?;
+        let mut existing_secret_keys: Vec<ListKeyResult> = Vec::new();
+        if keys.iter().any(|k| k.r#type == "sec") {
+            existing_secret_keys = self.list_keys(true, None, false)?;
+        }
+
+        let mut dry_run_result: Vec<ImportDryRunResult> = Vec::new();
+        for key in keys {
+            let existing_keys: &Vec<ListKeyResult> = if key.r#type == "sec" {
+                &existing_secret_keys
+            } else {
+                &existing_public_keys
+            };
+            let existing_key: Option<&ListKeyResult> =
+                existing_keys.iter().find(|k| k.fingerprint == key.fingerprint);
+            dry_run_result.push(ImportDryRunResult::new(key, existing_key));
+        }
+        return Ok(dry_run_result);
+    }
+
+    // import the keys of a gpg 1.x homedir ( pubring.gpg and secring.gpg ) into the keybox of the homedir of GPG
+    pub fn migrate_legacy_keyrings(
+        &self,
+        old_homedir: PathBuf,
+        dry_run: bool,
+    ) -> Result<LegacyMigrationResult, GPGError> {
+        // old_homedir: the homedir gpg 1.x was used with, it will only be read
+        // dry_run: if true, only report what will be changed in the keyring without importing
+
+        //*****************************************************************************************
+        //    NOTE: The legacy keyrings were imported as they were, a protected secret key keep its
+        //          passphrase. The trustdb.gpg of the old homedir was not migrated, set the trust
+        //          of the imported key(s) with trust_key.
+        //******************************************************************************************
+
+        self.require_version(2.1, "migrating legacy keyrings")?;
+        if !check_is_dir(&old_homedir) {
+            return Err(GPGError::new(
+                GPGErrorType::HomedirError(format!("{} is not a directory", old_homedir.display())),
+                None,
+            ));
+        }
+        // gpg keep using pubring.gpg of a homedir over the keybox, so it would be imported into itself
+        let is_same_homedir: bool = match (old_homedir.canonicalize(), self.homedir.canonicalize()) {
+            (Ok(old), Ok(homedir)) => old == homedir,
+            _ => false,
+        };
+        if is_same_homedir {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("old_homedir should not be the homedir of GPG".to_string()),
+                None,
+            ));
+        }
+        let keyrings: Vec<PathBuf> = ["pubring.gpg", "secring.gpg"]
+            .iter()
+            .map(|name| old_homedir.join(name))
+            .filter(|keyring| keyring.is_file())
+            .collect();
+        if keyrings.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::FileNotFoundError(format!(
+                    "no pubring.gpg or secring.gpg was found in {}",
+                    old_homedir.display()
+                )),
+                None,
+            ));
+        }
+
+        let mut migration: LegacyMigrationResult = LegacyMigrationResult {
+            keyrings: keyrings.clone(),
+            keys: Vec::new(),
+            imported: Vec::new(),
+            dry_run: dry_run,
+        };
+        for keyring in &keyrings {
+            match self.import_key_dry_run(InputSource::Path(keyring.clone()), None) {
+                Ok(mut keys) => migration.keys.append(&mut keys),
+                Err(e) => return Err(e),
+            }
+        }
+        if dry_run {
+            return Ok(migration);
+        }
+        for keyring in keyrings {
+            let result: CmdResult = self.import_key(InputSource::Path(keyring.clone()), false, None, None)?;
+            if !result.is_success() {
+                return Err(GPGError::new(
+                    GPGErrorType::GPGProcessError(format!("failed to import {}", keyring.display())),
+                    Some(result),
+                ));
+            }
+            for (fingerprint, _) in get_imported_keys(&result) {
+                if !migration.imported.contains(&fingerprint) {
+                    migration.imported.push(fingerprint);
+                }
+            }
+        }
+        return Ok(migration);
+    }
+
+    // import the public key(s) of another homedir ( ex. of another tenant ) into the homedir of GPG,
+    // the output of the exporting gpg was piped into the importing gpg, so the key(s) were never written to disk
+    // a GPGProcessError will be returned if any of the key(s) was not imported
+    // NOTE: the export always spawn a local gpg process ( the binary run by the runner, recorded to the audit sink and
+    //       metrics recorder ), only the import go through the runner, so it fail for a custom runner
+    pub fn import_from_homedir(&self, other_homedir: PathBuf, fingerprints: Vec<String>) -> Result<CmdResult, GPGError> {
+        // other_homedir: the homedir to export the key(s) from, it will only be read
+        // fingerprints: fingerprint(s) ( or keyid(s) ) of the key(s) to import, at least one should be provided
+
+        if fingerprints.is_empty() || fingerprints.iter().any(|fingerprint| fingerprint.trim().is_empty()) {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("fingerprints of the key(s) to import should be provided".to_string()),
+                None,
+            ));
+        }
+        if !check_is_dir(&other_homedir) {
+            return Err(GPGError::new(
+                GPGErrorType::HomedirError(format!("{} is not a directory", other_homedir.display())),
+                None,
+            ));
+        }
+        let other_homedir_arg: String = path_to_arg(&other_homedir)?;
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let mut export_args: Vec<String> = vec!["--export".to_string()];
+        export_args.append(&mut fingerprints.clone());
+        let gpg_program: PathBuf = self.local_gpg_program(&Operation::ExportPublicKey)?;
+        let recorder: LocalProcessRecorder = self.local_recorder();
+        let export: Result<CmdStream, GPGError> = handle_cmd_stream(
+            &gpg_program,
+            Some(export_args),
+            None,
+            self.version,
+            other_homedir_arg,
+            cmd_options.clone(),
+            self.env.clone(),
+            Operation::ExportPublicKey,
+        );
+        let mut export: CmdStream = match export {
+            Ok(export) => export,
+            Err(e) => return Err(recorder.record_error(Operation::ExportPublicKey, e)),
+        };
+        // the export read nothing from stdin
+        drop(export.process.stdin.take());
+        let exported: ChildStdout = export.process.stdout.take().unwrap();
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(vec!["--import".to_string()]),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Reader(Box::new(exported))),
+            Operation::ImportKey,
+        );
+
+        // the import finished reading the export, or failed and dropped it which end the export
+        // recorded as the export of the runner, an error means it could not be audited
+        let exported: CmdResult = recorder.record(Operation::ExportPublicKey, Ok(export.wait()))?;
+        let result: CmdResult = result?;
+        if exported.exit_status() != GpgExitStatus::Success {
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError(format!(
+                    "failed to export the key(s) from {} ( exit status {} )",
+                    other_homedir.display(),
+                    exported.return_code.unwrap_or(-1)
+                )),
+                Some(result),
+            ));
+        }
+        // gpg export nothing for a key not found without failing
+        let imported: Vec<String> = get_imported_keys(&result).into_iter().map(|(fingerprint, _)| fingerprint).collect();
+        let missing: Vec<String> = fingerprints
+            .into_iter()
+            .filter(|fingerprint| !imported.iter().any(|imported| imported.ends_with(&fingerprint.to_uppercase())))
+            .collect();
+        if !missing.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError(format!(
+                    "{} was not imported from {}", missing.join(", "), other_homedir.display()
+                )),
+                Some(result),
+            ));
+        }
+        return Ok(result);
+    }
+
+    fn gen_import_option_args(&self, import_option: ImportOption) -> Result<Vec<String>, GPGError> {
+        let legacy_supported: bool = !import_option.import_show
+            && !import_option.keep_ownertrust
+            && !import_option.restore
+            && import_option.keep_uid.is_none()
+            && import_option.drop_sig.is_none();
+        if !legacy_supported {
+            // the import options and filters were only introduced in gpg 2.1
+            self.require_version(2.1, "import options and filters")?;
+        }
+        if import_option.only_pubkeys && self.version < 2.3 {
+            // only-pubkeys was only introduced in gpg 2.3
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!(
+                    "only_pubkeys is not supported by gpg {}",
+                    self.full_version
+                )),
+                None,
+            ));
+        }
+        return Ok(import_option.gen_import_args());
+    }
+
+    fn import_key_file_buffer(
+        &self,
+        key_buffer: Vec<u8>,
+        merge_only: bool,
+        extra_args: Option<Vec<String>>,
+    ) -> Result<CmdResult, GPGError> {
+        let mut args: Vec<String> = vec!["--import".to_string()];
+        if merge_only {
+            args.append(&mut vec![
+                "--import-options".to_string(),
+                "merge-only".to_string(),
+            ]);
+        };
+        if let Some(extra_args) = extra_args {
+            match self.check_extra_args(extra_args) {
+                Ok(mut extra_args) => args.append(&mut extra_args),
+                Err(e) => return Err(e),
+            }
+        };
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Bytes(key_buffer)),
+            Operation::ImportKey,
+        );
+        return result;
+    }
+
+    //*******************************************************
+
+    //                   EXPORT KEY
+
+    //*******************************************************
+    pub fn export_public_key(
+        &self,
+        key_id: Option<Vec<String>>,
+        output: Option<PathBuf>,
+        export_option: Option<ExportOption>,
+    ) -> Result<CmdResult, GPGError> {
+        // key_id: list of keyid(s) to export, if not provided, all public keys will be exported
+        // output: path that the exported key file will be saved to
+        // export_option: export-minimal and export filters to apply, if not provided, keys will be exported as it is
+
+        let mut args: Vec<String> = vec!["--export".to_string()];
+        let output: PathBuf = match output {
+            Some(output) => output,
+            None => {
+                // if output folder not specified, system will create a exported_public_key folder in the set output dir when initalizling the gpg
+                // all exported public key will be saved to there with filename as public_key_<timestamp>.asc
+                let gpg_p_key_output_dir: PathBuf = self.get_output_dir(self.output_layout.exported_public_keys.as_deref())?;
+                let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
+                gpg_p_key_output_dir.join(format!("public_key_{}.asc", time_stamp))
+            }
+        };
+        set_output_without_confirmation(&mut args, &output, self.create_output_dirs)?;
+        if let Some(export_option) = export_option {
+            match self.gen_export_option_args(export_option) {
+                Ok(mut export_args) => args.append(&mut export_args),
+                Err(e) => return Err(e),
+            }
+        }
+        if let Some(mut key_id) = key_id {
+            args.append(&mut key_id);
+        }
+        let result: Result<CmdResult, GPGError> =
+            self.export_key(args, None, Operation::ExportPublicKey);
+        return result;
+    }
+
+    pub fn export_secret_key(
+        &self,
+        key_id: Option<Vec<String>>,
+        passphrase: Option<Passphrase>,
+        output: Option<PathBuf>,
+        export_option: Option<ExportOption>,
+    ) -> Result<CmdResult, GPGError> {
+        // key_id: list of keyid(s) to export, if not provided, all secret keys will be exported
+        // passphrase: for gpg version > 2.1, passphrase for passphrase proctected secret keys are required
+        // output: path that the exported key file will be saved to
+        // export_option: export-minimal and export filters to apply, if not provided, keys will be exported as it is
+
+        //*****************************************************************************
+        //  NOTE: If there are 2 or more secret key that are
+        //        passphrase proctected ( but different passphrase )
+        //        are being exported, keys that are protected by the provided passphrase
+        //        and keys that aren't passphrase protected will be exported
+        //        ( as gpg can only read 1 passphrase at a time from STDIN)
+        //*****************************************************************************
+
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+
+        let mut args: Vec<String> = vec!["--export-secret-key".to_string()];
+        let output: PathBuf = match output {
+            Some(output) => output,
+            None => {
+                // if output folder not specified, system will create a exported_secret_key folder in the set output dir when initalizling the gpg
+                // all exported secret key will be saved to there with filename as secret_key_<timestamp>.sec.asc
+                let gpg_s_key_output_dir: PathBuf = self.get_output_dir(self.output_layout.exported_secret_keys.as_deref())?;
+                let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
+                gpg_s_key_output_dir.join(format!("secret_key_{}.sec.asc", time_stamp))
+            }
+        };
+        set_output_without_confirmation(&mut args, &output, self.create_output_dirs)?;
+        if let Some(export_option) = export_option {
+            match self.gen_export_option_args(export_option) {
+                Ok(mut export_args) => args.append(&mut export_args),
+                Err(e) => return Err(e),
+            }
+        }
+        if let Some(mut key_id) = key_id {
+            args.append(&mut key_id);
+        }
+
+        let result: Result<CmdResult, GPGError> =
+            self.export_key(args, passphrase, Operation::ExportSecretKey);
+        return result;
+    }
+
+    fn gen_export_option_args(&self, export_option: ExportOption) -> Result<Vec<String>, GPGError> {
+        if export_option.keep_uid.is_some() || export_option.drop_subkey.is_some() {
+            // the export filters were only introduced in gpg 2.1
+            self.require_version(2.1, "export filters")?;
+        }
+        return Ok(export_option.gen_export_args());
+    }
+
+    fn export_key(
+        &self,
+        args: Vec<String>,
+        passphrase: Option<Passphrase>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            ops,
+        );
+        return self.add_output_info(result);
+    }
+
+    // same as export_public_key, but the exported key(s) will be written into the writer ( ex. a socket or buffer )
+    pub fn export_public_key_to_writer(
+        &self,
+        key_id: Option<Vec<String>>,
+        writer: &mut impl Write,
+        export_option: Option<ExportOption>,
+    ) -> Result<CmdResult, GPGError> {
+        // writer: where the exported key(s) will be written to
+
+        return self.write_output_to(writer, |output| self.export_public_key(key_id, Some(output), export_option));
+    }
+
+    // same as export_secret_key, but the exported key(s) will be written into the writer ( ex. a socket or buffer )
+    pub fn export_secret_key_to_writer(
+        &self,
+        key_id: Option<Vec<String>>,
+        passphrase: Option<Passphrase>,
+        writer: &mut impl Write,
+        export_option: Option<ExportOption>,
+    ) -> Result<CmdResult, GPGError> {
+        // writer: where the exported key(s) will be written to
+
+        return self.write_output_to(writer, |output| {
+            self.export_secret_key(key_id, passphrase, Some(output), export_option)
+        });
+    }
+
+    // export a single key as an ASCII armored document, whatever GPG.armor was set to
+    // an InvalidArgumentError will be returned if the key_id did not match exactly one key
+    pub fn export_armored_key(
+        &self,
+        key_id: String,
+        kind: KeyKind,
+        passphrase: Option<Passphrase>,
+        export_option: Option<ExportOption>,
+    ) -> Result<ArmoredKey, GPGError> {
+        // key_id: keyid or fingerprint of the key to export
+        // kind: export the public key or the secret key
+        // passphrase: for gpg version > 2.1, passphrase of the passphrase protected secret key
+        // export_option: export-minimal and export filters to apply, if not provided, the key will be exported as it is
+
+        let keys: Vec<ListKeyResult> = self.list_keys(kind == KeyKind::Secret, Some(vec![key_id.clone()]), false)?;
+        if keys.len() != 1 {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!("{} matched {} keys instead of 1", key_id, keys.len())),
+                None,
+            ));
+        }
+        let fingerprint: String = keys[0].fingerprint.clone();
+
+        let mut data: Vec<u8> = Vec::new();
+        let result: Result<CmdResult, GPGError> = self.write_output_to(&mut data, |output| {
+            let mut args: Vec<String> = vec!["--armor".to_string()];
+            match kind {
+                KeyKind::Public => args.push("--export".to_string()),
+                KeyKind::Secret => args.push("--export-secret-key".to_string()),
+            }
+            set_output_without_confirmation(&mut args, &output, false)?;
+            if let Some(export_option) = export_option {
+                match self.gen_export_option_args(export_option) {
+                    Ok(mut export_args) => args.append(&mut export_args),
+                    Err(e) => return Err(e),
+                }
+            }
+            args.push(fingerprint.clone());
+            let ops: Operation = if kind == KeyKind::Secret { Operation::ExportSecretKey } else { Operation::ExportPublicKey };
+            return self.export_key(args, passphrase, ops);
+        });
+        let result: CmdResult = result?;
+        // gpg may succeed without writing anything ( ex. the secret key was not available )
+        match String::from_utf8(data) {
+            Ok(data) if !data.trim().is_empty() => {
+                return Ok(ArmoredKey {
+                    kind: kind,
+                    fingerprint: fingerprint,
+                    data: data,
+                });
+            }
+            _ => {
+                return Err(GPGError::new(
+                    GPGErrorType::GPGProcessError(format!("no armored key was exported for {}", fingerprint)),
+                    Some(result),
+                ));
+            }
+        }
+    }
+
+    // render the key as DNS OPENPGPKEY record(s) ( RFC 7929 ) with --export-options export-dane,
+    // one record for each uid with an email, to be published in the zone of the domain of the email
+    pub fn dane_records(&self, key_id: String) -> Result<Vec<DaneRecord>, GPGError> {
+        // key_id: keyid, fingerprint or email of the key to publish
+
+        self.require_version(2.1, "export-dane")?;
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let args: Vec<String> = vec![
+            "--export-options".to_string(),
+            "export-dane".to_string(),
+            "--export".to_string(),
+            key_id.clone(),
+        ];
+        let result: CmdResult = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::ExportPublicKey,
+        )?;
+        // gpg export nothing for a key not found or a key without an email in its uid(s) without failing
+        let records: Vec<DaneRecord> = decode_dane_records(&result.raw_data.clone().unwrap_or_default());
+        if records.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError(format!("no OPENPGPKEY record was exported for {}", key_id)),
+                Some(result),
+            ));
+        }
+        return Ok(records);
+    }
+
+    //*******************************************************
+
+    //               BACKUP AND RESTORE KEY
+
+    //*******************************************************
+    pub fn backup_keys(
+        &self,
+        key_id: Option<Vec<String>>,
+        passphrase: Option<Passphrase>,
+        output: Option<PathBuf>,
+    ) -> Result<CmdResult, GPGError> {
+        // key_id: list of keyid(s) to backup, if not provided, all keys will be backed up
+        // passphrase: for gpg version > 2.1, passphrase for passphrase proctected secret keys are required
+        // output: path that the backup bundle will be saved to
+
+        //*****************************************************************************
+        //  NOTE: The backup bundle is a single armored text file containing
+        //        the secret keys, the public keys and the ownertrust of the keys.
+        //        Same as export_secret_key, secret keys protected by a different
+        //        passphrase than the one provided will not be included in the bundle
+        //*****************************************************************************
+
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+
+        let public_keys: Vec<ListKeyResult> = self.list_keys(false, key_id.clone(), false)?;
+        if public_keys.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("no key found to backup".to_string()),
+                None,
+            ));
+        }
+        let fingerprints: Vec<String> = public_keys.iter().map(|k| k.fingerprint.clone()).collect();
+        // only export secret keys that are available, public only keys will just be in the public part
+        let secret_fingerprints: Vec<String> = self
+            .list_keys(true, Some(fingerprints.clone()), false)?
+            .iter()
+            .map(|k| k.fingerprint.clone())
+            .collect();
+
+        let bundle_output: PathBuf = match output {
+            Some(output) => output,
+            None => {
+                // if output not specified, system will create a key_backup folder in the set output dir when initalizling the gpg
+                // all backup will be saved to there with filename as key_backup_<timestamp>.asc
+                let backup_output_dir: PathBuf = self.get_output_dir(self.output_layout.backups.as_deref())?;
+                let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
+                backup_output_dir.join(format!("key_backup_{}.asc", time_stamp))
+            }
+        };
+        check_output_parent(&bundle_output, self.create_output_dirs)?;
+
+        let mut bundle: String = format!(
+            "# crab-gnupg key backup created {}\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S")
+        );
+
+        // each armored part is exported into a staging directory and read back,
+        // the directory was removed once dropped, even if the backup failed part way
+        let staging: TempDir = create_temp_dir("backup")?;
+        if !secret_fingerprints.is_empty() {
+            let secret_part: PathBuf = staging.path().join("secret.asc");
+            let mut args: Vec<String> = vec!["--armor".to_string(), "--export-secret-key".to_string()];
+            set_output_without_confirmation(&mut args, &secret_part, false)?;
+            args.append(&mut secret_fingerprints.clone());
+            let result: CmdResult = self.export_key(args, passphrase, Operation::ExportSecretKey)?;
+            match std::fs::read_to_string(&secret_part) {
+                Ok(part) => bundle.push_str(&part),
+                Err(e) => {
+                    return Err(GPGError::new(
+                        GPGErrorType::ReadFailError(e.to_string()),
+                        Some(result),
+                    ));
+                }
+            }
+        }
+
+        let public_part: PathBuf = staging.path().join("public.asc");
+        let mut args: Vec<String> = vec!["--armor".to_string(), "--export".to_string()];
+        set_output_without_confirmation(&mut args, &public_part, false)?;
+        args.append(&mut fingerprints.clone());
+        let result: CmdResult = self.export_key(args, None, Operation::ExportPublicKey)?;
+        match std::fs::read_to_string(&public_part) {
+            Ok(part) => bundle.push_str(&part),
+            Err(e) => {
+                return Err(GPGError::new(
+                    GPGErrorType::ReadFailError(e.to_string()),
+                    Some(result),
+                ));
+            }
+        }
+
+        // ownertrust was written to stdout
+        let result: CmdResult = self.export_key(
+            vec!["--export-ownertrust".to_string()],
+            None,
+            Operation::BackupKey,
+        )?;
+        bundle.push_str("# ownertrust\n");
+        let ownertrust: String = match take_spilled_output(&result) {
+            Some(output) => output,
+            None => result.get_raw_data().unwrap_or("".to_string()),
+        };
+        bundle.push_str(&filter_ownertrust(&ownertrust, Some(&fingerprints)));
+
+        // the bundle hold the secret keys, so it was written to a temporary file only readable by the owner ( 0600 )
+        // next to the output and renamed over it once written completely
+        let bundle_dir: &Path = match bundle_output.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut bundle_file: NamedTempFile = create_temp_file_in(bundle_dir, "backup")?;
+        if let Err(e) = bundle_file.write_all(bundle.as_bytes()) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(e.to_string()),
+                Some(result),
+            ));
+        }
+        match bundle_file.persist(&bundle_output) {
+            Ok(_) => return Ok(result),
+            Err(e) => {
+                return Err(GPGError::new(
+                    GPGErrorType::WriteFailError(e.error.to_string()),
+                    Some(result),
+                ));
+            }
+        }
+    }
+
+    pub fn restore_keys(
+        &self,
+        input: InputSource,
+    ) -> Result<CmdResult, GPGError> {
+        // input: the backup bundle created by backup_keys, check InputSource
+
+        let buffer: Vec<u8> = input.read_all()?;
+
+        // gpg skip the text around the armored blocks when importing
+        let result: Result<CmdResult, GPGError> =
+            self.import_key_file_buffer(buffer.clone(), false, None);
+        let mut result: CmdResult = result?;
+
+        let ownertrust: String = filter_ownertrust(&String::from_utf8_lossy(&buffer), None);
+        if !ownertrust.is_empty() {
+            let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+            handle_cmd_io(
+                self.runner().as_ref(),
+                Some(vec!["--import-ownertrust".to_string()]),
+                None,
+                self.version,
+                self.homedir_arg(),
+                cmd_options,
+                self.env.clone(),
+                Some(InputSource::Bytes(ownertrust.as_bytes().to_vec())),
+                Operation::RestoreKey,
+            )?;
+        }
+        result.operation = Operation::RestoreKey;
+        return Ok(result);
+    }
+
+    //*******************************************************
+
+    //                   TRUST KEY
+
+    //*******************************************************
+    pub fn trust_key(
+        &self,
+        fingerprints: Vec<String>,
+        trust_level: TrustLevel,
+    ) -> Result<CmdResult, GPGError> {
+        // fingerprints: list of fingerprint(s) to trust
+        // trust_level: trust level to set for the key
+
+        if trust_level == TrustLevel::Revoked || trust_level == TrustLevel::Invalid {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!("{:?} could not be set as the trust of a key", trust_level)),
+                None,
+            ));
+        }
+        let args: Vec<String> = vec!["--import-ownertrust".to_string()];
+        let mut input_list: String = String::new();
+        for fingerprint in fingerprints {
+            input_list.push_str(&format!("{}:{}:\n", fingerprint, trust_level.value()));
+        }
+
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Bytes(input_list.as_bytes().to_vec())),
+            Operation::TrustKey,
+        );
+
+        return result;
+    }
+
+    //*******************************************************
+
+    //                 IMPORT AND TRUST
+
+    //*******************************************************
+    pub fn import_and_trust(&self, input: InputSource, trust_level: TrustLevel) -> Result<Vec<String>, GPGError> {
+        // input: the key(s) to import, check InputSource
+        // trust_level: trust level to set for every key imported
+
+        //*****************************************************************************************
+        //    NOTE: The fingerprint of every key gpg reported to be imported ( IMPORT_OK ) will be
+        //          returned, including key that was already in the keyring.
+        //          If the trust could not be set, the key(s) newly added by the import will be
+        //          deleted again so the keyring was left as it was, and the error returned.
+        //******************************************************************************************
+
+        if trust_level == TrustLevel::Revoked || trust_level == TrustLevel::Invalid {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!("{:?} could not be set as the trust of a key", trust_level)),
+                None,
+            ));
+        }
+        let result: CmdResult = self.import_key(input, false, None, None)?;
+        let imported: Vec<(String, u32)> = get_imported_keys(&result);
+        if imported.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("no key was imported".to_string()),
+                Some(result),
+            ));
+        }
+        let fingerprints: Vec<String> = imported.iter().map(|(fingerprint, _)| fingerprint.clone()).collect();
+        match self.trust_key(fingerprints.clone(), trust_level) {
+            Ok(_) => return Ok(fingerprints),
+            Err(e) => {
+                let new_keys: Vec<String> = imported
+                    .into_iter()
+                    .filter(|(_, reason)| reason & 1 != 0)
+                    .map(|(fingerprint, _)| fingerprint)
+                    .collect();
+                if !new_keys.is_empty() {
+                    let _ = self.delete_keys(new_keys, false, false, None);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    //*******************************************************
+
+    //                   SIGN KEY
+
+    //*******************************************************
+    pub fn sign_key(
+        &self,
+        signing_key_id: String,
+        target_key_id: String,
+        passphrase: Option<Passphrase>,
+        extra_args: Option<Vec<String>>,
+    ) -> Result<CmdResult, GPGError> {
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+
+        let mut args: Vec<String> = vec![
+            "--yes".to_string(),
+            "--default-key".to_string(),
+            signing_key_id,
+            "--sign-key".to_string(),
+            target_key_id,
+        ];
+        if let Some(extra_args) = extra_args {
+            match self.check_extra_args(extra_args) {
+                Ok(mut extra_args) => args.append(&mut extra_args),
+                Err(e) => return Err(e),
+            }
+        }
+
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            None,
+            Operation::SignKey,
+        );
+
+        return result;
+    }
+
+    //*******************************************************
+
+    //                 TRUST SIGN KEY
+
+    //*******************************************************
+    pub fn trust_sign_key(
+        &self,
+        signing_key_id: String,
+        target_key_id: String,
+        passphrase: Option<Passphrase>,
+        trust_level: TrustLevel,
+        depth: u8,
+        domain: Option<String>,
+    ) -> Result<CmdResult, GPGError> {
+        // signing_key_id: keyid of the key that will issue the trust signature
+        // target_key_id: keyid of the key to be trust signed
+        // passphrase: passphrase of the signing key if it was passphrase protected
+        // trust_level: only TrustLevel::Marginal or TrustLevel::Fully is allowed for a trust signature
+        // depth: how deep the delegation goes, 1 means the target key can only certify other keys directly
+        // domain: if provided, the trust will be restricted to user ids within this domain ( ex. example.com )
+
+        if passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+
+        // gpg prompt for trust signature only accept
+        // 1 = I trust marginally
+        // 2 = I trust fully
+        let trust_value: u8 = match trust_level {
+            TrustLevel::Marginal => 1,
+            TrustLevel::Fully => 2,
+            _ => {
+                return Err(GPGError::new(
+                    GPGErrorType::InvalidArgumentError(
+                        "Please choose between TrustLevel::Marginal or TrustLevel::Fully for a trust signature".to_string(),
+                    ),
+                    None,
+                ));
+            }
+        };
+
+        if depth == 0 {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(
+                    "Please choose between 1~255 as a depth for a trust signature".to_string(),
+                ),
+                None,
+            ));
+        }
+
+        let d: String = domain.unwrap_or("".to_string());
+        if d.contains("\n") || d.contains("\r") {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("domain invalid".to_string()),
+                None,
+            ));
+        }
+
+        // gpg will ask to confirm signing all user ids if the target key has more than 1 user id
+        let key_list: Result<Vec<ListKeyResult>, GPGError> =
+            self.list_keys(false, Some(vec![target_key_id.clone()]), false);
+        let sign_all: String = match key_list {
+            Ok(key_list) => {
+                if !key_list.is_empty() && key_list[0].uids.len() > 1 {
+                    "y\n".to_string()
+                } else {
+                    "".to_string()
+                }
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        };
+
+        let byte_input: Vec<u8> = format!(
+            "tsign\n{}{}\n{}\n{}\ny\nsave\n",
+            sign_all, trust_value, depth, d
+        )
+        .as_bytes()
+        .to_vec();
+
+        let args: Vec<String> = vec![
+            "--command-fd".to_string(),
+            "0".to_string(),
+            "--default-key".to_string(),
+            signing_key_id,
+            "--edit-key".to_string(),
+            target_key_id,
+        ];
+
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Bytes(byte_input)),
+            Operation::TrustSignKey,
+        );
+
+        return result;
+    }
+
+    //*******************************************************
+
+    //                 FILE ENCRYPTION
+
+    //*******************************************************
+    // to encrypt file, use the EncryptionOption struct to create the encryption options
+    pub fn encrypt(&self, encrypt_option: EncryptOption) -> Result<CmdResult, GPGError> {
+        // encryption_option: struct that contains all the encryption options ( refer to the struct for more info )
+
+        //*****************************************************************************************
+        //    NOTE: If signing with a passphrase-protected key,
+        //          an error will occur.
+        //          Please sign separately after encryption if using
+        //          passphrase-protected key.
+        //
+        //    Reason:
+        //           We stream all input to GPG through STDIN.
+        //           When signing with a passphrase-protected key,
+        //           GPG expects the passphrase to be entered after the file content.
+        //           However, since we are streaming input through STDIN,
+        //           it's impossible to distinguish between file content and the passphrase input.
+        //           As a result, the passphrase is mistakenly treated as part of the file data,
+        //           causing the signing process to fail for passphrase protected key.
+        //******************************************************************************************
+
+        let p: Option<Passphrase> = encrypt_option.passphrase.clone();
+
+        if p.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+
+        // only a file can be shredded, the input of other source was not kept by the crate
+        let shred_input: Option<(PathBuf, ShredMode)> = match encrypt_option.shred_input.clone() {
+            Some(mode) => match encrypt_option.input.path() {
+                Some(path) => Some((path.to_path_buf(), mode)),
+                None => {
+                    return Err(GPGError::new(
+                        GPGErrorType::InvalidArgumentError("shred_input only apply to InputSource::Path".to_string()),
+                        None,
+                    ));
+                }
+            },
+            None => None,
+        };
+
+        // sign with the default key of the GPG if the option did not provide one
+        let sign_key: Option<String> = encrypt_option.sign_key.clone().or(self.default_key.clone());
+        if encrypt_option.reject_unusable_keys {
+            let mut keyids: Vec<String> = encrypt_option.recipients.clone().unwrap_or_default();
+            if encrypt_option.sign && sign_key.is_some() {
+                keyids.push(sign_key.clone().unwrap());
+            }
+            match self.check_keys_usable(keyids) {
+                Ok(_) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        // generate encrypt operation arguments for gpg
+        let args: Result<Vec<String>, GPGError> = self.gen_encrypt_args(
+            encrypt_option.input.path().map(|p| p.to_path_buf()),
+            encrypt_option.recipients,
+            encrypt_option.sign,
+            sign_key,
+            encrypt_option.symmetric,
+            encrypt_option.symmetric_algo,
+            encrypt_option.s2k_count,
+            encrypt_option.s2k_digest_algo,
+            encrypt_option.s2k_cipher_algo,
+            encrypt_option.always_trust,
+            encrypt_option.passphrase,
+            encrypt_option.output,
+            encrypt_option.extra_args,
+        );
+
+        match args {
+            Ok(_) => {}
+            Err(e) => {
+                return Err(e);
+            }
+        }
+
+        let args: Vec<String> = args.unwrap();
+        let output: Option<String> = args.iter().position(|arg| arg == "--output").and_then(|p| args.get(p + 1)).cloned();
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            p,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(encrypt_option.input),
+            Operation::Encrypt,
+        );
+
+        match result {
+            Ok(result) => {
+                if let Some((input, mode)) = shred_input {
+                    // the plaintext should only be removed if the output holding it was complete
+                    let output_written: bool = output.is_some_and(|output| {
+                        std::fs::metadata(output).is_ok_and(|metadata| metadata.len() > 0)
+                    });
+                    let raw_data: String = result.raw_data.clone().unwrap_or_default();
+                    if !output_written || !raw_data.contains("[GNUPG:] END_ENCRYPTION") {
+                        return Err(GPGError::new(
+                            GPGErrorType::GPGProcessError(
+                                "the output could not be verified, the input was not shredded".to_string(),
+                            ),
+                            Some(result),
+                        ));
+                    }
+                    if let Err(e) = shred_file(&input, &mode) {
+                        return Err(GPGError::new(e.error_type, Some(result)));
+                    }
+                }
+                return self.add_output_info(Ok(result));
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+
+    // same as encrypt, but the encrypted output will be written into the writer ( ex. a socket or buffer ),
+    // the output of encrypt_option should not be provided
+    pub fn encrypt_to_writer(
+        &self,
+        mut encrypt_option: EncryptOption,
+        writer: &mut impl Write,
+    ) -> Result<CmdResult, GPGError> {
+        // writer: where the encrypted output will be written to
+
+        if encrypt_option.output.is_some() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("output should not be provided when writing to a writer".to_string()),
+                None,
+            ));
+        }
+        return self.write_output_to(writer, |output| {
+            encrypt_option.output = Some(output);
+            self.encrypt(encrypt_option)
+        });
+    }
+
+    // the fields of the EncryptOption were passed one by one, as run_pipeline encrypt without an EncryptOption
+    #[allow(clippy::too_many_arguments)]
+    fn gen_encrypt_args(
+        &self,
+        file_path: Option<PathBuf>,
+        recipients: Option<Vec<String>>,
+        sign: bool,
+        sign_key: Option<String>,
+        symmetric: bool,
+        symmetric_algo: Option<String>,
+        s2k_count: Option<u32>,
+        s2k_digest_algo: Option<DigestAlgo>,
+        s2k_cipher_algo: Option<CipherAlgo>,
+        always_trust: bool,
+        passphrase: Option<Passphrase>,
+        output: Option<PathBuf>,
+        extra_args: Option<Vec<String>>,
+    ) -> Result<Vec<String>, GPGError> {
+        let mut builder: ArgBuilder = self.arg_builder();
+        let mut encrypt_type: String = "".to_string();
+
+        if symmetric {
+            builder = builder.flag(Flag::Symmetric);
+            if self.version>=2.1{
+                builder = builder.flag(Flag::NoSymkeyCache);
+            }
+            if passphrase.is_none() {
+                return Err(GPGError::new(
+                    GPGErrorType::PassphraseError(
+                        "passphrase is required if encrypting symmetrically ".to_string(),
+                    ),
+                    None,
+                ));
+            }
+            if let Some(symmetric_algo) = symmetric_algo {
+                if !self.capabilities.supports_cipher(&symmetric_algo) {
+                    return Err(GPGError::new(
+                        GPGErrorType::InvalidArgumentError(format!(
+                            "cipher algorithm {} is not supported by gpg {}",
+                            symmetric_algo,
+                            self.full_version
+                        )),
+                        None,
+                    ));
+                }
+                builder = builder.option("--personal-cipher-preferences", symmetric_algo);
+            }
+            if let Some(count) = s2k_count {
+                if !(S2K_COUNT_MIN..=S2K_COUNT_MAX).contains(&count) {
+                    return Err(GPGError::new(
+                        GPGErrorType::InvalidArgumentError(format!(
+                            "s2k count {} is out of range, it should be between {} and {}",
+                            count, S2K_COUNT_MIN, S2K_COUNT_MAX
+                        )),
+                        None,
+                    ));
+                }
+                // the count only apply to the iterated and salted mode
+                builder = builder.option("--s2k-mode", "3").option("--s2k-count", count.to_string());
+            }
+            if let Some(digest) = s2k_digest_algo {
+                builder = builder.option("--s2k-digest-algo", digest.value());
+            }
+            if let Some(cipher) = s2k_cipher_algo {
+                builder = builder.option("--s2k-cipher-algo", cipher.value());
+            }
+            encrypt_type.push_str("pass_");
+        } else if s2k_count.is_some() || s2k_digest_algo.is_some() || s2k_cipher_algo.is_some() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(
+                    "s2k options only apply to symmetric encryption".to_string(),
+                ),
+                None,
+            ));
+        }
+        if let Some(recipients) = recipients {
+            builder = builder.flag(Flag::Encrypt);
+            for recipient in recipients {
+                builder = builder.recipient(recipient);
+            }
+            encrypt_type.push_str("keys_");
+        }
+
+        if encrypt_type.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(
+                    "Please choose symmetric or keys to encrypt your file".to_string(),
+                ),
+                None,
+            ));
+        }
+
+        if self.armor {
+            builder = builder.flag(Flag::Armor);
+        }
+        let output: PathBuf = match output {
+            Some(output) => output,
+            None => {
+                // if the system is handling the output
+                // the name wil be [<encryption_type>_encrypted_file_<YYYYMMDD_HH/MM/SS/NANO-SECOND>.<extension>]
+                // the encryption type will either [key] for public key encryption or [pass] for symmetric encryption or both
+                // the extension will be the same if file_path is provided,
+                // if a rust File type is provided, the file extension will be default to .gpg
+
+                let ext: String = get_file_extension(file_path.as_deref());
+                let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
+                self.get_default_output(
+                    self.output_layout.encrypted.as_deref(),
+                    format!("{}_encrypted_file_{}.{}", encrypt_type, time_stamp, ext),
+                    get_encrypted_file_name(file_path.as_deref(), self.armor),
+                )?
+            }
+        };
+        builder = builder.output(&output);
+
+        if sign {
+            builder = builder.flag(Flag::Sign);
+            if let Some(sign_key) = sign_key {
+                builder = builder.default_key(sign_key);
+            }
+        }
+
+        if always_trust {
+            builder = builder.option("--trust-model", "always");
+        }
+
+        if let Some(extra_args) = extra_args {
+            builder = builder.extra_args(extra_args);
+        }
+
+        return builder.build();
+    }
+
+    //*******************************************************
+
+    //                   FILE DECRYPTION
+
+    //*******************************************************
+    // to encrypt file, use the DecryptionOption struct to create the decryption options
+    pub fn decrypt(&self, decrypt_option: DecryptOption) -> Result<CmdResult, GPGError> {
+        // decrypt_option: struct that contains all the decryption options ( refer to the struct for more info )
+
+        let k_p: Option<Passphrase> = decrypt_option.key_passphrase.clone();
+        let p: Option<Passphrase> = decrypt_option.passphrase.clone();
+        let mut pass: Option<Passphrase> = None;
+
+        if let Some(k_p) = k_p {
+            if !is_passphrase_valid(k_p.expose()) {
+                return Err(GPGError::new(
+                    GPGErrorType::PassphraseError("key passphrase invalid".to_string()),
+                    None,
+                ));
+            }
+            pass = Some(k_p);
+        } else if let Some(p) = p {
+            if !is_passphrase_valid(p.expose()) {
+                return Err(GPGError::new(
+                    GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                    None,
+                ));
+            }
+            pass = Some(p);
+        }
+
+        let output: PathBuf = match decrypt_option.output {
+            Some(output) => output,
+            None => {
+                // if the system is handling the output
+                // the name wil be [decrypted_file_<YYYYMMDD_HH/MM/SS/NANO-SECOND>.<extension>]
+                // the extension will be the same if file_path is provided,
+                // if a rust File type is provided, the name will be extension will be default to gpg
+
+                let ext: String = get_file_extension(decrypt_option.input.path());
+                let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
+                let input_name: String = match decrypt_option.input.path() {
+                    Some(path) => get_decrypted_file_name(&get_input_file_name(Some(path), "")),
+                    None => "decrypted_file".to_string(),
+                };
+                let sub_dir: Option<&Path> = self.output_layout.decrypted.as_deref();
+                self.get_default_output(sub_dir, format!("decrypted_file_{}.{}", time_stamp, ext), input_name)?
+            }
+        };
+        let args: Vec<String> = self.gen_decrypt_args(
+            decrypt_option.recipient,
+            decrypt_option.always_trust,
+            Some(output),
+            decrypt_option.show_session_key,
+            decrypt_option.session_key,
+            decrypt_option.extra_args,
+        )?;
+        let output: Option<String> = args.iter().position(|arg| arg == "--output").and_then(|p| args.get(p + 1)).cloned();
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            pass,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(decrypt_option.input),
+            Operation::Decrypt,
+        );
+
+        match result {
+            Ok(result) => {
+                if decrypt_option.strict {
+                    if let Err(e) = reject_weak_crypto(result.clone()) {
+                        // the plaintext of data the policy does not accept should not be left for use
+                        if let Some(output) = output {
+                            let _ = std::fs::remove_file(output);
+                        }
+                        return Err(e);
+                    }
+                }
+                return self.add_output_info(Ok(result));
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+
+    // same as decrypt, but the decrypted output will be written into the writer ( ex. a socket or buffer ),
+    // the output of decrypt_option should not be provided
+    pub fn decrypt_to_writer(
+        &self,
+        mut decrypt_option: DecryptOption,
+        writer: &mut impl Write,
+    ) -> Result<CmdResult, GPGError> {
+        // writer: where the decrypted output will be written to
+
+        if decrypt_option.output.is_some() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("output should not be provided when writing to a writer".to_string()),
+                None,
+            ));
+        }
+        return self.write_output_to(writer, |output| {
+            decrypt_option.output = Some(output);
+            self.decrypt(decrypt_option)
+        });
+    }
+
+    fn gen_decrypt_args(
+        &self,
+        recipient: Option<String>,
+        always_trust: bool,
+        output: Option<PathBuf>,
+        show_session_key: bool,
+        session_key: Option<Passphrase>,
+        extra_args: Option<Vec<String>>,
+    ) -> Result<Vec<String>, GPGError> {
+        let mut builder: ArgBuilder = self.arg_builder().flag(Flag::Decrypt);
+        if show_session_key {
+            builder = builder.flag(Flag::ShowSessionKey);
+        }
+        if let Some(session_key) = session_key {
+            if session_key.is_empty() || session_key.expose().chars().any(|c| c.is_whitespace() || c.is_control()) {
+                return Err(GPGError::new(
+                    GPGErrorType::InvalidArgumentError("session key invalid".to_string()),
+                    None,
+                ));
+            }
+            builder = builder.option("--override-session-key", session_key.expose());
+        }
+        if let Some(recipient) = recipient {
+            builder = builder.recipient(recipient);
+        }
+        if always_trust {
+            builder = builder.option("--trust-model", "always");
+        }
+        // without an output, the decrypted output will be written to stdout
+        if let Some(output) = output {
+            builder = builder.output(&output);
+        }
+
+        if let Some(extra_args) = extra_args {
+            builder = builder.extra_args(extra_args);
+        }
+        return builder.build();
+    }
+
+    //*******************************************************
 
-        // NOTE: delete both public and secret key by default
-        // NOTE: If the fingerprint is for subkeys, but is_subkey was not set to true, it will remove the parent key also
+    //              SYMMETRIC RE-ENCRYPTION
 
-        let mut mode:String = "secret-and-public-key".to_string();
-        if is_secret {
-            mode = "secret-keys".to_string();
+    //*******************************************************
+    pub fn reencrypt_symmetric(
+        &self,
+        input: InputSource,
+        old_passphrase: Passphrase,
+        new_passphrase: Passphrase,
+        algo: Option<CipherAlgo>,
+        output: Option<PathBuf>,
+    ) -> Result<CmdResult, GPGError> {
+        // input: the symmetrically encrypted file, check InputSource
+        // old_passphrase: passphrase the file was encrypted with
+        // new_passphrase: passphrase to encrypt the file with
+        // algo: cipher to encrypt with [if not provided a highly ranked cipher willl be chosen]
+        // output: path to write the re-encrypted output,
+        //         will use the default output dir set in GPG if not provided and
+        //         with file name as [pass_encrypted_file_<datetime>.<extension>]
+
+        if !is_passphrase_valid(old_passphrase.expose()) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("old passphrase invalid".to_string()),
+                None,
+            ));
         }
-        let mut args: Vec<String> = vec![
-            "--yes".to_string(),
-            format!("--delete-{}", mode),
-        ];
-
-        if is_subkey{
-            let mut subkey_fingerprints: Vec<String> = Vec::new();
-            for f_p in fingerprints{
-                subkey_fingerprints.push(format!("{}!", f_p));
-            }
-            args.append(&mut subkey_fingerprints);
-        } else {
-            args.append(&mut fingerprints);
+        if !is_passphrase_valid(new_passphrase.expose()) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("new passphrase invalid".to_string()),
+                None,
+            ));
         }
-        let result:Result<CmdResult, GPGError> = handle_cmd_io(
-            Some(args),
-            passphrase,
-            self.version,
-            self.homedir.clone(),
-            self.options.clone(),
-            self.env.clone(),
-            None,
-            None,
-            None,
-            false,
-            false,
-            Operation::DeleteKey,
+        let output: PathBuf = self.get_reencrypt_output(&input, output, "pass_")?;
+        // the input will be replaced by the decrypted output
+        let encrypt_option: EncryptOption = EncryptOption::with_symmetric(
+            InputSource::Bytes(Vec::new()),
+            algo.map(|algo| algo.value()),
+            new_passphrase,
+            Some(output),
         );
-
-        return result;
+        return self.pipe_decrypt_to_encrypt(input, vec!["--decrypt".to_string()], Some(old_passphrase), encrypt_option);
     }
 
     //*******************************************************
 
-    //                   ADD SUBKEY
+    //                       REKEY
 
     //*******************************************************
-    pub fn add_subkey(
+    pub fn rekey(
         &self,
-        fingerprint: String,
-        passphrase: Option<String>,
-        algo: String,
-        usage: String,
-        expire: String // ISO format YYYY-MM-DD or "-" for no expiration
+        decrypt_option: DecryptOption,
+        new_recipients: Vec<String>,
+        output: Option<PathBuf>,
     ) -> Result<CmdResult, GPGError> {
-        if passphrase.is_some() {
-            if !is_passphrase_valid(&mut passphrase.as_ref().unwrap()) {
-                return Err(GPGError::new(
-                    GPGErrorType::PassphraseError("passphrase invalid".to_string()),
-                    None,
-                ));
-            }
+        // decrypt_option: how the input of it will be decrypted ( refer to the struct for more info ),
+        //                 its output, strict and show_session_key were not used as the plaintext was never written
+        // new_recipients: keyid(s) to encrypt the file to
+        // output: path to write the re-encrypted output,
+        //         will use the default output dir set in GPG if not provided and
+        //         with file name as [keys_encrypted_file_<datetime>.<extension>]
+
+        // a recipient removed from new_recipients can no longer decrypt the output ( ex. offboarding )
+        if new_recipients.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("no recipient to encrypt to".to_string()),
+                None,
+            ));
         }
+        let passphrase: Option<Passphrase> = decrypt_option.key_passphrase.or(decrypt_option.passphrase);
+        if passphrase.is_some() && !is_passphrase_valid(passphrase.as_ref().unwrap().expose()) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+        let args: Vec<String> = self.gen_decrypt_args(
+            decrypt_option.recipient,
+            decrypt_option.always_trust,
+            None,
+            false,
+            decrypt_option.session_key,
+            decrypt_option.extra_args,
+        )?;
+        let output: PathBuf = self.get_reencrypt_output(&decrypt_option.input, output, "keys_")?;
+        // the input will be replaced by the decrypted output
+        let encrypt_option: EncryptOption =
+            EncryptOption::default(InputSource::Bytes(Vec::new()), new_recipients, Some(output));
+        return self.pipe_decrypt_to_encrypt(decrypt_option.input, args, passphrase, encrypt_option);
+    }
 
-        let args:Vec<String> =vec!["--quick-add-key".to_string(), fingerprint, algo, usage, expire]; 
-
-        let result = handle_cmd_io(
-            Some(args),
+    // the output of the decrypting gpg was piped into the encrypting gpg as its input,
+    // so the plaintext was never written to disk
+    // NOTE: the decryption always spawn a local gpg process ( the binary run by the runner, recorded to the audit sink
+    //       and metrics recorder ), only the encryption go through the runner, so it fail for a custom runner
+    fn pipe_decrypt_to_encrypt(
+        &self,
+        input: InputSource,
+        decrypt_args: Vec<String>,
+        passphrase: Option<Passphrase>,
+        mut encrypt_option: EncryptOption,
+    ) -> Result<CmdResult, GPGError> {
+        // decrypt_args: arguments of the decryption, without an output so it will be written to stdout
+        // passphrase: passphrase for the decryption
+        // encrypt_option: the encryption, its output must be provided
+
+        let gpg_program: PathBuf = self.local_gpg_program(&Operation::Decrypt)?;
+        let mut reader: Box<dyn Read + Send> = input.into_reader()?;
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let recorder: LocalProcessRecorder = self.local_recorder();
+        let decryption: Result<CmdStream, GPGError> = handle_cmd_stream(
+            &gpg_program,
+            Some(decrypt_args),
             passphrase,
             self.version,
-            self.homedir.clone(),
-            self.options.clone(),
+            self.homedir_arg(),
+            cmd_options,
             self.env.clone(),
-            None,
-            None,
-            None,
-            false,
-            false,
-            Operation::AddSubKey,
+            Operation::Decrypt,
         );
-
+        let mut decryption: CmdStream = match decryption {
+            Ok(decryption) => decryption,
+            Err(e) => return Err(recorder.record_error(Operation::Decrypt, e)),
+        };
+        let mut stdin: ChildStdin = decryption.process.stdin.take().unwrap();
+        let writer: JoinHandle<()> = thread::spawn(move || {
+            // a failed decryption close its stdin early, the error is reported by its exit status
+            let _ = std::io::copy(&mut reader, &mut stdin);
+        });
+        let plaintext: ChildStdout = decryption.process.stdout.take().unwrap();
+        let output: Option<PathBuf> = encrypt_option.output.clone();
+        encrypt_option.input = InputSource::Reader(Box::new(plaintext));
+        let result: Result<CmdResult, GPGError> = self.encrypt(encrypt_option);
+
+        // the encryption finished reading the plaintext, or failed and dropped it which end the decryption
+        let _ = writer.join();
+        // recorded as the decryption of the runner, an error means it could not be audited
+        let decrypted: CmdResult = recorder.record(Operation::Decrypt, Ok(decryption.wait()))?;
+        if decrypted.exit_status() != GpgExitStatus::Success {
+            // whatever was encrypted is not the complete plaintext
+            if result.is_ok() {
+                if let Some(output) = output {
+                    let _ = std::fs::remove_file(output);
+                }
+            }
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError(format!(
+                    "failed to decrypt the input ( exit status {} )",
+                    decrypted.return_code.unwrap_or(-1)
+                )),
+                Some(decrypted),
+            ));
+        }
         return result;
+    }
 
+    fn get_reencrypt_output(
+        &self,
+        input: &InputSource,
+        output: Option<PathBuf>,
+        encrypt_type: &str,
+    ) -> Result<PathBuf, GPGError> {
+        match output {
+            Some(output) => return Ok(output),
+            None => {
+                // same as the default output of encrypt, as it could not be named after a piped input
+                let ext: String = get_file_extension(input.path());
+                let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
+                return self.get_default_output(
+                    self.output_layout.encrypted.as_deref(),
+                    format!("{}encrypted_file_{}.{}", encrypt_type, time_stamp, ext),
+                    get_encrypted_file_name(input.path(), self.armor),
+                );
+            }
+        }
     }
 
     //*******************************************************
 
-    //                   REVOKE KEY
+    //                   RAW COMMAND
 
     //*******************************************************
-    pub fn revoke_key(
-        &self,
-        keyid: String,
-        passphrase: Option<String>,
-        reason_code:u8,
-        revoke_desc: Option<String>,
-        is_subkey: bool,
-    ) -> Result<CmdResult, GPGError> {
-        let mut args:Vec<String> = vec![];
-        let mut desc:String = "".to_string();
+    // run a gpg command the crate does not wrap yet ( ex. --list-packets ), with the same homedir, options, env
+    // and runner as every other operation, the status and output were returned in the CmdResult as gpg gave them
+    pub fn run_raw(&self, command: RawCommand) -> Result<CmdResult, GPGError> {
+        // command: the arguments, input, passphrase, operation and capture mode, check RawCommand
 
-        if revoke_desc.is_some() {
-            desc = revoke_desc.unwrap();
+        if command.args.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("args of the command should not be empty".to_string()),
+                None,
+            ));
         }
-
-        if !(0..=3).contains(&reason_code){
-            // 0 = No reason specified
-            // 1 = Key has been compromised
-            // 2 = Key is superseded
-            // 3 = Key is no longer used
+        if command.passphrase.is_some() && !is_passphrase_valid(command.passphrase.as_ref().unwrap().expose()) {
             return Err(GPGError::new(
-                GPGErrorType::InvalidReasonCode("Please choose between 0~3 as a reason code for revoking a key".to_string()),
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
                 None,
             ));
         }
-
-        let mut byte_input:Vec<u8> = format!("revkey\ny\n{}\n{}\ny\nsave\n", reason_code, desc).as_bytes().to_vec();
-
-        if is_subkey {
-            let sequence: Result<u8, GPGError> = self.get_subkey_position(keyid.clone());
-            match sequence {
-                Ok(sequence) => {
-                    let selected_key = format!("key {}", sequence);
-                    byte_input = format!("{}\nrevkey\ny\n{}\n{}\ny\nsave\n", selected_key, reason_code, desc).as_bytes().to_vec();
-                }
-                Err(e) => {
-                    return Err(e);
-                }
+        let mut builder: ArgBuilder = self.arg_builder();
+        // the directory of the discarded output, removed once gpg finished
+        let mut discard_dir: Option<TempDir> = None;
+        match &command.capture {
+            CaptureMode::Stdout => {}
+            CaptureMode::File(output) => builder = builder.output(output),
+            CaptureMode::Discard => {
+                let output_dir: TempDir = create_temp_dir("output")?;
+                builder = builder.output(&output_dir.path().join("output"));
+                discard_dir = Some(output_dir);
             }
         }
-
-        args.append(&mut vec!["--command-fd".to_string(), "0".to_string(), "--edit-key".to_string(), keyid]);
-
-        let result = handle_cmd_io(
+        let args: Vec<String> = builder.extra_args(command.args).build()?;
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
             Some(args),
-            passphrase,
+            command.passphrase,
             self.version,
-            self.homedir.clone(),
-            self.options.clone(),
+            self.homedir_arg(),
+            cmd_options,
             self.env.clone(),
-            None,
-            None,
-            Some(byte_input),
-            true,
-            false,
-            Operation::RevokeKey,
+            command.input,
+            command.operation,
         );
-
-        return result;
+        drop(discard_dir);
+        if command.capture == CaptureMode::Discard {
+            return result;
+        }
+        return self.add_output_info(result);
     }
 
-    fn get_subkey_position(
-        &self,
-        keyid: String,
-    ) -> Result<u8, GPGError> {
-        let key_list: Result<Vec<ListKeyResult>, GPGError> = self.list_keys(false, Some(vec![keyid.clone()]), false);
-        match key_list {
-            Ok(key_list) => {
-                if key_list[0].subkeys.len() > 0 {
-                    let position: u8 = key_list[0].subkeys.iter().position(|x| x.keyid == keyid).unwrap() as u8;
-                    return Ok(position+1);
+    //*******************************************************
+
+    //                   PIPELINE
+
+    //*******************************************************
+    // encrypt or decrypt with the input streamed into gpg and its output streamed into the sink as gpg is processing it,
+    // with the progress reported, a timeout and a cancellation token configured in one PipelineSpec
+    // NOTE: the output was written as gpg produced it, whatever was written should be discarded if an error was returned
+    //       ( ex. a decryption found to be modified at the end ), and this always spawn a local gpg process ( the binary
+    //       run by the runner, recorded to the audit sink and metrics recorder ) so it fail for a custom runner
+    pub fn run_pipeline(&self, spec: PipelineSpec) -> Result<CmdResult, GPGError> {
+        // spec: struct that contains the input, output, operation, progress, timeout, cancellation and always_trust
+        //       ( refer to the struct for more info )
+
+        let PipelineSpec {
+            input,
+            mut output,
+            operation,
+            progress,
+            timeout,
+            cancellation,
+            always_trust,
+        } = spec;
+        let (args, passphrase, ops): (Result<Vec<String>, GPGError>, Option<Passphrase>, Operation) = match operation {
+            PipelineOperation::Encrypt(recipients) => {
+                if recipients.is_empty() {
+                    return Err(GPGError::new(
+                        GPGErrorType::InvalidArgumentError("no recipient to encrypt to".to_string()),
+                        None,
+                    ));
                 }
-                return Err(GPGError::new(
-                    GPGErrorType::KeyNotSubkey("keyid provided is not a subkey".to_string()),
+                // --output - write the output to stdout instead of the default output
+                let args: Result<Vec<String>, GPGError> = self.gen_encrypt_args(
                     None,
-                ));
-            },
-            Err(e) => {
-                return Err(e);
+                    Some(recipients),
+                    false,
+                    None,
+                    false,
+                    None,
+                    None,
+                    None,
+                    None,
+                    always_trust,
+                    None,
+                    Some(PathBuf::from("-")),
+                    None,
+                );
+                (args, None, Operation::Encrypt)
+            }
+            PipelineOperation::EncryptSymmetric(passphrase) => {
+                let args: Result<Vec<String>, GPGError> = self.gen_encrypt_args(
+                    None,
+                    None,
+                    false,
+                    None,
+                    true,
+                    None,
+                    None,
+                    None,
+                    None,
+                    always_trust,
+                    Some(passphrase.clone()),
+                    Some(PathBuf::from("-")),
+                    None,
+                );
+                (args, Some(passphrase), Operation::Encrypt)
+            }
+            PipelineOperation::Decrypt(passphrase) => {
+                let args: Result<Vec<String>, GPGError> = self.gen_decrypt_args(None, always_trust, None, false, None, None);
+                (args, passphrase, Operation::Decrypt)
             }
+        };
+        let args: Vec<String> = args?;
+        if passphrase.is_some() && !is_passphrase_valid(passphrase.as_ref().unwrap().expose()) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
         }
+        let gpg_program: PathBuf = self.local_gpg_program(&ops)?;
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let recorder: LocalProcessRecorder = self.local_recorder();
+        // same as handle_cmd_io, gpg was given an empty passphrase instead of asking the pinentry for one
+        let result: Result<CmdResult, GPGError> = handle_cmd_pipeline(
+            &gpg_program,
+            Some(args),
+            passphrase.or(Some(Passphrase::from(""))),
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            input,
+            &mut output,
+            progress.as_ref(),
+            timeout,
+            cancellation.as_ref(),
+            ops.clone(),
+        );
+        return recorder.record(ops, result);
     }
 
     //*******************************************************
 
-    //                   IMPORT KEY
+    //                   FILE SIGNING
 
     //*******************************************************
-    pub fn import_key(
+    pub fn sign(&self, sign_option: SignOption) -> Result<CmdResult, GPGError> {
+        // sign_option: struct that contains all the signing options ( refer to the struct for more info )
+
+        if sign_option.key_passphrase.as_ref().is_some_and(|p| !is_passphrase_valid(p.expose())) {
+            return Err(GPGError::new(
+                GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                None,
+            ));
+        }
+        // sign with the default key of the GPG if the option did not provide one
+        let keyid: Option<String> = sign_option.keyid.clone().or(self.default_key.clone());
+        if sign_option.reject_unusable_keys && keyid.is_some() {
+            match self.check_keys_usable(vec![keyid.clone().unwrap()]) {
+                Ok(_) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        // the path of the input is only used for naming the detached signature
+        let args: Vec<String> = self.gen_sign_args(
+            sign_option.input.path().map(|p| p.to_path_buf()),
+            keyid,
+            sign_option.clearsign,
+            sign_option.detach,
+            sign_option.output,
+            sign_option.extra_args,
+        )?;
+
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io_with_provider(
+            self.runner().as_ref(),
+            Some(args),
+            sign_option.key_passphrase,
+            self.passphrase_provider.as_deref(),
+            self.passphrase_retries,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(sign_option.input),
+            Operation::Sign,
+        );
+        return self.add_output_info(result);
+    }
+
+    // same as sign, but the signed output ( or the detached signature ) will be written into the writer
+    // ( ex. a socket or buffer ), the output of sign_option should not be provided
+    pub fn sign_to_writer(&self, mut sign_option: SignOption, writer: &mut impl Write) -> Result<CmdResult, GPGError> {
+        // writer: where the signed output will be written to
+
+        if sign_option.output.is_some() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("output should not be provided when writing to a writer".to_string()),
+                None,
+            ));
+        }
+        return self.write_output_to(writer, |output| {
+            sign_option.output = Some(output);
+            self.sign(sign_option)
+        });
+    }
+
+    fn gen_sign_args(
         &self,
-        file: Option<File>,
-        file_path: Option<String>,
-        merge_only: bool,
+        file_path: Option<PathBuf>,
+        keyid: Option<String>,
+        clearsign: bool,
+        detach: bool,
+        output: Option<PathBuf>,
         extra_args: Option<Vec<String>>,
-    ) -> Result<CmdResult, GPGError> {
-        let file: Result<File, GPGError> = get_file_obj(file, file_path);
-        match file {
-            Ok(mut file) => {
-                let mut buffer: Vec<u8> = Vec::new();
-                let _ = file.read_to_end(&mut buffer);
-                let result: Result<CmdResult, GPGError> = self.import_key_file_buffer(
-                    buffer, 
-                    merge_only, 
-                    extra_args);
-                match result {
-                    Ok(result) => {
-                        return Ok(result);
-                    }
-                    Err(e) => {
-                        return Err(e);
-                    }
-                }
-            }
-            Err(e)=>{
-                return Err(e);
-            }
+    ) -> Result<Vec<String>, GPGError> {
+        let mut builder: ArgBuilder = self.arg_builder().flag(Flag::Sign);
+        let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
+
+        if clearsign {
+            builder = builder.flag(Flag::Clearsign);
+        };
+        if detach {
+            builder = builder.flag(Flag::DetachSign);
+            let extension = if self.armor { ".asc" } else { ".sig" };
+            // a detached signature of a file path will be named after the file ( ex. foo.tar.gz.asc )
+            let file_path: PathBuf = match (output, file_path) {
+                (Some(output), _) => output,
+                (None, Some(file_path)) => get_conventional_sig_path(&file_path, self.armor),
+                (None, None) => self.get_default_output(
+                    self.output_layout.signatures.as_deref(),
+                    format!("detach_sign_{}{}", time_stamp, extension),
+                    format!("signature{}", extension),
+                )?,
+            };
+            builder = builder.output(&file_path);
+        } else {
+            let file_path: PathBuf = match output {
+                Some(output) => output,
+                None => {
+                    let extension: &str = if clearsign || self.armor { ".asc" } else { ".gpg" };
+                    let input_name: String = get_input_file_name(file_path.as_deref(), "signed_file");
+                    self.get_default_output(
+                        self.output_layout.signatures.as_deref(),
+                        format!("embedded_sign_{}.gpg", time_stamp),
+                        format!("{}{}", input_name, extension),
+                    )?
+                }
+            };
+            builder = builder.output(&file_path);
+        }
+
+        if let Some(keyid) = keyid {
+            builder = builder.default_key(keyid);
+        };
+
+        if self.armor {
+            builder = builder.flag(Flag::Armor);
         }
+
+        if let Some(extra_args) = extra_args {
+            builder = builder.extra_args(extra_args);
+        }
+
+        return builder.build();
     }
 
-    fn import_key_file_buffer(
+    //*******************************************************
+
+    //                   FILE VERIFICATION
+
+    //*******************************************************
+    pub fn verify_file(
         &self,
-        key_buffer: Vec<u8>,
-        merge_only: bool,
+        input: InputSource,
+        signature_file_path: Option<PathBuf>,
         extra_args: Option<Vec<String>>,
-    ) -> Result<CmdResult, GPGError> {
-        let mut args: Vec<String> = vec!["--import".to_string()];
-        if merge_only {
-            args.append(&mut vec![
-                "--import-options".to_string(),
-                "merge-only".to_string(),
-            ]);
-        };
-        if extra_args.is_some() {
-            args.append(&mut extra_args.unwrap());
-        };
+    ) -> Result<VerifyResult, GPGError> {
+        // input: the file to be verified, check InputSource
+        // signature_file_path: path to signature file
+        // extra_args: extra arguments to pass to gpg
+
+        //*****************************************************************************************
+        //    NOTE: If only the input is provided, it expected the input to include a
+        //          complete signature.
+        //          For detached signature, signature_file_path is required along
+        //          with the input
+        //          A bad signature will be returned as a VerifyResult that is not valid,
+        //          while a signature that could not be checked ( ex. missing public key ) will be an error
+        //******************************************************************************************
+
+        let args: Vec<String> = self.gen_verify_file_args(signature_file_path, extra_args)?;
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
         let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
             Some(args),
             None,
             self.version,
-            self.homedir.clone(),
-            self.options.clone(),
+            self.homedir_arg(),
+            cmd_options,
             self.env.clone(),
-            None,
-            None,
-            Some(key_buffer),
-            true,
-            false,
-            Operation::ImportKey,
+            Some(input),
+            Operation::VerifyFile,
         );
-        return result;
+        return decode_verify_result(result);
     }
 
-    //*******************************************************
+    // verify a file with the detached signature next to it named by convention ( ex. foo.tar.gz.sig or foo.tar.gz.asc )
+    pub fn verify_with_conventional_sig(&self, file_path: impl AsRef<Path>) -> Result<VerifyResult, GPGError> {
+        // file_path: path to the file to be verified
 
-    //                   EXPORT KEY
+        let file_path: PathBuf = file_path.as_ref().to_path_buf();
+        let signature_file_path: PathBuf = match find_conventional_sig(&file_path) {
+            Some(signature_file_path) => signature_file_path,
+            None => {
+                return Err(GPGError::new(
+                    GPGErrorType::FileNotFoundError(format!(
+                        "no detached signature found for {}",
+                        file_path.display()
+                    )),
+                    None,
+                ));
+            }
+        };
+        return self.verify_file(InputSource::Path(file_path), Some(signature_file_path), None);
+    }
 
-    //*******************************************************
-    pub fn export_public_key(
-        &self,
-        key_id: Option<Vec<String>>,
-        output: Option<String>,
-    ) -> Result<CmdResult, GPGError> {
-        // key_id: list of keyid(s) to export, if not provided, all public keys will be exported
-        // output: path that the exported key file will be saved to
+    // verify every file in a directory against the detached signature next to it named by convention
+    pub fn verify_directory(&self, dir: impl AsRef<Path>, policy: VerifyPolicy) -> Result<DirectoryVerifyResult, GPGError> {
+        // dir: path to the directory to be verified
+        // policy: how to walk the directory and verify the files, check VerifyPolicy
 
-        let mut args: Vec<String> = vec!["--export".to_string()];
-        if output.is_some() {
-            set_output_without_confirmation(&mut args, &output.unwrap());
-        } else {
-            // if output folder not specified, system will create a exported_public_key folder in the set output dir when initalizling the gpg
-            // all exported public key will be saved to there with filename as public_key_<timestamp>.asc
-            let gpg_p_key_output_dir = PathBuf::from(self.output_dir.clone())
-                .join("exported_public_key")
-                .to_string_lossy()
-                .to_string();
-            if !check_is_dir(gpg_p_key_output_dir.clone()) {
-                std::fs::create_dir_all(gpg_p_key_output_dir.clone()).unwrap();
+        //*****************************************************************************************
+        //    NOTE: A signature file ( .sig, .asc or .sign ) will only be treated as the detached
+        //          signature of the file it was named after if that file exists, otherwise it
+        //          will be treated as a data file. File will be reported relative to dir.
+        //******************************************************************************************
+
+        let dir: &Path = dir.as_ref();
+        if !check_is_dir(dir) {
+            return Err(GPGError::new(
+                GPGErrorType::FileNotFoundError(format!("{} is not a directory", dir.display())),
+                None,
+            ));
+        }
+        let mut files: Vec<PathBuf> = vec![];
+        collect_dir_files(dir, &policy, &mut files)?;
+        let file_set: HashSet<&PathBuf> = files.iter().collect();
+        let signatures: HashSet<PathBuf> = files
+            .iter()
+            .flat_map(|f| DETACHED_SIG_EXTENSIONS.iter().map(move |ext| append_extension(f, ext)))
+            .filter(|sig| file_set.contains(sig))
+            .collect();
+        let data_files: Vec<PathBuf> = files
+            .iter()
+            .filter(|f| !signatures.contains(*f))
+            .cloned()
+            .collect();
+
+        // verify the files in chunks, one thread for each chunk
+        let parallelism: usize = policy.parallelism.max(1);
+        let chunk_size: usize = data_files.len().div_ceil(parallelism).max(1);
+        let mut results: Vec<(PathBuf, Option<Result<bool, GPGError>>)> = vec![];
+        std::thread::scope(|s| {
+            let handles: Vec<_> = data_files
+                .chunks(chunk_size)
+                .map(|chunk| s.spawn(move || chunk.iter().map(|f| (f.clone(), self.verify_dir_file(f))).collect::<Vec<_>>()))
+                .collect();
+            for handle in handles {
+                // a panic while verifying a chunk was passed on, rather than leaving its files out of the result
+                match handle.join() {
+                    Ok(mut chunk_results) => results.append(&mut chunk_results),
+                    Err(e) => std::panic::resume_unwind(e),
+                }
             }
-            let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
-            let gpg_p_key_output = PathBuf::from(gpg_p_key_output_dir)
-                .join(format!("public_key_{}.asc", time_stamp))
+        });
+
+        let mut directory_result: DirectoryVerifyResult = DirectoryVerifyResult {
+            verified: vec![],
+            failed: vec![],
+            unsigned: vec![],
+            errors: HashMap::new(),
+        };
+        for (file, verified) in results {
+            let name: String = file
+                .strip_prefix(dir)
+                .unwrap_or(&file)
                 .to_string_lossy()
                 .to_string();
-            set_output_without_confirmation(&mut args, &gpg_p_key_output);
-        }
-        if key_id.is_some() {
-            args.append(&mut key_id.unwrap());
+            match verified {
+                Some(Ok(true)) => directory_result.verified.push(name),
+                Some(Ok(false)) => directory_result.failed.push(name),
+                Some(Err(e)) => {
+                    directory_result.errors.insert(name.clone(), e.error_type.to_string());
+                    directory_result.failed.push(name);
+                }
+                None => directory_result.unsigned.push(name),
+            }
         }
-        let result: Result<CmdResult, GPGError> =
-            self.export_key(args, None, Operation::ExportPublicKey);
-        return result;
+        directory_result.verified.sort();
+        directory_result.failed.sort();
+        directory_result.unsigned.sort();
+        return Ok(directory_result);
     }
 
-    pub fn export_secret_key(
-        &self,
-        key_id: Option<Vec<String>>,
-        passphrase: Option<String>,
-        output: Option<String>,
-    ) -> Result<CmdResult, GPGError> {
-        // key_id: list of keyid(s) to export, if not provided, all secret keys will be exported
-        // passphrase: for gpg version > 2.1, passphrase for passphrase proctected secret keys are required
-        // output: path that the exported key file will be saved to
+    // Some(Ok(true)) if the file has a good signature, Some(Ok(false)) if the signature was not good,
+    // Some(Err) if gpg failed to verify it and None if it was not signed
+    fn verify_dir_file(&self, file: &Path) -> Option<Result<bool, GPGError>> {
+        let signature_file_path: PathBuf = find_conventional_sig(file)?;
+        let result: Result<VerifyResult, GPGError> =
+            self.verify_file(InputSource::Path(file.to_path_buf()), Some(signature_file_path), None);
+        return Some(result.map(|result| result.valid));
+    }
 
-        //*****************************************************************************
-        //  NOTE: If there are 2 or more secret key that are
-        //        passphrase proctected ( but different passphrase )
-        //        are being exported, keys that are protected by the provided passphrase
-        //        and keys that aren't passphrase protected will be exported
-        //        ( as gpg can only read 1 passphrase at a time from STDIN)
-        //*****************************************************************************
+    // verify with the backend chosen in the option
+    // with VerifyBackend::Gpgv, only the keys in the keyring provided will be trusted
+    // and the homedir keyring and trustdb will not be consulted
+    pub fn verify(&self, verify_option: VerifyOption) -> Result<VerifyResult, GPGError> {
+        // verify_option: option for verification, check VerifyOption
+
+        let keyring: PathBuf = match verify_option.backend {
+            VerifyBackend::Gpg => {
+                return self.verify_file(
+                    verify_option.input,
+                    verify_option.signature_file_path,
+                    verify_option.extra_args,
+                );
+            }
+            VerifyBackend::Gpgv(keyring) => keyring,
+        };
 
-        if passphrase.is_some() {
-            if !is_passphrase_valid(&mut passphrase.as_ref().unwrap()) {
+        let keyring_path: PathBuf = match std::fs::canonicalize(&keyring) {
+            Ok(path) => path,
+            Err(e) => {
                 return Err(GPGError::new(
-                    GPGErrorType::PassphraseError("passphrase invalid".to_string()),
+                    GPGErrorType::FileNotFoundError(format!("{}: {}", keyring.display(), e)),
                     None,
                 ));
             }
+        };
+        // gpgv only understand binary keyring, an armored key will be dearmored into a temporary keyring,
+        // the directory holding it was kept until gpgv finished and removed once dropped
+        let mut keyring_dir: Option<TempDir> = None;
+        let mut keyring: PathBuf = keyring_path;
+        if is_armored_file(&keyring) {
+            let dearmored_dir: TempDir = create_temp_dir("gpgv_keyring")?;
+            let output: PathBuf = dearmored_dir.path().join("keyring.gpg");
+            keyring_dir = Some(dearmored_dir);
+            let output_arg: String = path_to_arg(&output)?;
+            let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+            let result: Result<CmdResult, GPGError> = handle_cmd_io(
+                self.runner().as_ref(),
+                Some(vec!["--dearmor".to_string(), "--output".to_string(), output_arg]),
+                None,
+                self.version,
+                self.homedir_arg(),
+                cmd_options,
+                self.env.clone(),
+                Some(InputSource::Path(keyring)),
+                Operation::VerifyFile,
+            );
+            result?;
+            keyring = output;
         }
 
-        let mut args: Vec<String> = vec!["--export-secret-key".to_string()];
-        if output.is_some() {
-            set_output_without_confirmation(&mut args, &output.unwrap());
-        } else {
-            // if output folder not specified, system will create a exported_secret_key folder in the set output dir when initalizling the gpg
-            // all exported secret key will be saved to there with filename as secret_key_<timestamp>.sec.asc
-            let gpg_s_key_output_dir = PathBuf::from(self.output_dir.clone())
-                .join("exported_secret_key")
-                .to_string_lossy()
-                .to_string();
-            if !check_is_dir(gpg_s_key_output_dir.clone()) {
-                std::fs::create_dir_all(gpg_s_key_output_dir.clone()).unwrap();
+        let keyring: String = path_to_arg(&keyring)?;
+        let mut args: Vec<String> = Vec::new();
+        if let Some(extra_args) = verify_option.extra_args {
+            match self.check_extra_args(extra_args) {
+                Ok(mut extra_args) => args.append(&mut extra_args),
+                Err(e) => return Err(e),
             }
-            let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
-            let gpg_s_key_output = PathBuf::from(gpg_s_key_output_dir)
-                .join(format!("secret_key_{}.sec.asc", time_stamp))
-                .to_string_lossy()
-                .to_string();
-            set_output_without_confirmation(&mut args, &gpg_s_key_output);
         }
-        if key_id.is_some() {
-            args.append(&mut key_id.unwrap());
+        if let Some(signature_file_path) = verify_option.signature_file_path {
+            match path_to_arg(&signature_file_path) {
+                Ok(signature_file_path) => args.append(&mut vec![signature_file_path, "-".to_string()]),
+                Err(e) => return Err(e),
+            }
         }
+        let result: Result<CmdResult, GPGError> = handle_gpgv_cmd_io(
+            self.runner().as_ref(),
+            Some(args),
+            keyring,
+            self.homedir_arg(),
+            self.env.clone(),
+            verify_option.input,
+        );
+        drop(keyring_dir);
+        return decode_verify_result(result);
+    }
 
-        let result: Result<CmdResult, GPGError> =
-            self.export_key(args, passphrase, Operation::ExportSecretKey);
-        return result;
+    // verify against only the keys provided, they will be imported into a temporary keyring removed afterward,
+    // the keyring and trustdb of the homedir will not be consulted or changed ( ex. a package installer
+    // verifying a download against the signing keys it ships with )
+    pub fn verify_with_keys(
+        &self,
+        input: InputSource,
+        signature_file_path: Option<PathBuf>,
+        keys: Vec<KeyBytes>,
+    ) -> Result<VerifyResult, GPGError> {
+        // input: the file to be verified, check InputSource
+        // signature_file_path: path to the detached signature, None if the file include the signature
+        // keys: the public key(s) the signature can be made by
+
+        if keys.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("at least one key is required to verify with".to_string()),
+                None,
+            ));
+        }
+        // the directory holding the keyring will be removed once dropped
+        let keyring_dir: TempDir = create_temp_dir("keyring")?;
+        // gpg before 2.1 does not understand keybox
+        let keyring_name: &str = if self.version > 0.0 && self.version < 2.1 { "tmp.gpg" } else { "tmp.kbx" };
+        match path_to_arg(&keyring_dir.path().join(keyring_name)) {
+            Ok(keyring) => return self.verify_with_temp_keyring(keyring, input, signature_file_path, keys),
+            Err(e) => return Err(e),
+        }
     }
 
-    fn export_key(
+    fn verify_with_temp_keyring(
         &self,
-        args: Vec<String>,
-        passphrase: Option<String>,
-        ops: Operation,
-    ) -> Result<CmdResult, GPGError> {
+        keyring: String,
+        input: InputSource,
+        signature_file_path: Option<PathBuf>,
+        keys: Vec<KeyBytes>,
+    ) -> Result<VerifyResult, GPGError> {
+        // like gpgv, every key in the keyring was trusted, so the trustdb of the homedir was not needed
+        let keyring_args: Vec<String> = vec![
+            "--no-default-keyring".to_string(),
+            "--keyring".to_string(),
+            keyring,
+            "--trust-model".to_string(),
+            "always".to_string(),
+        ];
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        // the keys were imported one by one, as armored and binary key can not be mixed in one input
+        // the import was part of the verification, it does not write to the homedir
+        for key in keys {
+            let mut args: Vec<String> = keyring_args.clone();
+            args.push("--import".to_string());
+            let result: Result<CmdResult, GPGError> = handle_cmd_io(
+                self.runner().as_ref(),
+                Some(args),
+                None,
+                self.version,
+                self.homedir_arg(),
+                cmd_options.clone(),
+                self.env.clone(),
+                Some(InputSource::Bytes(key)),
+                Operation::VerifyFile,
+            );
+            result?;
+        }
+
+        let mut args: Vec<String> = keyring_args;
+        match self.gen_verify_file_args(signature_file_path, None) {
+            Ok(mut verify_args) => args.append(&mut verify_args),
+            Err(e) => return Err(e),
+        }
         let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
             Some(args),
-            passphrase,
+            None,
             self.version,
-            self.homedir.clone(),
-            self.options.clone(),
+            self.homedir_arg(),
+            cmd_options,
             self.env.clone(),
-            None,
-            None,
-            None,
-            false,
-            false,
-            ops,
+            Some(input),
+            Operation::VerifyFile,
         );
-        return result;
+        return decode_verify_result(result);
     }
 
-    //*******************************************************
-
-    //                   TRUST KEY
+    // verify the signature of a raw git commit or tag object ( ex. output of `git cat-file commit <sha>` )
+    pub fn verify_git_object(&self, raw_object: Vec<u8>) -> Result<VerifyResult, GPGError> {
+        // raw_object: content of the commit or tag object
 
-    //*******************************************************
-    pub fn trust_key(
-        &self,
-        fingerprints: Vec<String>,
-        trust_level: TrustLevel,
-    ) -> Result<CmdResult, GPGError> {
-        // fingerprints: list of fingerprint(s) to trust
-        // trust_level: trust level to set for the key
+        let (payload, signature): (Vec<u8>, String) = split_git_signature(&raw_object)?;
 
-        let args: Vec<String> = vec!["--import-ownertrust".to_string()];
-        let mut input_list: String = String::new();
-        for fingerprint in fingerprints {
-            input_list.push_str(&format!("{}:{}:\n", fingerprint, trust_level.value()));
+        // gpg read the signed data from stdin, so the signature need to be in a file, removed once dropped
+        let mut signature_file: NamedTempFile = create_temp_file("git_signature")?;
+        let args: Vec<String> = self.gen_verify_file_args(Some(signature_file.path().to_path_buf()), None)?;
+        if let Err(e) = signature_file.write_all(signature.as_bytes()).and_then(|_| signature_file.flush()) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(e.to_string()),
+                None,
+            ));
         }
-
-        let result = handle_cmd_io(
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: Result<CmdResult, GPGError> = handle_cmd_io(
+            self.runner().as_ref(),
             Some(args),
             None,
             self.version,
-            self.homedir.clone(),
-            self.options.clone(),
+            self.homedir_arg(),
+            cmd_options,
             self.env.clone(),
-            None,
-            None,
-            Some(input_list.as_bytes().to_vec()),
-            true,
-            false,
-            Operation::TrustKey,
+            Some(InputSource::Bytes(payload)),
+            Operation::VerifyFile,
         );
-
-        return result;
+        drop(signature_file);
+        return decode_verify_result(result);
     }
 
-    //*******************************************************
-
-    //                   SIGN KEY
-
-    //*******************************************************
-    pub fn sign_key(
+    fn gen_verify_file_args(
         &self,
-        signing_key_id: String,
-        target_key_id: String,
-        passphrase: Option<String>,
+        signature_file_path: Option<PathBuf>,
         extra_args: Option<Vec<String>>,
-    ) -> Result<CmdResult, GPGError> {
-        if passphrase.is_some() {
-            if !is_passphrase_valid(passphrase.as_ref().unwrap()) {
-                return Err(GPGError::new(
-                    GPGErrorType::PassphraseError("passphrase invalid".to_string()),
-                    None,
-                ));
+    ) -> Result<Vec<String>, GPGError> {
+        let mut builder: ArgBuilder = self.arg_builder().flag(Flag::Verify);
+        if let Some(signature_file_path) = signature_file_path {
+            // the signed data was read from stdin
+            match path_to_arg(&signature_file_path) {
+                Ok(signature_file_path) => builder = builder.operand(signature_file_path).operand("-"),
+                Err(e) => return Err(e),
             }
         }
-
-        let mut args: Vec<String> = vec![
-            "--yes".to_string(),
-            "--default-key".to_string(),
-            signing_key_id,
-            "--sign-key".to_string(),
-            target_key_id,
-        ];
-        if extra_args.is_some() {
-            args.append(&mut extra_args.unwrap());
+        if let Some(extra_args) = extra_args {
+            builder = builder.extra_args(extra_args);
         }
-
-        let result = handle_cmd_io(
-            Some(args),
-            passphrase,
-            self.version,
-            self.homedir.clone(),
-            self.options.clone(),
-            self.env.clone(),
-            None,
-            None,
-            None,
-            false,
-            false,
-            Operation::SignKey,
-        );
-
-        return result;
+        return builder.build();
     }
 
     //*******************************************************
 
-    //                 FILE ENCRYPTION
+    //                 CHECKSUM MANIFEST
 
     //*******************************************************
-    // to encrypt file, use the EncryptionOption struct to create the encryption options
-    pub fn encrypt(&self, encrypt_option: EncryptOption) -> Result<CmdResult, GPGError> {
-        // encryption_option: struct that contains all the encryption options ( refer to the struct for more info )
+    pub fn sign_manifest(
+        &self,
+        paths: Vec<PathBuf>,
+        algo: Option<String>,
+        keyid: String,
+        key_passphrase: Option<Passphrase>,
+        output_dir: Option<PathBuf>,
+    ) -> Result<CmdResult, GPGError> {
+        // paths: path(s) to the file(s) to be listed in the manifest
+        // algo: digest algorithm to use ex) SHA256, SHA512 [default to SHA256]
+        // keyid: keyid for signing
+        // key_passphrase: required for passphrase protected private key
+        // output_dir: directory to write the manifest to [default to the output dir set in GPG]
 
         //*****************************************************************************************
-        //    NOTE: If signing with a passphrase-protected key,
-        //          an error will occur.
-        //          Please sign separately after encryption if using
-        //          passphrase-protected key.
-        //
-        //    Reason:
-        //           We stream all input to GPG through STDIN.
-        //           When signing with a passphrase-protected key,
-        //           GPG expects the passphrase to be entered after the file content.
-        //           However, since we are streaming input through STDIN,
-        //           it's impossible to distinguish between file content and the passphrase input.
-        //           As a result, the passphrase is mistakenly treated as part of the file data,
-        //           causing the signing process to fail for passphrase protected key.
+        //    NOTE: The manifest will be written as <ALGO>SUMS ( ex. SHA256SUMS ) in the format
+        //          of sha256sum and the clearsigned manifest as <ALGO>SUMS.asc next to it.
+        //          A file inside output_dir was listed with its path relative to output_dir,
+        //          any other file was listed with its file name only.
         //******************************************************************************************
 
-        let p: Option<String> = encrypt_option.passphrase.clone();
-
-        if p.is_some() {
-            if !is_passphrase_valid(p.as_ref().unwrap()) {
-                return Err(GPGError::new(
-                    GPGErrorType::PassphraseError("passphrase invalid".to_string()),
-                    None,
-                ));
-            }
+        let algo: String = algo.unwrap_or("SHA256".to_string()).to_uppercase();
+        if !self.capabilities.supports_digest(&algo) {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!(
+                    "digest algorithm {} is not supported by gpg {}",
+                    algo, self.full_version
+                )),
+                None,
+            ));
+        }
+        let output_dir: PathBuf = match output_dir {
+            Some(output_dir) => output_dir,
+            None => self.get_output_dir(None)?,
+        };
+        let manifest_path: PathBuf = output_dir.join(format!("{}SUMS", algo));
+        check_output_parent(&manifest_path, self.create_output_dirs)?;
+        let base_dir: PathBuf = std::fs::canonicalize(&output_dir).unwrap_or(output_dir.clone());
+
+        let mut manifest: String = String::new();
+        for path in paths.iter() {
+            let digest: String = self.gen_file_digest(path, &algo, Operation::SignManifest)?;
+            let file_path: PathBuf = std::fs::canonicalize(path).unwrap_or(path.clone());
+            let name: String = match file_path.strip_prefix(&base_dir) {
+                Ok(relative) => relative.to_string_lossy().to_string(),
+                Err(_) => file_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or(path.to_string_lossy().to_string()),
+            };
+            manifest.push_str(&format!("{}  {}\n", digest.to_lowercase(), name));
         }
 
-        // generate encrypt operation arguments for gpg
-        let args: Result<Vec<String>, GPGError> = self.gen_encrypt_args(
-            encrypt_option.file_path.clone(),
-            encrypt_option.recipients,
-            encrypt_option.sign,
-            encrypt_option.sign_key,
-            encrypt_option.symmetric,
-            encrypt_option.symmetric_algo,
-            encrypt_option.always_trust,
-            encrypt_option.passphrase,
-            encrypt_option.output,
-            encrypt_option.extra_args,
-        );
-
-        match args {
-            Ok(_) => {}
-            Err(e) => {
-                return Err(e);
-            }
+        if let Err(e) = std::fs::write(&manifest_path, manifest) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(e.to_string()),
+                None,
+            ));
         }
+        let mut sign_option: SignOption = SignOption::default(
+            InputSource::Path(manifest_path.clone()),
+            keyid,
+            key_passphrase,
+            Some(append_extension(&manifest_path, "asc")),
+        );
+        // clearsign is always armored
+        sign_option.extra_args = Some(vec!["--digest-algo".to_string(), algo]);
+        return self.sign(sign_option);
+    }
+
+    pub fn verify_manifest(&self, manifest_path: impl AsRef<Path>) -> Result<ManifestVerifyResult, GPGError> {
+        // manifest_path: path to the clearsigned manifest ( ex. SHA256SUMS.asc )
+
+        //*****************************************************************************************
+        //    NOTE: Only the content that was covered by the signature will be checked, the files
+        //          listed will be looked up relative to the directory of the manifest.
+        //******************************************************************************************
 
+        let manifest_path: &Path = manifest_path.as_ref();
+        // gpg write the content covered by the signature to a file, the directory holding it will be removed once dropped
+        let content_dir: TempDir = create_temp_dir("manifest")?;
+        let content_path: PathBuf = content_dir.path().join("manifest");
+        let content_arg: String = path_to_arg(&content_path)?;
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
         let result: Result<CmdResult, GPGError> = handle_cmd_io(
-            Some(args.unwrap()),
-            p,
+            self.runner().as_ref(),
+            Some(vec![
+                "--output".to_string(),
+                content_arg,
+                "--decrypt".to_string(),
+            ]),
+            None,
             self.version,
-            self.homedir.clone(),
-            self.options.clone(),
+            self.homedir_arg(),
+            cmd_options,
             self.env.clone(),
-            encrypt_option.file,
-            encrypt_option.file_path,
-            None,
-            true,
-            true,
-            Operation::Encrypt,
+            Some(InputSource::Path(manifest_path.to_path_buf())),
+            Operation::VerifyManifest,
         );
-
-        match result {
-            Ok(result) => {
-                return Ok(result);
-            }
+        let content: Result<String, std::io::Error> = std::fs::read_to_string(&content_path);
+        drop(content_dir);
+        let mut verify_result: CmdResult = result?;
+        // the manifest can only be trusted with a good signature
+        if !VerifyResult::from_cmd_result(verify_result.clone()).valid {
+            verify_result.success = false;
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError(verify_result.get_error_message()),
+                Some(verify_result),
+            ));
+        }
+        let content: String = match content {
+            Ok(content) => content,
             Err(e) => {
-                return Err(e);
+                return Err(GPGError::new(
+                    GPGErrorType::ReadFailError(e.to_string()),
+                    Some(verify_result),
+                ))
             }
-        }
-    }
-
-    fn gen_encrypt_args(
-        &self,
-        file_path: Option<String>,
-        recipients: Option<Vec<String>>,
-        sign: bool,
-        sign_key: Option<String>,
-        symmetric: bool,
-        symmetric_algo: Option<String>,
-        always_trust: bool,
-        passphrase: Option<String>,
-        output: Option<String>,
-        extra_args: Option<Vec<String>>,
-    ) -> Result<Vec<String>, GPGError> {
-        let mut args: Vec<String> = vec![];
-        let mut encrypt_type: String = "".to_string();
+        };
 
-        if symmetric {
-            args.append(&mut vec![
-                "--symmetric".to_string(),
-            ]);
-            if self.version>=2.1{
-                args.push("--no-symkey-cache".to_string());
+        let base_dir: PathBuf = manifest_path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or(PathBuf::from("."));
+        let mut manifest_result: ManifestVerifyResult = ManifestVerifyResult {
+            verify_result: verify_result,
+            verified: vec![],
+            failed: vec![],
+            missing: vec![],
+        };
+        for line in content.lines() {
+            // <digest>  <name> or <digest> *<name> for binary mode
+            let (digest, name): (&str, &str) = match line.split_once(' ') {
+                Some((digest, name)) => (digest, name.trim_start_matches([' ', '*'])),
+                None => continue,
+            };
+            let algo: &str = match digest.len() {
+                40 => "SHA1",
+                56 => "SHA224",
+                64 => "SHA256",
+                96 => "SHA384",
+                128 => "SHA512",
+                _ => {
+                    manifest_result.failed.push(name.to_string());
+                    continue;
+                }
+            };
+            // a name leaving the directory of the manifest ( absolute or with .. ) was not looked up
+            let name_path: &Path = Path::new(name);
+            if name.is_empty() || !name_path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+                manifest_result.failed.push(name.to_string());
+                continue;
             }
-            if passphrase.is_none() {
-                return Err(GPGError::new(
-                    GPGErrorType::PassphraseError(
-                        "passphrase is required if encrypting symmetrically ".to_string(),
-                    ),
-                    None,
-                ));
+            let file_path: PathBuf = base_dir.join(name_path);
+            if !file_path.is_file() {
+                manifest_result.missing.push(name.to_string());
+                continue;
             }
-            if symmetric_algo.is_some() {
-                args.append(&mut vec![
-                    "--personal-cipher-preferences".to_string(),
-                    symmetric_algo.unwrap(),
-                ]);
+            let file_digest: Result<String, GPGError> =
+                self.gen_file_digest(&file_path, algo, Operation::VerifyManifest);
+            match file_digest {
+                Ok(file_digest) if file_digest.eq_ignore_ascii_case(digest) => {
+                    manifest_result.verified.push(name.to_string())
+                }
+                _ => manifest_result.failed.push(name.to_string()),
             }
-            encrypt_type.push_str("pass_");
         }
-        if recipients.is_some() {
-            args.push("--encrypt".to_string());
-            for recipient in recipients.unwrap() {
-                args.append(&mut vec!["--recipient".to_string(), recipient]);
+        return Ok(manifest_result);
+    }
+
+    // compute the digest of a file with gpg --print-md, return the digest in hex
+    fn gen_file_digest(&self, path: &Path, algo: &str, ops: Operation) -> Result<String, GPGError> {
+        let cmd_options: Option<Vec<String>> = self.cmd_options()?;
+        let result: CmdResult = handle_cmd_io(
+            self.runner().as_ref(),
+            Some(vec!["--print-md".to_string(), algo.to_string()]),
+            None,
+            self.version,
+            self.homedir_arg(),
+            cmd_options,
+            self.env.clone(),
+            Some(InputSource::Path(path.to_path_buf())),
+            ops,
+        )?;
+        // with colons, the digest of stdin was printed as :<algo id>:<digest>:
+        let digest: Option<String> = result.raw_data.as_ref().and_then(|raw_data| {
+            raw_data.lines().find_map(|l| {
+                let fields: Vec<&str> = l.split(':').collect();
+                if fields.len() >= 3 && fields[0].is_empty() && !fields[2].is_empty() {
+                    return Some(fields[2].to_string());
+                }
+                return None;
+            })
+        });
+        match digest {
+            Some(digest) => return Ok(digest),
+            None => {
+                return Err(GPGError::new(
+                    GPGErrorType::GPGProcessError(format!("failed to compute {} digest of {}", algo, path.display())),
+                    Some(result),
+                ))
             }
-            encrypt_type.push_str("keys_");
         }
+    }
+}
 
-        if args.len() == 0 {
+// collect the files in a directory following the policy
+fn collect_dir_files(dir: &Path, policy: &VerifyPolicy, files: &mut Vec<PathBuf>) -> Result<(), GPGError> {
+    let entries: std::fs::ReadDir = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
             return Err(GPGError::new(
-                GPGErrorType::InvalidArgumentError(
-                    "Please choose symmetric or keys to encrypt your file".to_string(),
-                ),
+                GPGErrorType::ReadFailError(e.to_string()),
                 None,
             ));
         }
-
-        if self.armor {
-            args.push("--armor".to_string());
-        }
-        if output.is_some() {
-            set_output_without_confirmation(&mut args, &output.unwrap());
-        } else {
-            // if the system is handling the output
-            // the name wil be [<encryption_type>_encrypted_file_<YYYYMMDD_HH/MM/SS/NANO-SECOND>.<extension>]
-            // the encryption type will either [key] for public key encryption or [pass] for symmetric encryption or both
-            // the extension will be the same if file_path is provided,
-            // if a rust File type is provided, the file extension will be default to .gpg
-
-            let ext: String = get_file_extension(file_path);
-            let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
-            let out: String = PathBuf::from(self.output_dir.clone())
-                .join(format!(
-                    "{}_encrypted_file_{}.{}",
-                    encrypt_type, time_stamp, ext
-                ))
-                .to_string_lossy()
-                .to_string();
-            args.append(&mut vec!["--output".to_string(), out]);
+    };
+    for entry in entries.flatten() {
+        let path: PathBuf = entry.path();
+        let is_hidden: bool = entry.file_name().to_string_lossy().starts_with('.');
+        if is_hidden && !policy.include_hidden {
+            continue;
         }
-
-        if sign {
-            if sign_key.is_some() {
-                args.append(&mut vec![
-                    "--sign".to_string(),
-                    "--default-key".to_string(),
-                    sign_key.unwrap(),
-                ]);
-            } else {
-                args.push("--sign".to_string());
+        // symlink will not be followed to avoid walking out of the directory or in loop
+        let file_type: std::fs::FileType = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+        if file_type.is_dir() {
+            if policy.recursive {
+                collect_dir_files(&path, policy, files)?;
             }
+        } else if file_type.is_file() {
+            files.push(path);
         }
+    }
+    return Ok(());
+}
 
-        if always_trust {
-            args.append(&mut vec!["--trust-model".to_string(), "always".to_string()]);
-        }
+// turn the result of a smartcard operation into an error if the card was not usable or
+// the operation was stopped before it was completed
+fn handle_card_result(
+    result: Result<CmdResult, GPGError>,
+    unexpected_prompt: Option<String>,
+) -> Result<CmdResult, GPGError> {
+    let cmd_result: Option<CmdResult> = match &result {
+        Ok(result) => Some(result.clone()),
+        Err(e) => e.cmd_result.clone(),
+    };
+    let card_problem: Option<String> = cmd_result.as_ref().and_then(|r| {
+        r.problem
+            .as_ref()
+            .and_then(|problems| problems.iter().find_map(|p| p.get("card").cloned()))
+    });
+    if let Some(card_problem) = card_problem {
+        return Err(GPGError::new(
+            GPGErrorType::CardError(card_problem),
+            cmd_result,
+        ));
+    }
+    if let Some(unexpected_prompt) = unexpected_prompt {
+        return Err(GPGError::new(
+            GPGErrorType::CardError(unexpected_prompt),
+            cmd_result,
+        ));
+    }
+    return result;
+}
 
-        if extra_args.is_some() {
-            args.append(&mut extra_args.unwrap());
-        }
+//*******************************************************
+
+//               RELATED TO GPG BUILDER
+
+//*******************************************************
+// construct a GPG object with settings that GPG::init does not take
+// ex) GPGBuilder::default().homedir(homedir).require_version(">=2.2").build()
+#[derive(Debug, Clone)]
+pub struct GPGBuilder {
+    // homedir: a path to a directory where the local key were at, None for system set homedir
+    pub homedir: Option<PathBuf>,
+    // output_dir: a path to a directory where the output files from gpg will save to, None for system set output dir
+    pub output_dir: Option<PathBuf>,
+    // armor: a boolean to indicate if the output should be armored
+    pub armor: bool,
+    // runner: to run the gpg command, None to spawn a local gpg process ( same as GPG::init )
+    pub runner: Option<Arc<dyn GpgRunner>>,
+    // passphrase_provider: set as GPG.passphrase_provider, check PassphraseProvider
+    pub passphrase_provider: Option<Arc<dyn PassphraseProvider>>,
+    // passphrase_retries: set as GPG.passphrase_retries
+    pub passphrase_retries: u32,
+    // audit_sink: set as GPG.audit_sink, check AuditSink
+    pub audit_sink: Option<Arc<dyn AuditSink>>,
+    // metrics_recorder: set as GPG.metrics_recorder, check MetricsRecorder
+    pub metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+    // homedir_lock_timeout: set as GPG.homedir_lock_timeout
+    pub homedir_lock_timeout: Option<Duration>,
+    // read_only: set as GPG.read_only
+    pub read_only: bool,
+    // no_output_dir: set as GPG.no_output_dir, the output_dir will not be created when building
+    pub no_output_dir: bool,
+    // list_keys_cache_ttl: set as GPG.list_keys_cache_ttl
+    pub list_keys_cache_ttl: Option<Duration>,
+    // required_version: the version of gpg the application depends on ( ex. >=2.2 ),
+    //                   build will fail with UnsupportedVersion if gpg found does not satisfy it
+    pub required_version: Option<String>,
+    // gpg_program: the gpg binary to run instead of gpg found on PATH, only for the default runner
+    pub gpg_program: Option<PathBuf>,
+    // gpg_selection: a version requirement ( ex. =2 for the newest 2.x ) to choose the gpg binary to run
+    //                from find_gpg_installations when building, ignored if gpg_program was set
+    pub gpg_selection: Option<String>,
+    // verbosity: set as GPG.verbosity
+    pub verbosity: Verbosity,
+    // output_layout: set as GPG.output_layout
+    pub output_layout: OutputLayout,
+    // output_retention: set as GPG.output_retention
+    pub output_retention: Option<OutputRetention>,
+    // output_checksum: set as GPG.output_checksum
+    pub output_checksum: Option<DigestAlgo>,
+}
 
-        return Ok(args);
+impl Default for GPGBuilder {
+    // with system set homedir and output dir, armored output and a local gpg process
+    fn default() -> GPGBuilder {
+        return GPGBuilder {
+            homedir: None,
+            output_dir: None,
+            armor: true,
+            runner: None,
+            passphrase_provider: None,
+            passphrase_retries: 0,
+            audit_sink: None,
+            metrics_recorder: None,
+            homedir_lock_timeout: Some(DEFAULT_HOMEDIR_LOCK_TIMEOUT),
+            read_only: false,
+            no_output_dir: false,
+            list_keys_cache_ttl: None,
+            required_version: None,
+            gpg_program: None,
+            gpg_selection: None,
+            verbosity: Verbosity::Normal,
+            output_layout: OutputLayout::default(),
+            output_retention: None,
+            output_checksum: None,
+        };
     }
+}
 
-    //*******************************************************
+impl GPGBuilder {
+    pub fn homedir(mut self, homedir: PathBuf) -> GPGBuilder {
+        self.homedir = Some(homedir);
+        return self;
+    }
 
-    //                   FILE DECRYPTION
+    pub fn output_dir(mut self, output_dir: PathBuf) -> GPGBuilder {
+        self.output_dir = Some(output_dir);
+        return self;
+    }
 
-    //*******************************************************
-    // to encrypt file, use the DecryptionOption struct to create the decryption options
-    pub fn decrypt(&self, decrypt_option: DecryptOption) -> Result<CmdResult, GPGError> {
-        // decrypt_option: struct that contains all the decryption options ( refer to the struct for more info )
+    pub fn armor(mut self, armor: bool) -> GPGBuilder {
+        self.armor = armor;
+        return self;
+    }
 
-        let k_p: Option<String> = decrypt_option.key_passphrase.clone();
-        let p: Option<String> = decrypt_option.passphrase.clone();
-        let mut pass: Option<String> = None;
+    pub fn runner(mut self, runner: Arc<dyn GpgRunner>) -> GPGBuilder {
+        self.runner = Some(runner);
+        return self;
+    }
 
-        if k_p.is_some() {
-            if !is_passphrase_valid(k_p.as_ref().unwrap()) {
-                return Err(GPGError::new(
-                    GPGErrorType::PassphraseError("key passphrase invalid".to_string()),
-                    None,
-                ));
-            }
-            pass = k_p;
-        } else if p.is_some() {
-            if !is_passphrase_valid(p.as_ref().unwrap()) {
-                return Err(GPGError::new(
-                    GPGErrorType::PassphraseError("passphrase invalid".to_string()),
-                    None,
-                ));
-            }
-            pass = p;
-        }
+    pub fn passphrase_provider(mut self, provider: Arc<dyn PassphraseProvider>) -> GPGBuilder {
+        self.passphrase_provider = Some(provider);
+        return self;
+    }
 
-        let args: Vec<String> = self.gen_decrypt_args(
-            decrypt_option.file_path.clone(),
-            decrypt_option.recipient,
-            decrypt_option.always_trust,
-            decrypt_option.output,
-            decrypt_option.extra_args,
-        );
-        let result: Result<CmdResult, GPGError> = handle_cmd_io(
-            Some(args),
-            pass,
-            self.version,
-            self.homedir.clone(),
-            self.options.clone(),
-            self.env.clone(),
-            decrypt_option.file,
-            decrypt_option.file_path,
-            None,
-            true,
-            true,
-            Operation::Decrypt,
-        );
+    pub fn passphrase_retries(mut self, retries: u32) -> GPGBuilder {
+        self.passphrase_retries = retries;
+        return self;
+    }
 
-        match result {
-            Ok(result) => {
-                return Ok(result);
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        }
+    pub fn audit_sink(mut self, sink: Arc<dyn AuditSink>) -> GPGBuilder {
+        self.audit_sink = Some(sink);
+        return self;
     }
 
-    fn gen_decrypt_args(
-        &self,
-        file_path: Option<String>,
-        recipient: Option<String>,
-        always_trust: bool,
-        output: Option<String>,
-        extra_args: Option<Vec<String>>,
-    ) -> Vec<String> {
-        let mut args: Vec<String> = vec!["--decrypt".to_string()];
-        if recipient.is_some() {
-            args.append(&mut vec!["--recipient".to_string(), recipient.unwrap()]);
-        }
-        if always_trust {
-            args.append(&mut vec!["--trust-model".to_string(), "always".to_string()]);
-        }
-        if output.is_some() {
-            set_output_without_confirmation(&mut args, &output.unwrap());
-        } else {
-            // if the system is handling the output
-            // the name wil be [<encryption_type>_encrypted_file_<YYYYMMDD_HH/MM/SS/NANO-SECOND>.<extension>]
-            // the encryption type will either [key] for public key encryption or [pass] for symmetric encryption
-            // the extension will be the same if file_path is provided,
-            // if a rust File type is provided, the name will be extension will be default to gpg
-
-            let ext: String = get_file_extension(file_path);
-            let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
-            let out: String = PathBuf::from(self.output_dir.clone())
-                .join(format!("decrypted_file_{}.{}", time_stamp, ext))
-                .to_string_lossy()
-                .to_string();
-            args.append(&mut vec!["--output".to_string(), out]);
-        }
+    pub fn metrics_recorder(mut self, recorder: Arc<dyn MetricsRecorder>) -> GPGBuilder {
+        self.metrics_recorder = Some(recorder);
+        return self;
+    }
 
-        if extra_args.is_some() {
-            args.append(&mut extra_args.unwrap());
-        }
-        return args;
+    // None to not lock the homedir
+    pub fn homedir_lock_timeout(mut self, timeout: Option<Duration>) -> GPGBuilder {
+        self.homedir_lock_timeout = timeout;
+        return self;
     }
 
-    //*******************************************************
+    pub fn read_only(mut self, read_only: bool) -> GPGBuilder {
+        self.read_only = read_only;
+        return self;
+    }
 
-    //                   FILE SIGNING
+    pub fn no_output_dir(mut self, no_output_dir: bool) -> GPGBuilder {
+        self.no_output_dir = no_output_dir;
+        return self;
+    }
 
-    //*******************************************************
-    pub fn sign(&self, sign_option: SignOption) -> Result<CmdResult, GPGError> {
-        // sign_option: struct that contains all the signing options ( refer to the struct for more info )
+    pub fn list_keys_cache_ttl(mut self, ttl: Duration) -> GPGBuilder {
+        self.list_keys_cache_ttl = Some(ttl);
+        return self;
+    }
 
-        if sign_option.key_passphrase.is_some() {
-            if !is_passphrase_valid(sign_option.key_passphrase.as_ref().unwrap()) {
-                return Err(GPGError::new(
-                    GPGErrorType::PassphraseError("passphrase invalid".to_string()),
-                    None,
-                ));
-            }
-        };
-        let args: Vec<String> = self.gen_sign_args(
-            sign_option.keyid.clone(),
-            sign_option.clearsign,
-            sign_option.detach,
-            sign_option.output,
-            sign_option.extra_args,
-        );
+    pub fn verbosity(mut self, verbosity: Verbosity) -> GPGBuilder {
+        self.verbosity = verbosity;
+        return self;
+    }
 
-        let result: Result<CmdResult, GPGError> = handle_cmd_io(
-            Some(args),
-            sign_option.key_passphrase,
-            self.version,
-            self.homedir.clone(),
-            self.options.clone(),
-            self.env.clone(),
-            sign_option.file,
-            sign_option.file_path,
-            None,
-            true,
-            true,
-            Operation::Sign,
-        );
-        match result {
-            Ok(result) => {
-                return Ok(result);
-            }
-            Err(e) => {
-                return Err(e);
+    pub fn output_layout(mut self, output_layout: OutputLayout) -> GPGBuilder {
+        self.output_layout = output_layout;
+        return self;
+    }
+
+    pub fn output_retention(mut self, output_retention: OutputRetention) -> GPGBuilder {
+        self.output_retention = Some(output_retention);
+        return self;
+    }
+
+    pub fn output_checksum(mut self, algo: DigestAlgo) -> GPGBuilder {
+        self.output_checksum = Some(algo);
+        return self;
+    }
+
+    // operator can be one of >=, >, <=, <, =, a requirement without operator will be treated as >=
+    // ex) ">=2.2", ">2.1", "=2.4", "2.2.27"
+    pub fn require_version(mut self, requirement: &str) -> GPGBuilder {
+        self.required_version = Some(requirement.to_string());
+        return self;
+    }
+
+    // run the gpg binary at the path ( ex. /usr/local/bin/gpg2 ) instead of gpg found on PATH
+    pub fn gpg_program(mut self, gpg_program: PathBuf) -> GPGBuilder {
+        self.gpg_program = Some(gpg_program);
+        return self;
+    }
+
+    // run the newest gpg binary installed satisfying the requirement, check find_gpg_installations
+    // ex) "=2" for the newest 2.x, ">=2.2"
+    pub fn select_gpg(mut self, requirement: &str) -> GPGBuilder {
+        self.gpg_selection = Some(requirement.to_string());
+        return self;
+    }
+
+    // initialize the GPG object and check the version of gpg found against the required version
+    pub fn build(self) -> Result<GPG, GPGError> {
+        // an invalid requirement is a mistake of the application, reported before gpg was probed
+        if let Some(requirement) = &self.required_version {
+            parse_version_requirement(requirement)?;
+        }
+        let gpg_program: Option<PathBuf> = match (self.gpg_program, &self.gpg_selection) {
+            (Some(gpg_program), _) => Some(gpg_program),
+            (None, Some(requirement)) => match select_gpg_installation(&find_gpg_installations(), requirement) {
+                Ok(installation) => Some(installation.path),
+                Err(e) => return Err(e),
+            },
+            (None, None) => None,
+        };
+        if gpg_program.is_some() && self.runner.is_some() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(
+                    "the gpg binary can not be chosen for a custom runner".to_string(),
+                ),
+                None,
+            ));
+        }
+        // same as GPG::init_with_runner for a custom runner or gpg binary, GPG::init otherwise
+        let (runner, cached_program): (Arc<dyn GpgRunner>, Option<PathBuf>) = match (self.runner, gpg_program) {
+            (Some(runner), _) => (runner, None),
+            // cached separately from gpg found on PATH for the same homedir
+            (None, Some(gpg_program)) => {
+                (Arc::new(ProcessRunner::with_gpg_program(gpg_program.clone())), Some(gpg_program))
             }
+            (None, None) => (Arc::new(ProcessRunner::default()), Some(PathBuf::from("gpg"))),
+        };
+        let mut gpg: GPG =
+            GPG::init_gpg(self.homedir, self.output_dir, self.armor, runner, cached_program, self.no_output_dir)?;
+        if let Some(requirement) = &self.required_version {
+            check_version_requirement(&gpg.full_version, requirement)?;
         }
+        gpg.passphrase_provider = self.passphrase_provider;
+        gpg.passphrase_retries = self.passphrase_retries;
+        gpg.audit_sink = self.audit_sink;
+        gpg.metrics_recorder = self.metrics_recorder;
+        gpg.homedir_lock_timeout = self.homedir_lock_timeout;
+        gpg.read_only = self.read_only;
+        gpg.list_keys_cache_ttl = self.list_keys_cache_ttl;
+        gpg.verbosity = self.verbosity;
+        gpg.output_layout = self.output_layout;
+        gpg.output_retention = self.output_retention;
+        gpg.output_checksum = self.output_checksum;
+        return Ok(gpg);
     }
+}
 
-    fn gen_sign_args(
-        &self,
-        keyid: Option<String>,
-        clearsign: bool,
-        detach: bool,
-        output: Option<String>,
-        extra_args: Option<Vec<String>>,
-    ) -> Vec<String> {
-        let mut args: Vec<String> = vec!["--sign".to_string()];
-        let time_stamp: String = Local::now().format("%Y%m%d-%H:%M:%S:%9f").to_string();
+//*******************************************************
 
-        if clearsign {
-            args.push("--clearsign".to_string());
+//              RELATED TO OUTPUT LAYOUT
+
+//*******************************************************
+// the sub directory of the output_dir ( ex. encrypted ) the output of each operation will be written to when the
+// operation did not provide one, created if it does not exist, None to write it to the output_dir itself.
+// A sub directory must be a relative path inside the output_dir, an OutputDirError will be returned otherwise
+// ex) OutputLayout { decrypted: Some(PathBuf::from("inbox")), ..OutputLayout::by_operation() }
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputLayout {
+    // encrypted: for encrypt, reencrypt_symmetric and rekey
+    pub encrypted: Option<PathBuf>,
+    // decrypted: for decrypt
+    pub decrypted: Option<PathBuf>,
+    // signatures: for sign, a detached signature of a file was still written next to the file
+    pub signatures: Option<PathBuf>,
+    // exported_public_keys: for export_public_key
+    pub exported_public_keys: Option<PathBuf>,
+    // exported_secret_keys: for export_secret_key
+    pub exported_secret_keys: Option<PathBuf>,
+    // backups: for backup_keys
+    pub backups: Option<PathBuf>,
+}
+
+impl Default for OutputLayout {
+    // for default, the encrypted, decrypted and signed output were written to the output_dir itself,
+    // the exported keys and backups to their own sub directory
+    fn default() -> OutputLayout {
+        return OutputLayout {
+            encrypted: None,
+            decrypted: None,
+            signatures: None,
+            exported_public_keys: Some(PathBuf::from("exported_public_key")),
+            exported_secret_keys: Some(PathBuf::from("exported_secret_key")),
+            backups: Some(PathBuf::from("key_backup")),
         };
-        if detach {
-            args.push("--detach-sign".to_string());
-            let extension = if self.armor { ".asc" } else { ".sig" };
-            let file_path: String = output.unwrap_or(
-                PathBuf::from(self.output_dir.clone())
-                    .join(format!("detach_sign_{}{}", time_stamp, extension))
-                    .to_string_lossy()
-                    .to_string(),
-            );
-            set_output_without_confirmation(&mut args, &file_path);
-        } else {
-            let file_path: String = output.unwrap_or(
-                PathBuf::from(self.output_dir.clone())
-                    .join(format!("embedded_sign_{}.gpg", time_stamp))
-                    .to_string_lossy()
-                    .to_string(),
-            );
-            set_output_without_confirmation(&mut args, &file_path);
-        }
+    }
+}
 
-        if keyid.is_some() {
-            args.append(&mut vec!["--default-key".to_string(), keyid.unwrap()]);
+impl OutputLayout {
+    // for by_operation, every operation had its own sub directory
+    // ( encrypted, decrypted, signatures, exported_public_key, exported_secret_key, key_backup )
+    pub fn by_operation() -> OutputLayout {
+        return OutputLayout {
+            encrypted: Some(PathBuf::from("encrypted")),
+            decrypted: Some(PathBuf::from("decrypted")),
+            signatures: Some(PathBuf::from("signatures")),
+            ..OutputLayout::default()
         };
+    }
+}
 
-        if self.armor {
-            args.push("--armor".to_string());
-        }
+//*******************************************************
 
-        if extra_args.is_some() {
-            args.append(&mut extra_args.unwrap());
-        }
+//             RELATED TO OUTPUT RETENTION
+
+//*******************************************************
+// how long and how much the output the crate created in the output_dir will be kept for, check GPG.clean_output_dir
+// only the default output named with a timestamp ( ex. decrypted_file_<datetime>.txt ) in the output_dir and the
+// sub directories of the OutputLayout was counted and removed, an output named after the input
+// ( OutputNaming::FromInput ) or provided to the operation was never touched
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputRetention {
+    // max_age: an output last written to longer ago than this will be removed, None to keep them regardless of age
+    pub max_age: Option<Duration>,
+    // max_total_size: the oldest output(s) will be removed until the rest was within this many bytes,
+    //                 None to not limit the size
+    pub max_total_size: Option<u64>,
+}
+
+impl OutputRetention {
+    pub fn new(max_age: Option<Duration>, max_total_size: Option<u64>) -> OutputRetention {
+        return OutputRetention {
+            max_age: max_age,
+            max_total_size: max_total_size,
+        };
+    }
+}
+
+//*******************************************************
 
-        return args;
-    }
+//            RELATED TO OPERATION OVERRIDES
 
-    //*******************************************************
+//*******************************************************
+// settings to override for a one-off operation, check GPG.with_overrides
+// ex) OperationOverrides { homedir: Some(homedir), ..OperationOverrides::default() }
+#[derive(Debug, Clone)]
+pub struct OperationOverrides {
+    // homedir: run the operation in this homedir instead ( ex. another keyring ), created if it does not exist
+    pub homedir: Option<PathBuf>,
+    // env: environment variables added to GPG.env, replacing the one of the same name
+    pub env: Option<HashMap<String, String>>,
+    // options: arguments appended to GPG.options, checked the same way unless unsafe_extra_args was set
+    pub options: Option<Vec<String>>,
+    // timeout: gpg will be killed if it was still running after this long and a TimeoutError returned,
+    //          only supported by ProcessRunner ( or a runner implementing GpgRunner.with_timeout )
+    pub timeout: Option<Duration>,
+    // verbosity: how much gpg will log for the operation ( ex. Verbosity::Debug to diagnose a failing operation )
+    pub verbosity: Option<Verbosity>,
+}
 
-    //                   FILE VERIFICATION
+impl Default for OperationOverrides {
+    // override nothing
+    fn default() -> OperationOverrides {
+        return OperationOverrides {
+            homedir: None,
+            env: None,
+            options: None,
+            timeout: None,
+            verbosity: None,
+        };
+    }
+}
 
-    //*******************************************************
-    pub fn verify_file(
-        &self,
-        file: Option<File>,
-        file_path: Option<String>,
-        signature_file_path: Option<String>,
-        extra_args: Option<Vec<String>>,
-    ) -> Result<CmdResult, GPGError> {
-        // file: file object
-        // file_path: path to file
-        // signature_file_path: path to signature file
-        // extra_args: extra arguments to pass to gpg
+//*******************************************************
 
-        //*****************************************************************************************
-        //    NOTE: If only file or file_path is provided, it expected the file to include a
-        //          complete signature.
-        //          For detached signature, signature_file_path is required along
-        //          with file or file_path
-        //******************************************************************************************
+//              RELATED TO RAW COMMAND
 
-        let args: Vec<String> = self.gen_verify_file_args(signature_file_path, extra_args);
-        let result: Result<CmdResult, GPGError> = handle_cmd_io(
-            Some(args),
-            None,
-            self.version,
-            self.homedir.clone(),
-            self.options.clone(),
-            self.env.clone(),
-            file,
-            file_path.clone(),
-            None,
-            true,
-            true,
-            Operation::VerifyFile,
-        );
-        match result {
-            Ok(result) => {
-                return Ok(result);
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        }
-    }
+//*******************************************************
+// a gpg command the crate does not wrap yet, run through the same process layer as every operation, check GPG.run_raw
+// ex) RawCommand { input: Some(InputSource::Path(path)), ..RawCommand::new(vec!["--list-packets".to_string()], Operation::NotSet) }
+#[derive(Debug)]
+pub struct RawCommand {
+    // args: the arguments of the command, passed after the ones set by crab-gnupg ( --homedir, --status-fd ... )
+    //       and GPG.options, checked the same way as extra_args unless unsafe_extra_args was set
+    pub args: Vec<String>,
+    // input: written into stdin after the passphrase, None if gpg does not read any
+    pub input: Option<InputSource>,
+    // passphrase: passed to gpg through --passphrase-fd ( ex. of the secret key, or to encrypt symmetrically ),
+    //             an empty passphrase will be passed if not provided so gpg will not ask the pinentry
+    pub passphrase: Option<Passphrase>,
+    // operation: what the command was for, a mutating one ( ex. Operation::ImportKey ) will be refused if read only,
+    //            lock the homedir and invalidate the cached listing, like the operation wrapped by the crate
+    pub operation: Operation,
+    // capture: what will be kept of the output, check CaptureMode
+    pub capture: CaptureMode,
+}
 
-    fn gen_verify_file_args(
-        &self,
-        signature_file_path: Option<String>,
-        extra_args: Option<Vec<String>>,
-    ) -> Vec<String> {
-        let mut args: Vec<String> = vec!["--verify".to_string()];
-        if signature_file_path.is_some() {
-            args.append(&mut vec![signature_file_path.unwrap(), "-".to_string()]);
-        }
-        if extra_args.is_some() {
-            args.append(&mut extra_args.unwrap());
-        }
-        return args;
+impl RawCommand {
+    // for new, without input nor passphrase, and the output kept in raw_data of the CmdResult
+    pub fn new(args: Vec<String>, operation: Operation) -> RawCommand {
+        return RawCommand {
+            args: args,
+            input: None,
+            passphrase: None,
+            operation: operation,
+            capture: CaptureMode::Stdout,
+        };
     }
 }
 
@@ -1187,10 +5627,8 @@ impl GPG {
 //*******************************************************
 #[derive(Debug)]
 pub struct EncryptOption {
-    // file: file object
-    pub file: Option<File>,
-    // file_path: path to file
-    pub file_path: Option<String>,
+    // input: the file to be processed, check InputSource
+    pub input: InputSource,
     // receipients: list of receipients keyid
     pub recipients: Option<Vec<String>>,
     // sign: whether to sign the file
@@ -1205,26 +5643,36 @@ pub struct EncryptOption {
     // always_trust: whether to always trust keys
     pub always_trust: bool,
     // passphrase: passphrase to use for symmetric encryption [required if symmetric is true]
-    pub passphrase: Option<String>,
+    pub passphrase: Option<Passphrase>,
     // output: path to write the encrypted output,
     //         will use the default output dir set in GPG if not provided and
     //         with file name as [<encryption_type>_encrypted_file_<datetime>.<extension>]
-    pub output: Option<String>,
+    pub output: Option<PathBuf>,
     // extra_args: extra arguments to pass to gpg
     pub extra_args: Option<Vec<String>>,
+    // reject_unusable_keys: whether to return an error before encrypting if any recipient or sign key was revoked, expired or disabled
+    pub reject_unusable_keys: bool,
+    // s2k_count: how many bytes of the salted passphrase will be hashed into the key for symmetric encryption,
+    //            between S2K_COUNT_MIN and S2K_COUNT_MAX [if not provided, gpg will use its default]
+    pub s2k_count: Option<u32>,
+    // s2k_digest_algo: digest algorithm to hash the passphrase with for symmetric encryption
+    pub s2k_digest_algo: Option<DigestAlgo>,
+    // s2k_cipher_algo: cipher to encrypt with for symmetric encryption, when symmetric_algo was not provided
+    pub s2k_cipher_algo: Option<CipherAlgo>,
+    // shred_input: remove the input file as the mode once it was encrypted and the output was verified,
+    //              only for InputSource::Path [if not provided, the input will be left as it is]
+    pub shred_input: Option<ShredMode>,
 }
 
 impl EncryptOption {
     // for default, it will be a encryption with just keys and always trust will be true
     pub fn default(
-        file: Option<File>,
-        file_path: Option<String>,
+        input: InputSource,
         recipients: Vec<String>,
-        output: Option<String>,
+        output: Option<PathBuf>,
     ) -> EncryptOption {
         return EncryptOption {
-            file: file,
-            file_path: file_path,
+            input: input,
             recipients: Some(recipients),
             sign: false,
             sign_key: None,
@@ -1234,20 +5682,23 @@ impl EncryptOption {
             passphrase: None,
             output: output,
             extra_args: None,
+            reject_unusable_keys: false,
+            s2k_count: None,
+            s2k_digest_algo: None,
+            s2k_cipher_algo: None,
+            shred_input: None,
         };
     }
 
     // for with_symmetric, it will be a encryption with passphrase instead of keys and always trust will be true
     pub fn with_symmetric(
-        file: Option<File>,
-        file_path: Option<String>,
+        input: InputSource,
         symmetric_algo: Option<String>,
-        passphrase: String,
-        output: Option<String>,
+        passphrase: Passphrase,
+        output: Option<PathBuf>,
     ) -> EncryptOption {
         return EncryptOption {
-            file: file,
-            file_path: file_path,
+            input: input,
             recipients: None,
             sign: false,
             sign_key: None,
@@ -1257,21 +5708,24 @@ impl EncryptOption {
             passphrase: Some(passphrase),
             output: output,
             extra_args: None,
+            reject_unusable_keys: false,
+            s2k_count: None,
+            s2k_digest_algo: None,
+            s2k_cipher_algo: None,
+            shred_input: None,
         };
     }
 
     // for with_key_and_symmetric, it will be a encryption with both passphrase and keys and always trust will be true
     pub fn with_key_and_symmetric(
-        file: Option<File>,
-        file_path: Option<String>,
+        input: InputSource,
         recipients: Option<Vec<String>>,
         symmetric_algo: Option<String>,
-        passphrase: String,
-        output: Option<String>,
+        passphrase: Passphrase,
+        output: Option<PathBuf>,
     ) -> EncryptOption {
         return EncryptOption {
-            file: file,
-            file_path: file_path,
+            input: input,
             recipients: recipients,
             sign: false,
             sign_key: None,
@@ -1281,6 +5735,49 @@ impl EncryptOption {
             passphrase: Some(passphrase),
             output: output,
             extra_args: None,
+            reject_unusable_keys: false,
+            s2k_count: None,
+            s2k_digest_algo: None,
+            s2k_cipher_algo: None,
+            shred_input: None,
+        };
+    }
+}
+
+// a struct to represent the options of encrypt_for_emails
+//*******************************************************
+
+//       RELATED TO GPG EMAIL ENCRYPTION OPTION
+
+//*******************************************************
+#[derive(Debug, Clone)]
+pub struct EmailEncryptOption {
+    // locate: whether to retrieve the key of an email without a usable key in the keyring with --locate-external-keys
+    //         [the retrieved key will be imported into the keyring]
+    pub locate: bool,
+    // locate_mechanisms: the auto-key-locate mechanisms to try in order ( ex. wkd, keyserver ),
+    //                    gpg's configured mechanisms will be used if not provided
+    pub locate_mechanisms: Option<Vec<String>>,
+    // output: path to write the encrypted output, like EncryptOption.output
+    pub output: Option<PathBuf>,
+}
+
+impl EmailEncryptOption {
+    // for default, only the keys already in the keyring will be used
+    pub fn default(output: Option<PathBuf>) -> EmailEncryptOption {
+        return EmailEncryptOption {
+            locate: false,
+            locate_mechanisms: None,
+            output: output,
+        };
+    }
+
+    // for with_locate, the key of an email not in the keyring will be located with the mechanisms
+    pub fn with_locate(locate_mechanisms: Option<Vec<String>>, output: Option<PathBuf>) -> EmailEncryptOption {
+        return EmailEncryptOption {
+            locate: true,
+            locate_mechanisms: locate_mechanisms,
+            output: output,
         };
     }
 }
@@ -1295,63 +5792,94 @@ impl EncryptOption {
 //*******************************************************
 #[derive(Debug)]
 pub struct DecryptOption {
-    // file: file object
-    pub file: Option<File>,
-    // file_path: path to file
-    pub file_path: Option<String>,
+    // input: the file to be processed, check InputSource
+    pub input: InputSource,
     // recipients: recipients keyid
     pub recipient: Option<String>,
     // always_trust: whether to always trust keys
     pub always_trust: bool,
     // passphrase: passphrase if file if symmetric encrypted [required if it was symmetric encrypted]
-    pub passphrase: Option<String>,
+    pub passphrase: Option<Passphrase>,
     // key_passphrase: passphrase if file is key encrypted and need passphrase protected private key to decrypt
-    pub key_passphrase: Option<String>,
+    pub key_passphrase: Option<Passphrase>,
     // output: path to write the decrypted output,
     //         will use the default output dir with file name as [decrypted_file_<datetime>.<extension>] set in GPG if not provided
-    pub output: Option<String>,
+    pub output: Option<PathBuf>,
     // extra_args: extra arguments to pass to gpg
     pub extra_args: Option<Vec<String>>,
+    // strict: fail with a WeakCryptoError if the data was not integrity protected ( MDC ), was encrypted with a
+    //         legacy cipher ( ex. CAST5, 3DES ) or was signed with a weak digest ( ex. SHA1 ), the output was removed then
+    pub strict: bool,
+    // show_session_key: whether to report the session key in decryption_info of the result, so the data can be
+    //                   decrypted by others without the secret key
+    pub show_session_key: bool,
+    // session_key: decrypt with the session key reported by show_session_key instead of the secret key or passphrase
+    //              NOTE: it was passed to gpg as an argument, it can be seen by other users in the process list
+    pub session_key: Option<Passphrase>,
 }
 
 impl DecryptOption {
     // for default, it will be a decryption with secret key and always trust will be true
     // [key_passphrase is required for passphrase protected private key]
     pub fn default(
-        file: Option<File>,
-        file_path: Option<String>,
+        input: InputSource,
         recipient: String,
-        key_passphrase: Option<String>,
-        output: Option<String>,
+        key_passphrase: Option<Passphrase>,
+        output: Option<PathBuf>,
     ) -> DecryptOption {
         return DecryptOption {
-            file: file,
-            file_path: file_path,
+            input: input,
             recipient: Some(recipient),
             always_trust: true,
             passphrase: None,
             key_passphrase: key_passphrase,
             output: output,
             extra_args: None,
+            strict: false,
+            show_session_key: false,
+            session_key: None,
         };
     }
 
     // for with_symmetric, it will be a decryption with passphrase instead of secret keys and always trust will be true
     pub fn with_symmetric(
-        file: Option<File>,
-        file_path: Option<String>,
-        passphrase: String,
-        output: Option<String>,
+        input: InputSource,
+        passphrase: Passphrase,
+        output: Option<PathBuf>,
     ) -> DecryptOption {
         return DecryptOption {
-            file: file,
-            file_path: file_path,
+            input: input,
             recipient: None,
             always_trust: true,
             passphrase: Some(passphrase),
             key_passphrase: None,
             output: output,
             extra_args: None,
+            strict: false,
+            show_session_key: false,
+            session_key: None,
+        };
+    }
+
+    // for override_session_key, it will be a decryption with a session key ( ex. shared by the one reporting it with
+    // show_session_key ) instead of secret keys or passphrase, only the message it was reported for can be decrypted
+    // NOTE: the session key was passed to gpg as an argument, it can be seen by other users in the process list
+    pub fn override_session_key(
+        input: InputSource,
+        session_key: Passphrase,
+        output: Option<PathBuf>,
+    ) -> DecryptOption {
+        return DecryptOption {
+            input: input,
+            recipient: None,
+            always_trust: true,
+            passphrase: None,
+            key_passphrase: None,
+            output: output,
+            extra_args: None,
+            strict: false,
+            show_session_key: false,
+            session_key: Some(session_key),
         };
     }
 }
@@ -1366,66 +5894,584 @@ impl DecryptOption {
 //*******************************************************
 #[derive(Debug)]
 pub struct SignOption {
-    // file: file object
-    pub file: Option<File>,
-    // file_path: path to file
-    pub file_path: Option<String>,
+    // input: the file to be processed, check InputSource
+    pub input: InputSource,
     // keyid: keyid for signing
     pub keyid: Option<String>,
     // key_passphrase: required for passphrase protected private key
-    pub key_passphrase: Option<String>,
+    pub key_passphrase: Option<Passphrase>,
     // clearsign: Whether to use clear signing
     pub clearsign: bool,
     // detach: Whether to produce a detached signature.
     pub detach: bool,
     // output: path to write the detached signature or embedded sign file
     //         if output not specified:
-    //           will use the default output dir with file name as [<sign_type>_<datetime>.<sig or gpg>] set in GPG if
-    //           file is provided instead of file_path or detached signature
-    pub output: Option<String>,
+    //           a detached signature of an InputSource::Path will be written next to it as [<path>.<asc or sig>]
+    //           otherwise will use the default output dir with file name as [<sign_type>_<datetime>.<sig or gpg>] set in GPG
+    pub output: Option<PathBuf>,
     // extra_args: extra arguments to pass to gpg
     pub extra_args: Option<Vec<String>>,
+    // reject_unusable_keys: whether to return an error before signing if the sign key was revoked, expired or disabled
+    pub reject_unusable_keys: bool,
 }
 
 impl SignOption {
     // for default, it will be an embedded signing with secret key with clearsign
     // [key_passphrase is required for passphrase protected private key]
     pub fn default(
-        file: Option<File>,
-        file_path: Option<String>,
+        input: InputSource,
         keyid: String,
-        key_passphrase: Option<String>,
-        output: Option<String>,
+        key_passphrase: Option<Passphrase>,
+        output: Option<PathBuf>,
     ) -> SignOption {
         return SignOption {
-            file: file,
-            file_path: file_path,
+            input: input,
             keyid: Some(keyid),
             key_passphrase: key_passphrase,
             clearsign: true,
             detach: false,
             output: output,
             extra_args: None,
+            reject_unusable_keys: false,
         };
     }
 
     // for detached, it will be a detached signing with secret key without clearsign
     pub fn detached(
-        file: Option<File>,
-        file_path: Option<String>,
+        input: InputSource,
         keyid: String,
-        key_passphrase: Option<String>,
-        output: Option<String>,
+        key_passphrase: Option<Passphrase>,
+        output: Option<PathBuf>,
     ) -> SignOption {
         return SignOption {
-            file: file,
-            file_path: file_path,
+            input: input,
             keyid: Some(keyid),
             key_passphrase: key_passphrase,
             clearsign: false,
             detach: true,
             output: output,
             extra_args: None,
+            reject_unusable_keys: false,
+        };
+    }
+}
+
+//*******************************************************
+
+//       RELATED TO GPG CARD GENERATE KEY OPTION
+
+//*******************************************************
+#[derive(Debug, Clone)]
+pub struct CardGenerateOption {
+    // admin_pin: admin PIN of the card
+    pub admin_pin: Passphrase,
+    // user_pin: user PIN of the card
+    pub user_pin: Passphrase,
+    // name: name of the uid for the generated key
+    pub name: String,
+    // email: email of the uid for the generated key
+    pub email: String,
+    // comment: comment of the uid for the generated key
+    pub comment: Option<String>,
+    // expire: expiration of the key ex) "0" for no expiration, "1y", "2w"
+    pub expire: String,
+    // replace: whether to replace the keys that was already on the card
+    pub replace: bool,
+}
+
+impl CardGenerateOption {
+    // for default, the keys will be generated without expiration and will not replace the keys already on the card
+    pub fn default(admin_pin: Passphrase, user_pin: Passphrase, name: String, email: String) -> CardGenerateOption {
+        return CardGenerateOption {
+            admin_pin: admin_pin,
+            user_pin: user_pin,
+            name: name,
+            email: email,
+            comment: None,
+            expire: "0".to_string(),
+            replace: false,
+        };
+    }
+}
+
+//*******************************************************
+
+//            RELATED TO GPG KEY PREFERENCES
+
+//*******************************************************
+// the algorithms a key advertise to whoever encrypt or sign for it, the first supported one will be chosen
+// set it when generating a key with the Preferences argument of gen_key, or on an existing key with set_key_preferences
+#[derive(Debug, Clone)]
+pub struct KeyPreferences {
+    // ciphers: cipher algorithm(s) in the order of preference
+    pub ciphers: Vec<CipherAlgo>,
+    // digests: digest algorithm(s) in the order of preference
+    pub digests: Vec<DigestAlgo>,
+    // compressions: compression algorithm(s) in the order of preference
+    pub compressions: Vec<CompressAlgo>,
+}
+
+impl Default for KeyPreferences {
+    // for default, AES256 and SHA512 were preferred, followed by the weaker AES and SHA2 variants
+    fn default() -> KeyPreferences {
+        return KeyPreferences {
+            ciphers: vec![CipherAlgo::Aes256, CipherAlgo::Aes192, CipherAlgo::Aes128],
+            digests: vec![DigestAlgo::Sha512, DigestAlgo::Sha384, DigestAlgo::Sha256],
+            compressions: vec![CompressAlgo::Zlib, CompressAlgo::Bzip2, CompressAlgo::Zip],
+        };
+    }
+}
+
+// the preference list as gpg expect it ex) AES256 AES192 AES SHA512 SHA384 SHA256 ZLIB BZIP2 ZIP
+impl Display for KeyPreferences {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut preferences: Vec<String> = self.ciphers.iter().map(|c| c.value()).collect();
+        preferences.extend(self.digests.iter().map(|d| d.value()));
+        preferences.extend(self.compressions.iter().map(|c| c.value()));
+        return write!(f, "{}", preferences.join(" "));
+    }
+}
+
+//*******************************************************
+
+//         RELATED TO GPG VERIFY POLICY
+
+//*******************************************************
+#[derive(Debug, Clone)]
+pub struct VerifyPolicy {
+    // recursive: whether to walk into the sub directories
+    pub recursive: bool,
+    // include_hidden: whether to verify hidden files and walk into hidden directories ( name starting with . )
+    pub include_hidden: bool,
+    // parallelism: number of files to be verified at the same time
+    pub parallelism: usize,
+}
+
+impl Default for VerifyPolicy {
+    // for default, it will walk into the sub directories ( hidden ones excluded ) and verify one file at a time
+    fn default() -> VerifyPolicy {
+        return VerifyPolicy {
+            recursive: true,
+            include_hidden: false,
+            parallelism: 1,
+        };
+    }
+}
+
+impl VerifyPolicy {
+    // for parallel, it will be the same as default but verify up to the number of files provided at the same time
+    pub fn parallel(parallelism: usize) -> VerifyPolicy {
+        return VerifyPolicy {
+            recursive: true,
+            include_hidden: false,
+            parallelism: parallelism,
+        };
+    }
+}
+
+//*******************************************************
+
+//         RELATED TO GPG VERIFY OPTION
+
+//*******************************************************
+#[derive(Debug)]
+pub struct VerifyOption {
+    // input: the file to be processed, check InputSource
+    pub input: InputSource,
+    // signature_file_path: path to the detached signature, None if the file include the signature
+    pub signature_file_path: Option<PathBuf>,
+    // extra_args: extra arguments to pass to gpg or gpgv
+    pub extra_args: Option<Vec<String>>,
+    // backend: the program used for verification, check VerifyBackend
+    pub backend: VerifyBackend,
+}
+
+impl VerifyOption {
+    // for default, it will verify with gpg against the keys in the homedir
+    pub fn default(
+        input: InputSource,
+        signature_file_path: Option<PathBuf>,
+    ) -> VerifyOption {
+        return VerifyOption {
+            input: input,
+            signature_file_path: signature_file_path,
+            extra_args: None,
+            backend: VerifyBackend::Gpg,
+        };
+    }
+
+    // change the program used for verification
+    // ex) VerifyOption::default(InputSource::Path(path), Some(sig)).backend(VerifyBackend::Gpgv(keyring))
+    pub fn backend(mut self, backend: VerifyBackend) -> VerifyOption {
+        self.backend = backend;
+        return self;
+    }
+}
+
+//*******************************************************
+
+//         RELATED TO GPG EXPORT OPTION
+
+//*******************************************************
+#[derive(Debug, Clone)]
+pub struct ExportOption {
+    // minimal: Whether to export only the most recent self-signature of each uid
+    //          ( third-party signatures will be removed )
+    pub minimal: bool,
+    // keep_uid: filter expression of the uid(s) to keep, eg: "mbox =~ @example.com"
+    pub keep_uid: Option<String>,
+    // drop_subkey: filter expression of the subkey(s) to drop, eg: "usage =~ a"
+    pub drop_subkey: Option<String>,
+}
+
+impl Default for ExportOption {
+    // for default, keys will be exported as it is
+    fn default() -> ExportOption {
+        return ExportOption {
+            minimal: false,
+            keep_uid: None,
+            drop_subkey: None,
+        };
+    }
+}
+
+impl ExportOption {
+    // for minimal, keys will be exported without third-party signatures
+    pub fn minimal() -> ExportOption {
+        return ExportOption {
+            minimal: true,
+            keep_uid: None,
+            drop_subkey: None,
+        };
+    }
+
+    // for with_filter, keys will be exported with the provided filter expression(s)
+    pub fn with_filter(
+        minimal: bool,
+        keep_uid: Option<String>,
+        drop_subkey: Option<String>,
+    ) -> ExportOption {
+        return ExportOption {
+            minimal: minimal,
+            keep_uid: keep_uid,
+            drop_subkey: drop_subkey,
+        };
+    }
+
+    fn gen_export_args(self) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        if self.minimal {
+            args.append(&mut vec![
+                "--export-options".to_string(),
+                "export-minimal".to_string(),
+            ]);
+        }
+        if let Some(keep_uid) = self.keep_uid {
+            args.append(&mut vec![
+                "--export-filter".to_string(),
+                format!("keep-uid={}", keep_uid),
+            ]);
+        }
+        if let Some(drop_subkey) = self.drop_subkey {
+            args.append(&mut vec![
+                "--export-filter".to_string(),
+                format!("drop-subkey={}", drop_subkey),
+            ]);
+        }
+        return args;
+    }
+}
+
+//*******************************************************
+
+//         RELATED TO GPG IMPORT OPTION
+
+//*******************************************************
+#[derive(Debug, Clone)]
+pub struct ImportOption {
+    // import_show: Whether to show a listing of the key(s) as imported
+    pub import_show: bool,
+    // only_pubkeys: Whether to ignore the secret keys and only import the public keys ( gpg >= 2.3 )
+    pub only_pubkeys: bool,
+    // keep_ownertrust: Whether to keep the current ownertrust of the existing keys
+    pub keep_ownertrust: bool,
+    // restore: Whether to restore the key(s) from a backup ( keep all the data of the key(s) )
+    pub restore: bool,
+    // keep_uid: filter expression of the uid(s) to keep, eg: "mbox =~ @example.com"
+    pub keep_uid: Option<String>,
+    // drop_sig: filter expression of the signature(s) to drop, eg: "sig_created_d < 2020-01-01"
+    pub drop_sig: Option<String>,
+}
+
+impl Default for ImportOption {
+    // for default, keys will be imported as it is
+    fn default() -> ImportOption {
+        return ImportOption {
+            import_show: false,
+            only_pubkeys: false,
+            keep_ownertrust: false,
+            restore: false,
+            keep_uid: None,
+            drop_sig: None,
+        };
+    }
+}
+
+impl ImportOption {
+    // for with_filter, only the uid(s) and signature(s) that pass the filter expression(s) will be imported
+    pub fn with_filter(keep_uid: Option<String>, drop_sig: Option<String>) -> ImportOption {
+        return ImportOption {
+            import_show: false,
+            only_pubkeys: false,
+            keep_ownertrust: false,
+            restore: false,
+            keep_uid: keep_uid,
+            drop_sig: drop_sig,
+        };
+    }
+
+    fn gen_import_args(self) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        let mut import_options: Vec<&str> = Vec::new();
+        if self.import_show {
+            import_options.push("import-show");
+        }
+        if self.only_pubkeys {
+            import_options.push("only-pubkeys");
+        }
+        if self.keep_ownertrust {
+            import_options.push("keep-ownertrust");
+        }
+        if self.restore {
+            import_options.push("restore");
+        }
+        if !import_options.is_empty() {
+            args.append(&mut vec![
+                "--import-options".to_string(),
+                import_options.join(","),
+            ]);
+        }
+        if let Some(keep_uid) = self.keep_uid {
+            args.append(&mut vec![
+                "--import-filter".to_string(),
+                format!("keep-uid={}", keep_uid),
+            ]);
+        }
+        if let Some(drop_sig) = self.drop_sig {
+            args.append(&mut vec![
+                "--import-filter".to_string(),
+                format!("drop-sig={}", drop_sig),
+            ]);
+        }
+        return args;
+    }
+}
+
+//*******************************************************
+
+//         RELATED TO GPG KEY QUERY
+
+//*******************************************************
+#[derive(Debug, Clone)]
+pub struct KeyQuery {
+    // email: only keys with a uid of this email address ( case insensitive )
+    pub email: Option<String>,
+    // substring: only keys with a uid, keyid or fingerprint containing this string ( case insensitive )
+    pub substring: Option<String>,
+    // can_encrypt: only keys that can be used for encryption
+    pub can_encrypt: bool,
+    // can_sign: only keys that can be used for signing
+    pub can_sign: bool,
+    // not_expired: only keys that are not expired
+    pub not_expired: bool,
+    // not_revoked: only keys that are not revoked
+    pub not_revoked: bool,
+    // secret_available: only keys with the secret key in the keyring
+    pub secret_available: bool,
+    // min_trust: only keys with a validity of at least this trust level
+    pub min_trust: Option<TrustLevel>,
+}
+
+impl Default for KeyQuery {
+    // for default, all keys will be matched
+    fn default() -> KeyQuery {
+        return KeyQuery {
+            email: None,
+            substring: None,
+            can_encrypt: false,
+            can_sign: false,
+            not_expired: false,
+            not_revoked: false,
+            secret_available: false,
+            min_trust: None,
+        };
+    }
+}
+
+impl KeyQuery {
+    // for encryption, usable keys of the email that can be used for encryption will be matched
+    pub fn for_encryption(email: String) -> KeyQuery {
+        return KeyQuery {
+            email: Some(email),
+            substring: None,
+            can_encrypt: true,
+            can_sign: false,
+            not_expired: true,
+            not_revoked: true,
+            secret_available: false,
+            min_trust: None,
+        };
+    }
+
+    // for signing, usable keys of the email that can be used for signing and have the secret key will be matched
+    pub fn for_signing(email: String) -> KeyQuery {
+        return KeyQuery {
+            email: Some(email),
+            substring: None,
+            can_encrypt: false,
+            can_sign: true,
+            not_expired: true,
+            not_revoked: true,
+            secret_available: true,
+            min_trust: None,
+        };
+    }
+
+    fn is_match(&self, key: &ListKeyResult, secret_fingerprints: &[String], now: i64) -> bool {
+        if let Some(email) = &self.email {
+            let email: String = email.to_lowercase();
+            let has_email: bool = key.uids.iter().any(|uid| {
+                let uid: String = uid.to_lowercase();
+                uid == email || uid.contains(&format!("<{}>", email))
+            });
+            if !has_email {
+                return false;
+            }
+        }
+        if let Some(substring) = &self.substring {
+            let substring: String = substring.to_lowercase();
+            let has_substring: bool = key.uids.iter().any(|uid| uid.to_lowercase().contains(&substring))
+                || key.keyid.to_lowercase().contains(&substring)
+                || key.fingerprint.to_lowercase().contains(&substring);
+            if !has_substring {
+                return false;
+            }
+        }
+        // capital letter in the capability field is the usable capability of the whole key ( including subkeys )
+        if self.can_encrypt && !key.cap.contains("E") {
+            return false;
+        }
+        if self.can_sign && !key.cap.contains("S") {
+            return false;
+        }
+        if self.not_expired && (key.is_expired || key.expiry_timestamp.is_some_and(|expires| expires <= now)) {
+            return false;
+        }
+        if self.not_revoked && key.is_revoked {
+            return false;
+        }
+        if self.secret_available && !secret_fingerprints.contains(&key.fingerprint) {
+            return false;
+        }
+        if let Some(min_trust) = &self.min_trust {
+            // a validity that was not a trust level was not trusted
+            let validity: u8 = key.validity_level.as_ref().map(|level| level.value()).unwrap_or(0);
+            if validity < min_trust.value() {
+                return false;
+            }
+        }
+        return true;
+    }
+}
+
+//*******************************************************
+
+//           RELATED TO GPG COMMON OPTIONS
+
+//*******************************************************
+// typed version of the common options to be passed to every gpg command
+// prefer this over GPG.options, as the value was passed to gpg as it is
+#[derive(Debug, Clone)]
+pub struct GpgOptions {
+    // keyserver: keyserver to search / receive keys from ex) hkps://keys.openpgp.org
+    pub keyserver: Option<String>,
+    // trust_model: how gpg decide if a key was valid, check TrustModel
+    pub trust_model: Option<TrustModel>,
+    // cipher_algo: symmetric cipher to use regardless of the preference of the recipient ex) AES256
+    pub cipher_algo: Option<String>,
+    // digest_algo: digest algorithm to use for signing ex) SHA512
+    pub digest_algo: Option<String>,
+    // compress_algo: compression algorithm to use ex) ZLIB, Uncompressed
+    pub compress_algo: Option<String>,
+    // throw_keyids: whether to hide the keyid of the recipient(s) in the encrypted message
+    pub throw_keyids: bool,
+    // auto_key_retrieve: whether to retrieve the missing key of a signature from the keyserver when verifying
+    pub auto_key_retrieve: bool,
+    // weak_digests: digest algorithms whose signature will be rejected when verifying ( ex. SHA1 ), gpg 2.1 or later
+    pub weak_digests: Option<Vec<DigestAlgo>>,
+    // personal_cipher_preferences: ciphers to choose from in order when encrypting, among those the recipients accept
+    pub personal_cipher_preferences: Option<Vec<CipherAlgo>>,
+    // personal_digest_preferences: digest algorithms to choose from in order when signing
+    pub personal_digest_preferences: Option<Vec<DigestAlgo>>,
+}
+
+impl Default for GpgOptions {
+    // for default, nothing will be passed and gpg will use the gpg.conf of the homedir
+    fn default() -> GpgOptions {
+        return GpgOptions {
+            keyserver: None,
+            trust_model: None,
+            cipher_algo: None,
+            digest_algo: None,
+            compress_algo: None,
+            throw_keyids: false,
+            auto_key_retrieve: false,
+            weak_digests: None,
+            personal_cipher_preferences: None,
+            personal_digest_preferences: None,
         };
     }
 }
+
+impl GpgOptions {
+    fn gen_options_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        if self.keyserver.is_some() {
+            args.append(&mut vec!["--keyserver".to_string(), self.keyserver.clone().unwrap()]);
+        }
+        if let Some(trust_model) = &self.trust_model {
+            args.append(&mut vec![
+                "--trust-model".to_string(),
+                trust_model.value(),
+            ]);
+        }
+        if self.cipher_algo.is_some() {
+            args.append(&mut vec!["--cipher-algo".to_string(), self.cipher_algo.clone().unwrap()]);
+        }
+        if self.digest_algo.is_some() {
+            args.append(&mut vec!["--digest-algo".to_string(), self.digest_algo.clone().unwrap()]);
+        }
+        if self.compress_algo.is_some() {
+            args.append(&mut vec!["--compress-algo".to_string(), self.compress_algo.clone().unwrap()]);
+        }
+        if self.throw_keyids {
+            args.push("--throw-keyids".to_string());
+        }
+        if self.auto_key_retrieve {
+            args.push("--auto-key-retrieve".to_string());
+        }
+        // --weak-digest can be given more than once, each for an algorithm
+        for digest in self.weak_digests.iter().flatten() {
+            args.append(&mut vec!["--weak-digest".to_string(), digest.value()]);
+        }
+        if let Some(ciphers) = &self.personal_cipher_preferences {
+            let ciphers: Vec<String> = ciphers.iter().map(|c| c.value()).collect();
+            args.append(&mut vec!["--personal-cipher-preferences".to_string(), ciphers.join(" ")]);
+        }
+        if let Some(digests) = &self.personal_digest_preferences {
+            let digests: Vec<String> = digests.iter().map(|d| d.value()).collect();
+            args.append(&mut vec!["--personal-digest-preferences".to_string(), digests.join(" ")]);
+        }
+        return args;
+    }
+}
@@ -0,0 +1,154 @@
+use keyring::Entry;
+
+use crate::gnupg::GPG;
+use crate::utils::{
+    enums::Operation,
+    errors::{GPGError, GPGErrorType},
+    response::ListKeyResult,
+    secret::{Passphrase, PassphraseProvider},
+};
+
+// the service the passphrases were stored under by default
+pub const DEFAULT_KEYCHAIN_SERVICE: &str = "crab-gnupg";
+
+//*******************************************************
+
+//               RELATED TO OS KEYCHAIN
+
+//*******************************************************
+// passphrases of the keys stored in the keychain of the OS ( macOS Keychain, Windows Credential Manager
+// or Secret Service ), keyed by the fingerprint of the primary key
+#[derive(Debug, Clone)]
+pub struct Keychain {
+    // service: the service the passphrases were stored under, so different applications do not share them
+    pub service: String,
+}
+
+impl Default for Keychain {
+    // with the service DEFAULT_KEYCHAIN_SERVICE ( crab-gnupg )
+    fn default() -> Keychain {
+        return Keychain {
+            service: DEFAULT_KEYCHAIN_SERVICE.to_string(),
+        };
+    }
+}
+
+impl Keychain {
+    pub fn with_service(service: String) -> Keychain {
+        return Keychain { service: service };
+    }
+
+    // retrieve the passphrase of the key, None if it was not stored
+    pub fn get(&self, fingerprint: &str) -> Result<Option<Passphrase>, GPGError> {
+        // fingerprint: fingerprint of the primary key
+
+        let entry: Entry = self.entry(fingerprint)?;
+        match entry.get_password() {
+            Ok(passphrase) => return Ok(Some(Passphrase::new(passphrase))),
+            Err(keyring::Error::NoEntry) => return Ok(None),
+            Err(e) => return Err(keychain_error(e)),
+        }
+    }
+
+    // store the passphrase of the key, replacing the one already stored
+    pub fn set(&self, fingerprint: &str, passphrase: &Passphrase) -> Result<(), GPGError> {
+        // fingerprint: fingerprint of the primary key
+
+        let entry: Entry = self.entry(fingerprint)?;
+        if let Err(e) = entry.set_password(passphrase.expose()) {
+            return Err(keychain_error(e));
+        }
+        return Ok(());
+    }
+
+    // remove the passphrase of the key, return false if it was not stored
+    pub fn delete(&self, fingerprint: &str) -> Result<bool, GPGError> {
+        // fingerprint: fingerprint of the primary key
+
+        let entry: Entry = self.entry(fingerprint)?;
+        match entry.delete_credential() {
+            Ok(()) => return Ok(true),
+            Err(keyring::Error::NoEntry) => return Ok(false),
+            Err(e) => return Err(keychain_error(e)),
+        }
+    }
+
+    fn entry(&self, fingerprint: &str) -> Result<Entry, GPGError> {
+        let fingerprint: String = normalize_fingerprint(fingerprint)?;
+        match Entry::new(&self.service, &fingerprint) {
+            Ok(entry) => return Ok(entry),
+            Err(e) => return Err(keychain_error(e)),
+        }
+    }
+}
+
+//*******************************************************
+
+//           RELATED TO OS KEYCHAIN PROVIDER
+
+//*******************************************************
+// a PassphraseProvider looking up the passphrase of the key gpg asked for in the keychain
+// gpg only report the keyid, it was looked up in the homedir of the GPG for the fingerprint
+#[derive(Debug, Clone)]
+pub struct KeychainProvider {
+    // keychain: where the passphrases were stored
+    pub keychain: Keychain,
+    // to look up the fingerprint of the keyid, without a passphrase provider of its own
+    gpg: GPG,
+}
+
+impl KeychainProvider {
+    pub fn new(gpg: &GPG, keychain: Keychain) -> KeychainProvider {
+        // gpg: the GPG the provider will be set on
+
+        let mut gpg: GPG = gpg.clone();
+        gpg.passphrase_provider = None;
+        return KeychainProvider {
+            keychain: keychain,
+            gpg: gpg,
+        };
+    }
+}
+
+impl PassphraseProvider for KeychainProvider {
+    fn get_passphrase(&self, key_hint: &str, ops: Operation) -> Option<Passphrase> {
+        let _ = ops;
+        // a symmetric encrypted file does not belong to any key
+        if key_hint.is_empty() {
+            return None;
+        }
+        let keys: Vec<ListKeyResult> = match self.gpg.list_keys(true, Some(vec![key_hint.to_string()]), false) {
+            Ok(keys) => keys,
+            Err(_) => return None,
+        };
+        // the passphrase was stored under the primary key, but the hint can also be the keyid of one of its subkeys
+        let key_hint: String = key_hint.to_uppercase();
+        let key: &ListKeyResult = keys.iter().find(|k| {
+            k.fingerprint.ends_with(&key_hint)
+                || k.subkeys
+                    .iter()
+                    .any(|s| s.keyid == key_hint || s.fingerprint.ends_with(&key_hint))
+        })?;
+        return self.keychain.get(&key.fingerprint).unwrap_or(None);
+    }
+}
+
+// the fingerprint without space in upper case, as gpg list it
+fn normalize_fingerprint(fingerprint: &str) -> Result<String, GPGError> {
+    let fingerprint: String = fingerprint
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase();
+    if !(fingerprint.len() == 40 || fingerprint.len() == 64) || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(GPGError::new(
+            GPGErrorType::InvalidArgumentError(format!("fingerprint invalid: {}", fingerprint)),
+            None,
+        ));
+    }
+    return Ok(fingerprint);
+}
+
+fn keychain_error(error: keyring::Error) -> GPGError {
+    return GPGError::new(GPGErrorType::KeychainError(error.to_string()), None);
+}
@@ -1,4 +1,29 @@
+// the crate return explicitly and write each field of a struct literal as field: value, as its code style
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+// GPGError carry the CmdResult of the failed gpg command by value ( a public field since the first release ),
+// so every function returning it has a large Err variant
+#![allow(clippy::result_large_err)]
+
+pub mod args;
+pub mod audit;
+pub mod config;
+pub mod diff;
 pub mod gnupg;
+#[cfg(feature = "keyring")]
+pub mod keychain;
+pub mod metrics;
+#[cfg(feature = "pgp_mime")]
+pub mod pgp_mime;
+pub mod pipeline;
+pub mod pool;
 #[doc(hidden)]
 pub mod process;
+pub mod runner;
+pub mod status;
+#[doc(hidden)]
+pub mod temp;
+#[cfg(feature = "test_util")]
+pub mod test_support;
 pub mod utils;
+#[cfg(feature = "notify")]
+pub mod watcher;
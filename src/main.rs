@@ -1,6 +1,25 @@
+// the same lints as allowed in lib.rs, for the modules compiled again in the binary
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::result_large_err)]
+
+pub mod args;
+pub mod audit;
+pub mod config;
+pub mod diff;
 pub mod gnupg;
+pub mod metrics;
+#[cfg(feature = "pgp_mime")]
+pub mod pgp_mime;
+pub mod pipeline;
+pub mod pool;
 pub mod process;
+pub mod runner;
+pub mod status;
+pub mod temp;
+#[cfg(feature = "test_util")]
+pub mod test_support;
 pub mod utils;
+#[cfg(feature = "notify")]
+pub mod watcher;
 
 fn main(){
 
@@ -0,0 +1,152 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io::Read,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::runner::GpgRunner;
+use crate::utils::{
+    enums::{GpgExitStatus, Operation},
+    errors::GPGError,
+    response::CmdResult,
+    secret::Passphrase,
+};
+
+//*******************************************************
+
+//              RELATED TO OPERATION METRIC
+
+//*******************************************************
+// the timing and outcome of one gpg ( or gpgv ) command
+#[derive(Debug, Clone)]
+pub struct OperationMetric {
+    pub operation: Operation,
+    // whether the command succeeded, false if it could not be started
+    pub success: bool,
+    pub exit_status: GpgExitStatus,
+    // including the time a mutating command waited for the lock of the homedir
+    pub duration: Duration,
+}
+
+// receive the metric of every gpg command run, set it on GPG.metrics_recorder
+// it was called on the thread running the operation, it should return quickly ( ex. increment a counter )
+pub trait MetricsRecorder: Debug + Send + Sync {
+    fn record(&self, metric: &OperationMetric);
+}
+
+//*******************************************************
+
+//              RELATED TO METRICS RUNNER
+
+//*******************************************************
+// a runner reporting the metric of every command run by the runner it wraps to the recorder
+// GPG wrap its runner with it when GPG.metrics_recorder was set
+#[derive(Debug, Clone)]
+pub struct MetricsRunner {
+    pub runner: Arc<dyn GpgRunner>,
+    pub recorder: Arc<dyn MetricsRecorder>,
+}
+
+impl MetricsRunner {
+    pub fn new(runner: Arc<dyn GpgRunner>, recorder: Arc<dyn MetricsRecorder>) -> MetricsRunner {
+        return MetricsRunner {
+            runner: runner,
+            recorder: recorder,
+        };
+    }
+
+    fn report(&self, started: Instant, ops: Operation, result: &Result<CmdResult, GPGError>) {
+        self.recorder.record(&get_operation_metric(started, ops, result.as_ref()));
+    }
+}
+
+pub(crate) fn get_operation_metric(
+    started: Instant,
+    ops: Operation,
+    result: Result<&CmdResult, &GPGError>,
+) -> OperationMetric {
+    match result {
+        Ok(result) => {
+            return OperationMetric {
+                operation: ops,
+                success: result.is_success(),
+                exit_status: result.exit_status(),
+                duration: started.elapsed(),
+            }
+        }
+        Err(_) => {
+            return OperationMetric {
+                operation: ops,
+                success: false,
+                exit_status: GpgExitStatus::Unknown,
+                duration: started.elapsed(),
+            }
+        }
+    }
+}
+
+impl GpgRunner for MetricsRunner {
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: Option<Box<dyn Read + Send>>,
+        env: Option<HashMap<String, String>>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        let started: Instant = Instant::now();
+        let result: Result<CmdResult, GPGError> = self.runner.run(args, stdin, env, ops.clone());
+        self.report(started, ops, &result);
+        return result;
+    }
+
+    fn run_interactive(
+        &self,
+        args: Vec<String>,
+        stdin_prefix: Option<Passphrase>,
+        env: Option<HashMap<String, String>>,
+        responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        let started: Instant = Instant::now();
+        let result: Result<CmdResult, GPGError> =
+            self.runner.run_interactive(args, stdin_prefix, env, responder, ops.clone());
+        self.report(started, ops, &result);
+        return result;
+    }
+    fn with_timeout(&self, timeout: Duration) -> Option<Arc<dyn GpgRunner>> {
+        return self
+            .runner
+            .with_timeout(timeout)
+            .map(|runner| Arc::new(MetricsRunner::new(runner, self.recorder.clone())) as Arc<dyn GpgRunner>);
+    }
+}
+
+//*******************************************************
+
+//          RELATED TO METRICS CRATE INTEGRATION
+
+//*******************************************************
+// report to the recorder installed for the metrics crate ( ex. a prometheus exporter ):
+// counter crab_gnupg_operations_total with label operation and outcome ( success / failure )
+// histogram crab_gnupg_operation_duration_seconds with label operation
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone)]
+pub struct GlobalMetricsRecorder;
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for GlobalMetricsRecorder {
+    fn record(&self, metric: &OperationMetric) {
+        let operation: String = metric.operation.to_string();
+        let outcome: &str = if metric.success { "success" } else { "failure" };
+        ::metrics::counter!(
+            "crab_gnupg_operations_total",
+            "operation" => operation.clone(),
+            "outcome" => outcome
+        )
+        .increment(1);
+        ::metrics::histogram!("crab_gnupg_operation_duration_seconds", "operation" => operation)
+            .record(metric.duration.as_secs_f64());
+    }
+}
@@ -0,0 +1,603 @@
+use std::io::Write;
+
+use chrono::Local;
+use tempfile::NamedTempFile;
+
+use crate::gnupg::{DecryptOption, EncryptOption, SignOption, GPG};
+use crate::temp::create_temp_file;
+use crate::utils::{
+    enums::InputSource,
+    errors::{GPGError, GPGErrorType},
+    response::{CmdResult, SignResult, VerifyResult},
+    secret::Passphrase,
+};
+
+//*******************************************************
+
+//           RELATED TO PGP/MIME ( RFC 3156 )
+
+//*******************************************************
+// a file attached to a PGP/MIME message
+#[derive(Debug, Clone)]
+pub struct MimeAttachment {
+    // filename: name of the file shown to the receiver
+    pub filename: String,
+    // content_type: mime type of the file ex) application/pdf
+    pub content_type: String,
+    // data: content of the file
+    pub data: Vec<u8>,
+}
+
+impl MimeAttachment {
+    pub fn new(filename: String, content_type: String, data: Vec<u8>) -> MimeAttachment {
+        return MimeAttachment {
+            filename: filename,
+            content_type: content_type,
+            data: data,
+        };
+    }
+}
+
+// a struct to represent the option to build a PGP/MIME message
+#[derive(Debug, Clone)]
+pub struct PgpMimeOption {
+    // body: plaintext body of the message
+    pub body: String,
+    // attachments: files attached to the message
+    pub attachments: Vec<MimeAttachment>,
+    // recipients: list of recipients keyid, the message will be encrypted if provided
+    pub recipients: Option<Vec<String>>,
+    // sign_key: keyid for signing, the message will be signed if provided
+    pub sign_key: Option<String>,
+    // key_passphrase: required for passphrase protected sign key
+    pub key_passphrase: Option<Passphrase>,
+    // always_trust: whether to always trust the recipients keys
+    pub always_trust: bool,
+}
+
+impl PgpMimeOption {
+    // for signed, the message will be a multipart/signed message
+    pub fn signed(
+        body: String,
+        attachments: Vec<MimeAttachment>,
+        sign_key: String,
+        key_passphrase: Option<Passphrase>,
+    ) -> PgpMimeOption {
+        return PgpMimeOption {
+            body: body,
+            attachments: attachments,
+            recipients: None,
+            sign_key: Some(sign_key),
+            key_passphrase: key_passphrase,
+            always_trust: true,
+        };
+    }
+
+    // for encrypted, the message will be a multipart/encrypted message
+    pub fn encrypted(body: String, attachments: Vec<MimeAttachment>, recipients: Vec<String>) -> PgpMimeOption {
+        return PgpMimeOption {
+            body: body,
+            attachments: attachments,
+            recipients: Some(recipients),
+            sign_key: None,
+            key_passphrase: None,
+            always_trust: true,
+        };
+    }
+
+    // for signed_and_encrypted, the message will be signed first and the signed message will be encrypted
+    pub fn signed_and_encrypted(
+        body: String,
+        attachments: Vec<MimeAttachment>,
+        recipients: Vec<String>,
+        sign_key: String,
+        key_passphrase: Option<Passphrase>,
+    ) -> PgpMimeOption {
+        return PgpMimeOption {
+            body: body,
+            attachments: attachments,
+            recipients: Some(recipients),
+            sign_key: Some(sign_key),
+            key_passphrase: key_passphrase,
+            always_trust: true,
+        };
+    }
+}
+
+// content of an incoming PGP/MIME message after it was decrypted and verified
+#[derive(Debug, Clone)]
+pub struct PgpMimeMessage {
+    // body: plaintext body of the message
+    pub body: String,
+    // attachments: files attached to the message
+    pub attachments: Vec<MimeAttachment>,
+    // encrypted: whether the message was encrypted
+    pub encrypted: bool,
+    // signed: whether the message was signed ( the signature was verified if true )
+    pub signed: bool,
+    // verify_result: result of the signature verification if the message was signed
+    pub verify_result: Option<CmdResult>,
+}
+
+// build a PGP/MIME message ( the Content-Type header and the body of the message )
+// the caller was expected to add the other mail headers ( From, To, Subject, MIME-Version ) in front of it
+pub fn build_pgp_mime(gpg: &GPG, option: PgpMimeOption) -> Result<String, GPGError> {
+    // gpg: initialized GPG
+    // option: option to build the message, check PgpMimeOption
+
+    if option.recipients.is_none() && option.sign_key.is_none() {
+        return Err(GPGError::new(
+            GPGErrorType::InvalidArgumentError(
+                "Please provide recipients or sign key to build a PGP/MIME message".to_string(),
+            ),
+            None,
+        ));
+    }
+    let mut entity: String = build_content_entity(&option.body, &option.attachments);
+
+    // sign then encrypt ( RFC 3156 6.1 ), so passphrase protected sign key can be used
+    if let Some(sign_key) = option.sign_key {
+        let (signature, micalg): (String, String) = detach_sign(gpg, &entity, sign_key, option.key_passphrase)?;
+        let boundary: String = gen_boundary("signed");
+        entity = format!(
+            "Content-Type: multipart/signed; micalg={micalg};\r\n protocol=\"application/pgp-signature\"; boundary=\"{b}\"\r\n\r\n\
+             This is an OpenPGP/MIME signed message (RFC 3156).\r\n\
+             --{b}\r\n{entity}\r\n\
+             --{b}\r\n\
+             Content-Type: application/pgp-signature; name=\"signature.asc\"\r\n\
+             Content-Description: OpenPGP digital signature\r\n\
+             Content-Disposition: attachment; filename=\"signature.asc\"\r\n\r\n\
+             {signature}\r\n\
+             --{b}--\r\n",
+            micalg = micalg,
+            b = boundary,
+            entity = entity,
+            signature = to_crlf(signature.trim_end()),
+        );
+    }
+
+    if let Some(recipients) = option.recipients {
+        let encrypted: String = encrypt(gpg, &entity, recipients, option.always_trust)?;
+        let boundary: String = gen_boundary("encrypted");
+        entity = format!(
+            "Content-Type: multipart/encrypted;\r\n protocol=\"application/pgp-encrypted\"; boundary=\"{b}\"\r\n\r\n\
+             This is an OpenPGP/MIME encrypted message (RFC 3156).\r\n\
+             --{b}\r\n\
+             Content-Type: application/pgp-encrypted\r\n\
+             Content-Description: PGP/MIME version identification\r\n\r\n\
+             Version: 1\r\n\r\n\
+             --{b}\r\n\
+             Content-Type: application/octet-stream; name=\"encrypted.asc\"\r\n\
+             Content-Description: OpenPGP encrypted message\r\n\
+             Content-Disposition: inline; filename=\"encrypted.asc\"\r\n\r\n\
+             {encrypted}\r\n\
+             --{b}--\r\n",
+            b = boundary,
+            encrypted = to_crlf(encrypted.trim_end()),
+        );
+    }
+    return Ok(entity);
+}
+
+// decrypt and verify an incoming PGP/MIME message ( the message can include the other mail headers )
+pub fn open_pgp_mime(
+    gpg: &GPG,
+    message: &[u8],
+    recipient: Option<String>,
+    key_passphrase: Option<Passphrase>,
+) -> Result<PgpMimeMessage, GPGError> {
+    // gpg: initialized GPG
+    // message: the raw message
+    // recipient: keyid to decrypt the message, required if the message was encrypted
+    // key_passphrase: passphrase of the recipient key if it was passphrase protected
+
+    let mut result: PgpMimeMessage = PgpMimeMessage {
+        body: "".to_string(),
+        attachments: vec![],
+        encrypted: false,
+        signed: false,
+        verify_result: None,
+    };
+    let mut entity: Vec<u8> = message.to_vec();
+
+    let (headers, body) = split_entity(&entity);
+    let content_type: String = get_header(&headers, "content-type").unwrap_or("text/plain".to_string());
+    if content_type.to_lowercase().starts_with("multipart/encrypted") {
+        let parts: Vec<Vec<u8>> = get_multipart(&content_type, body)?;
+        if parts.len() != 2 {
+            return Err(invalid_message("multipart/encrypted should have 2 parts"));
+        }
+        let (_, encrypted) = split_entity(&parts[1]);
+        if recipient.is_none() {
+            return Err(invalid_message("recipient is required to decrypt the message"));
+        }
+        entity = decrypt(gpg, encrypted, recipient.unwrap(), key_passphrase)?;
+        result.encrypted = true;
+    }
+
+    let (headers, body) = split_entity(&entity);
+    let content_type: String = get_header(&headers, "content-type").unwrap_or("text/plain".to_string());
+    if content_type.to_lowercase().starts_with("multipart/signed") {
+        let parts: Vec<Vec<u8>> = get_multipart(&content_type, body)?;
+        if parts.len() != 2 {
+            return Err(invalid_message("multipart/signed should have 2 parts"));
+        }
+        // the signature was made over the first part with CRLF line endings, the bytes were kept as they were
+        // as the part can be in any charset ( ex. 8bit latin-1 )
+        let signed_part: Vec<u8> = to_crlf_bytes(&parts[0]);
+        let (_, signature) = split_entity(&parts[1]);
+        let verify_result: CmdResult = verify_detached(gpg, &signed_part, signature)?;
+        result.signed = true;
+        result.verify_result = Some(verify_result);
+        entity = parts[0].clone();
+    }
+
+    collect_content(&entity, &mut result)?;
+    return Ok(result);
+}
+
+//*******************************************************
+
+//           BUILDING AND PARSING MIME ENTITY
+
+//*******************************************************
+// build the plaintext entity, a multipart/mixed entity if there are attachments
+fn build_content_entity(body: &str, attachments: &[MimeAttachment]) -> String {
+    let text_entity: String = if is_7bit_safe(body) {
+        format!(
+            "Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 7bit\r\n\r\n{}",
+            to_crlf(body)
+        )
+    } else {
+        format!(
+            "Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+            base64_encode(body.as_bytes())
+        )
+    };
+    if attachments.is_empty() {
+        return text_entity;
+    }
+    let boundary: String = gen_boundary("mixed");
+    let mut entity: String = format!("Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n", boundary);
+    entity.push_str(&format!("--{}\r\n{}\r\n", boundary, text_entity));
+    for attachment in attachments.iter() {
+        let filename: String = attachment.filename.replace(['"', '\\', '\r', '\n'], "_");
+        entity.push_str(&format!(
+            "--{}\r\nContent-Type: {}; name=\"{}\"\r\nContent-Transfer-Encoding: base64\r\nContent-Disposition: attachment; filename=\"{}\"\r\n\r\n{}\r\n",
+            boundary,
+            attachment.content_type,
+            filename,
+            filename,
+            base64_encode(&attachment.data)
+        ));
+    }
+    entity.push_str(&format!("--{}--\r\n", boundary));
+    return entity;
+}
+
+// walk through the plaintext entity and collect the body and the attachments
+fn collect_content(entity: &[u8], result: &mut PgpMimeMessage) -> Result<(), GPGError> {
+    let (headers, body) = split_entity(entity);
+    let content_type: String = get_header(&headers, "content-type").unwrap_or("text/plain".to_string());
+    if content_type.to_lowercase().starts_with("multipart/") {
+        let parts: Vec<Vec<u8>> = get_multipart(&content_type, body)?;
+        for part in parts.iter() {
+            collect_content(part, result)?;
+        }
+        return Ok(());
+    }
+
+    let encoding: String = get_header(&headers, "content-transfer-encoding").unwrap_or("7bit".to_string());
+    let data: Vec<u8> = match encoding.to_lowercase().as_str() {
+        "base64" => base64_decode(body),
+        "quoted-printable" => quoted_printable_decode(body),
+        _ => body.to_vec(),
+    };
+    let disposition: String = get_header(&headers, "content-disposition").unwrap_or("".to_string());
+    let filename: Option<String> =
+        get_header_param(&disposition, "filename").or(get_header_param(&content_type, "name"));
+    let is_attachment: bool = disposition.to_lowercase().starts_with("attachment") || filename.is_some();
+    if !is_attachment && content_type.to_lowercase().starts_with("text/plain") && result.body.is_empty() {
+        result.body = String::from_utf8_lossy(&data).replace("\r\n", "\n");
+        return Ok(());
+    }
+    let mime_type: String = content_type.split(';').next().unwrap_or("").trim().to_string();
+    result.attachments.push(MimeAttachment::new(
+        filename.unwrap_or("attachment".to_string()),
+        mime_type,
+        data,
+    ));
+    return Ok(());
+}
+
+// split an entity into its headers ( unfolded, name in lowercase ) and its body
+fn split_entity(entity: &[u8]) -> (Vec<(String, String)>, &[u8]) {
+    // the headers end at the first empty line, either with CRLF or LF line endings
+    let (header_end, body_start): (usize, usize) = match (find(entity, b"\r\n\r\n"), find(entity, b"\n\n")) {
+        (Some(i), Some(j)) if j < i => (j, j + 2),
+        (Some(i), _) => (i, i + 4),
+        (None, Some(j)) => (j, j + 2),
+        (None, None) => (entity.len(), entity.len()),
+    };
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in String::from_utf8_lossy(&entity[..header_end]).lines() {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            // folded header, continuation of the previous one
+            if let Some(last) = headers.last_mut() {
+                last.1.push(' ');
+                last.1.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    return (headers, &entity[body_start..]);
+}
+
+fn get_header(headers: &[(String, String)], name: &str) -> Option<String> {
+    return headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+}
+
+// retrieve a parameter of a header value ex) boundary of multipart/mixed; boundary="abc"
+fn get_header_param(value: &str, param: &str) -> Option<String> {
+    for p in value.split(';').skip(1) {
+        if let Some((name, v)) = p.split_once('=') {
+            if name.trim().eq_ignore_ascii_case(param) {
+                return Some(v.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    return None;
+}
+
+// split the body of a multipart entity into its parts ( without the line break before each delimiter )
+fn get_multipart(content_type: &str, body: &[u8]) -> Result<Vec<Vec<u8>>, GPGError> {
+    let boundary: String = match get_header_param(content_type, "boundary") {
+        Some(boundary) => boundary,
+        None => return Err(invalid_message("multipart without boundary")),
+    };
+    let delimiter: Vec<u8> = format!("--{}", boundary).into_bytes();
+    let mut parts: Vec<Vec<u8>> = Vec::new();
+    let mut current: Option<usize> = None;
+    let mut offset: usize = 0;
+    for line in body.split_inclusive(|b| *b == b'\n') {
+        if line.starts_with(&delimiter) {
+            if let Some(start) = current {
+                // the line break before the delimiter belong to the delimiter
+                let mut end: usize = offset;
+                if end > start && body[end - 1] == b'\n' {
+                    end -= 1;
+                }
+                if end > start && body[end - 1] == b'\r' {
+                    end -= 1;
+                }
+                parts.push(body[start..end].to_vec());
+            }
+            if line[delimiter.len()..].starts_with(b"--") {
+                return Ok(parts);
+            }
+            current = Some(offset + line.len());
+        }
+        offset += line.len();
+    }
+    return Err(invalid_message("multipart without closing delimiter"));
+}
+
+//*******************************************************
+
+//              GPG OPERATION ON MIME ENTITY
+
+//*******************************************************
+// produce an armored detached signature of the entity and the micalg parameter matching its digest
+// the output of gpg was written to a temporary file ( check GPG.sign_to_writer ), never to the output_dir
+fn detach_sign(
+    gpg: &GPG,
+    entity: &str,
+    keyid: String,
+    key_passphrase: Option<Passphrase>,
+) -> Result<(String, String), GPGError> {
+    let input: InputSource = InputSource::Bytes(entity.as_bytes().to_vec());
+    let mut option: SignOption = SignOption::detached(input, keyid, key_passphrase, None);
+    option.extra_args = Some(vec!["--armor".to_string()]);
+    let mut output: Vec<u8> = Vec::new();
+    let result: CmdResult = gpg.sign_to_writer(option, &mut output)?;
+    let micalg: String = get_micalg(&result)?;
+    return Ok((String::from_utf8_lossy(&output).to_string(), micalg));
+}
+
+// the digest gpg used was taken from the SIG_CREATED status ( gpg pick it from the key preferences )
+// micalg was "pgp-" followed by the lowercase name of the digest ( RFC 3156 5 )
+fn get_micalg(result: &CmdResult) -> Result<String, GPGError> {
+    match result.signatures.first() {
+        Some(SignResult { hash_algo: Some(hash_algo), .. }) => {
+            return Ok(format!("pgp-{}", hash_algo.value().to_lowercase()));
+        }
+        Some(signature) => {
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError(format!(
+                    "unknown digest algorithm {} in the signature",
+                    signature.hash_algo_id
+                )),
+                Some(result.clone()),
+            ));
+        }
+        None => {
+            return Err(GPGError::new(
+                GPGErrorType::GPGProcessError("gpg did not report the created signature".to_string()),
+                Some(result.clone()),
+            ));
+        }
+    }
+}
+
+fn encrypt(gpg: &GPG, entity: &str, recipients: Vec<String>, always_trust: bool) -> Result<String, GPGError> {
+    let input: InputSource = InputSource::Bytes(entity.as_bytes().to_vec());
+    let mut option: EncryptOption = EncryptOption::default(input, recipients, None);
+    option.always_trust = always_trust;
+    option.extra_args = Some(vec!["--armor".to_string()]);
+    let mut output: Vec<u8> = Vec::new();
+    gpg.encrypt_to_writer(option, &mut output)?;
+    return Ok(String::from_utf8_lossy(&output).to_string());
+}
+
+fn decrypt(gpg: &GPG, encrypted: &[u8], recipient: String, key_passphrase: Option<Passphrase>) -> Result<Vec<u8>, GPGError> {
+    let input: InputSource = InputSource::Bytes(encrypted.to_vec());
+    let option: DecryptOption = DecryptOption::default(input, recipient, key_passphrase, None);
+    let mut output: Vec<u8> = Vec::new();
+    gpg.decrypt_to_writer(option, &mut output)?;
+    return Ok(output);
+}
+
+fn verify_detached(gpg: &GPG, data: &[u8], signature: &[u8]) -> Result<CmdResult, GPGError> {
+    // gpg read the signed data from stdin, so only the signature need to be in a file
+    let mut signature_file: NamedTempFile = create_temp_file("signature")?;
+    if let Err(e) = signature_file.write_all(signature).and_then(|_| signature_file.flush()) {
+        return Err(GPGError::new(GPGErrorType::WriteFailError(e.to_string()), None));
+    }
+    let result: Result<VerifyResult, GPGError> = gpg.verify_file(
+        InputSource::Bytes(data.to_vec()),
+        Some(signature_file.path().to_path_buf()),
+        None,
+    );
+    match result {
+        Ok(result) => {
+            // a message with a bad signature should not be opened
+            if !result.valid {
+                let mut cmd_result: CmdResult = result.cmd_result;
+                return Err(GPGError::new(
+                    GPGErrorType::GPGProcessError(cmd_result.get_error_message()),
+                    Some(cmd_result),
+                ));
+            }
+            return Ok(result.cmd_result);
+        }
+        Err(e) => return Err(e),
+    }
+}
+
+//*******************************************************
+
+//                 ENCODING HELPERS
+
+//*******************************************************
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// base64 with lines of 76 characters as required by MIME
+fn base64_encode(data: &[u8]) -> String {
+    let mut encoded: String = String::new();
+    for chunk in data.chunks(3) {
+        let b: [u32; 3] = [
+            chunk[0] as u32,
+            *chunk.get(1).unwrap_or(&0) as u32,
+            *chunk.get(2).unwrap_or(&0) as u32,
+        ];
+        let n: u32 = (b[0] << 16) | (b[1] << 8) | b[2];
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_CHARS[((n >> (18 - i * 6)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    let lines: Vec<String> = encoded
+        .as_bytes()
+        .chunks(76)
+        .map(|l| String::from_utf8_lossy(l).to_string())
+        .collect();
+    return lines.join("\r\n");
+}
+
+fn base64_decode(data: &[u8]) -> Vec<u8> {
+    let mut decoded: Vec<u8> = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits: u32 = 0;
+    for c in data.iter() {
+        let value: u32 = match BASE64_CHARS.iter().position(|b| b == c) {
+            Some(value) => value as u32,
+            None => continue, // line breaks and padding
+        };
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    return decoded;
+}
+
+fn quoted_printable_decode(data: &[u8]) -> Vec<u8> {
+    let mut decoded: Vec<u8> = Vec::new();
+    let mut i: usize = 0;
+    while i < data.len() {
+        if data[i] != b'=' {
+            decoded.push(data[i]);
+            i += 1;
+            continue;
+        }
+        // soft line break
+        if data[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+            continue;
+        }
+        if data[i + 1..].starts_with(b"\n") {
+            i += 2;
+            continue;
+        }
+        let hex: String = String::from_utf8_lossy(&data[i + 1..(i + 3).min(data.len())]).to_string();
+        match u8::from_str_radix(&hex, 16) {
+            Ok(byte) if hex.len() == 2 => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(data[i]);
+                i += 1;
+            }
+        }
+    }
+    return decoded;
+}
+
+// text that can be sent as 7bit without being altered by mail transfer ( RFC 3156 3. )
+fn is_7bit_safe(text: &str) -> bool {
+    return text.is_ascii()
+        && text
+            .lines()
+            .all(|l| l.len() < 998 && !l.ends_with(' ') && !l.ends_with('\t') && !l.starts_with("From "));
+}
+
+fn to_crlf(text: &str) -> String {
+    return text.replace("\r\n", "\n").replace('\n', "\r\n");
+}
+
+// same as to_crlf, without decoding the data as UTF-8
+fn to_crlf_bytes(data: &[u8]) -> Vec<u8> {
+    let mut crlf: Vec<u8> = Vec::with_capacity(data.len());
+    for (i, byte) in data.iter().enumerate() {
+        if *byte == b'\n' && (i == 0 || data[i - 1] != b'\r') {
+            crlf.push(b'\r');
+        }
+        crlf.push(*byte);
+    }
+    return crlf;
+}
+
+fn gen_boundary(kind: &str) -> String {
+    return format!("crab-gnupg-{}-{}", kind, Local::now().format("%Y%m%d%H%M%S%9f"));
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    return haystack.windows(needle.len()).position(|w| w == needle);
+}
+
+fn invalid_message(message: &str) -> GPGError {
+    return GPGError::new(
+        GPGErrorType::InvalidArgumentError(format!("PGP/MIME message invalid: {}", message)),
+        None,
+    );
+}
@@ -0,0 +1,388 @@
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tempfile::NamedTempFile;
+
+use crate::gnupg::{DecryptOption, EncryptOption, SignOption, GPG};
+use crate::status::StatusLine;
+use crate::temp::create_temp_file;
+use crate::utils::enums::InputSource;
+use crate::utils::errors::{GPGError, GPGErrorType};
+use crate::utils::response::{CmdResult, VerifyResult};
+use crate::utils::secret::Passphrase;
+use crate::utils::utils::decode_verify_result;
+
+//*******************************************************
+
+//              RELATED TO PIPELINE
+
+//*******************************************************
+// what gpg was run for in the pipeline ( check GPG.run_pipeline )
+#[derive(Debug, Clone)]
+pub enum PipelineOperation {
+    // encrypt to the recipient(s) keyid
+    Encrypt(Vec<String>),
+    // encrypt with the passphrase instead of keys
+    EncryptSymmetric(Passphrase),
+    // decrypt, with the passphrase of the secret key ( or the passphrase it was symmetrically encrypted with )
+    Decrypt(Option<Passphrase>),
+}
+
+// how far the pipeline was, reported to the progress callback every time gpg read or wrote a chunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PipelineProgress {
+    // bytes_read: bytes of the input written to gpg so far
+    pub bytes_read: u64,
+    // bytes_written: bytes of the output gpg wrote to the sink so far
+    pub bytes_written: u64,
+}
+
+// called from the thread feeding the input and the thread writing the output, it should return quickly
+pub type ProgressCallback = Box<dyn Fn(&PipelineProgress) + Send + Sync>;
+
+// cancel a running pipeline from another thread, gpg will be killed and a CancelledError returned
+// clones share the same state, so keep one and move another into the PipelineSpec
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        return CancellationToken {
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        return self.cancelled.load(Ordering::SeqCst);
+    }
+}
+
+// everything a pipeline need, the input was streamed into gpg and its output streamed into the sink
+// without being collected in memory or written to a temporary file
+// ex) PipelineSpec { timeout: Some(timeout), ..PipelineSpec::new(input, Box::new(socket), operation) }
+pub struct PipelineSpec<'a> {
+    // input: the data to be processed, check InputSource
+    pub input: InputSource,
+    // output: where the output of gpg will be written to ( ex. a socket, a file or a buffer )
+    pub output: Box<dyn Write + 'a>,
+    // operation: what gpg was run for, check PipelineOperation
+    pub operation: PipelineOperation,
+    // progress: called with the bytes read and written so far, None to not report
+    pub progress: Option<ProgressCallback>,
+    // timeout: gpg will be killed if it was still running after this long and a TimeoutError returned
+    pub timeout: Option<Duration>,
+    // cancellation: gpg will be killed once it was cancelled and a CancelledError returned,
+    //               the input is only checked between reads so a read blocking on the input is waited for
+    pub cancellation: Option<CancellationToken>,
+    // always_trust: whether to always trust the keys of the recipients ( and the signer when decrypting )
+    pub always_trust: bool,
+}
+
+impl<'a> PipelineSpec<'a> {
+    // without progress, timeout or cancellation, and without always trusting the keys
+    pub fn new(input: InputSource, output: Box<dyn Write + 'a>, operation: PipelineOperation) -> PipelineSpec<'a> {
+        return PipelineSpec {
+            input: input,
+            output: output,
+            operation: operation,
+            progress: None,
+            timeout: None,
+            cancellation: None,
+            always_trust: false,
+        };
+    }
+}
+
+//*******************************************************
+
+//              RELATED TO CHAINED PIPELINE
+
+//*******************************************************
+// a step of a Pipeline, the data of a step was the output of the step before it
+#[derive(Debug, Clone)]
+pub enum PipelineStep {
+    // sign the data with an embedded signature, with the default key of the GPG if keyid was not provided
+    Sign {
+        keyid: Option<String>,
+        key_passphrase: Option<Passphrase>,
+    },
+    // make a detached signature of the data, the data was passed on unchanged and the signature kept in the outcome
+    DetachSign {
+        keyid: Option<String>,
+        key_passphrase: Option<Passphrase>,
+    },
+    // encrypt to the recipient(s) keyid
+    Encrypt(Vec<String>),
+    // encrypt with the passphrase instead of keys
+    EncryptSymmetric(Passphrase),
+    // decrypt, with the passphrase of the secret key ( or the passphrase it was symmetrically encrypted with )
+    Decrypt(Option<Passphrase>),
+    // verify the signature of the data, against the detached signature if there was one ( check Pipeline.signature ),
+    // the signature checked by the decryption before it, or the embedded signature which will be stripped from the data
+    // signers: fingerprint(s) of the key(s) the signature should be made by, None to accept any key in the keyring
+    Verify(Option<Vec<String>>),
+}
+
+// the output of a Pipeline
+#[derive(Debug)]
+pub struct PipelineOutcome {
+    // data: the output of the last step
+    pub data: Vec<u8>,
+    // signature: the detached signature made by DetachSign ( or provided with Pipeline.signature )
+    pub signature: Option<Vec<u8>>,
+    // verify_result: the result of the last Verify, None if the pipeline did not verify
+    pub verify_result: Option<VerifyResult>,
+    // results: the result of gpg for every step, in order
+    pub results: Vec<CmdResult>,
+}
+
+// compose operations into one call with one error path ( ex. detached sign then encrypt, decrypt then verify ),
+// the data between the steps was kept in memory, the first error stop the pipeline and was returned
+// ex) Pipeline::new(InputSource::Path(path)).detach_sign(None, None).encrypt(recipients).run(&gpg)
+pub struct Pipeline {
+    input: InputSource,
+    steps: Vec<PipelineStep>,
+    signature: Option<Vec<u8>>,
+}
+
+impl Pipeline {
+    pub fn new(input: InputSource) -> Pipeline {
+        // input: the data for the first step, check InputSource
+
+        return Pipeline {
+            input: input,
+            steps: Vec::new(),
+            signature: None,
+        };
+    }
+
+    // append a step
+    pub fn step(mut self, step: PipelineStep) -> Pipeline {
+        self.steps.push(step);
+        return self;
+    }
+
+    pub fn sign(self, keyid: Option<String>, key_passphrase: Option<Passphrase>) -> Pipeline {
+        return self.step(PipelineStep::Sign {
+            keyid: keyid,
+            key_passphrase: key_passphrase,
+        });
+    }
+
+    pub fn detach_sign(self, keyid: Option<String>, key_passphrase: Option<Passphrase>) -> Pipeline {
+        return self.step(PipelineStep::DetachSign {
+            keyid: keyid,
+            key_passphrase: key_passphrase,
+        });
+    }
+
+    pub fn encrypt(self, recipients: Vec<String>) -> Pipeline {
+        return self.step(PipelineStep::Encrypt(recipients));
+    }
+
+    pub fn encrypt_symmetric(self, passphrase: Passphrase) -> Pipeline {
+        return self.step(PipelineStep::EncryptSymmetric(passphrase));
+    }
+
+    pub fn decrypt(self, passphrase: Option<Passphrase>) -> Pipeline {
+        return self.step(PipelineStep::Decrypt(passphrase));
+    }
+
+    pub fn verify(self, signers: Option<Vec<String>>) -> Pipeline {
+        return self.step(PipelineStep::Verify(signers));
+    }
+
+    // the detached signature to verify the data against ( ex. received along with the encrypted file )
+    pub fn signature(mut self, signature: Vec<u8>) -> Pipeline {
+        self.signature = Some(signature);
+        return self;
+    }
+
+    // run the steps in order with the GPG
+    pub fn run(self, gpg: &GPG) -> Result<PipelineOutcome, GPGError> {
+        if self.steps.is_empty() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("the pipeline has no step to run".to_string()),
+                None,
+            ));
+        }
+        let mut outcome: PipelineOutcome = PipelineOutcome {
+            data: Vec::new(),
+            signature: self.signature,
+            verify_result: None,
+            results: Vec::new(),
+        };
+        let mut input: Option<InputSource> = Some(self.input);
+        // the result of the decryption just before, which already checked the signature inside the data
+        let mut decrypted: Option<CmdResult> = None;
+        for step in self.steps {
+            // the first step read the input, the others read the output of the step before
+            let data: InputSource = match input.take() {
+                Some(input) => input,
+                None => InputSource::Bytes(std::mem::take(&mut outcome.data)),
+            };
+            let is_decrypt: bool = matches!(step, PipelineStep::Decrypt(_));
+            let mut output: Vec<u8> = Vec::new();
+            let result: Result<CmdResult, GPGError> = match step {
+                PipelineStep::Sign { keyid, key_passphrase } => {
+                    gpg.sign_to_writer(sign_option(data, keyid, key_passphrase, false), &mut output)
+                }
+                PipelineStep::DetachSign { keyid, key_passphrase } => {
+                    // the data was needed for both the signing and the next step
+                    output = read_input(data)?;
+                    let mut signature: Vec<u8> = Vec::new();
+                    let option: SignOption = sign_option(InputSource::Bytes(output.clone()), keyid, key_passphrase, true);
+                    let result: Result<CmdResult, GPGError> = gpg.sign_to_writer(option, &mut signature);
+                    outcome.signature = Some(signature);
+                    result
+                }
+                PipelineStep::Encrypt(recipients) => {
+                    gpg.encrypt_to_writer(EncryptOption::default(data, recipients, None), &mut output)
+                }
+                PipelineStep::EncryptSymmetric(passphrase) => {
+                    gpg.encrypt_to_writer(EncryptOption::with_symmetric(data, None, passphrase, None), &mut output)
+                }
+                PipelineStep::Decrypt(passphrase) => gpg.decrypt_to_writer(decrypt_option(data, passphrase), &mut output),
+                PipelineStep::Verify(signers) => {
+                    match verify_data(gpg, data, outcome.signature.as_deref(), decrypted.take(), &mut output) {
+                        Ok(verify_result) => match check_signer(&verify_result, signers) {
+                            Ok(_) => {
+                                let result: CmdResult = verify_result.cmd_result.clone();
+                                outcome.verify_result = Some(verify_result);
+                                Ok(result)
+                            }
+                            Err(e) => Err(e),
+                        },
+                        Err(e) => Err(e),
+                    }
+                }
+            };
+            let result: CmdResult = result?;
+            decrypted = if is_decrypt { Some(result.clone()) } else { None };
+            outcome.results.push(result);
+            outcome.data = output;
+        }
+        return Ok(outcome);
+    }
+}
+
+fn sign_option(input: InputSource, keyid: Option<String>, key_passphrase: Option<Passphrase>, detach: bool) -> SignOption {
+    return SignOption {
+        input: input,
+        keyid: keyid,
+        key_passphrase: key_passphrase,
+        clearsign: false,
+        detach: detach,
+        output: None,
+        extra_args: None,
+        reject_unusable_keys: false,
+    };
+}
+
+fn decrypt_option(input: InputSource, passphrase: Option<Passphrase>) -> DecryptOption {
+    return DecryptOption {
+        input: input,
+        recipient: None,
+        always_trust: true,
+        passphrase: passphrase,
+        key_passphrase: None,
+        output: None,
+        extra_args: None,
+        strict: false,
+        show_session_key: false,
+        session_key: None,
+    };
+}
+
+fn read_input(input: InputSource) -> Result<Vec<u8>, GPGError> {
+    if let InputSource::Bytes(bytes) = input {
+        return Ok(bytes);
+    }
+    let mut reader: Box<dyn Read + Send> = input.into_reader()?;
+    let mut content: Vec<u8> = Vec::new();
+    if let Err(e) = reader.read_to_end(&mut content) {
+        return Err(GPGError::new(
+            GPGErrorType::ReadFailError(format!("failed to read the input: {}", e)),
+            None,
+        ));
+    }
+    return Ok(content);
+}
+
+// verify the data of a Verify step, the data ( without the embedded signature ) was written to output
+fn verify_data(
+    gpg: &GPG,
+    data: InputSource,
+    signature: Option<&[u8]>,
+    decrypted: Option<CmdResult>,
+    output: &mut Vec<u8>,
+) -> Result<VerifyResult, GPGError> {
+    // signature: the detached signature of the data, if there was one
+    // decrypted: the result of the decryption just before the Verify step, if there was one
+
+    if let Some(signature) = signature {
+        let content: Vec<u8> = read_input(data)?;
+        // gpg only read a detached signature from a file
+        let mut signature_file: NamedTempFile = create_temp_file("signature")?;
+        if let Err(e) = signature_file.write_all(signature).and_then(|_| signature_file.flush()) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to write the signature: {}", e)),
+                None,
+            ));
+        }
+        let result: Result<VerifyResult, GPGError> =
+            gpg.verify_file(InputSource::Bytes(content.clone()), Some(signature_file.path().to_path_buf()), None);
+        *output = content;
+        return result;
+    }
+    if let Some(decrypted) = decrypted {
+        // the data was signed before it was encrypted, gpg checked the signature while decrypting
+        let verify_result: VerifyResult = VerifyResult::from_cmd_result(decrypted);
+        if verify_result.key_id.is_some() {
+            *output = read_input(data)?;
+            return Ok(verify_result);
+        }
+    }
+    // gpg write the signed content while checking the embedded signature
+    return decode_verify_result(gpg.decrypt_to_writer(decrypt_option(data, None), output));
+}
+
+// the signature should be good and made by one of the signers ( the signing subkey or its primary key )
+fn check_signer(verify_result: &VerifyResult, signers: Option<Vec<String>>) -> Result<(), GPGError> {
+    if !verify_result.valid {
+        let mut result: CmdResult = verify_result.cmd_result.clone();
+        result.success = false;
+        return Err(GPGError::new(
+            GPGErrorType::GPGProcessError(format!("the signature was not valid: {}", result.get_error_message())),
+            Some(result),
+        ));
+    }
+    let signers: Vec<String> = match signers {
+        Some(signers) => signers,
+        None => return Ok(()),
+    };
+    let mut fingerprints: Vec<String> = verify_result.fingerprint.clone().into_iter().collect();
+    for status in verify_result.cmd_result.status_lines() {
+        if let StatusLine::ValidSig { primary_fingerprint: Some(primary_fingerprint), .. } = status {
+            fingerprints.push(primary_fingerprint);
+        }
+    }
+    if signers.iter().any(|signer| fingerprints.iter().any(|fingerprint| fingerprint.eq_ignore_ascii_case(signer))) {
+        return Ok(());
+    }
+    return Err(GPGError::new(
+        GPGErrorType::GPGProcessError(format!(
+            "the signature was made by {} which was not one of the accepted signers",
+            verify_result.fingerprint.clone().unwrap_or_default()
+        )),
+        Some(verify_result.cmd_result.clone()),
+    ));
+}
@@ -0,0 +1,230 @@
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::config::run_gpgconf;
+use crate::gnupg::{GPGBuilder, GPG};
+use crate::utils::errors::{GPGError, GPGErrorType};
+
+//*******************************************************
+
+//               RELATED TO GPG POOL OPTION
+
+//*******************************************************
+// the contexts a GpgPool will be initialized with
+#[derive(Debug, Clone)]
+pub struct GpgPoolOption {
+    // builder: the settings of every context, its homedir will be replaced by the homedir of the context
+    pub builder: GPGBuilder,
+    // homedirs: the homedir of every context keyed by the name the context will be checked out with ( ex. the tenant )
+    pub homedirs: HashMap<String, PathBuf>,
+    // prewarm_agent: whether to start the gpg-agent of every homedir when the context was added,
+    //                so the first operation needing a secret key does not wait for the agent to start
+    pub prewarm_agent: bool,
+    // checkout_timeout: how long checkout will wait for a context checked out by another thread,
+    //                   None to wait until it was checked in
+    pub checkout_timeout: Option<Duration>,
+}
+
+impl GpgPoolOption {
+    // without prewarming the agent, checkout waiting up to 30 seconds
+    pub fn default(builder: GPGBuilder, homedirs: HashMap<String, PathBuf>) -> GpgPoolOption {
+        return GpgPoolOption {
+            builder: builder,
+            homedirs: homedirs,
+            prewarm_agent: false,
+            checkout_timeout: Some(Duration::from_secs(30)),
+        };
+    }
+}
+
+//*******************************************************
+
+//                  RELATED TO GPG POOL
+
+//*******************************************************
+// the initialized contexts of the pool, None for a context checked out
+#[derive(Debug)]
+struct PoolState {
+    contexts: HashMap<String, Option<GPG>>,
+}
+
+// a set of pre-initialized GPG keyed by name, each on its own homedir, for a multi-tenant service to route the
+// operation to the keyring of the tenant without initializing gpg for every request
+// a context can only be checked out by one thread at a time, it will be checked in when the PooledGpg was dropped
+#[derive(Debug, Clone)]
+pub struct GpgPool {
+    // builder: the settings a context added later will be initialized with
+    builder: GPGBuilder,
+    prewarm_agent: bool,
+    checkout_timeout: Option<Duration>,
+    // shared with the checked out PooledGpg, the condvar was notified when a context was checked in
+    state: Arc<(Mutex<PoolState>, Condvar)>,
+}
+
+impl GpgPool {
+    // initialize a context for every homedir, fail with the first context that could not be initialized
+    pub fn new(pool_option: GpgPoolOption) -> Result<GpgPool, GPGError> {
+        // pool_option: the contexts and settings of the pool, check GpgPoolOption
+
+        let pool: GpgPool = GpgPool {
+            builder: pool_option.builder,
+            prewarm_agent: pool_option.prewarm_agent,
+            checkout_timeout: pool_option.checkout_timeout,
+            state: Arc::new((
+                Mutex::new(PoolState {
+                    contexts: HashMap::new(),
+                }),
+                Condvar::new(),
+            )),
+        };
+        for (name, homedir) in pool_option.homedirs {
+            pool.add(name, homedir)?;
+        }
+        return Ok(pool);
+    }
+
+    // initialize a context for the homedir ( ex. for a new tenant ), replacing the context of the same name
+    // that was not checked out
+    pub fn add(&self, name: String, homedir: PathBuf) -> Result<(), GPGError> {
+        // name: the name the context will be checked out with
+        // homedir: the homedir of the context, created if it does not exist
+
+        let gpg: GPG = self.builder.clone().homedir(homedir).build()?;
+        if self.prewarm_agent {
+            // the agent will keep running for the next operation
+            run_gpgconf(&gpg.homedir, &["--launch", "gpg-agent"])?;
+        }
+        let mut state = self.state.0.lock().unwrap();
+        if let Some(None) = state.contexts.get(&name) {
+            return Err(GPGError::new(
+                GPGErrorType::PoolError(format!("context {} was checked out, it could not be replaced", name)),
+                None,
+            ));
+        }
+        state.contexts.insert(name, Some(gpg));
+        self.state.1.notify_all();
+        return Ok(());
+    }
+
+    // remove the context from the pool, return false if there was no such context
+    // NOTE: a context checked out will be dropped instead of checked in
+    pub fn remove(&self, name: &str) -> bool {
+        return self.state.0.lock().unwrap().contexts.remove(name).is_some();
+    }
+
+    // the name of every context in the pool, including those checked out
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.state.0.lock().unwrap().contexts.keys().cloned().collect();
+        names.sort();
+        return names;
+    }
+
+    // how many contexts were not checked out
+    pub fn available(&self) -> usize {
+        return self.state.0.lock().unwrap().contexts.values().filter(|c| c.is_some()).count();
+    }
+
+    // check out the context, waiting up to checkout_timeout if it was checked out by another thread
+    // an InvalidArgumentError will be returned for a name not in the pool, and a PoolError if it timed out
+    pub fn checkout(&self, name: &str) -> Result<PooledGpg, GPGError> {
+        // name: the name the context was added with
+
+        let started: Instant = Instant::now();
+        let mut state = self.state.0.lock().unwrap();
+        loop {
+            match state.contexts.get_mut(name) {
+                Some(context) => {
+                    if let Some(gpg) = context.take() {
+                        return Ok(PooledGpg {
+                            name: name.to_string(),
+                            gpg: Some(gpg),
+                            state: self.state.clone(),
+                        });
+                    }
+                }
+                None => {
+                    return Err(GPGError::new(
+                        GPGErrorType::InvalidArgumentError(format!("no context named {} in the pool", name)),
+                        None,
+                    ));
+                }
+            }
+            state = match self.checkout_timeout {
+                Some(timeout) => {
+                    let remaining: Duration = timeout.saturating_sub(started.elapsed());
+                    if remaining.is_zero() {
+                        return Err(GPGError::new(
+                            GPGErrorType::PoolError(format!(
+                                "timed out after {:?} waiting for context {} to be checked in",
+                                timeout, name
+                            )),
+                            None,
+                        ));
+                    }
+                    self.state.1.wait_timeout(state, remaining).unwrap().0
+                }
+                None => self.state.1.wait(state).unwrap(),
+            };
+        }
+    }
+
+    // check the context in, same as dropping it
+    pub fn checkin(&self, pooled: PooledGpg) {
+        drop(pooled);
+    }
+}
+
+//*******************************************************
+
+//                 RELATED TO POOLED GPG
+
+//*******************************************************
+// a context checked out of a GpgPool, deref ( mutably ) to the GPG of the context
+// it will be checked in when dropped, any change made to it ( ex. passphrase_provider ) will be kept in the pool
+#[derive(Debug)]
+pub struct PooledGpg {
+    // name: the name of the context
+    pub name: String,
+    // only None after it was checked in
+    gpg: Option<GPG>,
+    state: Arc<(Mutex<PoolState>, Condvar)>,
+}
+
+impl Deref for PooledGpg {
+    type Target = GPG;
+
+    fn deref(&self) -> &GPG {
+        return self.gpg.as_ref().unwrap();
+    }
+}
+
+impl DerefMut for PooledGpg {
+    fn deref_mut(&mut self) -> &mut GPG {
+        return self.gpg.as_mut().unwrap();
+    }
+}
+
+impl Drop for PooledGpg {
+    fn drop(&mut self) {
+        let gpg: GPG = match self.gpg.take() {
+            Some(gpg) => gpg,
+            None => return,
+        };
+        let mut state = match self.state.0.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        // the context was dropped if it was removed from the pool ( or replaced ) while it was checked out
+        if let Some(context) = state.contexts.get_mut(&self.name) {
+            if context.is_none() {
+                *context = Some(gpg);
+            }
+        }
+        self.state.1.notify_all();
+    }
+}
@@ -1,98 +1,664 @@
 use std::{
     collections::HashMap,
-    fs::File,
-    io::{Error, Read, Write},
+    io::{BufRead, BufReader, Cursor, Error, Read, Write},
+    path::Path,
     process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Stdio},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
+use tempfile::NamedTempFile;
+
+use crate::pipeline::{CancellationToken, PipelineProgress, ProgressCallback};
+use crate::runner::GpgRunner;
+use crate::status::split_status_line;
+use crate::temp::create_temp_file;
 use crate::utils::{
-    enums::Operation,
+    enums::{GpgExitStatus, InputSource, Operation},
     errors::{GPGError, GPGErrorType},
     response::CmdResult,
-    utils::get_file_obj,
+    secret::{Passphrase, PassphraseProvider},
+    utils::{get_passphrase_hint, is_bad_passphrase, redact_args},
 };
 
 const BUFFER_SIZE: usize = 8192;
+// output of gpg larger than this ( in bytes ) will be written to a temporary file by default
+pub const DEFAULT_OUTPUT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+// how often a process run with a timeout was checked for having exited
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 //*******************************************************
 
 //             RELATED TO COMMAND PROCESS
 
 //*******************************************************
-// a centralized function to spawn Command and handle its IO
+// a centralized function to run gpg through the runner and handle its IO
+// each part of the gpg invocation was passed on its own, shared by the other variants of handle_cmd_io
+#[allow(clippy::too_many_arguments)]
 pub fn handle_cmd_io(
+    runner: &dyn GpgRunner,
     cmd_args: Option<Vec<String>>,
-    passphrase: Option<String>,
+    passphrase: Option<Passphrase>,
     version: f32,
     homedir: String,
     options: Option<Vec<String>>,
     env: Option<HashMap<String, String>>,
-    file: Option<File>,
-    file_path: Option<String>,
-    byte_input: Option<Vec<u8>>,
-    write: bool,
-    file_needed: bool,
+    input: Option<InputSource>,
     ops: Operation,
 ) -> Result<CmdResult, GPGError> {
-    let mut write_thread: Option<JoinHandle<()>> = None;
-    let passphrase: Option<String> = if passphrase.is_some() {passphrase.clone()} else {Some("".to_string())};
-    let process: Result<Child, Error> = start_process(
-        Some(cmd_args.unwrap()),
-        passphrase.clone(),
-        version,
-        homedir,
-        options,
-        env,
+    // input: written into stdin after the passphrase, None if gpg does not need any input
+
+    let passphrase: Option<Passphrase> = if passphrase.is_some() {passphrase} else {Some(Passphrase::from(""))};
+    let args: Vec<String> = generate_cmd_args(cmd_args, passphrase.is_some(), version, homedir, options);
+    return run_cmd_io(runner, args, passphrase, env, input, ops);
+}
+
+// a variant of handle_cmd_io for operation that may need the passphrase of a secret key
+// without a passphrase, gpg will be run without one first, if it failed asking for a passphrase ( NEED_PASSPHRASE )
+// the provider will be consulted with the key gpg asked for and gpg will be run again with the passphrase provided
+#[allow(clippy::too_many_arguments)]
+pub fn handle_cmd_io_with_provider(
+    runner: &dyn GpgRunner,
+    cmd_args: Option<Vec<String>>,
+    passphrase: Option<Passphrase>,
+    provider: Option<&dyn PassphraseProvider>,
+    retries: u32,
+    version: f32,
+    homedir: String,
+    options: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    input: Option<InputSource>,
+    ops: Operation,
+) -> Result<CmdResult, GPGError> {
+    // provider: to be consulted if passphrase was None, check PassphraseProvider
+    // retries: how many more times the provider will be consulted if the passphrase it returned was bad
+
+    if passphrase.is_some() || provider.is_none() {
+        return handle_cmd_io(runner, cmd_args, passphrase, version, homedir, options, env, input, ops);
+    }
+    let provider: &dyn PassphraseProvider = provider.unwrap();
+    // the input will be written to gpg again every time the passphrase was asked for
+    let (input, mut retry_input): (Option<InputSource>, Option<InputSource>) = match input {
+        Some(input) => match input.duplicate() {
+            Ok((input, retry_input)) => (Some(input), Some(retry_input)),
+            Err(e) => return Err(e),
+        },
+        None => (None, None),
+    };
+
+    // without --passphrase-fd, gpg in batch mode will report the key it need the passphrase of and fail
+    let mut args: Vec<String> = vec![];
+    if version >= 2.1 {
+        args.append(&mut vec!["--pinentry-mode".to_string(), "loopback".to_string()]);
+    }
+    args.append(&mut cmd_args.clone().unwrap());
+    let args: Vec<String> = generate_cmd_args(Some(args), false, version, homedir.clone(), options.clone());
+    let mut error: GPGError = match run_cmd_io(runner, args, None, env.clone(), input, ops.clone()) {
+        Ok(result) => return Ok(result),
+        Err(e) => e,
+    };
+    let key_hint: String = match error.cmd_result.as_ref().and_then(get_passphrase_hint) {
+        Some(key_hint) => key_hint,
+        None => return Err(error),
+    };
+
+    // gpg may have created the output before asking for the passphrase, it should be overwritten
+    let mut cmd_args: Vec<String> = cmd_args.unwrap();
+    if cmd_args.contains(&"--output".to_string()) && !cmd_args.contains(&"--yes".to_string()) {
+        cmd_args.insert(0, "--yes".to_string());
+    }
+    for attempt in 0..=retries {
+        if attempt > 0 {
+            provider.on_bad_passphrase(&key_hint, ops.clone());
+        }
+        let passphrase: Passphrase = match provider.get_passphrase(&key_hint, ops.clone()) {
+            Some(passphrase) => passphrase,
+            None => return Err(error),
+        };
+        // the last attempt can take the input, the others write a copy of it
+        let input: Option<InputSource> = match retry_input.take() {
+            Some(source) if attempt < retries => match source.duplicate() {
+                Ok((source, input)) => {
+                    retry_input = Some(source);
+                    Some(input)
+                }
+                Err(e) => return Err(e),
+            },
+            source => source,
+        };
+        error = match handle_cmd_io(runner, Some(cmd_args.clone()), Some(passphrase), version, homedir.clone(), options.clone(), env.clone(), input, ops.clone()) {
+            Ok(result) => return Ok(result),
+            Err(e) => e,
+        };
+        // only a bad passphrase was worth asking the provider again
+        if !error.cmd_result.as_ref().is_some_and(is_bad_passphrase) {
+            return Err(error);
+        }
+    }
+    return Err(error);
+}
+
+// write the passphrase and the input into stdin of gpg and check the result
+fn run_cmd_io(
+    runner: &dyn GpgRunner,
+    args: Vec<String>,
+    passphrase: Option<Passphrase>,
+    env: Option<HashMap<String, String>>,
+    input: Option<InputSource>,
+    ops: Operation,
+) -> Result<CmdResult, GPGError> {
+    // gpg read the passphrase from stdin before the input
+    let mut stdin: Box<dyn Read + Send> = Box::new(std::io::empty());
+    if let Some(passphrase) = passphrase {
+        stdin = Box::new(Cursor::new(passphrase.to_line()));
+    }
+    if let Some(input) = input {
+        match input.into_reader() {
+            Ok(reader) => stdin = Box::new(stdin.chain(reader)),
+            Err(e) => return Err(e.with_context(ops, &args)),
+        }
+    }
+    let mut result: CmdResult = match runner.run(args.clone(), Some(stdin), env, ops.clone()) {
+        Ok(result) => result,
+        Err(mut e) => {
+            if let Some(result) = e.cmd_result.as_mut() {
+                remove_spilled_output(result);
+            }
+            return Err(e.with_context(ops, &args));
+        }
+    };
+    result.args = redact_args(&args);
+    if result.is_success() {
+        return Ok(result);
+    }
+    remove_spilled_output(&mut result);
+    return Err(GPGError::new(
+        GPGErrorType::GPGProcessError(result.get_error_message()),
+        Some(result),
+    ));
+}
+
+// a variant of handle_cmd_io for operation that need to answer gpg prompt one by one through --command-fd
+#[allow(clippy::too_many_arguments)]
+pub fn handle_cmd_interactive(
+    runner: &dyn GpgRunner,
+    cmd_args: Option<Vec<String>>,
+    passphrase: Option<Passphrase>,
+    version: f32,
+    homedir: String,
+    options: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+    ops: Operation,
+) -> Result<CmdResult, GPGError> {
+    // responder: receive the prompt type ( GET_LINE, GET_BOOL or GET_HIDDEN ) and the prompt keyword ( ex. keyedit.prompt )
+    //            and return the answer, returning None will close stdin and gpg will treat it as end of input
+
+    let passphrase: Option<Passphrase> = if passphrase.is_some() {passphrase} else {Some(Passphrase::from(""))};
+    return run_cmd_interactive(runner, cmd_args, passphrase, version, homedir, options, env, responder, ops);
+}
+
+// a variant of handle_cmd_interactive for smartcard operation ( or any operation leaving the passphrase to the responder )
+// no passphrase will be preset, every passphrase and PIN gpg asks for will be passed to the responder
+// as a GET_HIDDEN passphrase.enter prompt in the order gpg asks for it
+#[allow(clippy::too_many_arguments)]
+pub fn handle_card_cmd_interactive(
+    runner: &dyn GpgRunner,
+    cmd_args: Option<Vec<String>>,
+    version: f32,
+    homedir: String,
+    options: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+    ops: Operation,
+) -> Result<CmdResult, GPGError> {
+    // with a preset passphrase ( --passphrase-fd ), gpg would answer every PIN request with it
+    let mut args: Vec<String> = vec![];
+    if version >= 2.1 {
+        args.append(&mut vec!["--pinentry-mode".to_string(), "loopback".to_string()]);
+    }
+    args.append(&mut cmd_args.unwrap());
+    return run_cmd_interactive(runner, Some(args), None, version, homedir, options, env, responder, ops);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_cmd_interactive(
+    runner: &dyn GpgRunner,
+    cmd_args: Option<Vec<String>>,
+    passphrase: Option<Passphrase>,
+    version: f32,
+    homedir: String,
+    options: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+    ops: Operation,
+) -> Result<CmdResult, GPGError> {
+    let mut args: Vec<String> = vec!["--command-fd".to_string(), "0".to_string()];
+    args.append(&mut cmd_args.unwrap());
+    let args: Vec<String> = generate_cmd_args(Some(args), passphrase.is_some(), version, homedir, options);
+    // gpg read the passphrase from the same fd before reading any command
+    let stdin_prefix: Option<Passphrase> = passphrase.map(|p| p.to_line());
+    let mut result: CmdResult = match runner.run_interactive(args.clone(), stdin_prefix, env, responder, ops.clone()) {
+        Ok(result) => result,
+        Err(mut e) => {
+            if let Some(result) = e.cmd_result.as_mut() {
+                remove_spilled_output(result);
+            }
+            return Err(e.with_context(ops, &args));
+        }
+    };
+    result.args = redact_args(&args);
+    if result.is_success() {
+        return Ok(result);
+    }
+    remove_spilled_output(&mut result);
+    return Err(GPGError::new(
+        GPGErrorType::GPGProcessError(result.get_error_message()),
+        Some(result),
+    ));
+}
+
+// a variant of handle_cmd_io for operation that read the output from stdout as gpg is writing it
+// NOTE: this always spawn a local gpg process as the output was streamed from the child process
+#[allow(clippy::too_many_arguments)]
+pub fn handle_cmd_stream(
+    gpg_program: &Path,
+    cmd_args: Option<Vec<String>>,
+    passphrase: Option<Passphrase>,
+    version: f32,
+    homedir: String,
+    options: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    ops: Operation,
+) -> Result<CmdStream, GPGError> {
+    // gpg_program: the gpg binary to spawn ( ex. the one run by the runner of GPG )
+    // passphrase: will be written to stdin before returning, the input should be written after it
+    // the returned process still have its stdin and stdout, stderr will be collected until it was waited for
+
+    let args: Vec<String> = replace_gpg_program(
+        generate_cmd_args(cmd_args, passphrase.is_some(), version, homedir, options),
+        gpg_program,
     );
-    let mut cmd_process = match process {
+    let mut cmd_process: Child = match spawn_process(args.clone(), env) {
         Ok(child) => child,
         Err(e) => {
-            return Err(GPGError::new(
-                GPGErrorType::FailedToStartProcess(e.to_string()),
-                None,
-            ))
+            return Err(GPGError::new(GPGErrorType::FailedToStartProcess(e.to_string()), None).with_context(ops, &args));
+        }
+    };
+    if let Some(passphrase) = passphrase {
+        let stdin: &mut ChildStdin = cmd_process.stdin.as_mut().unwrap();
+        if let Err(e) = stdin.write_all(passphrase.to_line().expose().as_bytes()) {
+            let _ = cmd_process.kill();
+            let _ = cmd_process.wait();
+            return Err(GPGError::new(GPGErrorType::WriteFailError(e.to_string()), None).with_context(ops, &args));
+        }
+    }
+    let stderr: ChildStderr = cmd_process.stderr.take().unwrap();
+    let stderr_ops: Operation = ops.clone();
+    let stderr_thread: JoinHandle<CmdResult> = thread::spawn(move || {
+        let mut result: CmdResult = CmdResult::init(stderr_ops);
+        read_cmd_response(stderr, Arc::new(Mutex::new(&mut result)));
+        return result;
+    });
+    return Ok(CmdStream {
+        process: cmd_process,
+        args: redact_args(&args),
+        ops: ops,
+        stderr_thread: stderr_thread,
+    });
+}
+
+// a gpg process spawned by handle_cmd_stream, writing its stdin and reading its stdout were left to the caller
+pub struct CmdStream {
+    pub process: Child,
+    // the arguments gpg was spawned with ( starting with the program ), secret arguments were redacted
+    pub args: Vec<String>,
+    ops: Operation,
+    // collecting the status gpg wrote to stderr, so gpg will not block on a full pipe
+    stderr_thread: JoinHandle<CmdResult>,
+}
+
+impl CmdStream {
+    // wait for gpg to exit, the CmdResult carry the status gpg reported and its exit code
+    pub fn wait(mut self) -> CmdResult {
+        let exit_code: i32 = match self.process.wait() {
+            Ok(status) => status.code().unwrap_or(-1),
+            Err(_) => -1,
+        };
+        let mut result: CmdResult = match self.stderr_thread.join() {
+            Ok(result) => result,
+            Err(_) => CmdResult::init(self.ops),
+        };
+        result.set_return_code(exit_code);
+        result.args = self.args;
+        return result;
+    }
+}
+
+// a variant of handle_cmd_stream for GPG.run_pipeline, the input was written to stdin and stdout was written to the
+// output as gpg is reading and writing them, while the status was still collected into the CmdResult
+// NOTE: this always spawn a local gpg process as the output was streamed from the child process
+#[allow(clippy::too_many_arguments)]
+pub fn handle_cmd_pipeline(
+    gpg_program: &Path,
+    cmd_args: Option<Vec<String>>,
+    passphrase: Option<Passphrase>,
+    version: f32,
+    homedir: String,
+    options: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    input: InputSource,
+    output: &mut dyn Write,
+    progress: Option<&ProgressCallback>,
+    timeout: Option<Duration>,
+    cancellation: Option<&CancellationToken>,
+    ops: Operation,
+) -> Result<CmdResult, GPGError> {
+    // gpg_program: the gpg binary to spawn ( ex. the one run by the runner of GPG )
+    // passphrase: will be written to stdin before the input
+    // progress: called with the bytes read and written so far from the thread doing it
+    // timeout: the process will be killed if it was still running after this long, None to wait for it
+    // cancellation: the process will be killed once it was cancelled, the input is only checked for the cancellation
+    //               between reads, so this will not return before a read blocking on the input returned
+
+    let args: Vec<String> = replace_gpg_program(
+        generate_cmd_args(cmd_args, passphrase.is_some(), version, homedir, options),
+        gpg_program,
+    );
+    let mut reader: Box<dyn Read + Send> = match input.into_reader() {
+        Ok(reader) => reader,
+        Err(e) => return Err(e.with_context(ops, &args)),
+    };
+    let mut cmd_process: Child = match spawn_process(args.clone(), env) {
+        Ok(child) => child,
+        Err(e) => {
+            return Err(GPGError::new(GPGErrorType::FailedToStartProcess(e.to_string()), None).with_context(ops, &args));
         }
     };
     let mut stdin: ChildStdin = cmd_process.stdin.take().unwrap();
-    match passphrase {
-        Some(passphrase) => {
-            let _ = stdin.write_all(passphrase.as_bytes());
-            let _ = stdin.write_all("\n".as_bytes());
+    if let Some(passphrase) = passphrase {
+        if let Err(e) = stdin.write_all(passphrase.to_line().expose().as_bytes()) {
+            let _ = cmd_process.kill();
+            let _ = cmd_process.wait();
+            return Err(GPGError::new(GPGErrorType::WriteFailError(e.to_string()), None).with_context(ops, &args));
         }
-        None => {}
     }
-    if write {
-        let file: Result<File, GPGError> = get_file_obj(file, file_path);
-        match file {
-            Ok(file) => {
-                write_thread = Some(start_writing_process(Some(file), byte_input, stdin));
-            }
-            Err(err) => {
-                if file_needed {
-                    return Err(err);
+    let stderr: ChildStderr = cmd_process.stderr.take().unwrap();
+    let mut stdout: ChildStdout = cmd_process.stdout.take().unwrap();
+
+    let bytes_read: &AtomicU64 = &AtomicU64::new(0);
+    let bytes_written: &AtomicU64 = &AtomicU64::new(0);
+    let report = || {
+        if let Some(progress) = progress {
+            progress(&PipelineProgress {
+                bytes_read: bytes_read.load(Ordering::SeqCst),
+                bytes_written: bytes_written.load(Ordering::SeqCst),
+            });
+        }
+    };
+    let mut result = CmdResult::init(ops.clone());
+    let share_result: Arc<Mutex<&mut CmdResult>> = Arc::new(Mutex::new(&mut result));
+    let mut stopped: Option<GPGErrorType> = None;
+    let mut write_error: Option<Error> = None;
+    let mut input_cancelled: bool = false;
+    thread::scope(|s| {
+        s.spawn(|| {
+            read_cmd_response(stderr, Arc::clone(&share_result));
+        });
+        let cancelled: &mut bool = &mut input_cancelled;
+        s.spawn(move || {
+            let mut buffer: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
+            loop {
+                // gpg may complete the truncated input before it was killed, reported as cancelled all the same
+                if cancellation.is_some_and(|c| c.is_cancelled()) {
+                    *cancelled = true;
+                    break;
+                }
+                let n: usize = match reader.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                // gpg exited ( or was killed ) without reading the whole input
+                if stdin.write_all(&buffer[..n]).is_err() {
+                    break;
                 }
-                write_thread = Some(start_writing_process(None, byte_input, stdin));
+                bytes_read.fetch_add(n as u64, Ordering::SeqCst);
+                report();
             }
+            // dropping stdin to let gpg know there will be no more input
+            drop(stdin);
+        });
+        if timeout.is_some() || cancellation.is_some() {
+            let (process, stopped): (&mut Child, &mut Option<GPGErrorType>) = (&mut cmd_process, &mut stopped);
+            s.spawn(move || *stopped = wait_with_cancellation(process, timeout, cancellation));
         }
+        let mut buffer: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
+        loop {
+            let n: usize = match stdout.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            if let Err(e) = output.write_all(&buffer[..n]) {
+                // gpg will fail writing to a closed stdout and exit
+                write_error = Some(e);
+                break;
+            }
+            bytes_written.fetch_add(n as u64, Ordering::SeqCst);
+            report();
+        }
+        drop(stdout);
+    });
+    let exit_code: i32 = match cmd_process.wait() {
+        Ok(status) => status.code().unwrap_or(-1),
+        Err(_) => -1,
+    };
+    result.set_return_code(exit_code);
+    result.args = redact_args(&args);
+    if let Err(e) = output.flush() {
+        write_error = write_error.or(Some(e));
+    }
+    if stopped.is_none() && input_cancelled {
+        stopped = Some(GPGErrorType::CancelledError("the operation was cancelled before the whole input was read".to_string()));
+    }
+    if let Some(error_type) = stopped {
+        result.success = false;
+        return Err(GPGError::new(error_type, Some(result)).with_context(ops, &args));
+    }
+    if let Some(e) = write_error {
+        result.success = false;
+        return Err(GPGError::new(
+            GPGErrorType::WriteFailError(format!("failed to write the output: {}", e)),
+            Some(result),
+        )
+        .with_context(ops, &args));
     }
-    let mut result = CmdResult::init(ops);
-    let share_result: Arc<Mutex<&mut CmdResult>> = Arc::new(Mutex::new(&mut result));
-    collect_cmd_output_response(cmd_process, share_result, write_thread);
     if result.is_success() {
         return Ok(result);
     }
+    return Err(GPGError::new(
+        GPGErrorType::GPGProcessError(result.get_error_message()),
+        Some(result),
+    )
+    .with_context(ops, &args));
+}
+
+// a variant of handle_cmd_io to verify signature with gpgv against the keyring provided
+pub fn handle_gpgv_cmd_io(
+    runner: &dyn GpgRunner,
+    cmd_args: Option<Vec<String>>,
+    keyring: String,
+    homedir: String,
+    env: Option<HashMap<String, String>>,
+    input: InputSource,
+) -> Result<CmdResult, GPGError> {
+    // keyring: path to the keyring file, only keys in this keyring will be used to verify
+    // input: the signed file ( or the data of a detached signature )
+
+    // gpgv does not accept most of the options of gpg
+    let mut args: Vec<String> = vec![
+        "gpgv".to_string(),
+        "--status-fd".to_string(),
+        "2".to_string(),
+        "--homedir".to_string(),
+        homedir,
+        "--keyring".to_string(),
+        keyring,
+    ];
+    args.append(&mut cmd_args.unwrap());
+    let stdin: Box<dyn Read + Send> = match input.into_reader() {
+        Ok(reader) => reader,
+        Err(e) => return Err(e.with_context(Operation::VerifyFile, &args)),
+    };
+    let mut result: CmdResult = match runner.run(args.clone(), Some(stdin), env, Operation::VerifyFile) {
+        Ok(result) => result,
+        Err(e) => return Err(e.with_context(Operation::VerifyFile, &args)),
+    };
+    result.args = redact_args(&args);
+    // gpgv only exit with 0 if the signature was good and made by a key in the keyring
+    if result.is_success() && result.exit_status() == GpgExitStatus::Success {
+        return Ok(result);
+    }
+    result.success = false;
     return Err(GPGError::new(
         GPGErrorType::GPGProcessError(result.get_error_message()),
         Some(result),
     ));
 }
 
+// spawn the program ( first element of args ), write stdin to it and collect its output into a CmdResult
+// this is what the default runner ( ProcessRunner ) do
+pub(crate) fn exec_process(
+    args: Vec<String>,
+    stdin: Option<Box<dyn Read + Send>>,
+    env: Option<HashMap<String, String>>,
+    ops: Operation,
+    memory_limit: Option<usize>,
+    timeout: Option<Duration>,
+) -> Result<CmdResult, GPGError> {
+    // memory_limit: output larger than this ( in bytes ) will be written to a temporary file instead of raw_data
+    // timeout: the process will be killed if it was still running after this long, None to wait for it
+
+    // the output was already written to the file, whatever gpg still write to stdout was not needed
+    let discard_output: bool = is_output_to_file(&args);
+    let process: Result<Child, Error> = spawn_process(args, env);
+    let mut cmd_process = match process {
+        Ok(child) => child,
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::FailedToStartProcess(e.to_string()),
+                None,
+            ))
+        }
+    };
+    let child_stdin: ChildStdin = cmd_process.stdin.take().unwrap();
+    let write_thread: JoinHandle<()> = start_writing_process(stdin, child_stdin);
+    let mut result = CmdResult::init(ops);
+    let share_result: Arc<Mutex<&mut CmdResult>> = Arc::new(Mutex::new(&mut result));
+    let collected: Result<bool, Error> =
+        collect_cmd_output_response(cmd_process, share_result, Some(write_thread), memory_limit, discard_output, timeout);
+    match collected {
+        Ok(false) => return Ok(result),
+        Ok(true) => return Err(timeout_error(result, timeout.unwrap_or_default())),
+        Err(e) => return Err(spill_error(result, e)),
+    }
+}
+
+// spawn the program ( first element of args ) and answer the prompt it asks through the responder
+// this is what the default runner ( ProcessRunner ) do
+pub(crate) fn exec_process_interactive(
+    args: Vec<String>,
+    stdin_prefix: Option<Passphrase>,
+    env: Option<HashMap<String, String>>,
+    responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+    ops: Operation,
+    memory_limit: Option<usize>,
+    timeout: Option<Duration>,
+) -> Result<CmdResult, GPGError> {
+    // memory_limit: output larger than this ( in bytes ) will be written to a temporary file instead of raw_data
+    // timeout: the process will be killed if it was still running after this long, None to wait for it
+
+    let discard_output: bool = is_output_to_file(&args);
+    let process: Result<Child, Error> = spawn_process(args, env);
+    let mut cmd_process = match process {
+        Ok(child) => child,
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::FailedToStartProcess(e.to_string()),
+                None,
+            ))
+        }
+    };
+    let mut stdin: Option<ChildStdin> = cmd_process.stdin.take();
+    if let Some(stdin_prefix) = stdin_prefix {
+        let _ = stdin.as_mut().unwrap().write_all(stdin_prefix.as_ref());
+    }
+    let stderr: ChildStderr = cmd_process.stderr.take().unwrap();
+    let stdout: ChildStdout = cmd_process.stdout.take().unwrap();
+
+    let mut result = CmdResult::init(ops);
+    let share_result: Arc<Mutex<&mut CmdResult>> = Arc::new(Mutex::new(&mut result));
+    let mut timed_out: bool = false;
+    let mut spilled: Result<(), Error> = Ok(());
+    thread::scope(|s| {
+        s.spawn(|| {
+            spilled = read_cmd_output(stdout, Arc::clone(&share_result), memory_limit, discard_output);
+        });
+        if let Some(timeout) = timeout {
+            let (process, killed): (&mut Child, &mut bool) = (&mut cmd_process, &mut timed_out);
+            s.spawn(move || *killed = wait_with_timeout(process, timeout));
+        }
+        // status line need to be read as soon as it was written as gpg will wait for the answer
+        for line in BufReader::new(stderr).lines() {
+            let line: String = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            share_result.lock().unwrap().set_raw_data(format!("{}\n", line));
+            share_result.lock().unwrap().handle_response_line(&line);
+            let (prompt_type, keyword): (&str, &str) = match split_status_line(&line) {
+                Some((prompt_type, args)) if prompt_type.starts_with("GET_") && stdin.is_some() => {
+                    (prompt_type, args.split_whitespace().next().unwrap_or(""))
+                }
+                _ => continue,
+            };
+            match responder(prompt_type, keyword) {
+                Some(answer) => {
+                    let r: Result<(), Error> = stdin.as_mut().unwrap().write_all(format!("{}\n", answer).as_bytes());
+                    if r.is_err() {
+                        stdin = None;
+                    }
+                }
+                None => {
+                    // dropping stdin to let gpg know there will be no more input
+                    stdin = None;
+                }
+            }
+        }
+    });
+    drop(stdin);
+    let exit_code: i32 = match cmd_process.wait() {
+        Ok(status) => status.code().unwrap_or(-1),
+        Err(_) => -1,
+    };
+    result.set_return_code(exit_code);
+    if timed_out {
+        return Err(timeout_error(result, timeout.unwrap_or_default()));
+    }
+    if let Err(e) = spilled {
+        return Err(spill_error(result, e));
+    }
+    return Ok(result);
+}
+
 // generate a list of arguments to be passed to gpg process
 fn generate_cmd_args(
     cmd_args: Option<Vec<String>>,
-    passphrase: Option<String>,
+    passphrase: bool,
     version: f32,
     homedir: String,
     options: Option<Vec<String>>,
@@ -110,7 +676,7 @@ fn generate_cmd_args(
         "--no-tty".to_string(),
         "--no-verbose".to_string(),
     ];
-    if passphrase.is_some() && version >= 2.1 {
+    if passphrase && version >= 2.1 {
         args.insert(1, "--pinentry-mode".to_string());
         args.insert(2, "loopback".to_string());
     }
@@ -121,33 +687,38 @@ fn generate_cmd_args(
     ]);
     args.append(&mut vec!["--homedir".to_string(), homedir]);
     // TODO: add keyring and secret keyring support
-    if passphrase.is_some() {
+    if passphrase {
         args.append(&mut vec!["--passphrase-fd".to_string(), "0".to_string()]);
+        // gpg 1.x will ask the agent instead of reading the passphrase from stdin if use-agent was set in gpg.conf
+        if version > 0.0 && version < 2.0 {
+            args.push("--no-use-agent".to_string());
+        }
     }
-    if options.is_some() {
-        args.append(&mut options.unwrap());
+    if let Some(mut options) = options {
+        args.append(&mut options);
     }
     args.append(&mut cmd_args.unwrap());
     return args;
 }
 
-// start a process and return the child process
-pub fn start_process(
-    cmd_args: Option<Vec<String>>,
-    passphrase: Option<String>,
-    version: f32,
-    homedir: String,
-    options: Option<Vec<String>>,
+// replace the program of a gpg command with the gpg binary, gpgv was left as it is
+pub(crate) fn replace_gpg_program(mut args: Vec<String>, gpg_program: &Path) -> Vec<String> {
+    if args.first().is_some_and(|program| program == "gpg") {
+        args[0] = gpg_program.to_string_lossy().to_string();
+    }
+    return args;
+}
+
+// spawn the program ( first element of cmd_args ) with piped stdin, stdout and stderr
+fn spawn_process(
+    cmd_args: Vec<String>,
     env: Option<HashMap<String, String>>,
 ) -> Result<Child, Error> {
-    let cmd_args: Vec<String> =
-        generate_cmd_args(cmd_args, passphrase, version, homedir.clone(), options);
-
     let mut command = Command::new(&cmd_args[0]); // The first element of the vector is the command
     // Pass the rest of the arguments to the command
     command.args(&cmd_args[1..]);
-    if env.is_some() {
-        for (key, value) in env.unwrap() {
+    if let Some(env) = env {
+        for (key, value) in env {
             command.env(key, value);
         }
     };
@@ -161,24 +732,35 @@ pub fn start_process(
 }
 
 // to collect output / response from the Command process
+// return true if the process was killed as it was still running after the timeout,
+// or the error if the output could not be written to the temporary file
 pub fn collect_cmd_output_response(
     mut cmd_process: Child,
     result: Arc<Mutex<&mut CmdResult>>,
     writer: Option<JoinHandle<()>>,
-) {
+    memory_limit: Option<usize>,
+    discard_output: bool,
+    timeout: Option<Duration>,
+) -> Result<bool, Error> {
     let stderr: ChildStderr = cmd_process.stderr.take().unwrap();
     let stdout: ChildStdout = cmd_process.stdout.take().unwrap();
 
+    let mut timed_out: bool = false;
+    let mut spilled: Result<(), Error> = Ok(());
     thread::scope(|s| {
         s.spawn(|| {
-            read_cmd_output(stdout, Arc::clone(&result));
+            spilled = read_cmd_output(stdout, Arc::clone(&result), memory_limit, discard_output);
         });
         s.spawn(|| {
             read_cmd_response(stderr, Arc::clone(&result));
         });
+        if let Some(timeout) = timeout {
+            let (process, killed): (&mut Child, &mut bool) = (&mut cmd_process, &mut timed_out);
+            s.spawn(move || *killed = wait_with_timeout(process, timeout));
+        }
     });
-    if writer.is_some() {
-        let _ = writer.unwrap().join();
+    if let Some(writer) = writer {
+        let _ = writer.join();
     }
     let exit_status: Result<ExitStatus, Error> = cmd_process.wait();
     let exit_code = match exit_status {
@@ -188,29 +770,165 @@ pub fn collect_cmd_output_response(
         }
     };
     result.lock().unwrap().set_return_code(exit_code);
+    spilled?;
+    return Ok(timed_out);
+}
+
+// wait for the process to exit, killing it if it was still running after the timeout
+// return true if it was killed
+fn wait_with_timeout(cmd_process: &mut Child, timeout: Duration) -> bool {
+    let started: Instant = Instant::now();
+    loop {
+        match cmd_process.try_wait() {
+            Ok(Some(_)) => return false,
+            Ok(None) => {}
+            Err(_) => return false,
+        }
+        if started.elapsed() >= timeout {
+            let _ = cmd_process.kill();
+            let _ = cmd_process.wait();
+            return true;
+        }
+        thread::sleep(TIMEOUT_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())));
+    }
+}
+
+// wait for the process to exit, killing it if it was still running after the timeout or once it was cancelled
+// return the error to report if it was killed
+fn wait_with_cancellation(
+    cmd_process: &mut Child,
+    timeout: Option<Duration>,
+    cancellation: Option<&CancellationToken>,
+) -> Option<GPGErrorType> {
+    let started: Instant = Instant::now();
+    loop {
+        match cmd_process.try_wait() {
+            Ok(Some(_)) => return None,
+            Ok(None) => {}
+            Err(_) => return None,
+        }
+        let stopped: Option<GPGErrorType> = if cancellation.is_some_and(|c| c.is_cancelled()) {
+            Some(GPGErrorType::CancelledError("gpg was killed as the operation was cancelled".to_string()))
+        } else if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+            Some(GPGErrorType::TimeoutError(format!(
+                "gpg was killed as it was still running after {:?}",
+                timeout.unwrap()
+            )))
+        } else {
+            None
+        };
+        if stopped.is_some() {
+            let _ = cmd_process.kill();
+            let _ = cmd_process.wait();
+            return stopped;
+        }
+        thread::sleep(TIMEOUT_POLL_INTERVAL);
+    }
+}
+
+fn timeout_error(mut result: CmdResult, timeout: Duration) -> GPGError {
+    result.success = false;
+    return GPGError::new(
+        GPGErrorType::TimeoutError(format!("gpg was killed as it was still running after {:?}", timeout)),
+        Some(result),
+    );
+}
+
+// the error of a command whose output could not be written to the temporary file, as the output was lost
+fn spill_error(mut result: CmdResult, error: Error) -> GPGError {
+    result.success = false;
+    return GPGError::new(
+        GPGErrorType::WriteFailError(format!("failed to write the output of gpg to a temporary file: {}", error)),
+        Some(result),
+    );
+}
+
+// remove the output spilled to a temporary file of a failed command, as no caller will read it ( it may be plaintext )
+fn remove_spilled_output(result: &mut CmdResult) {
+    if let Some(output_file) = result.output_file.take() {
+        let _ = std::fs::remove_file(output_file);
+    }
 }
 
 // read output from stdout
-fn read_cmd_output(mut stdout: ChildStdout, result: Arc<Mutex<&mut CmdResult>>) {
-    let mut output_lines: Vec<String> = Vec::new();
+// return the error if the output larger than the memory limit could not be written to the temporary file,
+// the file was removed then and the rest of the output discarded
+fn read_cmd_output(
+    mut stdout: ChildStdout,
+    result: Arc<Mutex<&mut CmdResult>>,
+    memory_limit: Option<usize>,
+    discard_output: bool,
+) -> Result<(), Error> {
+    // memory_limit: output larger than this ( in bytes ) will be written to a temporary file instead of raw_data
+    // discard_output: drain stdout without keeping it, for gpg writing its output to a file
+
+    if discard_output {
+        let _ = std::io::copy(&mut stdout, &mut std::io::sink());
+        return Ok(());
+    }
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut spill: Option<NamedTempFile> = None;
     loop {
         let mut buffer: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
         let line: Result<usize, Error> = stdout.read(&mut buffer);
-        match line {
+        let n: usize = match line {
             Ok(n) => {
-                if n <= 0 {
+                if n == 0 {
                     break;
                 }
+                n
             }
             Err(_) => {
                 break;
             }
+        };
+        if let Some(file) = spill.as_mut() {
+            if let Err(e) = file.write_all(&buffer[..n]) {
+                // dropping the file remove it, gpg should still be able to write the rest of its output
+                drop(spill);
+                let _ = std::io::copy(&mut stdout, &mut std::io::sink());
+                return Err(e);
+            }
+            continue;
+        }
+        output.extend_from_slice(&buffer[..n]);
+        if memory_limit.is_some_and(|limit| output.len() > limit) {
+            match create_temp_file("output") {
+                Ok(mut file) => {
+                    if let Err(e) = file.write_all(&output) {
+                        drop(file);
+                        let _ = std::io::copy(&mut stdout, &mut std::io::sink());
+                        return Err(e);
+                    }
+                    output = Vec::new();
+                    spill = Some(file);
+                }
+                Err(_) => {
+                    // keep the output in memory if the temporary file could not be created
+                }
+            }
+        }
+    }
+    match spill {
+        Some(file) => {
+            // from here on, it was up to the caller to remove it ( take_spilled_output )
+            let (_, path) = file.keep().map_err(|e| e.error)?;
+            result.lock().unwrap().output_file = Some(path.to_string_lossy().to_string());
+        }
+        None => {
+            result.lock().unwrap().set_raw_data(String::from_utf8_lossy(&output).to_string());
         }
-        let line_string = String::from_utf8_lossy(&buffer[..line.unwrap()]);
-        output_lines.push(line_string.to_string());
     }
-    result.lock().unwrap().set_raw_data(output_lines.join(""));
     drop(stdout);
+    return Ok(());
+}
+
+// whether gpg was told to write its output to a file with --output / -o, its stdout does not need to be kept then
+fn is_output_to_file(args: &[String]) -> bool {
+    return args
+        .windows(2)
+        .any(|w| (w[0] == "--output" || w[0] == "-o") && w[1] != "-");
 }
 
 // read response from stderr
@@ -221,7 +939,7 @@ fn read_cmd_response(mut stderr: ChildStderr, result: Arc<Mutex<&mut CmdResult>>
         let response_line = stderr.read_to_end(&mut buffer);
         match response_line {
             Ok(n) => {
-                if n <= 0 {
+                if n == 0 {
                     break;
                 }
             }
@@ -236,77 +954,28 @@ fn read_cmd_response(mut stderr: ChildStderr, result: Arc<Mutex<&mut CmdResult>>
     result.lock().unwrap().set_raw_data(data.clone());
     // the following process was to handle the status line(s)
     for response_line_string in data.clone().split("\n") {
-        if response_line_string.len() >= 9 {
-            if &response_line_string[0..9] == "[GNUPG:] " {
-                // Split into at most 2 parts based on whitespace
-                let parts = &response_line_string[9..].splitn(2, char::is_whitespace);
-
-                let mut p = parts.clone();
-                let keyword: &str = p.next().unwrap_or(""); // First part, default to empty string if no part
-                let value: String = p.next().unwrap_or("").to_string(); // Second part, default to empty string if no part
-                result.lock().unwrap().handle_status(keyword, value);
-            } else if &response_line_string[0..5] == "gpg: " {
-                let debug = &response_line_string[5..];
-                if debug.contains("unknown keyword") {
-                    result.lock().unwrap().handle_status("UNKNOWN_KEYWORD", "unknown keyword".to_string());
-                } else if debug.contains("No passphrase given") {
-                    result.lock().unwrap().handle_status("NO_PASSPHRASE", "No passphrase given".to_string());
-                } else if debug.contains("invalid fingerprint") {
-                    result.lock().unwrap().handle_status("INVALID_FINGERPRINT", "invalid fingerprint".to_string());
-                } else if debug.contains("Bad passphrase") {
-                    result.lock().unwrap().handle_status("BAD_PASSPHRASE", "bad passphrase".to_string());
-                }
-                result.lock().unwrap().capture_debug_log(debug.to_string());
-            }
-        }
+        result.lock().unwrap().handle_response_line(response_line_string);
     }
     drop(stderr);
 }
 
 // start writing process
-fn start_writing_process(
-    file: Option<File>,
-    byte_input: Option<Vec<u8>>,
-    stdin: ChildStdin,
-) -> JoinHandle<()> {
-    // TODO: implement write to stdin
+fn start_writing_process(stdin: Option<Box<dyn Read + Send>>, child_stdin: ChildStdin) -> JoinHandle<()> {
     let write_process: JoinHandle<()> = thread::spawn(move || {
-        let _ = write_to_stdin(file, byte_input, stdin);
+        let _ = write_to_stdin(stdin, child_stdin);
     });
     return write_process;
 }
 
 // write to stdin
-fn write_to_stdin(
-    file: Option<File>,
-    byte_input: Option<Vec<u8>>,
-    mut stdin: ChildStdin,
-) -> Result<(), GPGError> {
-    match byte_input {
-        Some(byte_input) => {
-            let r: Result<(), Error> = stdin.write_all(&byte_input);
-            match r {
-                Ok(_) => {
-                    return Ok(());
-                }
-                Err(e) => {
-                    return Err(GPGError::new(
-                        GPGErrorType::WriteFailError(e.to_string()),
-                        None,
-                    ));
-                }
-            }
-        }
-        None => {}
-    }
-
-    match file {
-        Some(mut file) => loop {
+fn write_to_stdin(stdin: Option<Box<dyn Read + Send>>, mut child_stdin: ChildStdin) -> Result<(), GPGError> {
+    if let Some(mut stdin) = stdin {
+        loop {
             let mut buffer: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
-            let data: Result<usize, Error> = file.read(&mut buffer);
+            let data: Result<usize, Error> = stdin.read(&mut buffer);
             match data {
                 Ok(n) => {
-                    if n <= 0 {
+                    if n == 0 {
                         break;
                     }
                 }
@@ -317,7 +986,7 @@ fn write_to_stdin(
                     ));
                 }
             }
-            let r: Result<(), Error> = stdin.write_all(&buffer[..data.unwrap()]);
+            let r: Result<(), Error> = child_stdin.write_all(&buffer[..data.unwrap()]);
             match r {
                 Ok(_) => {
                     continue;
@@ -329,11 +998,10 @@ fn write_to_stdin(
                     ));
                 }
             }
-        },
-        None => {}
+        }
     }
 
-    drop(stdin);
+    drop(child_stdin);
 
     return Ok(());
 }
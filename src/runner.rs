@@ -0,0 +1,293 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    fs::File,
+    io::Read,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::audit::{get_audit_record, AuditRecord, AuditSink};
+use crate::gnupg::invalidate_key_cache;
+use crate::metrics::{get_operation_metric, MetricsRecorder};
+use crate::process::{exec_process, exec_process_interactive, replace_gpg_program, DEFAULT_OUTPUT_MEMORY_LIMIT};
+use crate::utils::{
+    enums::Operation,
+    errors::{GPGError, GPGErrorType},
+    response::CmdResult,
+    secret::Passphrase,
+    utils::{lock_homedir, wait_for_gpg_locks},
+};
+
+//*******************************************************
+
+//              RELATED TO GPG RUNNER
+
+//*******************************************************
+// the way a gpg ( or gpgv ) command is executed
+// GPG will hand every command to its runner, implement this to run gpg somewhere else ( ex. over ssh )
+// or to return a canned CmdResult in unit test without gpg installed
+pub trait GpgRunner: Debug + Send + Sync {
+    // run the command and collect its output and status into a CmdResult
+    // an Err should only be returned if the command could not be started at all,
+    // a command that fail should return Ok with the CmdResult, as GPG will check it
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: Option<Box<dyn Read + Send>>,
+        env: Option<HashMap<String, String>>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError>;
+    // args: the full command line, the first element is the program ( gpg or gpgv )
+    // stdin: what to write into the stdin of the command, stdin should be closed once it was all written
+    // env: additional environment variables for the command
+    // ops: the operation of the command, to initialize the CmdResult with
+
+    // run a command that read its answer one by one from stdin ( --command-fd 0 )
+    // every status line starting with "[GNUPG:] GET_" should be passed to the responder
+    // with its prompt type and keyword, and the answer returned written to stdin followed by a newline,
+    // responder returning None means stdin should be closed
+    // the default implementation does not support it, operation such as edit_key will fail with it
+    fn run_interactive(
+        &self,
+        args: Vec<String>,
+        stdin_prefix: Option<Passphrase>,
+        env: Option<HashMap<String, String>>,
+        responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        // stdin_prefix: to be written into stdin before any answer ( ex. the passphrase )
+
+        let _ = (args, stdin_prefix, env, responder);
+        return Err(GPGError::new(
+            GPGErrorType::FailedToStartProcess(format!(
+                "interactive command is not supported by this runner: {}",
+                ops
+            )),
+            None,
+        ));
+    }
+
+    // a runner running the command like this one, but killing it if it was still running after the timeout
+    // ( reported as a TimeoutError ), used by GPG.with_overrides
+    // the default implementation does not support it and return None
+    fn with_timeout(&self, timeout: Duration) -> Option<Arc<dyn GpgRunner>> {
+        let _ = timeout;
+        return None;
+    }
+}
+
+// the default runner, spawn the command as a local process
+#[derive(Debug, Clone)]
+pub struct ProcessRunner {
+    // memory_limit: output of gpg larger than this ( in bytes ) will be written to a temporary file
+    //               ( CmdResult.output_file ) instead of being kept in raw_data, None for no limit
+    pub memory_limit: Option<usize>,
+    // timeout: the process will be killed if it was still running after this long, None to wait for it
+    pub timeout: Option<Duration>,
+    // gpg_program: the gpg binary to run instead of gpg found on PATH ( ex. one of find_gpg_installations )
+    pub gpg_program: Option<PathBuf>,
+}
+
+impl Default for ProcessRunner {
+    // with a memory limit of DEFAULT_OUTPUT_MEMORY_LIMIT ( 64 MiB ) and no timeout
+    fn default() -> ProcessRunner {
+        return ProcessRunner {
+            memory_limit: Some(DEFAULT_OUTPUT_MEMORY_LIMIT),
+            timeout: None,
+            gpg_program: None,
+        };
+    }
+}
+
+impl ProcessRunner {
+    pub fn with_memory_limit(memory_limit: Option<usize>) -> ProcessRunner {
+        // memory_limit: in bytes, None for no limit
+        return ProcessRunner {
+            memory_limit: memory_limit,
+            timeout: None,
+            gpg_program: None,
+        };
+    }
+
+    // with the default memory limit, running the gpg binary at the path instead of gpg found on PATH
+    pub fn with_gpg_program(gpg_program: PathBuf) -> ProcessRunner {
+        return ProcessRunner {
+            memory_limit: Some(DEFAULT_OUTPUT_MEMORY_LIMIT),
+            timeout: None,
+            gpg_program: Some(gpg_program),
+        };
+    }
+
+    // replace the program of a gpg command with gpg_program, gpgv was left as it is
+    fn program_args(&self, args: Vec<String>) -> Vec<String> {
+        match &self.gpg_program {
+            Some(gpg_program) => return replace_gpg_program(args, gpg_program),
+            None => return args,
+        }
+    }
+}
+
+impl GpgRunner for ProcessRunner {
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: Option<Box<dyn Read + Send>>,
+        env: Option<HashMap<String, String>>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        return exec_process(self.program_args(args), stdin, env, ops, self.memory_limit, self.timeout);
+    }
+
+    fn run_interactive(
+        &self,
+        args: Vec<String>,
+        stdin_prefix: Option<Passphrase>,
+        env: Option<HashMap<String, String>>,
+        responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        return exec_process_interactive(self.program_args(args), stdin_prefix, env, responder, ops, self.memory_limit, self.timeout);
+    }
+
+    fn with_timeout(&self, timeout: Duration) -> Option<Arc<dyn GpgRunner>> {
+        let mut runner: ProcessRunner = self.clone();
+        runner.timeout = Some(timeout);
+        return Some(Arc::new(runner));
+    }
+}
+
+// a runner guarding the homedir for the command of a mutating operation ( import, trust, gen_key ... ) run by
+// the runner it wraps: refused if the GPG was read only, else the lock of the homedir was held while it was run,
+// so GPG on the same homedir do not write to the keyring or trustdb at the same time,
+// and the listing of the homedir cached by list_keys was invalidated once it was run
+#[derive(Debug, Clone)]
+pub(crate) struct HomedirGuardRunner {
+    pub runner: Arc<dyn GpgRunner>,
+    pub homedir: PathBuf,
+    pub lock_timeout: Option<Duration>,
+    pub read_only: bool,
+}
+
+impl HomedirGuardRunner {
+    fn guard(&self, ops: &Operation) -> Result<Option<File>, GPGError> {
+        if !ops.is_mutating() {
+            return Ok(None);
+        }
+        if self.read_only {
+            return Err(GPGError::new(
+                GPGErrorType::ReadOnlyError(format!("{} is not allowed as GPG was set to read only", ops)),
+                None,
+            ));
+        }
+        let timeout: Duration = match self.lock_timeout {
+            Some(timeout) => timeout,
+            None => return Ok(None),
+        };
+        let started: Instant = Instant::now();
+        let lock: File = lock_homedir(&self.homedir, timeout)?;
+        // gpg would wait forever for a lock file held by another process, or left behind by a killed one
+        wait_for_gpg_locks(&self.homedir, timeout.saturating_sub(started.elapsed()))?;
+        return Ok(Some(lock));
+    }
+}
+
+impl GpgRunner for HomedirGuardRunner {
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: Option<Box<dyn Read + Send>>,
+        env: Option<HashMap<String, String>>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        // the lock was released when _lock was dropped after the command finished
+        let _lock: Option<File> = self.guard(&ops)?;
+        let result: Result<CmdResult, GPGError> = self.runner.run(args, stdin, env, ops.clone());
+        if ops.is_mutating() {
+            invalidate_key_cache(Some(&self.homedir));
+        }
+        return result;
+    }
+
+    fn run_interactive(
+        &self,
+        args: Vec<String>,
+        stdin_prefix: Option<Passphrase>,
+        env: Option<HashMap<String, String>>,
+        responder: &mut dyn FnMut(&str, &str) -> Option<String>,
+        ops: Operation,
+    ) -> Result<CmdResult, GPGError> {
+        let _lock: Option<File> = self.guard(&ops)?;
+        let result: Result<CmdResult, GPGError> =
+            self.runner.run_interactive(args, stdin_prefix, env, responder, ops.clone());
+        if ops.is_mutating() {
+            invalidate_key_cache(Some(&self.homedir));
+        }
+        return result;
+    }
+}
+
+// record a gpg process GPG spawned itself instead of handing it to its runner ( the operation streaming from a
+// local gpg process ) to the audit sink and the metrics recorder, as AuditRunner and MetricsRunner do for the runner
+#[derive(Debug, Clone)]
+pub(crate) struct LocalProcessRecorder {
+    pub sink: Option<Arc<dyn AuditSink>>,
+    pub recorder: Option<Arc<dyn MetricsRecorder>>,
+    // when the process was started
+    pub time: DateTime<Local>,
+    pub started: Instant,
+}
+
+impl LocalProcessRecorder {
+    // to record a process started now
+    pub fn new(sink: Option<Arc<dyn AuditSink>>, recorder: Option<Arc<dyn MetricsRecorder>>) -> LocalProcessRecorder {
+        return LocalProcessRecorder {
+            sink: sink,
+            recorder: recorder,
+            time: Local::now(),
+            started: Instant::now(),
+        };
+    }
+
+    // record the process once it exited, an error carrying the CmdResult was recorded as the command run by the
+    // runner failing, and a process that failed to be audited was returned ( and reported ) as failed
+    pub fn record(&self, ops: Operation, result: Result<CmdResult, GPGError>) -> Result<CmdResult, GPGError> {
+        // the arguments were taken from the CmdResult, or the context of the error if gpg could not be started
+        let args: Vec<String> = match &result {
+            Ok(result) => result.args.clone(),
+            Err(e) => match (&e.cmd_result, &e.context) {
+                (Some(result), _) if !result.args.is_empty() => result.args.clone(),
+                (_, Some(context)) => context.args.clone(),
+                _ => vec![],
+            },
+        };
+        let mut result: Result<CmdResult, GPGError> = result;
+        if let Some(sink) = &self.sink {
+            let record: AuditRecord =
+                get_audit_record(&args, self.time, self.started.elapsed(), ops.clone(), get_outcome(&result));
+            if let Err(e) = sink.record(&record) {
+                result = Err(e);
+            }
+        }
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&get_operation_metric(self.started, ops, get_outcome(&result)));
+        }
+        return result;
+    }
+
+    // record a process that could not be started, the error was returned ( or the one of the audit sink )
+    pub fn record_error(&self, ops: Operation, error: GPGError) -> GPGError {
+        return self.record(ops, Err(error)).unwrap_err();
+    }
+}
+
+// the CmdResult of gpg even if it failed, or the error if gpg could not be started
+fn get_outcome(result: &Result<CmdResult, GPGError>) -> Result<&CmdResult, &GPGError> {
+    match result {
+        Ok(result) => return Ok(result),
+        Err(e) => return e.cmd_result.as_ref().ok_or(e),
+    }
+}
@@ -0,0 +1,462 @@
+use std::str::FromStr;
+
+use crate::utils::secret::Passphrase;
+
+// every status line gpg wrote to --status-fd starts with it
+pub const STATUS_PREFIX: &str = "[GNUPG:] ";
+
+//*******************************************************
+
+//              RELATED TO STATUS LINE
+
+//*******************************************************
+// a status line gpg reported with --status-fd, refer to doc/DETAILS in the GnuPG source for the meaning of each
+// a keyword not known to the crate ( or a line missing a required field ) will be kept as Other
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusLine {
+    // NEWSIG [<signers_uid>]
+    NewSig { signer_uid: Option<String> },
+    // GOODSIG / EXPSIG / EXPKEYSIG / REVKEYSIG / BADSIG <long_keyid_or_fpr> <username>
+    GoodSig { keyid: String, username: String },
+    ExpSig { keyid: String, username: String },
+    ExpKeySig { keyid: String, username: String },
+    RevKeySig { keyid: String, username: String },
+    BadSig { keyid: String, username: String },
+    // ERRSIG <keyid> <pkalgo> <hashalgo> <sig_class> <time> <rc> [<fpr>]
+    ErrSig {
+        keyid: String,
+        pubkey_algo: u8,
+        hash_algo: u8,
+        sig_class: String,
+        timestamp: String,
+        rc: u32,
+        fingerprint: Option<String>,
+    },
+    // VALIDSIG <fpr> <sig_creation_date> <sig-timestamp> <expire-timestamp> <sig-version> <reserved>
+    //          <pubkey-algo> <hash-algo> <sig-class> [<primary-key-fpr>]
+    ValidSig {
+        fingerprint: String,
+        creation_date: String,
+        timestamp: String,
+        expire_timestamp: String,
+        sig_version: u8,
+        pubkey_algo: u8,
+        hash_algo: u8,
+        sig_class: String,
+        primary_fingerprint: Option<String>,
+    },
+    // SIG_ID <radix64_string> <sig_creation_date> <sig-timestamp>
+    SigId { id: String, creation_date: String, timestamp: String },
+    // SIG_CREATED <type> <pk_algo> <hash_algo> <class> <timestamp> <keyfpr>
+    SigCreated {
+        sig_type: String,
+        pubkey_algo: u8,
+        hash_algo: u8,
+        sig_class: String,
+        timestamp: String,
+        fingerprint: String,
+    },
+    // KEY_CONSIDERED <fpr> <flags>
+    KeyConsidered { fingerprint: String, flags: u32 },
+    // TRUST_UNDEFINED / TRUST_NEVER / TRUST_MARGINAL / TRUST_FULLY / TRUST_ULTIMATE [0 [<validation_model>]]
+    // level: the part after TRUST_ ex) ULTIMATE
+    Trust { level: String, validation_model: Option<String> },
+    // KEYEXPIRED <expire-timestamp>
+    KeyExpired { timestamp: String },
+    KeyRevoked,
+    SigExpired,
+    // NO_PUBKEY / NO_SECKEY <long keyid>
+    NoPubkey { keyid: String },
+    NoSeckey { keyid: String },
+    // ENC_TO <long_keyid> <keytype> <keylength>
+    EncTo { keyid: String, pubkey_algo: u8, key_length: u32 },
+    BeginDecryption,
+    EndDecryption,
+    DecryptionOkay,
+    DecryptionFailed,
+    GoodMdc,
+    // DECRYPTION_INFO <mdc_method> <sym_algo> [<aead_algo>]
+    DecryptionInfo { mdc_method: u8, sym_algo: u8, aead_algo: Option<u8> },
+    // SESSION_KEY <algo>:<hexdigits>
+    SessionKey(Passphrase),
+    // BEGIN_ENCRYPTION <mdc_method> <sym_algo> [<aead_algo>]
+    BeginEncryption { mdc_method: u8, sym_algo: u8, aead_algo: Option<u8> },
+    EndEncryption,
+    BeginSigning,
+    // PLAINTEXT <format> <timestamp> <filename>
+    Plaintext { format: String, timestamp: String, filename: Option<String> },
+    // PLAINTEXT_LENGTH <length>
+    PlaintextLength(u64),
+    // NODATA <what>
+    NoData(u32),
+    // USERID_HINT <long main keyid> <string>
+    UserIdHint { keyid: String, user_id: String },
+    // NEED_PASSPHRASE <long keyid> <long main keyid> <keytype> <keylength>
+    NeedPassphrase { keyid: String, main_keyid: String, pubkey_algo: u8, key_length: u32 },
+    // NEED_PASSPHRASE_SYM <cipher_algo> <s2k_mode> <s2k_hash>
+    NeedPassphraseSym { cipher_algo: u8, s2k_mode: u8, s2k_hash: u8 },
+    // BAD_PASSPHRASE <long keyid>
+    BadPassphrase { keyid: Option<String> },
+    GoodPassphrase,
+    MissingPassphrase,
+    // INV_RECP / INV_SGNR <reason> <requested_recipient>
+    InvRecp { reason: u32, recipient: String },
+    InvSgnr { reason: u32, signer: String },
+    // NO_RECP / NO_SGNR <reserved>
+    NoRecp,
+    NoSgnr,
+    // KEY_CREATED <type> <fingerprint> [<handle>]
+    KeyCreated { key_type: String, fingerprint: String, handle: Option<String> },
+    // KEY_NOT_CREATED [<handle>]
+    KeyNotCreated { handle: Option<String> },
+    // IMPORTED <long keyid> <username>
+    Imported { keyid: String, username: String },
+    // IMPORT_OK <reason> [<fingerprint>]
+    ImportOk { reason: u32, fingerprint: Option<String> },
+    // IMPORT_PROBLEM <reason> [<fingerprint>]
+    ImportProblem { reason: u32, fingerprint: Option<String> },
+    // IMPORT_RES / EXPORT_RES <count> ... every counter in the order gpg reported it
+    ImportRes(Vec<u64>),
+    ExportRes(Vec<u64>),
+    // EXPORTED <fingerprint>
+    Exported { fingerprint: String },
+    // DELETE_PROBLEM <reason_code>
+    DeleteProblem(u32),
+    // ATTRIBUTE <fpr> <octets> <type> <index> <count> <timestamp> <expiredate> <flags>
+    Attribute {
+        fingerprint: String,
+        octets: usize,
+        attr_type: u8,
+        index: u32,
+        count: u32,
+        timestamp: String,
+        expire: String,
+        flags: u8,
+    },
+    // GET_BOOL / GET_LINE / GET_HIDDEN <keyword>
+    GetBool(String),
+    GetLine(String),
+    GetHidden(String),
+    GotIt,
+    // PROGRESS <what> <char> <cur> <total> [<units>]
+    Progress { what: String, char: String, current: u64, total: u64, units: Option<String> },
+    // CARDCTRL <what> [<serialno>]
+    CardCtrl { what: u32, serialno: Option<String> },
+    // SC_OP_FAILURE [<code>]
+    ScOpFailure(Option<u32>),
+    ScOpSuccess,
+    // ERROR <error location> <error code> [<more>]
+    Error { location: String, code: u32, more: Option<String> },
+    // FAILURE <location> <error_code>
+    Failure { location: String, code: u32 },
+    // SUCCESS [<location>]
+    Success(Option<String>),
+    // any other status, with the rest of the line after the keyword
+    Other { keyword: String, args: String },
+}
+
+impl StatusLine {
+    // parse a line gpg wrote to --status-fd ( ex. [GNUPG:] GOODSIG 535F8AD3664C3DFC Alice ),
+    // None if it was not a status line ( ex. a gpg: log line )
+    pub fn parse(line: &str) -> Option<StatusLine> {
+        return split_status_line(line).map(|(keyword, args)| StatusLine::from_parts(keyword, args));
+    }
+
+    // build a status from its keyword and the rest of the line
+    pub fn from_parts(keyword: &str, args: &str) -> StatusLine {
+        return match parse_known(keyword, args) {
+            Some(status) => status,
+            None => StatusLine::Other { keyword: keyword.to_string(), args: args.to_string() },
+        };
+    }
+
+    // the keyword of the status ex) GOODSIG
+    pub fn keyword(&self) -> &str {
+        return match self {
+            StatusLine::NewSig { .. } => "NEWSIG",
+            StatusLine::GoodSig { .. } => "GOODSIG",
+            StatusLine::ExpSig { .. } => "EXPSIG",
+            StatusLine::ExpKeySig { .. } => "EXPKEYSIG",
+            StatusLine::RevKeySig { .. } => "REVKEYSIG",
+            StatusLine::BadSig { .. } => "BADSIG",
+            StatusLine::ErrSig { .. } => "ERRSIG",
+            StatusLine::ValidSig { .. } => "VALIDSIG",
+            StatusLine::SigId { .. } => "SIG_ID",
+            StatusLine::SigCreated { .. } => "SIG_CREATED",
+            StatusLine::KeyConsidered { .. } => "KEY_CONSIDERED",
+            StatusLine::Trust { level, .. } => match level.as_str() {
+                "UNDEFINED" => "TRUST_UNDEFINED",
+                "NEVER" => "TRUST_NEVER",
+                "MARGINAL" => "TRUST_MARGINAL",
+                "FULLY" => "TRUST_FULLY",
+                _ => "TRUST_ULTIMATE",
+            },
+            StatusLine::KeyExpired { .. } => "KEYEXPIRED",
+            StatusLine::KeyRevoked => "KEYREVOKED",
+            StatusLine::SigExpired => "SIGEXPIRED",
+            StatusLine::NoPubkey { .. } => "NO_PUBKEY",
+            StatusLine::NoSeckey { .. } => "NO_SECKEY",
+            StatusLine::EncTo { .. } => "ENC_TO",
+            StatusLine::BeginDecryption => "BEGIN_DECRYPTION",
+            StatusLine::EndDecryption => "END_DECRYPTION",
+            StatusLine::DecryptionOkay => "DECRYPTION_OKAY",
+            StatusLine::DecryptionFailed => "DECRYPTION_FAILED",
+            StatusLine::GoodMdc => "GOODMDC",
+            StatusLine::DecryptionInfo { .. } => "DECRYPTION_INFO",
+            StatusLine::SessionKey(_) => "SESSION_KEY",
+            StatusLine::BeginEncryption { .. } => "BEGIN_ENCRYPTION",
+            StatusLine::EndEncryption => "END_ENCRYPTION",
+            StatusLine::BeginSigning => "BEGIN_SIGNING",
+            StatusLine::Plaintext { .. } => "PLAINTEXT",
+            StatusLine::PlaintextLength(_) => "PLAINTEXT_LENGTH",
+            StatusLine::NoData(_) => "NODATA",
+            StatusLine::UserIdHint { .. } => "USERID_HINT",
+            StatusLine::NeedPassphrase { .. } => "NEED_PASSPHRASE",
+            StatusLine::NeedPassphraseSym { .. } => "NEED_PASSPHRASE_SYM",
+            StatusLine::BadPassphrase { .. } => "BAD_PASSPHRASE",
+            StatusLine::GoodPassphrase => "GOOD_PASSPHRASE",
+            StatusLine::MissingPassphrase => "MISSING_PASSPHRASE",
+            StatusLine::InvRecp { .. } => "INV_RECP",
+            StatusLine::InvSgnr { .. } => "INV_SGNR",
+            StatusLine::NoRecp => "NO_RECP",
+            StatusLine::NoSgnr => "NO_SGNR",
+            StatusLine::KeyCreated { .. } => "KEY_CREATED",
+            StatusLine::KeyNotCreated { .. } => "KEY_NOT_CREATED",
+            StatusLine::Imported { .. } => "IMPORTED",
+            StatusLine::ImportOk { .. } => "IMPORT_OK",
+            StatusLine::ImportProblem { .. } => "IMPORT_PROBLEM",
+            StatusLine::ImportRes(_) => "IMPORT_RES",
+            StatusLine::ExportRes(_) => "EXPORT_RES",
+            StatusLine::Exported { .. } => "EXPORTED",
+            StatusLine::DeleteProblem(_) => "DELETE_PROBLEM",
+            StatusLine::Attribute { .. } => "ATTRIBUTE",
+            StatusLine::GetBool(_) => "GET_BOOL",
+            StatusLine::GetLine(_) => "GET_LINE",
+            StatusLine::GetHidden(_) => "GET_HIDDEN",
+            StatusLine::GotIt => "GOT_IT",
+            StatusLine::Progress { .. } => "PROGRESS",
+            StatusLine::CardCtrl { .. } => "CARDCTRL",
+            StatusLine::ScOpFailure(_) => "SC_OP_FAILURE",
+            StatusLine::ScOpSuccess => "SC_OP_SUCCESS",
+            StatusLine::Error { .. } => "ERROR",
+            StatusLine::Failure { .. } => "FAILURE",
+            StatusLine::Success(_) => "SUCCESS",
+            StatusLine::Other { keyword, .. } => keyword,
+        };
+    }
+}
+
+//*******************************************************
+
+//              RELATED TO GPG PROMPT
+
+//*******************************************************
+// a question gpg asked through --command-fd ( GET_BOOL, GET_LINE or GET_HIDDEN status )
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpgPrompt {
+    pub kind: PromptKind,
+    // keyword: what gpg asked for ex) keyedit.prompt, keygen.name, passphrase.enter
+    pub keyword: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PromptKind {
+    // a yes or no question ( GET_BOOL )
+    Bool,
+    // a line of text ( GET_LINE ) ex) a command at the edit-key prompt
+    Line,
+    // a passphrase or PIN ( GET_HIDDEN )
+    Hidden,
+}
+
+// the answer to a GpgPrompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromptAnswer {
+    Yes,
+    No,
+    Line(String),
+    Hidden(Passphrase),
+    // stop answering, gpg will treat it as end of input and stop the operation
+    Cancel,
+}
+
+impl GpgPrompt {
+    // the prompt of a GET_BOOL, GET_LINE or GET_HIDDEN status, None for any other status
+    pub fn from_status(status: &StatusLine) -> Option<GpgPrompt> {
+        let (kind, keyword): (PromptKind, &String) = match status {
+            StatusLine::GetBool(keyword) => (PromptKind::Bool, keyword),
+            StatusLine::GetLine(keyword) => (PromptKind::Line, keyword),
+            StatusLine::GetHidden(keyword) => (PromptKind::Hidden, keyword),
+            _ => return None,
+        };
+        return Some(GpgPrompt { kind: kind, keyword: keyword.clone() });
+    }
+}
+
+// every status line in the output of gpg ( ex. raw_data of CmdResult ), any other line will be skipped
+pub fn parse_status_lines(data: &str) -> Vec<StatusLine> {
+    return data.lines().filter_map(StatusLine::parse).collect();
+}
+
+// split a status line into its keyword and the rest of the line, None if it was not a status line
+// ex) [GNUPG:] IMPORT_OK 1 ABCD... -> ("IMPORT_OK", "1 ABCD...")
+pub fn split_status_line(line: &str) -> Option<(&str, &str)> {
+    let line: &str = line.trim_end_matches(['\r', '\n']).strip_prefix(STATUS_PREFIX)?;
+    let mut parts = line.splitn(2, char::is_whitespace);
+    let keyword: &str = parts.next().unwrap_or("");
+    if keyword.is_empty() {
+        return None;
+    }
+    return Some((keyword, parts.next().unwrap_or("")));
+}
+
+// None if a required field was missing or could not be parsed
+fn parse_known(keyword: &str, args: &str) -> Option<StatusLine> {
+    let fields: Vec<&str> = args.split_whitespace().collect();
+    let text = |i: usize| -> Option<String> { fields.get(i).map(|f| f.to_string()) };
+    // the rest of the line after the first field, for a user id that may contain whitespace
+    let rest: String = args.split_once(char::is_whitespace).map(|(_, rest)| rest.trim()).unwrap_or("").to_string();
+    let status: StatusLine = match keyword {
+        "NEWSIG" => StatusLine::NewSig { signer_uid: text(0) },
+        "GOODSIG" => StatusLine::GoodSig { keyid: text(0)?, username: rest },
+        "EXPSIG" => StatusLine::ExpSig { keyid: text(0)?, username: rest },
+        "EXPKEYSIG" => StatusLine::ExpKeySig { keyid: text(0)?, username: rest },
+        "REVKEYSIG" => StatusLine::RevKeySig { keyid: text(0)?, username: rest },
+        "BADSIG" => StatusLine::BadSig { keyid: text(0)?, username: rest },
+        "ERRSIG" => StatusLine::ErrSig {
+            keyid: text(0)?,
+            pubkey_algo: number(&fields, 1)?,
+            hash_algo: number(&fields, 2)?,
+            sig_class: text(3)?,
+            timestamp: text(4)?,
+            rc: number(&fields, 5)?,
+            // gpg without the fingerprint of the key reported a dash
+            fingerprint: text(6).filter(|f| f != "-"),
+        },
+        "VALIDSIG" => StatusLine::ValidSig {
+            fingerprint: text(0)?,
+            creation_date: text(1)?,
+            timestamp: text(2)?,
+            expire_timestamp: text(3)?,
+            sig_version: number(&fields, 4)?,
+            pubkey_algo: number(&fields, 6)?,
+            hash_algo: number(&fields, 7)?,
+            sig_class: text(8)?,
+            primary_fingerprint: text(9),
+        },
+        "SIG_ID" => StatusLine::SigId { id: text(0)?, creation_date: text(1)?, timestamp: text(2)? },
+        "SIG_CREATED" => StatusLine::SigCreated {
+            sig_type: text(0)?,
+            pubkey_algo: number(&fields, 1)?,
+            hash_algo: number(&fields, 2)?,
+            sig_class: text(3)?,
+            timestamp: text(4)?,
+            fingerprint: text(5)?,
+        },
+        "KEY_CONSIDERED" => StatusLine::KeyConsidered { fingerprint: text(0)?, flags: number(&fields, 1).unwrap_or(0) },
+        "TRUST_UNDEFINED" | "TRUST_NEVER" | "TRUST_MARGINAL" | "TRUST_FULLY" | "TRUST_ULTIMATE" => StatusLine::Trust {
+            level: keyword["TRUST_".len()..].to_string(),
+            validation_model: text(1),
+        },
+        "KEYEXPIRED" => StatusLine::KeyExpired { timestamp: text(0)? },
+        "KEYREVOKED" => StatusLine::KeyRevoked,
+        "SIGEXPIRED" => StatusLine::SigExpired,
+        "NO_PUBKEY" => StatusLine::NoPubkey { keyid: text(0)? },
+        "NO_SECKEY" => StatusLine::NoSeckey { keyid: text(0)? },
+        "ENC_TO" => StatusLine::EncTo {
+            keyid: text(0)?,
+            pubkey_algo: number(&fields, 1)?,
+            key_length: number(&fields, 2).unwrap_or(0),
+        },
+        "BEGIN_DECRYPTION" => StatusLine::BeginDecryption,
+        "END_DECRYPTION" => StatusLine::EndDecryption,
+        "DECRYPTION_OKAY" => StatusLine::DecryptionOkay,
+        "DECRYPTION_FAILED" => StatusLine::DecryptionFailed,
+        "GOODMDC" => StatusLine::GoodMdc,
+        "DECRYPTION_INFO" => StatusLine::DecryptionInfo {
+            mdc_method: number(&fields, 0)?,
+            sym_algo: number(&fields, 1)?,
+            aead_algo: number(&fields, 2).filter(|algo| *algo != 0),
+        },
+        "SESSION_KEY" => StatusLine::SessionKey(Passphrase::new(text(0)?)),
+        "BEGIN_ENCRYPTION" => StatusLine::BeginEncryption {
+            mdc_method: number(&fields, 0)?,
+            sym_algo: number(&fields, 1)?,
+            aead_algo: number(&fields, 2).filter(|algo| *algo != 0),
+        },
+        "END_ENCRYPTION" => StatusLine::EndEncryption,
+        "BEGIN_SIGNING" => StatusLine::BeginSigning,
+        "PLAINTEXT" => StatusLine::Plaintext { format: text(0)?, timestamp: text(1)?, filename: text(2) },
+        "PLAINTEXT_LENGTH" => StatusLine::PlaintextLength(number(&fields, 0)?),
+        "NODATA" => StatusLine::NoData(number(&fields, 0)?),
+        "USERID_HINT" => StatusLine::UserIdHint { keyid: text(0)?, user_id: rest },
+        "NEED_PASSPHRASE" => StatusLine::NeedPassphrase {
+            keyid: text(0)?,
+            main_keyid: text(1)?,
+            pubkey_algo: number(&fields, 2).unwrap_or(0),
+            key_length: number(&fields, 3).unwrap_or(0),
+        },
+        "NEED_PASSPHRASE_SYM" => StatusLine::NeedPassphraseSym {
+            cipher_algo: number(&fields, 0)?,
+            s2k_mode: number(&fields, 1)?,
+            s2k_hash: number(&fields, 2)?,
+        },
+        "BAD_PASSPHRASE" => StatusLine::BadPassphrase { keyid: text(0) },
+        "GOOD_PASSPHRASE" => StatusLine::GoodPassphrase,
+        "MISSING_PASSPHRASE" => StatusLine::MissingPassphrase,
+        "INV_RECP" => StatusLine::InvRecp { reason: number(&fields, 0)?, recipient: rest },
+        "INV_SGNR" => StatusLine::InvSgnr { reason: number(&fields, 0)?, signer: rest },
+        "NO_RECP" => StatusLine::NoRecp,
+        "NO_SGNR" => StatusLine::NoSgnr,
+        "KEY_CREATED" => StatusLine::KeyCreated { key_type: text(0)?, fingerprint: text(1)?, handle: text(2) },
+        "KEY_NOT_CREATED" => StatusLine::KeyNotCreated { handle: text(0) },
+        "IMPORTED" => StatusLine::Imported { keyid: text(0)?, username: rest },
+        "IMPORT_OK" => StatusLine::ImportOk { reason: number(&fields, 0)?, fingerprint: text(1) },
+        "IMPORT_PROBLEM" => StatusLine::ImportProblem { reason: number(&fields, 0)?, fingerprint: text(1) },
+        "IMPORT_RES" => StatusLine::ImportRes(numbers(&fields)?),
+        "EXPORT_RES" => StatusLine::ExportRes(numbers(&fields)?),
+        "EXPORTED" => StatusLine::Exported { fingerprint: text(0)? },
+        "DELETE_PROBLEM" => StatusLine::DeleteProblem(number(&fields, 0)?),
+        "ATTRIBUTE" => StatusLine::Attribute {
+            fingerprint: text(0)?,
+            octets: number(&fields, 1)?,
+            attr_type: number(&fields, 2)?,
+            index: number(&fields, 3)?,
+            count: number(&fields, 4)?,
+            timestamp: text(5)?,
+            expire: text(6)?,
+            flags: number(&fields, 7)?,
+        },
+        "GET_BOOL" => StatusLine::GetBool(text(0)?),
+        "GET_LINE" => StatusLine::GetLine(text(0)?),
+        "GET_HIDDEN" => StatusLine::GetHidden(text(0)?),
+        "GOT_IT" => StatusLine::GotIt,
+        "PROGRESS" => StatusLine::Progress {
+            what: text(0)?,
+            char: text(1)?,
+            current: number(&fields, 2)?,
+            total: number(&fields, 3)?,
+            units: text(4),
+        },
+        "CARDCTRL" => StatusLine::CardCtrl { what: number(&fields, 0)?, serialno: text(1) },
+        "SC_OP_FAILURE" => StatusLine::ScOpFailure(number(&fields, 0)),
+        "SC_OP_SUCCESS" => StatusLine::ScOpSuccess,
+        "ERROR" => StatusLine::Error {
+            location: text(0)?,
+            code: number(&fields, 1)?,
+            more: Some(fields.iter().skip(2).cloned().collect::<Vec<&str>>().join(" ")).filter(|m| !m.is_empty()),
+        },
+        "FAILURE" => StatusLine::Failure { location: text(0)?, code: number(&fields, 1)? },
+        "SUCCESS" => StatusLine::Success(text(0)),
+        _ => return None,
+    };
+    return Some(status);
+}
+
+fn number<T: FromStr>(fields: &[&str], i: usize) -> Option<T> {
+    return fields.get(i).and_then(|f| f.parse::<T>().ok());
+}
+
+fn numbers(fields: &[&str]) -> Option<Vec<u64>> {
+    return fields.iter().map(|f| f.parse::<u64>().ok()).collect();
+}
@@ -0,0 +1,122 @@
+#[cfg(unix)]
+use std::fs::Permissions;
+use std::path::Path;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use tempfile::{Builder, NamedTempFile, TempDir};
+
+use crate::utils::errors::{GPGError, GPGErrorType};
+
+// every temporary file and directory name start with this, so those left by a killed process can be found
+const TEMP_PREFIX: &str = "crab_gnupg_";
+
+//*******************************************************
+
+//             RELATED TO TEMPORARY FILE
+
+//*******************************************************
+// every temporary file or directory the crate create was made through here:
+//   spilled output ( process ): the output of gpg larger than the memory limit of the runner,
+//                               kept as CmdResult.output_file and removed by take_spilled_output
+//   attribute directory ( GPG.get_photos ): the photo id data gpg write to a file while listing the key
+//   keyring directory ( GPG.verify_with_keys ): the keyring the provided keys were imported into
+//   ephemeral root ( EphemeralGpg ): the homedir and output_dir of the isolated gpg
+//   output directory ( GPG.encrypt_to_writer ... ): the output of gpg until it was copied into the writer,
+//                                                  or thrown away ( GPG.run_raw with CaptureMode::Discard )
+//   gpgv keyring directory ( GPG.verify with VerifyBackend::Gpgv ): the armored keyring dearmored for gpgv
+//   git signature file ( GPG.verify_git_object ): the signature of the git object while gpg verify it
+//   manifest directory ( GPG.verify_manifest ): the content of the manifest covered by the signature
+//   signature file ( open_pgp_mime ): the detached signature of a PGP/MIME message while it was verified
+//   health check file ( GPG.health_check ): created in the homedir to check it was writable
+//   restore directory ( GPG.restore_snapshot ): created in the homedir, the snapshot was unpacked into it
+//                                              before its entries were renamed into the homedir
+//   backup directory ( GPG.backup_keys ): the armored secret and public keys while they were read into the bundle
+//   backup file ( GPG.backup_keys ): created next to the output, the bundle was written into it and renamed over the output
+//   conf file ( GpgConf.save, AgentConf.save ): created next to the conf, the conf was written into it and renamed over the conf
+// the input and the batch script ( ex. of gen_key ) were always written to the stdin of gpg, never to a file
+//
+// a file was only readable and writable by the owner ( 0600 ), a directory only accessible by the owner ( 0700 )
+// both had a random name and will be removed once dropped, so an operation failing part way will not leave it behind
+
+// create a temporary file named crab_gnupg_<name>_<random> in the temp dir of the system
+pub fn create_temp_file(name: &str) -> Result<NamedTempFile, GPGError> {
+    // name: what the file was for ( ex. output ), to tell them apart
+
+    let prefix: String = format!("{}{}_", TEMP_PREFIX, name);
+    let mut builder: Builder = Builder::new();
+    builder.prefix(&prefix);
+    #[cfg(unix)]
+    builder.permissions(Permissions::from_mode(0o600));
+    match builder.tempfile() {
+        Ok(file) => return Ok(file),
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to create temporary file: {}", e)),
+                None,
+            ));
+        }
+    }
+}
+
+// create a temporary file named crab_gnupg_<name>_<random> in the directory
+pub fn create_temp_file_in(dir: &Path, name: &str) -> Result<NamedTempFile, GPGError> {
+    // dir: where the file will be created ( ex. the homedir )
+    // name: what the file was for ( ex. health ), to tell them apart
+
+    let prefix: String = format!("{}{}_", TEMP_PREFIX, name);
+    let mut builder: Builder = Builder::new();
+    builder.prefix(&prefix);
+    #[cfg(unix)]
+    builder.permissions(Permissions::from_mode(0o600));
+    match builder.tempfile_in(dir) {
+        Ok(file) => return Ok(file),
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to create temporary file in {}: {}", dir.display(), e)),
+                None,
+            ));
+        }
+    }
+}
+
+// create a temporary directory named crab_gnupg_<name>_<random> in the temp dir of the system
+pub fn create_temp_dir(name: &str) -> Result<TempDir, GPGError> {
+    // name: what the directory was for ( ex. keyring ), to tell them apart
+
+    let prefix: String = format!("{}{}_", TEMP_PREFIX, name);
+    let mut builder: Builder = Builder::new();
+    builder.prefix(&prefix);
+    #[cfg(unix)]
+    builder.permissions(Permissions::from_mode(0o700));
+    match builder.tempdir() {
+        Ok(dir) => return Ok(dir),
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to create temporary directory: {}", e)),
+                None,
+            ));
+        }
+    }
+}
+
+// create a temporary directory named crab_gnupg_<name>_<random> in the directory
+pub fn create_temp_dir_in(dir: &Path, name: &str) -> Result<TempDir, GPGError> {
+    // dir: where the directory will be created ( ex. the homedir )
+    // name: what the directory was for ( ex. restore ), to tell them apart
+
+    let prefix: String = format!("{}{}_", TEMP_PREFIX, name);
+    let mut builder: Builder = Builder::new();
+    builder.prefix(&prefix);
+    #[cfg(unix)]
+    builder.permissions(Permissions::from_mode(0o700));
+    match builder.tempdir_in(dir) {
+        Ok(dir) => return Ok(dir),
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to create temporary directory in {}: {}", dir.display(), e)),
+                None,
+            ));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::{collections::HashMap, path::PathBuf, process::Command};
+
+use tempfile::TempDir;
+
+use crate::gnupg::GPG;
+use crate::temp::create_temp_dir;
+use crate::utils::{
+    errors::{GPGError, GPGErrorType},
+    response::ListKeyResult,
+};
+
+//*******************************************************
+
+//              RELATED TO EPHEMERAL GPG
+
+//*******************************************************
+// an isolated gpg homedir with a passphrase-less key for integration test
+// the homedir and output_dir will be removed, and its gpg-agent stopped, once it was dropped
+#[derive(Debug)]
+pub struct EphemeralGpg {
+    // gpg: a GPG object initialized with the isolated homedir and output_dir
+    pub gpg: GPG,
+    // root: the temporary directory holding the homedir and the output_dir
+    pub root: PathBuf,
+    // fingerprint: fingerprint of the generated key
+    pub fingerprint: String,
+    // keyid: keyid of the generated key
+    pub keyid: String,
+    // uid: user id of the generated key ex) Test Key <test@example.invalid>
+    pub uid: String,
+}
+
+impl EphemeralGpg {
+    // create an isolated homedir with a key for Test Key <test@example.invalid>
+    pub fn new() -> Result<EphemeralGpg, GPGError> {
+        return EphemeralGpg::with_uid("Test Key".to_string(), "test@example.invalid".to_string());
+    }
+
+    // create an isolated homedir with a key for the name and email provided
+    pub fn with_uid(name: String, email: String) -> Result<EphemeralGpg, GPGError> {
+        // name: name of the user id of the key to generate
+        // email: email of the user id of the key to generate
+
+        // the directory will be removed once dropped, until it was handed to the EphemeralGpg
+        let root: TempDir = create_temp_dir("ephemeral")?;
+        let homedir: PathBuf = root.path().join("home");
+        let output_dir: PathBuf = root.path().join("output");
+        for dir in [&homedir, &output_dir] {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                return Err(GPGError::new(
+                    GPGErrorType::HomedirError(e.to_string()),
+                    None,
+                ));
+            }
+        }
+        let gpg: GPG = GPG::init(
+            Some(homedir),
+            Some(output_dir),
+            true,
+        )?;
+        // from here on, dropping the EphemeralGpg will clean up the directory
+        let mut ephemeral: EphemeralGpg = EphemeralGpg {
+            gpg: gpg,
+            root: root.into_path(),
+            fingerprint: String::new(),
+            keyid: String::new(),
+            uid: String::new(),
+        };
+
+        // ed25519 was used as it was much faster to generate than RSA
+        let mut args: HashMap<String, String> = HashMap::new();
+        args.insert("Key-Type".to_string(), "EDDSA".to_string());
+        args.insert("Key-Curve".to_string(), "ed25519".to_string());
+        args.insert("Key-Usage".to_string(), "sign,cert".to_string());
+        args.insert("Subkey-Type".to_string(), "ECDH".to_string());
+        args.insert("Subkey-Curve".to_string(), "cv25519".to_string());
+        args.insert("Subkey-Usage".to_string(), "encrypt".to_string());
+        args.insert("Name-Real".to_string(), name);
+        args.insert("Name-Email".to_string(), email);
+        ephemeral.gpg.gen_key(None, Some(args))?;
+
+        let keys: Vec<ListKeyResult> = ephemeral.gpg.list_keys(true, None, false)?;
+        let key: &ListKeyResult = match keys.first() {
+            Some(key) => key,
+            None => {
+                return Err(GPGError::new(
+                    GPGErrorType::GPGProcessError("generated key was not found".to_string()),
+                    None,
+                ));
+            }
+        };
+        ephemeral.fingerprint = key.fingerprint.clone();
+        ephemeral.keyid = key.keyid.clone();
+        ephemeral.uid = key.uids.first().cloned().unwrap_or_default();
+        return Ok(ephemeral);
+    }
+
+    // the homedir of the isolated gpg
+    pub fn homedir(&self) -> PathBuf {
+        return self.gpg.homedir.clone();
+    }
+
+    // the output_dir of the isolated gpg
+    pub fn output_dir(&self) -> PathBuf {
+        return self.gpg.output_dir.clone();
+    }
+}
+
+impl Drop for EphemeralGpg {
+    fn drop(&mut self) {
+        // the agent started for the homedir would otherwise keep running after the homedir was removed
+        let _ = Command::new("gpgconf")
+            .arg("--homedir")
+            .arg(&self.gpg.homedir)
+            .args(["--kill", "all"])
+            .output();
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
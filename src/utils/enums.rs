@@ -1,4 +1,11 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Debug, Display, Formatter},
+    fs::File,
+    io::{Cursor, Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use crate::utils::errors::{GPGError, GPGErrorType};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operation {
@@ -9,16 +16,28 @@ pub enum Operation {
     DeleteKey,
     AddSubKey,
     RevokeKey,
+    AddPhoto,
+    SetPrimaryUid,
+    EditKey,
     SearchKey,
     ImportKey,
     TrustKey,
     SignKey,
+    TrustSignKey,
     ExportPublicKey,
     ExportSecretKey,
+    BackupKey,
+    RestoreKey,
     Encrypt,
     Decrypt,
     Sign,
     VerifyFile,
+    KeyToCard,
+    CardGenerateKey,
+    SignManifest,
+    VerifyManifest,
+    Maintain,
+    Interactive,
 }
 
 #[doc(hidden)]
@@ -32,21 +51,187 @@ impl Display for Operation {
             Operation::DeleteKey => write!(f, "DeleteKey"),
             Operation::AddSubKey => write!(f, "AddSubKey"),
             Operation::RevokeKey => write!(f, "RevokeKey"),
+            Operation::AddPhoto => write!(f, "AddPhoto"),
+            Operation::SetPrimaryUid => write!(f, "SetPrimaryUid"),
+            Operation::EditKey => write!(f, "EditKey"),
             Operation::SearchKey => write!(f, "SearchKey"),
             Operation::ImportKey => write!(f, "ImportKey"),
             Operation::TrustKey => write!(f, "TrustKey"),
             Operation::SignKey => write!(f, "SignKey"),
+            Operation::TrustSignKey => write!(f, "TrustSignKey"),
             Operation::ExportPublicKey => write!(f, "ExportPublicKey"),
             Operation::ExportSecretKey => write!(f, "ExportSecretKey"),
+            Operation::BackupKey => write!(f, "BackupKey"),
+            Operation::RestoreKey => write!(f, "RestoreKey"),
             Operation::Encrypt => write!(f, "Encrypt"),
             Operation::Decrypt => write!(f, "Decrypt"),
             Operation::Sign => write!(f, "Sign"),
             Operation::VerifyFile => write!(f, "VerifyFile"),
+            Operation::KeyToCard => write!(f, "KeyToCard"),
+            Operation::CardGenerateKey => write!(f, "CardGenerateKey"),
+            Operation::SignManifest => write!(f, "SignManifest"),
+            Operation::VerifyManifest => write!(f, "VerifyManifest"),
+            Operation::Maintain => write!(f, "Maintain"),
+            Operation::Interactive => write!(f, "Interactive"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[doc(hidden)]
+impl Operation {
+    // what the exit code of gpg ( or gpgv ) mean for this operation, None if the runner did not report one
+    pub fn exit_status(&self, return_code: Option<i32>) -> GpgExitStatus {
+        match return_code {
+            None => GpgExitStatus::Unknown,
+            Some(0) => GpgExitStatus::Success,
+            // gpg and gpgv exit with 1 if at least one signature was bad, it is not a failure of the command
+            Some(1) if self.is_verification() => GpgExitStatus::BadSignature,
+            Some(code) => GpgExitStatus::Error(code),
+        }
+    }
+
+    pub fn is_verification(&self) -> bool {
+        return *self == Operation::VerifyFile || *self == Operation::VerifyManifest;
+    }
+
+    // whether the operation write to the keyring or trustdb of the homedir
+    pub fn is_mutating(&self) -> bool {
+        return matches!(
+            self,
+            Operation::GenerateKey
+                | Operation::DeleteKey
+                | Operation::AddSubKey
+                | Operation::RevokeKey
+                | Operation::AddPhoto
+                | Operation::SetPrimaryUid
+                | Operation::EditKey
+                | Operation::ImportKey
+                | Operation::TrustKey
+                | Operation::SignKey
+                | Operation::TrustSignKey
+                | Operation::RestoreKey
+                | Operation::KeyToCard
+                | Operation::CardGenerateKey
+                | Operation::Maintain
+                | Operation::Interactive
+        );
+    }
+}
+
+// the exit code of gpg ( and gpgv ), for a command line tool to exit with the same code ( check GPGError.exit_code )
+pub const EXIT_CODE_SUCCESS: i32 = 0;
+// at least one signature was bad
+pub const EXIT_CODE_BAD_SIGNATURE: i32 = 1;
+// any other error ( ex. no signature, a missing key, an invalid argument )
+pub const EXIT_CODE_ERROR: i32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpgExitStatus {
+    // exit code 0
+    Success,
+    // exit code 1 of a verification, the signature was checked and it was bad
+    BadSignature,
+    // any other exit code, gpg failed to complete the operation ( -1 if gpg was terminated by a signal )
+    Error(i32),
+    // the runner did not report an exit code
+    Unknown,
+}
+
+// a warning gpg reported about the operation, the operation may still have succeeded ( check CmdResult.warnings )
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpgWarning {
+    // the key used was not certified with a trusted signature ( TRUST_UNDEFINED / TRUST_NEVER )
+    UntrustedKey,
+    // the key used had expired ( KEYEXPIRED / EXPKEYSIG )
+    ExpiredKey,
+    // the key used had been revoked ( KEYREVOKED / REVKEYSIG )
+    RevokedKey,
+    // the signature had expired ( EXPSIG )
+    ExpiredSignature,
+    // the data was encrypted with a legacy cipher ( IDEA, 3DES, CAST5, BLOWFISH ), with the name of the cipher
+    LegacyCipher(String),
+    // the signature was made with a weak digest algorithm ( MD5, SHA1, RIPEMD160 ), with the name of the algorithm
+    WeakDigest(String),
+    // the data was encrypted without integrity protection ( MDC ), it could have been modified
+    MissingMdc,
+    // any other warning gpg logged ( gpg: WARNING: ... ), with the message
+    Other(String),
+}
+
+#[doc(hidden)]
+impl GpgWarning {
+    // the warning a status line reported, None if it was not a warning
+    pub fn from_status(keyword: &str, value: &str) -> Option<GpgWarning> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        match keyword {
+            "TRUST_UNDEFINED" | "TRUST_NEVER" => return Some(GpgWarning::UntrustedKey),
+            "KEYEXPIRED" | "EXPKEYSIG" => return Some(GpgWarning::ExpiredKey),
+            "KEYREVOKED" | "REVKEYSIG" => return Some(GpgWarning::RevokedKey),
+            "EXPSIG" => return Some(GpgWarning::ExpiredSignature),
+            // DECRYPTION_INFO <mdc_method> <sym_algo> [<aead_algo>]
+            "DECRYPTION_INFO" => {
+                let aead: bool = fields.get(2).is_some_and(|aead| *aead != "0");
+                if fields.first() == Some(&"0") && !aead {
+                    return Some(GpgWarning::MissingMdc);
+                }
+                let cipher: Option<&str> = match fields.get(1) {
+                    Some(&"1") => Some("IDEA"),
+                    Some(&"2") => Some("3DES"),
+                    Some(&"3") => Some("CAST5"),
+                    Some(&"4") => Some("BLOWFISH"),
+                    _ => None,
+                };
+                return cipher.map(|cipher| GpgWarning::LegacyCipher(cipher.to_string()));
+            }
+            // VALIDSIG <fingerprint> <date> <timestamp> <expire> <version> <reserved> <pubkey_algo> <hash_algo> ...
+            "VALIDSIG" => {
+                let digest: Option<&str> = match fields.get(7) {
+                    Some(&"1") => Some("MD5"),
+                    Some(&"2") => Some("SHA1"),
+                    Some(&"3") => Some("RIPEMD160"),
+                    _ => None,
+                };
+                return digest.map(|digest| GpgWarning::WeakDigest(digest.to_string()));
+            }
+            _ => return None,
+        }
+    }
+
+    // the warning a log line ( without the gpg: prefix ) reported, None if it was not a warning
+    pub fn from_log(log: &str) -> Option<GpgWarning> {
+        let message: &str = match log.strip_prefix("WARNING: ") {
+            Some(message) => message.trim(),
+            None => return None,
+        };
+        // already reported by the status line, the log only repeat it
+        if message.contains("not certified with a trusted signature") {
+            return Some(GpgWarning::UntrustedKey);
+        }
+        if message.contains("not integrity protected") {
+            return Some(GpgWarning::MissingMdc);
+        }
+        return Some(GpgWarning::Other(message.to_string()));
+    }
+}
+
+#[doc(hidden)]
+impl Display for GpgWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpgWarning::UntrustedKey => write!(f, "the key is not certified with a trusted signature"),
+            GpgWarning::ExpiredKey => write!(f, "the key has expired"),
+            GpgWarning::RevokedKey => write!(f, "the key has been revoked"),
+            GpgWarning::ExpiredSignature => write!(f, "the signature has expired"),
+            GpgWarning::LegacyCipher(cipher) => write!(f, "encrypted with the legacy cipher {}", cipher),
+            GpgWarning::WeakDigest(digest) => write!(f, "signed with the weak digest algorithm {}", digest),
+            GpgWarning::MissingMdc => write!(f, "the message was not integrity protected"),
+            GpgWarning::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TrustLevel {
     Expired,
     Undefined,
@@ -54,12 +239,37 @@ pub enum TrustLevel {
     Marginal,
     Fully,
     Ultimate,
+    // only reported by the validity of a key, it could not be set as the trust of a key
+    Revoked,
+    Invalid,
+}
+
+impl TrustLevel {
+    // decode the validity ( or ownertrust ) field of the colon listing, None for the character that was not a
+    // trust level ( ex. w = well known private part, s = special validity )
+    // https://github.com/gpg/gnupg/blob/master/doc/DETAILS
+    pub fn from_colon_char(value: char) -> Option<TrustLevel> {
+        match value {
+            'u' => return Some(TrustLevel::Ultimate),
+            'f' => return Some(TrustLevel::Fully),
+            'm' => return Some(TrustLevel::Marginal),
+            'n' => return Some(TrustLevel::Never),
+            'o' | 'q' | '-' => return Some(TrustLevel::Undefined),
+            'e' => return Some(TrustLevel::Expired),
+            'r' => return Some(TrustLevel::Revoked),
+            // d ( disabled ) was only reported by gpg before 2.1
+            'i' | 'd' => return Some(TrustLevel::Invalid),
+            _ => return None,
+        }
+    }
 }
 
 #[doc(hidden)]
 impl TrustLevel {
+    // Revoked and Invalid were not trusted at all
     pub fn value(&self) -> u8 {
         match &self {
+            TrustLevel::Revoked | TrustLevel::Invalid => 0,
             TrustLevel::Expired => 1,
             TrustLevel::Undefined => 2,
             TrustLevel::Never => 3,
@@ -79,6 +289,8 @@ pub enum DeleteProblem{
 }
 #[doc(hidden)]
 impl DeleteProblem {
+    // the description of a DELETE_PROBLEM code rather than a parse of DeleteProblem, so it is not FromStr
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &str) -> String {
         match value {
             "1" => String::from("No Such Key"),
@@ -88,4 +300,412 @@ impl DeleteProblem {
             _ => format!("Unknown error: {}", value),  
         }
     }
-}
\ No newline at end of file
+}
+
+pub enum CardProblem {
+    NoCard = 4,
+    NoReader = 5,
+    NoCardSupport = 6,
+}
+#[doc(hidden)]
+impl CardProblem {
+    // None for the CARDCTRL code that was not a problem ( ex. 3 card with serial number )
+    // the description of a CARDCTRL code rather than a parse of CardProblem, so it is not FromStr
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(value: &str) -> Option<String> {
+        match value {
+            "4" => Some(String::from("No card available")),
+            "5" => Some(String::from("No card reader available")),
+            "6" => Some(String::from("No card support available")),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CardSlot {
+    // the signature key slot of an OpenPGP card
+    Signature,
+    // the encryption key slot of an OpenPGP card
+    Encryption,
+    // the authentication key slot of an OpenPGP card
+    Authentication,
+}
+
+#[doc(hidden)]
+impl CardSlot {
+    // the answer gpg expected when asking where to store the key
+    pub fn value(&self) -> String {
+        match &self {
+            CardSlot::Signature => String::from("1"),
+            CardSlot::Encryption => String::from("2"),
+            CardSlot::Authentication => String::from("3"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditKeyCommand {
+    // a command to be entered at the edit-key prompt ex) "uid 1", "primary", "addphoto"
+    Command(String),
+    // an answer to a question asked by gpg ex) a file path, a reason code
+    Answer(String),
+    // answer yes to a y/N question
+    Yes,
+    // answer no to a y/N question
+    No,
+    // save the changes and quit
+    Save,
+    // quit without saving
+    Quit,
+}
+
+#[doc(hidden)]
+impl EditKeyCommand {
+    pub fn value(&self) -> String {
+        match &self {
+            EditKeyCommand::Command(command) => command.clone(),
+            EditKeyCommand::Answer(answer) => answer.clone(),
+            EditKeyCommand::Yes => String::from("y"),
+            EditKeyCommand::No => String::from("n"),
+            EditKeyCommand::Save => String::from("save"),
+            EditKeyCommand::Quit => String::from("quit"),
+        }
+    }
+
+    // check if this command was the right kind of input for the prompt gpg is asking
+    pub fn is_expected(&self, prompt_type: &str, keyword: &str) -> bool {
+        let is_command_prompt: bool = prompt_type == "GET_LINE" && keyword == "keyedit.prompt";
+        match &self {
+            EditKeyCommand::Command(_) | EditKeyCommand::Save | EditKeyCommand::Quit => is_command_prompt,
+            EditKeyCommand::Answer(_) => {
+                (prompt_type == "GET_LINE" || prompt_type == "GET_HIDDEN") && !is_command_prompt
+            }
+            EditKeyCommand::Yes | EditKeyCommand::No => prompt_type == "GET_BOOL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyBackend {
+    // verify with gpg, using the keyring and trustdb of the homedir
+    Gpg,
+    // verify with gpgv, only the keys in the keyring file ( path ) provided will be used
+    // the keyring can be a binary or an armored export of the public key(s)
+    Gpgv(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrustModel {
+    // the web of trust combined with the trust signatures, the default of gpg
+    Pgp,
+    // the web of trust only
+    Classic,
+    // trust on first use
+    Tofu,
+    // trust on first use combined with the web of trust
+    TofuPgp,
+    // only the ownertrust set directly on the key will be used
+    Direct,
+    // every key will be trusted
+    Always,
+    // let gpg choose the model from the trustdb
+    Auto,
+}
+
+#[doc(hidden)]
+impl TrustModel {
+    // the value of --trust-model
+    pub fn value(&self) -> String {
+        match &self {
+            TrustModel::Pgp => String::from("pgp"),
+            TrustModel::Classic => String::from("classic"),
+            TrustModel::Tofu => String::from("tofu"),
+            TrustModel::TofuPgp => String::from("tofu+pgp"),
+            TrustModel::Direct => String::from("direct"),
+            TrustModel::Always => String::from("always"),
+            TrustModel::Auto => String::from("auto"),
+        }
+    }
+}
+
+// a symmetric cipher of OpenPGP
+#[derive(Debug, Clone, PartialEq)]
+pub enum CipherAlgo {
+    Idea,
+    TripleDes,
+    Cast5,
+    Blowfish,
+    Aes128,
+    Aes192,
+    Aes256,
+    Twofish,
+    Camellia128,
+    Camellia192,
+    Camellia256,
+}
+
+#[doc(hidden)]
+impl CipherAlgo {
+    // the name gpg know the cipher by
+    pub fn value(&self) -> String {
+        match &self {
+            CipherAlgo::Idea => String::from("IDEA"),
+            CipherAlgo::TripleDes => String::from("3DES"),
+            CipherAlgo::Cast5 => String::from("CAST5"),
+            CipherAlgo::Blowfish => String::from("BLOWFISH"),
+            CipherAlgo::Aes128 => String::from("AES"),
+            CipherAlgo::Aes192 => String::from("AES192"),
+            CipherAlgo::Aes256 => String::from("AES256"),
+            CipherAlgo::Twofish => String::from("TWOFISH"),
+            CipherAlgo::Camellia128 => String::from("CAMELLIA128"),
+            CipherAlgo::Camellia192 => String::from("CAMELLIA192"),
+            CipherAlgo::Camellia256 => String::from("CAMELLIA256"),
+        }
+    }
+
+    // the cipher of the OpenPGP algorithm id gpg report in the status ( ex. DECRYPTION_INFO ), None if unknown
+    pub fn from_id(id: u8) -> Option<CipherAlgo> {
+        match id {
+            1 => return Some(CipherAlgo::Idea),
+            2 => return Some(CipherAlgo::TripleDes),
+            3 => return Some(CipherAlgo::Cast5),
+            4 => return Some(CipherAlgo::Blowfish),
+            7 => return Some(CipherAlgo::Aes128),
+            8 => return Some(CipherAlgo::Aes192),
+            9 => return Some(CipherAlgo::Aes256),
+            10 => return Some(CipherAlgo::Twofish),
+            11 => return Some(CipherAlgo::Camellia128),
+            12 => return Some(CipherAlgo::Camellia192),
+            13 => return Some(CipherAlgo::Camellia256),
+            _ => return None,
+        }
+    }
+}
+
+// a digest algorithm of OpenPGP
+#[derive(Debug, Clone, PartialEq)]
+pub enum DigestAlgo {
+    Md5,
+    Sha1,
+    Ripemd160,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+#[doc(hidden)]
+impl DigestAlgo {
+    // the name gpg know the digest algorithm by
+    pub fn value(&self) -> String {
+        match &self {
+            DigestAlgo::Md5 => String::from("MD5"),
+            DigestAlgo::Sha1 => String::from("SHA1"),
+            DigestAlgo::Ripemd160 => String::from("RIPEMD160"),
+            DigestAlgo::Sha224 => String::from("SHA224"),
+            DigestAlgo::Sha256 => String::from("SHA256"),
+            DigestAlgo::Sha384 => String::from("SHA384"),
+            DigestAlgo::Sha512 => String::from("SHA512"),
+        }
+    }
+
+    // the digest of the OpenPGP algorithm id gpg report in the status ( ex. SIG_CREATED ), None if unknown
+    pub fn from_id(id: u8) -> Option<DigestAlgo> {
+        match id {
+            1 => return Some(DigestAlgo::Md5),
+            2 => return Some(DigestAlgo::Sha1),
+            3 => return Some(DigestAlgo::Ripemd160),
+            8 => return Some(DigestAlgo::Sha256),
+            9 => return Some(DigestAlgo::Sha384),
+            10 => return Some(DigestAlgo::Sha512),
+            11 => return Some(DigestAlgo::Sha224),
+            _ => return None,
+        }
+    }
+}
+
+// a compression algorithm of OpenPGP
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompressAlgo {
+    Uncompressed,
+    Zip,
+    Zlib,
+    Bzip2,
+}
+
+#[doc(hidden)]
+impl CompressAlgo {
+    // the name gpg know the compression algorithm by
+    pub fn value(&self) -> String {
+        match &self {
+            CompressAlgo::Uncompressed => String::from("Uncompressed"),
+            CompressAlgo::Zip => String::from("ZIP"),
+            CompressAlgo::Zlib => String::from("ZLIB"),
+            CompressAlgo::Bzip2 => String::from("BZIP2"),
+        }
+    }
+}
+
+// how the output of an operation will be named when the option did not provide one
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputNaming {
+    // [<operation>_<datetime>.<extension>] in the output_dir, the default
+    Timestamp,
+    // after the input in the output_dir ( ex. file.txt -> file.txt.gpg, file.txt.gpg -> file.txt ),
+    // with -1, -2 ... appended before the extension if it was already taken ( ex. file.txt-1.gpg )
+    FromInput,
+}
+
+// how much gpg will log to stderr, the log was captured into debug_log of CmdResult
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verbosity {
+    // only errors ( --quiet )
+    Quiet,
+    // what gpg log by default
+    Normal,
+    // --verbose
+    Verbose,
+    // --verbose --debug <flags>, with the debug flags ( ex. ipc, "ipc,crypto" ) or a level ( basic, advanced, expert, guru )
+    // NOTE: the log may contain details of the keys and data, only enable it to diagnose an issue
+    Debug(String),
+}
+
+#[doc(hidden)]
+impl Verbosity {
+    pub fn gen_args(&self) -> Result<Vec<String>, GPGError> {
+        match self {
+            Verbosity::Quiet => return Ok(vec!["--quiet".to_string()]),
+            Verbosity::Normal => return Ok(vec![]),
+            Verbosity::Verbose => return Ok(vec!["--verbose".to_string()]),
+            Verbosity::Debug(flags) => {
+                let valid: bool = !flags.is_empty()
+                    && flags.chars().all(|c| c.is_ascii_alphanumeric() || c == ',' || c == '_');
+                if !valid {
+                    return Err(GPGError::new(
+                        GPGErrorType::InvalidArgumentError(format!("debug flags invalid: {}", flags)),
+                        None,
+                    ));
+                }
+                return Ok(vec!["--verbose".to_string(), "--debug".to_string(), flags.clone()]);
+            }
+        }
+    }
+}
+
+// whether a key was the public or the secret part, ex) the kind of an exported key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyKind {
+    Public,
+    Secret,
+}
+
+// how the plaintext file will be removed once it was encrypted
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShredMode {
+    // only remove the file
+    Unlink,
+    // overwrite the content of the file for the number of passes ( alternating zeros and ones ) before removing it
+    // NOTE: on a journaling or copy-on-write filesystem, or an SSD, the content may still be recoverable
+    Overwrite(u32),
+}
+
+// what will be kept of the output gpg write to stdout when running a RawCommand
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureMode {
+    // kept in raw_data of the CmdResult ( ex. a listing ), the default
+    Stdout,
+    // written to the file with --output, overwritten if it already exist
+    File(PathBuf),
+    // thrown away, only the status and the return code were kept ( ex. a command only changing the keyring )
+    Discard,
+}
+
+// where the input of an operation ( ex. the file to encrypt ) will be read from
+pub enum InputSource {
+    // path to the file, will be opened when the operation run
+    Path(PathBuf),
+    // an opened file, will be read from the beginning
+    FileHandle(File),
+    // the content itself
+    Bytes(Vec<u8>),
+    // anything readable, will be read until the end
+    Reader(Box<dyn Read + Send>),
+}
+
+#[doc(hidden)]
+impl Debug for InputSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputSource::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            InputSource::FileHandle(file) => f.debug_tuple("FileHandle").field(file).finish(),
+            InputSource::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            InputSource::Reader(_) => write!(f, "Reader"),
+        }
+    }
+}
+
+#[doc(hidden)]
+impl InputSource {
+    // the path of the input, only available for InputSource::Path
+    pub fn path(&self) -> Option<&Path> {
+        match &self {
+            InputSource::Path(path) => Some(path.as_path()),
+            _ => None,
+        }
+    }
+
+    // turn the input into what will be written into the stdin of gpg
+    pub fn into_reader(self) -> Result<Box<dyn Read + Send>, GPGError> {
+        match self {
+            InputSource::Path(path) => match File::open(&path) {
+                Ok(file) => return Ok(Box::new(file)),
+                Err(_) => {
+                    return Err(GPGError::new(
+                        GPGErrorType::FileNotFoundError(format!("File do not exist: {}", path.display())),
+                        None,
+                    ));
+                }
+            },
+            InputSource::FileHandle(mut file) => {
+                if let Err(e) = file.rewind() {
+                    return Err(GPGError::new(GPGErrorType::ReadFailError(e.to_string()), None));
+                }
+                return Ok(Box::new(file));
+            }
+            InputSource::Bytes(bytes) => return Ok(Box::new(Cursor::new(bytes))),
+            InputSource::Reader(reader) => return Ok(reader),
+        }
+    }
+
+    // read the whole input into memory
+    pub fn read_all(self) -> Result<Vec<u8>, GPGError> {
+        if let InputSource::Bytes(bytes) = self {
+            return Ok(bytes);
+        }
+        let mut reader: Box<dyn Read + Send> = self.into_reader()?;
+        let mut buffer: Vec<u8> = Vec::new();
+        if let Err(e) = reader.read_to_end(&mut buffer) {
+            return Err(GPGError::new(GPGErrorType::ReadFailError(e.to_string()), None));
+        }
+        return Ok(buffer);
+    }
+
+    // a copy of the input that can be written into stdin again, a Reader will be read into memory
+    // return the input ( as it might have been read into memory ) along with its copy
+    pub fn duplicate(self) -> Result<(InputSource, InputSource), GPGError> {
+        match self {
+            InputSource::Path(path) => return Ok((InputSource::Path(path.clone()), InputSource::Path(path))),
+            InputSource::FileHandle(file) => match file.try_clone() {
+                // both share the same cursor, into_reader will rewind it before it was read
+                Ok(copy) => return Ok((InputSource::FileHandle(file), InputSource::FileHandle(copy))),
+                Err(e) => return Err(GPGError::new(GPGErrorType::ReadFailError(e.to_string()), None)),
+            },
+            InputSource::Bytes(bytes) => return Ok((InputSource::Bytes(bytes.clone()), InputSource::Bytes(bytes))),
+            InputSource::Reader(reader) => match InputSource::Reader(reader).read_all() {
+                Ok(bytes) => return Ok((InputSource::Bytes(bytes.clone()), InputSource::Bytes(bytes))),
+                Err(e) => return Err(e),
+            },
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt::{Display, Formatter};
 
+use super::enums::{GpgExitStatus, Operation, EXIT_CODE_BAD_SIGNATURE, EXIT_CODE_ERROR};
 use super::response::CmdResult;
+use super::utils::redact_args;
 
 #[derive(Debug)]
 pub struct GPGError {
@@ -8,18 +10,178 @@ pub struct GPGError {
     pub error_type: GPGErrorType,
     // provide more insight if error occured during the gpg cmd process
     pub cmd_result: Option<CmdResult>,
+    // which operation and command the error came from, None if the error was raised before gpg was run
+    // ( ex. an invalid argument )
+    pub context: Option<ErrorContext>,
 }
 
+// the gpg command an error came from, to tell which step of a workflow failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorContext {
+    pub operation: Operation,
+    // the full argument vector ( starting with the program ), secret arguments ( ex. --passphrase ) were redacted
+    pub args: Vec<String>,
+    // the homedir passed to gpg with --homedir, None if gpg used its default homedir
+    pub homedir: Option<String>,
+}
+
+// the end of a gpg log line reporting the keyserver ( or dirmngr ) could not be reached for now
+const TRANSIENT_NETWORK_REASONS: [&str; 9] = [
+    "Connection refused",
+    "Connection timed out",
+    "Connection reset by peer",
+    "Network is unreachable",
+    "No route to host",
+    "Host is unreachable",
+    "Server indicated a failure",
+    "Try again later",
+    "Operation timed out",
+];
+// a gpg log line reporting the agent could not be reached for now ( ex. it was being restarted )
+const TRANSIENT_AGENT_LOGS: [&str; 3] = [
+    "can't connect to the agent",
+    "problem with the agent",
+    "Resource temporarily unavailable",
+];
+
 #[doc(hidden)]
 impl GPGError {
     pub fn new(error_type: GPGErrorType, cmd_result: Option<CmdResult>) -> GPGError {
+        // a result from a gpg process already know the command it was run with
+        let context: Option<ErrorContext> = cmd_result
+            .as_ref()
+            .filter(|result| !result.args.is_empty())
+            .map(|result| ErrorContext::new(result.operation.clone(), &result.args));
         return GPGError {
             error_type,
             cmd_result,
+            context,
+        };
+    }
+
+    // attach the command the error came from, unless the error already has one
+    pub fn with_context(mut self, ops: Operation, args: &[String]) -> GPGError {
+        if self.context.is_none() {
+            self.context = Some(ErrorContext::new(ops, args));
+        }
+        return self;
+    }
+}
+
+#[doc(hidden)]
+impl ErrorContext {
+    pub fn new(ops: Operation, args: &[String]) -> ErrorContext {
+        let homedir: Option<String> = args
+            .iter()
+            .position(|arg| arg == "--homedir")
+            .and_then(|p| args.get(p + 1))
+            .cloned();
+        return ErrorContext {
+            operation: ops,
+            args: redact_args(args),
+            homedir: homedir,
         };
     }
 }
 
+impl GPGError {
+    // the category of the error, a failure of gpg to reach the keyserver ( or dirmngr ) was Network
+    // whatever the type was, as gpg report it like any other failure
+    pub fn category(&self) -> GPGErrorCategory {
+        if self.get_network_log().is_some() {
+            return GPGErrorCategory::Network;
+        }
+        return self.error_type.category();
+    }
+
+    // whether the same operation may succeed if it was run again later: the keyserver or the agent could not be
+    // reached, gpg timed out, or the homedir was locked by another process
+    // NOTE: a bad passphrase, a missing key or an invalid argument will never be retryable
+    pub fn is_retryable(&self) -> bool {
+        if self.is_user_error() {
+            return false;
+        }
+        if let Some(log) = self.get_network_log() {
+            return TRANSIENT_NETWORK_REASONS.iter().any(|reason| log.contains(reason));
+        }
+        match &self.error_type {
+            GPGErrorType::TimeoutError(_) | GPGErrorType::HomedirLockError(_) => return true,
+            // a stale lock will be there until it was recovered
+            GPGErrorType::HomedirLocked(err) => return !err.contains("no longer running"),
+            GPGErrorType::GPGProcessError(_) | GPGErrorType::FailedToStartProcess(_) => {
+                return self
+                    .get_debug_log()
+                    .iter()
+                    .any(|log| TRANSIENT_AGENT_LOGS.iter().any(|agent_log| log.contains(agent_log)));
+            }
+            _ => return false,
+        }
+    }
+
+    // whether the error was caused by what the caller provided ( ex. a bad passphrase, an invalid argument )
+    // and should be reported to the user instead of being retried
+    pub fn is_user_error(&self) -> bool {
+        if self.error_type.category() == GPGErrorCategory::Usage {
+            return true;
+        }
+        if matches!(self.error_type, GPGErrorType::PassphraseError(_)) {
+            return true;
+        }
+        let raw_data: String = self
+            .cmd_result
+            .as_ref()
+            .and_then(|result| result.raw_data.clone())
+            .unwrap_or_default();
+        return raw_data.contains("[GNUPG:] BAD_PASSPHRASE")
+            || self
+                .get_debug_log()
+                .iter()
+                .any(|log| log.contains("Bad passphrase") || log.contains("No passphrase given"));
+    }
+
+    fn get_debug_log(&self) -> Vec<String> {
+        return self
+            .cmd_result
+            .as_ref()
+            .and_then(|result| result.debug_log.clone())
+            .unwrap_or_default();
+    }
+
+    // the exit code for a command line tool to exit with, the same as gpg ( or gpgv ) exited with if it was run,
+    // 1 for a bad signature and 2 for any other error ( ex. an invalid argument, gpg was killed or timed out )
+    pub fn exit_code(&self) -> i32 {
+        let exit_status: Option<GpgExitStatus> = self.cmd_result.as_ref().map(|result| result.exit_status());
+        match exit_status {
+            Some(GpgExitStatus::BadSignature) => return EXIT_CODE_BAD_SIGNATURE,
+            Some(GpgExitStatus::Error(code)) if code > 0 => return code,
+            _ => return EXIT_CODE_ERROR,
+        }
+    }
+
+    // ex) keyserver receive failed: Connection refused
+    fn get_network_log(&self) -> Option<String> {
+        return self.get_debug_log().into_iter().find(|log| {
+            let log: String = log.to_lowercase();
+            return (log.contains("keyserver") || log.contains("dirmngr")) && (log.contains("failed") || log.starts_with("error"));
+        });
+    }
+}
+
+// a coarse grouping of GPGErrorType, to decide how to handle an error without matching every type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GPGErrorCategory {
+    // gpg, the homedir or the environment was not set up as needed ( ex. gpg not found, unsupported version )
+    Configuration,
+    // what the caller provided was invalid ( ex. an invalid argument, a file or user id that does not exist )
+    Usage,
+    // the key or passphrase could not be used ( ex. bad passphrase, revoked key, weak algorithm )
+    Crypto,
+    // running gpg failed ( ex. gpg exited with an error, timed out, or the homedir was locked )
+    Process,
+    // gpg could not reach the keyserver ( or dirmngr )
+    Network,
+}
+
 #[derive(Debug)]
 pub enum GPGErrorType {
     HomedirError(String),
@@ -34,9 +196,72 @@ pub enum GPGErrorType {
     ReadFailError(String),
     PassphraseError(String),
     KeyNotSubkey(String),
+    UidNotFound(String),
+    UnexpectedPromptError(String),
     InvalidReasonCode(String),
     FileNotFoundError(String),
     FileNotProvidedError(String),
+    KeyUnusableError(String),
+    CardError(String),
+    UnsupportedVersion(String),
+    KeychainError(String),
+    HomedirLockError(String),
+    ReadOnlyError(String),
+    PoolError(String),
+    TimeoutError(String),
+    WeakCryptoError(String),
+    NoUsableKeyError(String),
+    AmbiguousKeyError(String),
+    HomedirLocked(String),
+    CancelledError(String),
+    UnresolvedRecipients(Vec<UnresolvedRecipient>),
+}
+
+// an email encrypt_for_emails could not resolve to exactly one key to encrypt to
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnresolvedRecipient {
+    // email: the email of the recipient
+    pub email: String,
+    // candidates: fingerprint of the usable keys, empty if there was no usable key, more than one if it was ambiguous
+    pub candidates: Vec<String>,
+}
+
+impl GPGErrorType {
+    pub fn category(&self) -> GPGErrorCategory {
+        match self {
+            GPGErrorType::HomedirError(_)
+            | GPGErrorType::OutputDirError(_)
+            | GPGErrorType::GPGInitError(_)
+            | GPGErrorType::GPGNotFoundError(_)
+            | GPGErrorType::UnsupportedVersion(_)
+            | GPGErrorType::KeychainError(_)
+            | GPGErrorType::ReadOnlyError(_)
+            | GPGErrorType::PoolError(_) => return GPGErrorCategory::Configuration,
+            GPGErrorType::InvalidArgumentError(_)
+            | GPGErrorType::KeyNotSubkey(_)
+            | GPGErrorType::UidNotFound(_)
+            | GPGErrorType::InvalidReasonCode(_)
+            | GPGErrorType::FileNotFoundError(_)
+            | GPGErrorType::FileNotProvidedError(_)
+            | GPGErrorType::AmbiguousKeyError(_) => return GPGErrorCategory::Usage,
+            GPGErrorType::PassphraseError(_)
+            | GPGErrorType::KeyUnusableError(_)
+            | GPGErrorType::CardError(_)
+            | GPGErrorType::WeakCryptoError(_)
+            | GPGErrorType::NoUsableKeyError(_)
+            | GPGErrorType::UnresolvedRecipients(_) => return GPGErrorCategory::Crypto,
+            GPGErrorType::GPGProcessError(_)
+            | GPGErrorType::FailedToStartProcess(_)
+            | GPGErrorType::FailedToRetrieveChildProcess(_)
+            | GPGErrorType::WriteFailError(_)
+            | GPGErrorType::ReadFailError(_)
+            | GPGErrorType::UnexpectedPromptError(_)
+            | GPGErrorType::HomedirLockError(_)
+            | GPGErrorType::TimeoutError(_)
+            | GPGErrorType::HomedirLocked(_)
+            | GPGErrorType::CancelledError(_) => return GPGErrorCategory::Process,
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -57,9 +282,34 @@ impl Display for GPGErrorType {
             GPGErrorType::ReadFailError(err) => write!(f, "[ReadFailError] {}", err),
             GPGErrorType::PassphraseError(err) => write!(f, "[PassphraseError] {}", err),
             GPGErrorType::KeyNotSubkey(err) => write!(f, "[KeyNotSubkey] {}", err),
+            GPGErrorType::UidNotFound(err) => write!(f, "[UidNotFound] {}", err),
+            GPGErrorType::UnexpectedPromptError(err) => write!(f, "[UnexpectedPromptError] {}", err),
             GPGErrorType::InvalidReasonCode(err) => write!(f, "[InvalidReasonCode] {}", err),
             GPGErrorType::FileNotFoundError(err) => write!(f, "[FileNotFoundError] {}", err),
             GPGErrorType::FileNotProvidedError(err) => write!(f, "[FileNotProvidedError] {}", err),
+            GPGErrorType::KeyUnusableError(err) => write!(f, "[KeyUnusableError] {}", err),
+            GPGErrorType::CardError(err) => write!(f, "[CardError] {}", err),
+            GPGErrorType::UnsupportedVersion(err) => write!(f, "[UnsupportedVersion] {}", err),
+            GPGErrorType::KeychainError(err) => write!(f, "[KeychainError] {}", err),
+            GPGErrorType::HomedirLockError(err) => write!(f, "[HomedirLockError] {}", err),
+            GPGErrorType::ReadOnlyError(err) => write!(f, "[ReadOnlyError] {}", err),
+            GPGErrorType::PoolError(err) => write!(f, "[PoolError] {}", err),
+            GPGErrorType::TimeoutError(err) => write!(f, "[TimeoutError] {}", err),
+            GPGErrorType::WeakCryptoError(err) => write!(f, "[WeakCryptoError] {}", err),
+            GPGErrorType::NoUsableKeyError(err) => write!(f, "[NoUsableKeyError] {}", err),
+            GPGErrorType::AmbiguousKeyError(err) => write!(f, "[AmbiguousKeyError] {}", err),
+            GPGErrorType::HomedirLocked(err) => write!(f, "[HomedirLocked] {}", err),
+            GPGErrorType::CancelledError(err) => write!(f, "[CancelledError] {}", err),
+            GPGErrorType::UnresolvedRecipients(recipients) => {
+                let reasons: Vec<String> = recipients
+                    .iter()
+                    .map(|r| match r.candidates.len() {
+                        0 => format!("{}: no usable key", r.email),
+                        n => format!("{}: {} usable keys ( {} )", r.email, n, r.candidates.join(", ")),
+                    })
+                    .collect();
+                write!(f, "[UnresolvedRecipients] {}", reasons.join("; "))
+            }
         }
     }
 }
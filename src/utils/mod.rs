@@ -1,5 +1,8 @@
 pub mod enums;
 pub mod errors;
 pub mod response;
+pub mod secret;
+// crab_gnupg::utils::utils was a public path since the first release
 #[doc(hidden)]
+#[allow(clippy::module_inception)]
 pub mod utils;
@@ -1,6 +1,28 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Lines, Write},
+    path::{Path, PathBuf},
+    process::ChildStdout,
+    time::{Duration, Instant, SystemTime},
+};
 
-use super::enums::{DeleteProblem, Operation};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use super::enums::{
+    CardProblem, CipherAlgo, DeleteProblem, DigestAlgo, GpgExitStatus, GpgWarning, KeyKind, Operation, TrustLevel, EXIT_CODE_BAD_SIGNATURE,
+    EXIT_CODE_ERROR, EXIT_CODE_SUCCESS,
+};
+use super::errors::{GPGError, GPGErrorType};
+use super::secret::Passphrase;
+use crate::process::CmdStream;
+use crate::runner::LocalProcessRecorder;
+use crate::status::{parse_status_lines, split_status_line, StatusLine};
+use super::utils::LIST_KEY_KEYWORDS;
+#[cfg(feature = "test_util")]
+use super::utils::{decode_list_key_result, get_gpg_capabilities};
 
 //*******************************************************
 
@@ -19,6 +41,22 @@ pub struct CmdResult {
     pub debug_log: Option<Vec<String>>,
     pub problem: Option<Vec<HashMap<String, String>>>,
     pub success: bool,
+    // path to the file holding the output of gpg if it was larger than the memory limit of the runner,
+    // the output will not be in raw_data then. The file is owned by the caller, who should remove it once it was
+    // no longer needed ( it may hold plaintext ), it was already removed if the operation returned an error
+    pub output_file: Option<String>,
+    // warnings gpg reported ( ex. untrusted key, legacy cipher ), the operation may still have succeeded
+    pub warnings: Vec<GpgWarning>,
+    // how the data was encrypted, only for decryption, check DecryptionInfo
+    pub decryption_info: Option<DecryptionInfo>,
+    // the signature(s) made, one for each signing key, only for signing ( including encrypt with sign ), check SignResult
+    pub signatures: Vec<SignResult>,
+    // the arguments gpg was run with ( starting with the program ), secret arguments were redacted
+    // empty if the result was not built from a gpg process
+    pub args: Vec<String>,
+    // the output file gpg wrote ( ex. the encrypted file ), None if the output was not written to a file
+    // ( ex. to a writer ) or the operation does not have one, check OutputInfo
+    pub output_info: Option<OutputInfo>,
 }
 
 #[doc(hidden)]
@@ -33,14 +71,19 @@ impl CmdResult {
             debug_log: None,
             problem: None,
             success: true,
+            output_file: None,
+            warnings: vec![],
+            decryption_info: None,
+            signatures: vec![],
+            args: vec![],
+            output_info: None,
         }
     }
 
     pub fn set_raw_data(&mut self, raw_data: String) {
-        if self.raw_data.is_none() {
-            self.raw_data = Some(raw_data);
-        } else {
-            self.raw_data.as_mut().unwrap().push_str(&raw_data);
+        match self.raw_data.as_mut() {
+            Some(data) => data.push_str(&raw_data),
+            None => self.raw_data = Some(raw_data),
         }
     }
 
@@ -51,17 +94,24 @@ impl CmdResult {
     pub fn handle_status(&mut self, keyword: &str, value: String) {
         self.status = Some(keyword.to_string());
         self.status_message = Some(value.to_string());
+        if let Some(warning) = GpgWarning::from_status(keyword, &value) {
+            self.add_warning(warning);
+        }
+        if keyword == "DECRYPTION_INFO" || keyword == "SESSION_KEY" {
+            self.decryption_info.get_or_insert_with(DecryptionInfo::init).handle_status(keyword, &value);
+        }
+        if keyword == "SIG_CREATED" {
+            if let Some(signature) = SignResult::from_status(&value) {
+                self.signatures.push(signature);
+            }
+        }
 
         if keyword == "FAILURE" {
             // for export secret key, there can be failure at the end if there are 1 or more key no exported due to passphrase
             // in this case if there are any key that exported even just partially, we should still consider it as success
             // for it to not export anything, there will be gpg: WARNING: nothing exported in the output
             if self.operation == Operation::ExportSecretKey {
-                if self.raw_data.as_ref().unwrap().contains("WARNING: nothing exported") {
-                    self.success = false;
-                } else {
-                    self.success = true;
-                }
+                self.success = !self.raw_data.as_ref().unwrap().contains("WARNING: nothing exported");
             } else{
                 self.success = false;
             }
@@ -73,11 +123,7 @@ impl CmdResult {
             problem.insert("status".to_string(), self.status.as_ref().unwrap().clone());
             problem.insert("key_id".to_string(), values[0].to_string());
             problem.insert("username".to_string(), values[1].to_string());
-            if self.problem.is_none() {
-                self.problem = Some(vec![problem]);
-            } else {
-                self.problem.as_mut().unwrap().push(problem);
-            }
+            self.problem.get_or_insert_with(Vec::new).push(problem);
         } else if keyword == "NODATA" {
             if self.raw_data.as_ref().unwrap().contains("no valid OpenPGP data found") {
                 self.success = false;
@@ -85,62 +131,111 @@ impl CmdResult {
         } else if keyword == "DELETE_PROBLEM" {
             let mut problem: HashMap<String, String> = HashMap::new();
             problem.insert("delete_problem".to_string(), DeleteProblem::from_str(value.as_str()));
-            if self.problem.is_none() {
-                self.problem = Some(vec![problem]);
-            } else {
-                self.problem.as_mut().unwrap().push(problem);
+            self.problem.get_or_insert_with(Vec::new).push(problem);
+            self.success = false;
+        } else if keyword == "CARDCTRL" {
+            let code: &str = value.split_whitespace().next().unwrap_or("");
+            if let Some(card_problem) = CardProblem::from_str(code) {
+                let mut problem: HashMap<String, String> = HashMap::new();
+                problem.insert("card".to_string(), card_problem);
+                self.problem.get_or_insert_with(Vec::new).push(problem);
+                self.success = false;
             }
+        } else if keyword == "SC_OP_FAILURE" {
             self.success = false;
+            let mut problem: HashMap<String, String> = HashMap::new();
+            problem.insert("card".to_string(), format!("smartcard operation failed {}", value).trim().to_string());
+            self.problem.get_or_insert_with(Vec::new).push(problem);
         } else if keyword == "UNKNOWN_KEYWORD" {
             self.success = false;
             let mut problem: HashMap<String, String> = HashMap::new();
             problem.insert("unknown_keyword".to_string(), value);
-            if self.problem.is_none() {
-                self.problem = Some(vec![problem]);
-            } else {
-                self.problem.as_mut().unwrap().push(problem);
-            }
+            self.problem.get_or_insert_with(Vec::new).push(problem);
         } else if keyword == "NO_PASSPHRASE" {
             if !self.raw_data.as_ref().unwrap().contains("No passphrase given - skipped") {
                 self.success = false;
                 let mut problem: HashMap<String, String> = HashMap::new();
                 problem.insert("passphrase".to_string(), value);
-                if self.problem.is_none() {
-                    self.problem = Some(vec![problem]);
-                } else {
-                    self.problem.as_mut().unwrap().push(problem);
-                }
+                self.problem.get_or_insert_with(Vec::new).push(problem);
             }
         } else if keyword == "INVALID_FINGERPRINT" {
             self.success = false;
             let mut problem: HashMap<String, String> = HashMap::new();
             problem.insert("fingerprint".to_string(), value);
-            if self.problem.is_none() {
-                self.problem = Some(vec![problem]);
-            } else {
-                self.problem.as_mut().unwrap().push(problem);
-            }
+            self.problem.get_or_insert_with(Vec::new).push(problem);
         } else if keyword == "BAD_PASSPHRASE" {
             self.success = false;
             let mut problem: HashMap<String, String> = HashMap::new();
             problem.insert("passphrase".to_string(), value);
-            if self.problem.is_none() {
-                self.problem = Some(vec![problem]);
-            } else {
-                self.problem.as_mut().unwrap().push(problem);
+            self.problem.get_or_insert_with(Vec::new).push(problem);
+        }
+    }
+
+    // handle a single line from stderr, either a status line or a debug log
+    pub fn handle_response_line(&mut self, response_line_string: &str) {
+        if response_line_string.len() >= 9 {
+            if let Some((keyword, value)) = split_status_line(response_line_string) {
+                self.handle_status(keyword, value.to_string());
+            } else if response_line_string.starts_with("gpg: ") || response_line_string.starts_with("gpgv: ") {
+                let debug = &response_line_string[response_line_string.find(": ").unwrap() + 2..];
+                if debug.contains("unknown keyword") {
+                    self.handle_status("UNKNOWN_KEYWORD", "unknown keyword".to_string());
+                } else if debug.contains("No passphrase given") {
+                    self.handle_status("NO_PASSPHRASE", "No passphrase given".to_string());
+                } else if debug.contains("invalid fingerprint") {
+                    self.handle_status("INVALID_FINGERPRINT", "invalid fingerprint".to_string());
+                } else if debug.contains("Bad passphrase") {
+                    self.handle_status("BAD_PASSPHRASE", "bad passphrase".to_string());
+                }
+                if let Some(warning) = GpgWarning::from_log(debug) {
+                    self.add_warning(warning);
+                }
+                self.capture_debug_log(debug.to_string());
+            } else if !response_line_string.trim().is_empty() {
+                // anything else gpg wrote ( ex. a hexdump of a debug log ) was kept as it was
+                self.capture_debug_log(response_line_string.to_string());
             }
         }
     }
 
     pub fn set_return_code(&mut self, return_code: i32) {
         self.return_code = Some(return_code);
+        match self.exit_status() {
+            GpgExitStatus::Success | GpgExitStatus::Unknown => {}
+            GpgExitStatus::BadSignature => {
+                self.success = false;
+                if self.status_message.is_none() {
+                    self.status_message = Some("bad signature".to_string());
+                }
+            }
+            GpgExitStatus::Error(code) => {
+                // same as the FAILURE status, a secret key export that exported anything is still a success
+                if self.operation == Operation::ExportSecretKey
+                    && self.raw_data.as_ref().is_some_and(|r| !r.contains("WARNING: nothing exported"))
+                {
+                    return;
+                }
+                self.success = false;
+                if self.status_message.is_none() {
+                    self.status_message = Some(format!("gpg exited with code {}", code));
+                }
+            }
+        }
+    }
+
+    // what the exit code mean for the operation, check GpgExitStatus
+    pub fn exit_status(&self) -> GpgExitStatus {
+        return self.operation.exit_status(self.return_code);
     }
 
     pub fn capture_debug_log(&mut self, debug_log: String) {
-        if self.debug_log.is_none() {
-            self.debug_log = Some(vec![debug_log]);
-        } else {
-            self.debug_log.as_mut().unwrap().push(debug_log);
+        self.debug_log.get_or_insert_with(Vec::new).push(debug_log);
+    }
+
+    // the same warning reported by several status or log lines was only kept once
+    pub fn add_warning(&mut self, warning: GpgWarning) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
         }
     }
 
@@ -148,6 +243,11 @@ impl CmdResult {
         return self.success;
     }
 
+    // every status line gpg reported for the operation, check StatusLine
+    pub fn status_lines(&self) -> Vec<StatusLine> {
+        return parse_status_lines(self.raw_data.as_deref().unwrap_or(""));
+    }
+
     pub fn get_error_message(&mut self) -> String {
         return self
             .status_message
@@ -158,22 +258,633 @@ impl CmdResult {
 
     pub fn clone_cmd_info(&mut self, cmd_result: &CmdResult) {
         self.raw_data = cmd_result.raw_data.clone();
-        self.return_code = cmd_result.return_code.clone();
+        self.return_code = cmd_result.return_code;
         self.status = cmd_result.status.clone();
         self.status_message = cmd_result.status_message.clone();
         self.operation = cmd_result.operation.clone();
         self.debug_log = cmd_result.debug_log.clone();
         self.problem = cmd_result.problem.clone();
         self.success = cmd_result.success;
+        self.output_file = cmd_result.output_file.clone();
+        self.warnings = cmd_result.warnings.clone();
+        self.decryption_info = cmd_result.decryption_info.clone();
+        self.signatures = cmd_result.signatures.clone();
+        self.args = cmd_result.args.clone();
+        self.output_info = cmd_result.output_info.clone();
+    }
+}
+
+// constructor for application to build a CmdResult in their own unit test
+#[cfg(feature = "test_util")]
+impl CmdResult {
+    // build a CmdResult the same way as it would be built from a gpg process
+    pub fn from_output(ops: Operation, output: &str, response: &str, return_code: i32) -> CmdResult {
+        // ops: the operation the result was for
+        // output: what gpg wrote to stdout ( ex. --with-colons listing )
+        // response: what gpg wrote to stderr ( ex. [GNUPG:] GOODSIG ... status lines and gpg: ... logs )
+        // return_code: exit code of gpg
+
+        let mut result: CmdResult = CmdResult::init(ops);
+        result.set_raw_data(output.to_string());
+        result.set_raw_data(response.to_string());
+        for response_line_string in response.split("\n") {
+            result.handle_response_line(response_line_string);
+        }
+        result.set_return_code(return_code);
+        return result;
+    }
+}
+
+//*******************************************************
+
+//            RELATED TO GPG CAPABILITIES
+
+//*******************************************************
+// algorithms supported by the installed gpg, retrieved from --list-config
+#[derive(Debug, Clone)]
+pub struct GPGCapabilities {
+    // pubkey: name of supported public key algorithm(s) ex) RSA, DSA, EDDSA
+    pub pubkey: Vec<String>,
+    // cipher: name of supported cipher algorithm(s) ex) AES256, TWOFISH
+    pub cipher: Vec<String>,
+    // digest: name of supported digest algorithm(s) ex) SHA256, SHA512
+    pub digest: Vec<String>,
+    // compress: name of supported compression algorithm(s) ex) ZIP, ZLIB
+    pub compress: Vec<String>,
+    // curve: name of supported curve(s) ex) cv25519, ed25519, nistp256
+    pub curve: Vec<String>,
+}
+
+impl GPGCapabilities {
+    #[doc(hidden)]
+    pub fn init() -> GPGCapabilities {
+        return GPGCapabilities {
+            pubkey: Vec::new(),
+            cipher: Vec::new(),
+            digest: Vec::new(),
+            compress: Vec::new(),
+            curve: Vec::new(),
+        };
+    }
+
+    // NOTE: if gpg did not report the list ( empty list ), every algorithm will be treated as supported
+    pub fn supports_pubkey(&self, algo: &str) -> bool {
+        return is_algo_in_list(&self.pubkey, algo);
+    }
+
+    pub fn supports_cipher(&self, algo: &str) -> bool {
+        return is_algo_in_list(&self.cipher, algo);
+    }
+
+    pub fn supports_digest(&self, algo: &str) -> bool {
+        return is_algo_in_list(&self.digest, algo);
+    }
+
+    pub fn supports_compress(&self, algo: &str) -> bool {
+        return is_algo_in_list(&self.compress, algo);
+    }
+
+    pub fn supports_curve(&self, curve: &str) -> bool {
+        return is_algo_in_list(&self.curve, curve);
+    }
+}
+
+fn is_algo_in_list(list: &[String], algo: &str) -> bool {
+    return list.is_empty() || list.iter().any(|a| a.eq_ignore_ascii_case(algo.trim()));
+}
+
+// constructor for application to build a GPGCapabilities in their own unit test
+#[cfg(feature = "test_util")]
+impl GPGCapabilities {
+    // build from the output of gpg --list-config --with-colons ex) cfg:digestname:SHA256;SHA512
+    pub fn from_list_config(list_config: &str) -> GPGCapabilities {
+        return get_gpg_capabilities(&CmdResult::from_output(Operation::Verify, list_config, "", 0));
+    }
+}
+
+//*******************************************************
+
+//                RELATED TO GPG CONFIG
+
+//*******************************************************
+// the configuration reported by gpg --list-config for the homedir
+#[derive(Debug, Clone)]
+pub struct GpgConfig {
+    // version: full version of gpg ex) 2.4.6
+    pub version: String,
+    // capabilities: name of supported algorithm(s) and curve(s), in the same order as the id(s) below
+    pub capabilities: GPGCapabilities,
+    // pubkey_ids: OpenPGP id of supported public key algorithm(s) ex) 1 ( RSA ), 22 ( EDDSA )
+    pub pubkey_ids: Vec<u8>,
+    // cipher_ids: OpenPGP id of supported cipher algorithm(s) ex) 9 ( AES256 )
+    pub cipher_ids: Vec<u8>,
+    // digest_ids: OpenPGP id of supported digest algorithm(s) ex) 8 ( SHA256 )
+    pub digest_ids: Vec<u8>,
+    // compress_ids: OpenPGP id of supported compression algorithm(s) ex) 1 ( ZIP )
+    pub compress_ids: Vec<u8>,
+    // groups: group defined in gpg.conf ( group <name>=<key> ... ), a recipient of the name will be expanded to the keys
+    pub groups: HashMap<String, Vec<String>>,
+    // other: any other config line gpg reported, keyed by its name with the raw value
+    pub other: HashMap<String, String>,
+}
+
+impl GpgConfig {
+    #[doc(hidden)]
+    pub fn init() -> GpgConfig {
+        return GpgConfig {
+            version: "0.0.0".to_string(),
+            capabilities: GPGCapabilities::init(),
+            pubkey_ids: Vec::new(),
+            cipher_ids: Vec::new(),
+            digest_ids: Vec::new(),
+            compress_ids: Vec::new(),
+            groups: HashMap::new(),
+            other: HashMap::new(),
+        };
+    }
+
+    // the keys a group expand to, None if the group was not defined
+    pub fn group(&self, name: &str) -> Option<&Vec<String>> {
+        return self.groups.get(name);
+    }
+}
+
+//*******************************************************
+
+//              RELATED TO HOMEDIR MAINTENANCE
+
+//*******************************************************
+// a lock file gpg created in the homedir ( ex. pubring.kbx.lock ) while it was writing to the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpgLock {
+    // path: the lock file
+    pub path: PathBuf,
+    // pid: the process holding the lock, None if the lock file could not be read
+    pub pid: Option<u32>,
+    // host: the host the process was running on, None if gpg did not record it
+    pub host: Option<String>,
+    // stale: whether it was created on this host by a process that was no longer running, check GPG.recover_locks
+    pub stale: bool,
+}
+
+// a result of the housekeeping of the homedir, check GPG.maintain
+#[derive(Debug, Clone)]
+pub struct MaintenanceResult {
+    // removed_locks: lock file(s) left by a gpg process that was no longer running, which were removed
+    pub removed_locks: Vec<PathBuf>,
+    // keybox_size_before: size of pubring.kbx ( in bytes ) before the maintenance, None if there was no keybox
+    pub keybox_size_before: Option<u64>,
+    // keybox_size_after: size of pubring.kbx ( in bytes ) after the maintenance, smaller if gpg compressed it
+    pub keybox_size_after: Option<u64>,
+    // trustdb: the result of gpg --check-trustdb
+    pub trustdb: CmdResult,
+}
+
+// the output file an operation wrote, so it can be checked and logged without reading its metadata again
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputInfo {
+    // path: the output file
+    pub path: PathBuf,
+    // size: size of the file in bytes
+    pub size: u64,
+    // created: when the file was created, the last modification time if the filesystem does not record it
+    pub created: SystemTime,
+    // checksum: the digest of the file in hex computed by gpg ( --print-md ), None unless GPG.output_checksum was set
+    pub checksum: Option<String>,
+    // checksum_algo: the digest algorithm of the checksum
+    pub checksum_algo: Option<DigestAlgo>,
+}
+
+#[doc(hidden)]
+impl OutputInfo {
+    // the size and creation time of the file, None if its metadata could not be read ( ex. it was not written )
+    pub fn from_path(path: &Path) -> Option<OutputInfo> {
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => return None,
+        };
+        if !metadata.is_file() {
+            return None;
+        }
+        let created: SystemTime = metadata
+            .created()
+            .or(metadata.modified())
+            .unwrap_or(SystemTime::now());
+        return Some(OutputInfo {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            created: created,
+            checksum: None,
+            checksum_algo: None,
+        });
     }
 }
 
+// an output the crate created in the output_dir with a timestamped name ( ex. decrypted_file_<datetime>.txt ),
+// check GPG.clean_output_dir
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagedOutput {
+    // path: the output file
+    pub path: PathBuf,
+    // size: size of the file in bytes
+    pub size: u64,
+    // modified: when the file was last written to
+    pub modified: SystemTime,
+}
+
+// a result of applying the retention policy to the output_dir, check GPG.clean_output_dir
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputCleanupResult {
+    // removed: output(s) older than the max age, or the oldest one(s) over the max total size, which were removed
+    pub removed: Vec<PathBuf>,
+    // freed_bytes: total size of the removed output(s) in bytes
+    pub freed_bytes: u64,
+    // kept: number of output(s) left in the output_dir
+    pub kept: usize,
+    // kept_bytes: total size of the output(s) left in bytes
+    pub kept_bytes: u64,
+}
+
+//*******************************************************
+
+//                RELATED TO AGENT RESPONSE
+
+//*******************************************************
+// a response of gpg-agent to an Assuan command, check GPG.agent_command
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentResponse {
+    // ok: whether the agent answered OK, false if it answered ERR
+    pub ok: bool,
+    // data: the data lines ( D ) joined together, with the percent escaping ( ex. %0A ) decoded
+    pub data: String,
+    // status: the status lines ( S ) without the S ex) KEYINFO <keygrip> D - - - C - - -
+    pub status: Vec<String>,
+    // error_code: the gpg error code of ERR ex) 67109139
+    pub error_code: Option<u32>,
+    // error: the description of ERR ex) Unknown IPC command <GPG Agent>
+    pub error: Option<String>,
+}
+
+#[doc(hidden)]
+impl AgentResponse {
+    // None if the agent did not answer with OK or ERR ( ex. it could not be reached )
+    pub fn from_output(output: &str) -> Option<AgentResponse> {
+        let mut response: AgentResponse = AgentResponse {
+            ok: false,
+            data: String::new(),
+            status: vec![],
+            error_code: None,
+            error: None,
+        };
+        for line in output.lines() {
+            if let Some(data) = line.strip_prefix("D ") {
+                response.data.push_str(&percent_decode(data));
+            } else if let Some(status) = line.strip_prefix("S ") {
+                response.status.push(status.to_string());
+            } else if line == "OK" || line.starts_with("OK ") {
+                response.ok = true;
+                return Some(response);
+            } else if let Some(error) = line.strip_prefix("ERR ") {
+                let (code, description): (&str, &str) = error.split_once(' ').unwrap_or((error, ""));
+                response.error_code = code.parse::<u32>().ok();
+                response.error = Some(description.to_string());
+                return Some(response);
+            }
+        }
+        return None;
+    }
+}
+
+// decode the %XX escaping of Assuan data ex) a%25b -> a%b
+fn percent_decode(data: &str) -> String {
+    let mut decoded: Vec<u8> = Vec::with_capacity(data.len());
+    let bytes: &[u8] = data.as_bytes();
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        let escaped: Option<u8> = if bytes[i] == b'%' && i + 3 <= bytes.len() {
+            std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    return String::from_utf8_lossy(&decoded).to_string();
+}
+
+// a key gpg-agent served over its ssh socket ( listed in sshcontrol ), check GPG.list_ssh_keys
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshKey {
+    // keygrip: keygrip of the key in the agent
+    pub keygrip: String,
+    // ssh_fingerprint: fingerprint shown by ssh ex) SHA256:Ck5DRH097rnW90VKg57u8OWh3+mvzUjDGMuPJVNR/sg
+    pub ssh_fingerprint: String,
+    // fingerprint: fingerprint of the gpg ( sub ) key with the keygrip, None if it was not in the keyring
+    pub fingerprint: Option<String>,
+    // cached: whether the passphrase of the key was cached
+    pub cached: bool,
+    // protected: whether the key was protected by a passphrase, false for a key without passphrase or on a card
+    pub protected: bool,
+}
+
+#[doc(hidden)]
+impl SshKey {
+    pub fn from_keyinfo(keyinfo: &str) -> Option<SshKey> {
+        // keyinfo: a status line of keyinfo --ssh-list --ssh-fpr
+        //   ex) KEYINFO <keygrip> <type> <serialno> <idstr> <cached> <protection> <ssh fingerprint> <ttl> <flags>
+
+        let fields: Vec<&str> = keyinfo.split_whitespace().collect();
+        if fields.len() < 8 || fields[0] != "KEYINFO" {
+            return None;
+        }
+        return Some(SshKey {
+            keygrip: fields[1].to_string(),
+            ssh_fingerprint: fields[7].to_string(),
+            fingerprint: None,
+            cached: fields[5] == "1",
+            protected: fields[6] == "P",
+        });
+    }
+}
+
+//*******************************************************
+
+//                RELATED TO HEALTH CHECK
+
+//*******************************************************
+// a report of whether gpg was ready to serve the operations, check GPG.health_check
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    // healthy: whether every check passed
+    pub healthy: bool,
+    // binary: gpg could be run, with its version
+    pub binary: HealthCheck,
+    // homedir: a file could be created in the homedir ( not checked if GPG was read only )
+    pub homedir: HealthCheck,
+    // agent: gpg-agent responded to getinfo version, with its version
+    pub agent: HealthCheck,
+    // keyring: the keys could be listed, with the number of keys
+    pub keyring: HealthCheck,
+}
+
+// a result of one check of the HealthReport
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthCheck {
+    pub passed: bool,
+    // message: what was found ( ex. the version ) or why the check failed
+    pub message: String,
+    // duration: how long the check took
+    pub duration: Duration,
+}
+
+#[doc(hidden)]
+impl HealthCheck {
+    pub fn run(check: impl FnOnce() -> Result<String, GPGError>) -> HealthCheck {
+        let started: Instant = Instant::now();
+        let result: Result<String, GPGError> = check();
+        return HealthCheck {
+            passed: result.is_ok(),
+            message: match result {
+                Ok(message) => message,
+                Err(e) => e.error_type.to_string(),
+            },
+            duration: started.elapsed(),
+        };
+    }
+}
+
+#[doc(hidden)]
+impl HealthReport {
+    pub fn new(binary: HealthCheck, homedir: HealthCheck, agent: HealthCheck, keyring: HealthCheck) -> HealthReport {
+        return HealthReport {
+            healthy: binary.passed && homedir.passed && agent.passed && keyring.passed,
+            binary: binary,
+            homedir: homedir,
+            agent: agent,
+            keyring: keyring,
+        };
+    }
+}
+
+//*******************************************************
+
+//              RELATED TO DANE RECORD
+
+//*******************************************************
+// a DNS OPENPGPKEY record ( RFC 7929 ) publishing a key for the email of one of its uid, check GPG.dane_records
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaneRecord {
+    // fingerprint: fingerprint of the key published
+    pub fingerprint: String,
+    // uid: the uid the record was for ex) Joe <joe@example.com>
+    pub uid: String,
+    // domain: domain of the email of the uid ex) example.com
+    pub domain: String,
+    // name: owner name of the record, the hashed local part of the email under _openpgpkey of the domain
+    //   ex) 78675cc176081372c43abab3ea9fb70c74381eb02dc6e93fb6d44d16._openpgpkey.example.com.
+    pub name: String,
+    // data: the key published, as the binary OpenPGP key ( rdata of the record )
+    pub data: Vec<u8>,
+}
+
+impl DaneRecord {
+    // the record as a line of a zone file, in the generic format of RFC 3597 understood by every DNS server
+    // ex) <name> IN TYPE61 \# 401 9833046a...
+    pub fn to_zone_line(&self) -> String {
+        let hex: String = self.data.iter().map(|byte| format!("{:02x}", byte)).collect();
+        return format!("{} IN TYPE61 \\# {} {}", self.name, self.data.len(), hex);
+    }
+}
+
+//*******************************************************
+
+//             RELATED TO GPG INSTALLATION
+
+//*******************************************************
+// a gpg binary found on the system, retrieved from find_gpg_installations
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpgInstallation {
+    // path: where the binary was found ( ex. /usr/local/bin/gpg2 ), not resolved if it was a symlink
+    pub path: PathBuf,
+    // version: major and minor version of the binary ex) 2.4
+    pub version: f32,
+    // full_version: full version of the binary ex) 2.4.6
+    pub full_version: String,
+}
+
+//*******************************************************
+
+//              RELATED TO GPG DIRECTORIES
+
+//*******************************************************
+// directories and sockets used by gpg, retrieved from gpgconf --list-dirs
+#[derive(Debug, Clone)]
+pub struct GpgDirs {
+    // homedir: the gpg homedir where the keys were at
+    pub homedir: String,
+    // socketdir: directory where the sockets of the homedir were at
+    pub socketdir: String,
+    // agent_socket: socket of gpg-agent
+    pub agent_socket: String,
+    // agent_ssh_socket: socket of gpg-agent for ssh support
+    pub agent_ssh_socket: String,
+    // agent_extra_socket: restricted socket of gpg-agent ( ex. for agent forwarding )
+    pub agent_extra_socket: String,
+    // agent_browser_socket: socket of gpg-agent for browser
+    pub agent_browser_socket: String,
+    // dirmngr_socket: socket of dirmngr
+    pub dirmngr_socket: String,
+    // keyboxd_socket: socket of keyboxd, empty before gpg 2.4
+    pub keyboxd_socket: String,
+    // sysconfdir: directory of the system wide configuration
+    pub sysconfdir: String,
+    // bindir: directory of the gpg binaries
+    pub bindir: String,
+    // libexecdir: directory of the gpg helper programs ( ex. gpg-preset-passphrase )
+    pub libexecdir: String,
+    // libdir: directory of the gpg libraries
+    pub libdir: String,
+    // datadir: directory of the gpg shared data
+    pub datadir: String,
+    // localedir: directory of the gpg locale data
+    pub localedir: String,
+}
+
+#[doc(hidden)]
+impl GpgDirs {
+    pub fn new(list_dirs: &str) -> GpgDirs {
+        // list_dirs: output of gpgconf --list-dirs ex) homedir:/home/user/.gnupg
+        let mut dirs: HashMap<String, String> = HashMap::new();
+        for line in list_dirs.lines() {
+            let mut parts = line.trim().splitn(2, ":");
+            let name: &str = parts.next().unwrap_or("");
+            let value: &str = parts.next().unwrap_or("");
+            dirs.insert(name.to_string(), unescape_percent(value));
+        }
+        let dir = |name: &str| -> String { dirs.get(name).cloned().unwrap_or(String::new()) };
+        return GpgDirs {
+            homedir: dir("homedir"),
+            socketdir: dir("socketdir"),
+            agent_socket: dir("agent-socket"),
+            agent_ssh_socket: dir("agent-ssh-socket"),
+            agent_extra_socket: dir("agent-extra-socket"),
+            agent_browser_socket: dir("agent-browser-socket"),
+            dirmngr_socket: dir("dirmngr-socket"),
+            keyboxd_socket: dir("keyboxd-socket"),
+            sysconfdir: dir("sysconfdir"),
+            bindir: dir("bindir"),
+            libexecdir: dir("libexecdir"),
+            libdir: dir("libdir"),
+            datadir: dir("datadir"),
+            localedir: dir("localedir"),
+        };
+    }
+}
+
+// the Assuan sockets of the homedir, for the custom Assuan client or the ssh-agent emulation ( SSH_AUTH_SOCK )
+// the socket may not exist until the daemon was started
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpgSockets {
+    // socketdir: directory where the sockets were at, may not be the homedir ( ex. /run/user/<uid>/gnupg/d.<hash> )
+    pub socketdir: PathBuf,
+    // agent: socket of gpg-agent
+    pub agent: PathBuf,
+    // agent_ssh: socket of gpg-agent speaking the ssh-agent protocol
+    pub agent_ssh: PathBuf,
+    // agent_extra: restricted socket of gpg-agent ( ex. for agent forwarding )
+    pub agent_extra: PathBuf,
+    // agent_browser: socket of gpg-agent for browser
+    pub agent_browser: PathBuf,
+    // dirmngr: socket of dirmngr
+    pub dirmngr: PathBuf,
+    // keyboxd: socket of keyboxd, None before gpg 2.4
+    pub keyboxd: Option<PathBuf>,
+}
+
+#[doc(hidden)]
+impl GpgSockets {
+    pub fn from_dirs(dirs: &GpgDirs) -> GpgSockets {
+        // dirs: the directories retrieved from gpgconf --list-dirs
+        return GpgSockets {
+            socketdir: PathBuf::from(&dirs.socketdir),
+            agent: PathBuf::from(&dirs.agent_socket),
+            agent_ssh: PathBuf::from(&dirs.agent_ssh_socket),
+            agent_extra: PathBuf::from(&dirs.agent_extra_socket),
+            agent_browser: PathBuf::from(&dirs.agent_browser_socket),
+            dirmngr: PathBuf::from(&dirs.dirmngr_socket),
+            keyboxd: if dirs.keyboxd_socket.is_empty() { None } else { Some(PathBuf::from(&dirs.keyboxd_socket)) },
+        };
+    }
+}
+
+// gpgconf escape special character in the value as %XX ex) %3a for :
+fn unescape_percent(value: &str) -> String {
+    let bytes: &[u8] = value.as_bytes();
+    let mut unescaped: Vec<u8> = Vec::new();
+    let mut idx: usize = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' && idx + 2 < bytes.len() {
+            let hex: String = String::from_utf8_lossy(&bytes[idx + 1..idx + 3]).to_string();
+            if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                unescaped.push(byte);
+                idx += 3;
+                continue;
+            }
+        }
+        unescaped.push(bytes[idx]);
+        idx += 1;
+    }
+    return String::from_utf8_lossy(&unescaped).to_string();
+}
+
+//*******************************************************
+
+//            RELATED TO HOMEDIR PERMISSION
+
+//*******************************************************
+// the result of checking the permission of the homedir and everything in it
+#[derive(Debug, Clone)]
+pub struct PermissionCheckResult {
+    // homedir: the homedir that was checked
+    pub homedir: PathBuf,
+    // insecure: path(s) accessible by group or other, gpg will warn about unsafe permission for them
+    //           after fixing, every path in the homedir that was fixed
+    pub insecure: Vec<InsecurePermission>,
+    // fixed: whether the permission of the insecure path(s) had been fixed
+    pub fixed: bool,
+}
+
+impl PermissionCheckResult {
+    // whether gpg will not warn about unsafe permission anymore
+    pub fn is_secure(&self) -> bool {
+        return self.insecure.is_empty() || self.fixed;
+    }
+}
+
+// a file or directory accessible by group or other
+#[derive(Debug, Clone)]
+pub struct InsecurePermission {
+    // path: path to the file or directory
+    pub path: PathBuf,
+    // mode: the permission when it was checked ex) 0o755
+    pub mode: u32,
+}
+
 //*******************************************************
 
 //            RELATED TO LIST KEY RESULT
 
 //*******************************************************
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ListKeyResult {
     // https://github.com/gpg/gnupg/blob/master/doc/DETAILS
     pub r#type: String,
@@ -199,9 +910,23 @@ pub struct ListKeyResult {
     pub comment: String,
     pub keygrip: String,
     pub uids: Vec<String>,
+    // hash of each uid in uids ( same order ), can be used to select a uid when editing key
+    pub uid_hashes: Vec<String>,
     pub sigs: Vec<Vec<String>>,
+    // parsed sig and rev record(s) of the key, its uid(s) and its subkey(s)
+    pub signatures: Vec<KeySignature>,
     pub subkeys: Vec<Subkey>,
     pub fingerprint: String,
+    // validity_level: the validity decoded, None if it was not a trust level
+    pub validity_level: Option<TrustLevel>,
+    // is_revoked: whether the key was revoked
+    pub is_revoked: bool,
+    // is_expired: whether the key was expired
+    pub is_expired: bool,
+    // is_disabled: whether the key was disabled
+    pub is_disabled: bool,
+    // expiry_timestamp: expiry time of the key in seconds since epoch, None if the key does not expire
+    pub expiry_timestamp: Option<i64>,
 }
 
 #[doc(hidden)]
@@ -231,9 +956,16 @@ impl ListKeyResult {
             comment: String::from("Unavailable"),
             keygrip: String::from("Unavailable"),
             uids: vec![],
+            uid_hashes: vec![],
             sigs: vec![],
+            signatures: vec![],
             subkeys: vec![],
             fingerprint: String::from(""),
+            validity_level: None,
+            is_revoked: false,
+            is_expired: false,
+            is_disabled: false,
+            expiry_timestamp: None,
         };
         let mut idx: usize = 0;
         if idx < args.len() {
@@ -315,11 +1047,27 @@ impl ListKeyResult {
         if idx < args.len() {
             result.comment = String::from(args[idx]);
         }
+        result.validity_level = result.validity.chars().next().and_then(TrustLevel::from_colon_char);
+        result.is_revoked = result.validity == "r";
+        result.is_expired = result.validity == "e";
+        // a disabled key has D in its capabilities
+        result.is_disabled = result.cap.contains("D");
+        result.expiry_timestamp = parse_expiry_timestamp(&result.expires);
         return result;
     }
 }
 
+// constructor for application to build ListKeyResult in their own unit test
+#[cfg(feature = "test_util")]
+impl ListKeyResult {
+    // build from the output of gpg --list-keys --with-colons, subkeys and signatures included
+    pub fn from_colons(colons: &str) -> Vec<ListKeyResult> {
+        return decode_list_key_result(CmdResult::from_output(Operation::ListKey, colons, "", 0));
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Subkey {
     pub r#type: String,
     pub validity: String,
@@ -342,6 +1090,16 @@ pub struct Subkey {
     pub updated: String,
     pub keygrip: String,
     pub fingerprint: String,
+    // validity_level: the validity decoded, None if it was not a trust level
+    pub validity_level: Option<TrustLevel>,
+    // is_revoked: whether the subkey was revoked
+    pub is_revoked: bool,
+    // is_expired: whether the subkey was expired
+    pub is_expired: bool,
+    // is_disabled: whether the subkey was disabled
+    pub is_disabled: bool,
+    // expiry_timestamp: expiry time of the subkey in seconds since epoch, None if the subkey does not expire
+    pub expiry_timestamp: Option<i64>,
 }
 
 #[doc(hidden)]
@@ -369,6 +1127,11 @@ impl Subkey {
             updated: String::from("Unavailable"),
             keygrip: String::from(""),
             fingerprint: String::from(""),
+            validity_level: None,
+            is_revoked: false,
+            is_expired: false,
+            is_disabled: false,
+            expiry_timestamp: None,
         };
         let mut idx: usize = 0;
         if idx < args.len() {
@@ -446,17 +1209,250 @@ impl Subkey {
         if idx < args.len() {
             result.updated = String::from(args[idx]);
         }
+        result.validity_level = result.validity.chars().next().and_then(TrustLevel::from_colon_char);
+        result.is_revoked = result.validity == "r";
+        result.is_expired = result.validity == "e";
+        result.is_disabled = result.cap.contains("D");
+        result.expiry_timestamp = parse_expiry_timestamp(&result.expires);
         return result
     }
 }
 
+// the key chosen by encryption_key_for or signing_key_for of GPG
+#[derive(Debug, Clone)]
+pub struct SelectedKey {
+    // fingerprint: fingerprint of the primary key or subkey chosen, can be passed as the recipient or keyid
+    pub fingerprint: String,
+    // is_subkey: whether a subkey of the key was chosen
+    pub is_subkey: bool,
+    // key: the key chosen or the key the subkey belong to
+    pub key: ListKeyResult,
+}
+
+// a primary key or subkey expiring soon or already expired, check GPG.expiring_keys
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiringKey {
+    // fingerprint: fingerprint of the primary key or subkey expiring
+    pub fingerprint: String,
+    // primary_fingerprint: fingerprint of the primary key, the same as fingerprint if it was not a subkey
+    pub primary_fingerprint: String,
+    // is_subkey: whether it was a subkey expiring
+    pub is_subkey: bool,
+    // uids: uids of the primary key, to tell the owner
+    pub uids: Vec<String>,
+    // cap: capability of the key ex) e for an encryption subkey
+    pub cap: String,
+    // expiry_timestamp: expiry time in seconds since epoch
+    pub expiry_timestamp: i64,
+    // is_expired: whether it was already expired
+    pub is_expired: bool,
+}
+
+// every key of the keyring with everything known about it, check GPG.dump_keyring
+// serializable with serde ( ex. to JSON ) with the serde feature
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyringDump {
+    // homedir: the homedir the keyring was at
+    pub homedir: PathBuf,
+    // gpg_version: full version of the gpg that listed the keyring ex) 2.4.6
+    pub gpg_version: String,
+    // created_at: when the dump was taken, in seconds since epoch
+    pub created_at: i64,
+    // keys: every public key of the keyring
+    pub keys: Vec<DumpedKey>,
+}
+
+// a key of the KeyringDump
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DumpedKey {
+    // key: the key with its uids, subkeys and signatures
+    pub key: ListKeyResult,
+    // has_secret: whether the secret key was in the keyring
+    pub has_secret: bool,
+    // ownertrust: the trust set for the owner of the key, None if it was not a trust level
+    pub ownertrust: Option<TrustLevel>,
+}
+
+// expiry field was in seconds since epoch with --fixed-list-mode, empty or 0 for no expiry
+fn parse_expiry_timestamp(expires: &str) -> Option<i64> {
+    match expires.parse::<i64>() {
+        Ok(timestamp) if timestamp > 0 => Some(timestamp),
+        _ => None,
+    }
+}
+
+// a signature ( sig ) or revocation signature ( rev ) record of a key
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeySignature {
+    // https://github.com/gpg/gnupg/blob/master/doc/DETAILS
+    // r#type: sig for a signature, rev for a revocation signature
+    pub r#type: String,
+    // validity: only available when checking signatures, ! = good, - = bad, ? = no public key to check, % = error
+    pub validity: String,
+    // is_valid: whether the signature was checked to be good, None if it was not checked or could not be checked
+    pub is_valid: Option<bool>,
+    pub algo: String,
+    // keyid: keyid of the signer
+    pub keyid: String,
+    // date: creation date of the signature
+    pub date: String,
+    // expires: expiration date of the signature, empty for no expiry
+    pub expires: String,
+    // signer_uid: user id of the signer, [User ID not found] if the signer key was not in the keyring
+    pub signer_uid: String,
+    // class: signature class ex) 10~13 for certification, 18 for subkey binding, 20 for key revocation
+    pub class: String,
+    // exportable: whether the signature was exportable ( false for local signature )
+    pub exportable: bool,
+    // is_revocation: whether this was a revocation signature
+    pub is_revocation: bool,
+    // issuer: fingerprint of the signer if available
+    pub issuer: String,
+    pub hash: String,
+    // target_uid: the user id this signature was made on, empty if it was made on the key or a subkey
+    pub target_uid: String,
+    // target_subkey: keyid of the subkey this signature was made on, empty if it was not made on a subkey
+    pub target_subkey: String,
+}
+
+#[doc(hidden)]
+impl KeySignature {
+    fn new(args: Vec<&str>, target_uid: String, target_subkey: String) -> Self {
+        let field = |idx: usize| -> String { args.get(idx).unwrap_or(&"").to_string() };
+        let validity: String = field(1);
+        let sig_class: String = field(10);
+        let is_valid: Option<bool> = match validity.as_str() {
+            "!" => Some(true),
+            "-" => Some(false),
+            _ => None,
+        };
+        return KeySignature {
+            r#type: field(0),
+            validity: validity,
+            is_valid: is_valid,
+            algo: field(3),
+            keyid: field(4),
+            date: field(5),
+            expires: field(6),
+            signer_uid: field(9),
+            class: sig_class.chars().take(2).collect(),
+            exportable: sig_class.ends_with("x"),
+            is_revocation: field(0) == "rev",
+            issuer: field(12),
+            hash: field(15),
+            target_uid: target_uid,
+            target_subkey: target_subkey,
+        };
+    }
+}
+
+// how the data was encrypted, reported by gpg when decrypting
+#[derive(Debug, Clone)]
+pub struct DecryptionInfo {
+    // cipher: the cipher the data was encrypted with, None if it was not known to the crate ( check cipher_id )
+    pub cipher: Option<CipherAlgo>,
+    // cipher_id: the OpenPGP id of the cipher ex) 9 for AES256
+    pub cipher_id: u8,
+    // aead_algo: the OpenPGP id of the AEAD mode ex) 2 for OCB, None if the data was not AEAD encrypted
+    pub aead_algo: Option<u8>,
+    // integrity_protected: whether the data was protected by MDC or AEAD
+    pub integrity_protected: bool,
+    // session_key: the session key as <cipher_id>:<hex> ( ex. 9:0A1B... ), only if show_session_key was set on
+    //              the DecryptOption. It can decrypt the data ( session_key of DecryptOption ) without the secret key
+    pub session_key: Option<Passphrase>,
+}
+
+#[doc(hidden)]
+impl DecryptionInfo {
+    pub fn init() -> DecryptionInfo {
+        return DecryptionInfo {
+            cipher: None,
+            cipher_id: 0,
+            aead_algo: None,
+            integrity_protected: false,
+            session_key: None,
+        };
+    }
+
+    pub fn handle_status(&mut self, keyword: &str, value: &str) {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        match keyword {
+            // DECRYPTION_INFO <mdc_method> <sym_algo> [<aead_algo>]
+            "DECRYPTION_INFO" => {
+                self.cipher_id = fields.get(1).and_then(|id| id.parse::<u8>().ok()).unwrap_or(0);
+                self.cipher = CipherAlgo::from_id(self.cipher_id);
+                self.aead_algo = fields.get(2).and_then(|id| id.parse::<u8>().ok()).filter(|id| *id != 0);
+                self.integrity_protected = fields.first().is_some_and(|mdc| *mdc != "0") || self.aead_algo.is_some();
+            }
+            // SESSION_KEY <algo>:<hexdigits>
+            "SESSION_KEY" => {
+                if let Some(session_key) = fields.first() {
+                    self.session_key = Some(Passphrase::new(session_key.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// a signature gpg made, reported by gpg when signing ( SIG_CREATED )
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignResult {
+    // sig_type: S for a standard ( embedded ) signature, D for a detached signature, C for a cleartext signature
+    pub sig_type: String,
+    // pubkey_algo: the OpenPGP id of the public key algorithm of the signing key ex) 1 for RSA, 22 for EdDSA
+    pub pubkey_algo: u8,
+    // hash_algo: the digest the data was hashed with, None if it was not known to the crate ( check hash_algo_id )
+    pub hash_algo: Option<DigestAlgo>,
+    // hash_algo_id: the OpenPGP id of the digest ex) 8 for SHA256
+    pub hash_algo_id: u8,
+    // sig_class: the signature class in hex ex) 00 for a binary document, 01 for a text document
+    pub sig_class: String,
+    // timestamp: when the signature was created, in seconds since epoch
+    pub timestamp: i64,
+    // fingerprint: fingerprint of the key that made the signature
+    pub fingerprint: String,
+}
+
+#[doc(hidden)]
+impl SignResult {
+    // SIG_CREATED <type> <pk_algo> <hash_algo> <class> <timestamp> <keyfpr>, None if a field was missing
+    pub fn from_status(value: &str) -> Option<SignResult> {
+        let (sig_type, pubkey_algo, hash_algo, sig_class, timestamp, fingerprint) =
+            match StatusLine::from_parts("SIG_CREATED", value) {
+                StatusLine::SigCreated { sig_type, pubkey_algo, hash_algo, sig_class, timestamp, fingerprint } => {
+                    (sig_type, pubkey_algo, hash_algo, sig_class, timestamp, fingerprint)
+                }
+                _ => return None,
+            };
+        let timestamp: i64 = match timestamp.parse::<i64>() {
+            Ok(timestamp) => timestamp,
+            Err(_) => return None,
+        };
+        return Some(SignResult {
+            sig_type: sig_type,
+            pubkey_algo: pubkey_algo,
+            hash_algo: DigestAlgo::from_id(hash_algo),
+            hash_algo_id: hash_algo,
+            sig_class: sig_class,
+            timestamp: timestamp,
+            fingerprint: fingerprint,
+        });
+    }
+}
+
 //  a result handler for handling the result of keys action ( mainly of retrieve key list related action )
 pub struct ListKey {
     // in_subkey: include subkeys
     // key list: a list of key
     // curkey: current processing key
     // fingerprints: a list of fingerprints
+    // curuid: current processing uid
     in_subkey: bool,
+    curuid: String,
     key_list: Option<Vec<ListKeyResult>>,
     curkey: Option<ListKeyResult>,
     fingerprints: Option<Vec<String>>,
@@ -467,6 +1463,7 @@ impl ListKey {
     pub fn init() -> ListKey {
         ListKey {
             in_subkey: false,
+            curuid: String::new(),
             key_list: Some(Vec::new()),
             curkey: None,
             fingerprints: Some(Vec::new()),
@@ -482,6 +1479,7 @@ impl ListKey {
             "sub" => self.sub(args),
             "ssb" => self.ssb(args),
             "sig" => self.sig(args),
+            "rev" => self.rev(args),
             "grp" => self.grp(args),
             _ => return,
         }
@@ -493,17 +1491,26 @@ impl ListKey {
         let uid = self.curkey.as_ref().unwrap().uid.clone();
         if !uid.is_empty() {
             self.curkey.as_mut().unwrap().uids.push(uid);
+            self.curkey.as_mut().unwrap().uid_hashes.push(String::new());
         }
         self.in_subkey = false;
+        self.curuid = String::new();
     }
 
     fn uid(&mut self, args: Vec<&str>) {
         let uid_index: usize = 9;
+        let uid_hash_index: usize = 7;
         self.curkey
             .as_mut()
             .unwrap()
             .uids
             .push(args[uid_index].to_string());
+        self.curkey
+            .as_mut()
+            .unwrap()
+            .uid_hashes
+            .push(args[uid_hash_index].to_string());
+        self.curuid = args[uid_index].to_string();
     }
 
     fn fpr(&mut self, args: Vec<&str>) {
@@ -521,12 +1528,14 @@ impl ListKey {
         let subkey: Subkey = Subkey::new(args);
         self.curkey.as_mut().unwrap().subkeys.push(subkey);
         self.in_subkey = true;
+        self.curuid = String::new();
     }
 
     fn ssb(&mut self, args: Vec<&str>) {
         let subkey: Subkey = Subkey::new(args);
         self.curkey.as_mut().unwrap().subkeys.push(subkey);
         self.in_subkey = true;
+        self.curuid = String::new();
     }
 
     fn sig(&mut self, args: Vec<&str>) {
@@ -535,6 +1544,21 @@ impl ListKey {
             args[9].to_string(),
             args[10].to_string(),
         ]);
+        self.push_signature(args);
+    }
+
+    fn rev(&mut self, args: Vec<&str>) {
+        self.push_signature(args);
+    }
+
+    fn push_signature(&mut self, args: Vec<&str>) {
+        // signature after a uid was made on the uid, after a subkey was made on the subkey
+        let mut target_subkey: String = String::new();
+        if self.in_subkey {
+            target_subkey = self.curkey.as_ref().unwrap().subkeys.last().unwrap().keyid.clone();
+        }
+        let signature: KeySignature = KeySignature::new(args, self.curuid.clone(), target_subkey);
+        self.curkey.as_mut().unwrap().signatures.push(signature);
     }
 
     fn grp(&mut self, args: Vec<&str>) {
@@ -555,9 +1579,479 @@ impl ListKey {
     }
 
     pub fn append_result(&mut self) {
-        if !self.curkey.is_none() {
-            let curkey = self.curkey.as_ref().unwrap().clone();
-            self.key_list.as_mut().unwrap().push(curkey);
+        if let Some(curkey) = &self.curkey {
+            self.key_list.as_mut().unwrap().push(curkey.clone());
+        }
+    }
+
+    // take out the current processing key without collecting it into the key list
+    pub fn take_current_key(&mut self) -> Option<ListKeyResult> {
+        return self.curkey.take();
+    }
+}
+
+//*******************************************************
+
+//              RELATED TO ARMORED KEY
+
+//*******************************************************
+// a single key exported as an ASCII armored document, check GPG.export_armored_key
+#[derive(Clone, PartialEq)]
+pub struct ArmoredKey {
+    // kind: whether the public or the secret key was exported
+    pub kind: KeyKind,
+    // fingerprint: fingerprint of the primary key exported
+    pub fingerprint: String,
+    // data: the armored document ex) -----BEGIN PGP PUBLIC KEY BLOCK----- ...
+    pub data: String,
+}
+
+impl ArmoredKey {
+    // the armored document
+    pub fn as_str(&self) -> &str {
+        return &self.data;
+    }
+
+    // write the armored document to the path, replacing the file if it exists
+    // a secret key written to a new file was only readable by the owner ( 0600 )
+    pub fn write_to(&self, path: impl AsRef<Path>) -> Result<(), GPGError> {
+        // path: the file to write to, its parent directory should exist
+
+        let mut options: OpenOptions = OpenOptions::new();
+        options.create(true).truncate(true).write(true);
+        #[cfg(unix)]
+        if self.kind == KeyKind::Secret {
+            options.mode(0o600);
+        }
+        let result: Result<(), std::io::Error> = options
+            .open(path.as_ref())
+            .and_then(|mut file| file.write_all(self.data.as_bytes()));
+        if let Err(e) = result {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to write {}: {}", path.as_ref().display(), e)),
+                None,
+            ));
+        }
+        return Ok(());
+    }
+}
+
+// the armored secret key was not printed, like Passphrase
+#[doc(hidden)]
+impl Debug for ArmoredKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let data: String = match self.kind {
+            KeyKind::Public => self.data.clone(),
+            KeyKind::Secret => "<redacted>".to_string(),
+        };
+        return f
+            .debug_struct("ArmoredKey")
+            .field("kind", &self.kind)
+            .field("fingerprint", &self.fingerprint)
+            .field("data", &data)
+            .finish();
+    }
+}
+
+//*******************************************************
+
+//            RELATED TO LIST KEY ITERATOR
+
+//*******************************************************
+// an iterator that parse the key listing as gpg is writing it, one key at a time,
+// a last Err was returned if gpg failed to list the keys ( ex. the homedir does not exist )
+pub struct ListKeyIter {
+    // stream: the running gpg process that is listing the keys, stderr was collected by it so gpg will not block
+    // lines: lines of the listing from stdout of the process
+    // recorder: the process was recorded to the audit sink and metrics recorder of GPG once it exited
+    // collected: the keys listed through a custom runner instead of a gpg process, returned one by one
+    // list_key: parser of the current processing key
+    // finished: whether the listing has ended
+    // error: why the listing failed, returned after the last key
+    stream: Option<CmdStream>,
+    lines: Option<Lines<BufReader<ChildStdout>>>,
+    recorder: Option<LocalProcessRecorder>,
+    collected: std::vec::IntoIter<ListKeyResult>,
+    list_key: ListKey,
+    finished: bool,
+    error: Option<GPGError>,
+}
+
+impl ListKeyIter {
+    pub(crate) fn new(mut stream: CmdStream, recorder: LocalProcessRecorder) -> ListKeyIter {
+        // nothing will be written to gpg for listing
+        drop(stream.process.stdin.take());
+        let stdout: ChildStdout = stream.process.stdout.take().unwrap();
+        return ListKeyIter {
+            stream: Some(stream),
+            lines: Some(BufReader::new(stdout).lines()),
+            recorder: Some(recorder),
+            collected: Vec::new().into_iter(),
+            list_key: ListKey::init(),
+            finished: false,
+            error: None,
+        };
+    }
+
+    // for the keys already listed ( ex. through a custom runner )
+    pub(crate) fn collected(keys: Vec<ListKeyResult>) -> ListKeyIter {
+        return ListKeyIter {
+            stream: None,
+            lines: None,
+            recorder: None,
+            collected: keys.into_iter(),
+            list_key: ListKey::init(),
+            finished: true,
+            error: None,
+        };
+    }
+
+    // kill gpg if it was still listing the keys
+    fn kill(&mut self) {
+        if let Some(stream) = self.stream.as_mut() {
+            let _ = stream.process.kill();
+        }
+    }
+
+    // wait for gpg once the listing ended, return the error if gpg did not exit successfully
+    // ( or could not be recorded )
+    fn finish(&mut self) -> Option<GPGError> {
+        if self.finished {
+            return None;
+        }
+        self.finished = true;
+        let mut result: Result<CmdResult, GPGError> = Ok(self.stream.take()?.wait());
+        if let Some(recorder) = self.recorder.take() {
+            result = recorder.record(Operation::ListKey, result);
+        }
+        let result: CmdResult = match result {
+            Ok(result) => result,
+            Err(e) => return Some(e),
+        };
+        if result.exit_status() == GpgExitStatus::Success {
+            return None;
+        }
+        return Some(GPGError::new(
+            GPGErrorType::GPGProcessError(format!(
+                "failed to list the keys ( exit status {} )",
+                result.return_code.unwrap_or(-1)
+            )),
+            Some(result),
+        ));
+    }
+}
+
+impl Iterator for ListKeyIter {
+    type Item = Result<ListKeyResult, GPGError>;
+
+    fn next(&mut self) -> Option<Result<ListKeyResult, GPGError>> {
+        while !self.finished {
+            let line: String = match self.lines.as_mut().and_then(|lines| lines.next()) {
+                Some(Ok(line)) => line.trim().to_string(),
+                Some(Err(e)) => {
+                    self.kill();
+                    self.finish();
+                    self.error = Some(GPGError::new(GPGErrorType::ReadFailError(e.to_string()), None));
+                    break;
+                }
+                None => {
+                    self.error = self.finish();
+                    break;
+                }
+            };
+            if line.is_empty() {
+                continue;
+            }
+            let l_key_pair: Vec<&str> = line.split(":").collect();
+            let k_w: &str = l_key_pair[0];
+            if !LIST_KEY_KEYWORDS.contains(&k_w) {
+                continue;
+            }
+            // a new pub or sec line means the previous key was complete
+            let mut completed_key: Option<ListKeyResult> = None;
+            if k_w == "pub" || k_w == "sec" {
+                completed_key = self.list_key.take_current_key();
+            }
+            self.list_key.call_method(k_w, l_key_pair);
+            if let Some(completed_key) = completed_key {
+                return Some(Ok(completed_key));
+            }
+        }
+        if let Some(key) = self.list_key.take_current_key() {
+            return Some(Ok(key));
+        }
+        if let Some(key) = self.collected.next() {
+            return Some(Ok(key));
+        }
+        return self.error.take().map(Err);
+    }
+}
+
+impl Drop for ListKeyIter {
+    fn drop(&mut self) {
+        // gpg will be killed if the iterator was dropped before the listing ended
+        if !self.finished {
+            self.kill();
+            self.finish();
+        }
+    }
+}
+
+//*******************************************************
+
+//            RELATED TO KEY ATTRIBUTE RESULT
+
+//*******************************************************
+// a result for attribute ( ex. photo id ) attached to a key
+#[derive(Debug, Clone)]
+pub struct KeyAttribute {
+    // https://github.com/gpg/gnupg/blob/master/doc/DETAILS#attribute
+    // fingerprint: fingerprint of the key the attribute belongs to
+    pub fingerprint: String,
+    // r#type: type of the attribute, 1 for image ( photo id )
+    pub r#type: String,
+    // index: index of the attribute within the key
+    pub index: String,
+    // count: total number of attribute within the key
+    pub count: String,
+    // timestamp: creation time of the attribute
+    pub timestamp: String,
+    // expire: expiry time of the attribute, 0 for no expiry
+    pub expire: String,
+    // is_primary: whether the attribute was marked as primary
+    pub is_primary: bool,
+    // is_revoked: whether the attribute was revoked
+    pub is_revoked: bool,
+    // is_expired: whether the attribute was expired
+    pub is_expired: bool,
+    // data: content of the attribute, for photo id this will be the JPEG image
+    pub data: Vec<u8>,
+}
+
+#[doc(hidden)]
+impl KeyAttribute {
+    pub fn new(args: Vec<&str>, data: Vec<u8>) -> KeyAttribute {
+        // args: [fpr, octets, type, index, count, timestamp, expiredate, flags]
+        let flags: u8 = args.get(7).unwrap_or(&"0").parse::<u8>().unwrap_or(0);
+        let mut data: Vec<u8> = data;
+        // image attribute starts with a 16 bytes header before the actual JPEG
+        if args.get(2) == Some(&"1") && data.len() >= 16 {
+            data = data[16..].to_vec();
         }
+        return KeyAttribute {
+            fingerprint: args.first().unwrap_or(&"").to_string(),
+            r#type: args.get(2).unwrap_or(&"").to_string(),
+            index: args.get(3).unwrap_or(&"").to_string(),
+            count: args.get(4).unwrap_or(&"").to_string(),
+            timestamp: args.get(5).unwrap_or(&"").to_string(),
+            expire: args.get(6).unwrap_or(&"").to_string(),
+            is_primary: flags & 0x01 != 0,
+            is_revoked: flags & 0x02 != 0,
+            is_expired: flags & 0x04 != 0,
+            data: data,
+        };
+    }
+}
+
+//*******************************************************
+
+//            RELATED TO IMPORT DRY RUN RESULT
+
+//*******************************************************
+// a result of what will be changed in the keyring if the key was imported
+#[derive(Debug, Clone)]
+pub struct ImportDryRunResult {
+    // fingerprint: fingerprint of the key to be imported
+    pub fingerprint: String,
+    // uids: all user id(s) of the key to be imported
+    pub uids: Vec<String>,
+    // is_secret: whether the key to be imported is a secret key
+    pub is_secret: bool,
+    // is_new_key: whether the key does not exist in the keyring yet
+    pub is_new_key: bool,
+    // new_uids: user id(s) that will be added to the keyring
+    pub new_uids: Vec<String>,
+    // new_subkeys: fingerprint of the subkey(s) that will be added to the keyring
+    pub new_subkeys: Vec<String>,
+    // key: the key as listed from the file to be imported
+    pub key: ListKeyResult,
+}
+
+#[doc(hidden)]
+impl ImportDryRunResult {
+    pub fn new(key: ListKeyResult, existing_key: Option<&ListKeyResult>) -> ImportDryRunResult {
+        // key: key listed from the file to be imported
+        // existing_key: the same key in the keyring if it was already there
+
+        // importing only merge new uid(s) and subkey(s) into an existing key, nothing will be replaced
+        let new_uids: Vec<String> = key
+            .uids
+            .iter()
+            .filter(|uid| existing_key.is_none() || !existing_key.unwrap().uids.contains(uid))
+            .cloned()
+            .collect();
+        let new_subkeys: Vec<String> = key
+            .subkeys
+            .iter()
+            .filter(|subkey| {
+                existing_key.is_none()
+                    || !existing_key
+                        .unwrap()
+                        .subkeys
+                        .iter()
+                        .any(|s| s.fingerprint == subkey.fingerprint)
+            })
+            .map(|subkey| subkey.fingerprint.clone())
+            .collect();
+        return ImportDryRunResult {
+            fingerprint: key.fingerprint.clone(),
+            uids: key.uids.clone(),
+            is_secret: key.r#type == "sec",
+            is_new_key: existing_key.is_none(),
+            new_uids: new_uids,
+            new_subkeys: new_subkeys,
+            key: key,
+        };
+    }
+}
+
+// a result of migrating the keyrings of a gpg 1.x homedir, check GPG.migrate_legacy_keyrings
+#[derive(Debug, Clone)]
+pub struct LegacyMigrationResult {
+    // keyrings: the legacy keyring file(s) found in the old homedir ( pubring.gpg, secring.gpg )
+    pub keyrings: Vec<PathBuf>,
+    // keys: what will be changed in the keyring by every key of the legacy keyrings
+    pub keys: Vec<ImportDryRunResult>,
+    // imported: fingerprint of the key(s) gpg reported to be imported, always empty for a dry run
+    pub imported: Vec<String>,
+    // dry_run: whether nothing was written to the keyring
+    pub dry_run: bool,
+}
+
+//*******************************************************
+
+//              RELATED TO VERIFY RESULT
+
+//*******************************************************
+// a result of verifying a signature, a bad signature is a result rather than an error
+#[derive(Debug, Clone)]
+pub struct VerifyResult {
+    // valid: whether the signature was good
+    pub valid: bool,
+    // key_id: long keyid of the key that made the signature
+    pub key_id: Option<String>,
+    // username: primary uid of the key that made the signature
+    pub username: Option<String>,
+    // fingerprint: fingerprint of the key that made the signature, only available for a good signature
+    pub fingerprint: Option<String>,
+    // exit_status: what the exit code of gpg ( or gpgv ) mean, check GpgExitStatus
+    pub exit_status: GpgExitStatus,
+    // warnings: warnings gpg reported about the signature ( ex. untrusted key, weak digest ), check GpgWarning
+    pub warnings: Vec<GpgWarning>,
+    // cmd_result: the result of the gpg ( or gpgv ) command
+    pub cmd_result: CmdResult,
+}
+
+#[doc(hidden)]
+impl VerifyResult {
+    pub fn from_cmd_result(cmd_result: CmdResult) -> VerifyResult {
+        let mut verify_result: VerifyResult = VerifyResult {
+            valid: false,
+            key_id: None,
+            username: None,
+            fingerprint: None,
+            exit_status: cmd_result.exit_status(),
+            warnings: cmd_result.warnings.clone(),
+            cmd_result: CmdResult::init(cmd_result.operation.clone()),
+        };
+        let mut good_signature: bool = false;
+        for status in cmd_result.status_lines() {
+            let is_good: bool = matches!(status, StatusLine::GoodSig { .. });
+            match status {
+                StatusLine::GoodSig { keyid, username }
+                | StatusLine::ExpSig { keyid, username }
+                | StatusLine::ExpKeySig { keyid, username }
+                | StatusLine::RevKeySig { keyid, username }
+                | StatusLine::BadSig { keyid, username } => {
+                    good_signature = is_good;
+                    verify_result.key_id = Some(keyid);
+                    verify_result.username = Some(username);
+                }
+                StatusLine::ErrSig { keyid, .. } => verify_result.key_id = Some(keyid),
+                StatusLine::ValidSig { fingerprint, .. } => verify_result.fingerprint = Some(fingerprint),
+                _ => {}
+            }
+        }
+        verify_result.valid = good_signature
+            && verify_result.exit_status == GpgExitStatus::Success
+            && cmd_result.is_success();
+        verify_result.cmd_result = cmd_result;
+        return verify_result;
+    }
+}
+
+impl VerifyResult {
+    // the exit code gpg ( or gpgv ) would exit with for the verification, 0 only if the signature was good
+    pub fn exit_code(&self) -> i32 {
+        if self.valid {
+            return EXIT_CODE_SUCCESS;
+        }
+        match self.exit_status {
+            GpgExitStatus::BadSignature => return EXIT_CODE_BAD_SIGNATURE,
+            GpgExitStatus::Error(code) if code > 0 => return code,
+            // gpg did not fail but the signature checked was not good ( ex. made by an expired or revoked key )
+            _ if self.key_id.is_some() => return EXIT_CODE_BAD_SIGNATURE,
+            _ => return EXIT_CODE_ERROR,
+        }
+    }
+}
+
+//*******************************************************
+
+//          RELATED TO MANIFEST VERIFY RESULT
+
+//*******************************************************
+// a result of verifying a signed checksum manifest ( ex. SHA256SUMS.asc )
+#[derive(Debug, Clone)]
+pub struct ManifestVerifyResult {
+    // verify_result: result of verifying the signature of the manifest
+    pub verify_result: CmdResult,
+    // verified: file(s) listed in the manifest that match their checksum
+    pub verified: Vec<String>,
+    // failed: file(s) listed in the manifest that do not match their checksum
+    pub failed: Vec<String>,
+    // missing: file(s) listed in the manifest that could not be found
+    pub missing: Vec<String>,
+}
+
+impl ManifestVerifyResult {
+    // whether every file listed in the manifest was found and match its checksum
+    pub fn is_valid(&self) -> bool {
+        return self.failed.is_empty() && self.missing.is_empty();
+    }
+}
+
+//*******************************************************
+
+//         RELATED TO DIRECTORY VERIFY RESULT
+
+//*******************************************************
+// a result of verifying the files in a directory against their detached signatures
+#[derive(Debug, Clone)]
+pub struct DirectoryVerifyResult {
+    // verified: file(s) with a good detached signature
+    pub verified: Vec<String>,
+    // failed: file(s) with a detached signature that could not be verified
+    pub failed: Vec<String>,
+    // unsigned: file(s) without a detached signature
+    pub unsigned: Vec<String>,
+    // errors: why gpg failed to verify the file(s) in failed, by file, a file with a bad signature has no error
+    pub errors: HashMap<String, String>,
+}
+
+impl DirectoryVerifyResult {
+    // whether every file in the directory had a good detached signature
+    pub fn is_valid(&self) -> bool {
+        return self.failed.is_empty() && self.unsigned.is_empty();
     }
 }
@@ -0,0 +1,97 @@
+use std::fmt::{Debug, Formatter};
+
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use super::enums::Operation;
+
+//*******************************************************
+
+//              RELATED TO GPG PASSPHRASE
+
+//*******************************************************
+// a passphrase ( or PIN ) to be passed to gpg
+// the value will never be shown in Debug, and with the zeroize feature it will be wiped from memory when dropped
+#[derive(Clone, PartialEq, Eq)]
+pub struct Passphrase {
+    value: String,
+}
+
+impl Passphrase {
+    pub fn new(value: String) -> Passphrase {
+        return Passphrase { value: value };
+    }
+
+    // retrieve the passphrase, avoid copying it into a String as the copy will not be wiped
+    pub fn expose(&self) -> &str {
+        return &self.value;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.value.is_empty();
+    }
+}
+
+#[doc(hidden)]
+impl Passphrase {
+    // the passphrase followed by a newline, as gpg read it from stdin
+    pub fn to_line(&self) -> Passphrase {
+        let mut line: String = String::with_capacity(self.value.len() + 1);
+        line.push_str(&self.value);
+        line.push('\n');
+        return Passphrase { value: line };
+    }
+}
+
+impl Debug for Passphrase {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        return write!(f, "Passphrase(<redacted>)");
+    }
+}
+
+impl From<String> for Passphrase {
+    fn from(value: String) -> Passphrase {
+        return Passphrase::new(value);
+    }
+}
+
+impl From<&str> for Passphrase {
+    fn from(value: &str) -> Passphrase {
+        return Passphrase::new(value.to_string());
+    }
+}
+
+// so the passphrase can be written into stdin with a Cursor without being copied
+impl AsRef<[u8]> for Passphrase {
+    fn as_ref(&self) -> &[u8] {
+        return self.value.as_bytes();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Passphrase {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+//*******************************************************
+
+//          RELATED TO GPG PASSPHRASE PROVIDER
+
+//*******************************************************
+// where to get the passphrase of a secret key when it was not provided to the operation
+// ( ex. from the keychain of the OS, or by prompting the user ), set it on GPG.passphrase_provider
+pub trait PassphraseProvider: Debug + Send + Sync {
+    // return the passphrase for the key, None if it was not available ( the operation will fail as without passphrase )
+    fn get_passphrase(&self, key_hint: &str, ops: Operation) -> Option<Passphrase>;
+    // key_hint: the long keyid of the primary key gpg asked the passphrase for ( NEED_PASSPHRASE ),
+    //           empty for the passphrase of a symmetric encrypted file ( NEED_PASSPHRASE_SYM )
+    // ops: the operation that need the passphrase
+
+    // called when gpg rejected the passphrase returned for the key, before the provider was consulted again
+    // ( ex. to tell the user the passphrase was wrong, or to remove it from the keychain )
+    fn on_bad_passphrase(&self, key_hint: &str, ops: Operation) {
+        let _ = (key_hint, ops);
+    }
+}
@@ -1,27 +1,60 @@
 use std::{
-    fs::{metadata, File}, io::{Seek, Write}, path::{Path, PathBuf}, process::Command
+    fs::{metadata, File, OpenOptions, TryLockError}, io::{Read, Seek, Write}, path::{Path, PathBuf}, process::Command,
+    thread, time::{Duration, Instant, SystemTime}
 };
 
 #[cfg(unix)]
 use std::{
     fs::set_permissions,
-    os::unix::fs::PermissionsExt
+    os::unix::fs::{OpenOptionsExt, PermissionsExt}
 };
 
 
 use regex::Regex;
 
+use crate::status::{split_status_line, StatusLine};
 use crate::utils::response::ListKey;
 
+use super::enums::{GpgExitStatus, GpgWarning, ShredMode};
 use super::errors::{GPGError, GPGErrorType};
-use super::response::{CmdResult, ListKeyResult};
+use super::response::{
+    AgentResponse, CmdResult, DaneRecord, GPGCapabilities, GpgConfig, GpgDirs, GpgInstallation, GpgLock, InsecurePermission, KeyAttribute, ListKeyResult, ManagedOutput, OutputCleanupResult, PermissionCheckResult,
+    VerifyResult,
+};
 
 const VERSION_REGEX: &str = r"^cfg:version:(\d+(\.\d+)*)";
-const LIST_KEY_KEYWORDS: [&str; 8] = ["pub", "uid", "sec", "fpr", "sub", "ssb", "sig", "grp"];
+// first line of gpg --version ex) gpg (GnuPG) 2.4.6
+const BINARY_VERSION_REGEX: &str = r"^gpg \([^)]*\) (\d+(\.\d+)*)";
+// name of a default output the crate created with a timestamp ( ex. keys_encrypted_file_20250101-12:00:00:000000000.gpg ),
+// only these were removed by the retention policy of the output_dir
+const MANAGED_OUTPUT_REGEX: &str =
+    r"^(\w*encrypted_file|decrypted_file|detach_sign|embedded_sign|public_key|secret_key|key_backup)_\d{8}-\d{2}:\d{2}:\d{2}:\d{9}";
+// name of the gpg binary, gpg 1.4 and 2.x were installed as gpg1 and gpg2 by some system when both exist
+const GPG_PROGRAM_NAMES: [&str; 3] = ["gpg", "gpg2", "gpg1"];
+// where gpg was commonly installed but may not be on PATH ( ex. a service started with a minimal environment )
+const COMMON_GPG_DIRS: [&str; 7] = [
+    "/usr/bin",
+    "/usr/local/bin",
+    "/opt/homebrew/bin",
+    "/opt/local/bin",
+    "/usr/local/MacGPG2/bin",
+    "C:\\Program Files (x86)\\GnuPG\\bin",
+    "C:\\Program Files\\GnuPG\\bin",
+];
+// arguments whose value will be replaced with REDACTED wherever the command was recorded ( ex. audit log, error )
+const SECRET_ARGS: [&str; 2] = ["--passphrase", "--override-session-key"];
+const REDACTED: &str = "<redacted>";
+// the oldest gpg ( major.minor ) that crab-gnupg can work with, gpg 1.4 will only be able to do a subset of the operations
+pub const MIN_GPG_VERSION: f32 = 1.4;
+// the file in the homedir locked while a mutating operation was running
+pub const HOMEDIR_LOCK_FILE: &str = ".crab-gnupg.lock";
+// how long a mutating operation will wait for the lock of the homedir by default
+pub const DEFAULT_HOMEDIR_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+const HOMEDIR_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+pub const LIST_KEY_KEYWORDS: [&str; 9] = ["pub", "uid", "sec", "fpr", "sub", "ssb", "sig", "rev", "grp"];
 
 // check if a path is a directory
-pub fn check_is_dir(path: String) -> bool {
-    let path = Path::new(&path);
+pub fn check_is_dir(path: &Path) -> bool {
 
     if !path.is_dir() {
         return false;
@@ -44,44 +77,49 @@ fn get_user_directory() -> PathBuf {
 fn get_download_directory() -> PathBuf {
     let home_dir: PathBuf = get_user_directory();
 
-    return PathBuf::from(home_dir).join("Downloads");
+    return home_dir.join("Downloads");
 }
 
 //  retrieve or generate the directory for gpg key
-pub fn get_or_create_gpg_homedir(path:String) -> String {
+pub fn get_or_create_gpg_homedir(path: Option<PathBuf>) -> PathBuf {
     let home_dir = get_user_directory();
     let gpg_directory: &str = if cfg!(unix) { ".gnupg" } else { "gnupg" };
-    let gpg_dir = if !path.is_empty() { path } else { home_dir.join(gpg_directory).to_string_lossy().to_string() };
-
-    if !check_is_dir(gpg_dir.clone()) {
-        std::fs::create_dir_all(gpg_dir.clone()).unwrap();
-    }
+    let gpg_dir: PathBuf = path.filter(|p| !p.as_os_str().is_empty()).unwrap_or(home_dir.join(gpg_directory));
 
-    // set the permission of the directory to 700 in unix systems
-    // else gpg will warn use with the following warning:
-    // [ gpg: WARNING: unsafe permissions on homedir '/Users/< NAME >/.gnupg' ]
     let gpg_dir_path: &Path = Path::new(&gpg_dir);
-    let metadata = metadata(gpg_dir_path);
+    if !check_is_dir(&gpg_dir) {
+        std::fs::create_dir_all(&gpg_dir).unwrap();
 
-    match metadata {
-        Ok(metadata) => {
+        // set the permission of the directory created to 700 in unix systems
+        // else gpg will warn use with the following warning:
+        // [ gpg: WARNING: unsafe permissions on homedir '/Users/< NAME >/.gnupg' ]
+        // an existing homedir was only checked, check GPG.fix_permissions
+        if let Ok(metadata) = metadata(gpg_dir_path) {
             #[cfg(unix)]
             {
                 let mut permissions = metadata.permissions();
                 permissions.set_mode(0o700); // 700 in octal
                 let _ = set_permissions(gpg_dir_path, permissions);
             }
+            #[cfg(not(unix))]
+            let _ = metadata;
         }
-        Err(_) => {}
     }
 
     let conf_path = gpg_dir_path.join("gpg-agent.conf");
     if !Path::new(&conf_path).exists() {
-        let mut file = File::create(conf_path).unwrap();
+        // an existing homedir may be on a read only filesystem
+        let mut file = match File::create(&conf_path) {
+            Ok(file) => file,
+            Err(_) => return gpg_dir,
+        };
 
         // Write the configuration to disable passphrase caching
         let _ = file.write_all(b"default-cache-ttl 0\n");
         let _ = file.write_all(b"max-cache-ttl 0\n");
+        // like the rest of the homedir, it should only be accessible by the owner
+        #[cfg(unix)]
+        let _ = set_permissions(&conf_path, std::fs::Permissions::from_mode(0o600));
 
         let _ = Command::new("gpgconf")
             .arg("--reload")
@@ -92,28 +130,415 @@ pub fn get_or_create_gpg_homedir(path:String) -> String {
 }
 
 //  retrieve or generate the directory for gpg output
-pub fn get_or_create_gpg_output_dir(path:String) -> String {
-    let download_dir = get_download_directory();
-    let gpg_output_dir = if !path.is_empty() { path } else { download_dir.join("gnupg_output").to_string_lossy().to_string() };
+pub fn get_or_create_gpg_output_dir(path: Option<PathBuf>) -> PathBuf {
+    let gpg_output_dir: PathBuf = get_gpg_output_dir(path);
 
-    if !check_is_dir(gpg_output_dir.clone()) {
-        std::fs::create_dir_all(gpg_output_dir.clone()).unwrap();
+    if !check_is_dir(&gpg_output_dir) {
+        std::fs::create_dir_all(&gpg_output_dir).unwrap();
     }
 
     return gpg_output_dir;
 }
 
+// the directory for gpg output, without creating it
+pub fn get_gpg_output_dir(path: Option<PathBuf>) -> PathBuf {
+    let download_dir = get_download_directory();
+    return path.filter(|p| !p.as_os_str().is_empty()).unwrap_or(download_dir.join("gnupg_output"));
+}
+
+// take the advisory lock of the homedir ( HOMEDIR_LOCK_FILE in the homedir ), waiting up to timeout for
+// another GPG ( in this or another process ) holding it, the lock will be released when the returned file was dropped
+pub fn lock_homedir(homedir: &Path, timeout: Duration) -> Result<File, GPGError> {
+    let path: PathBuf = homedir.join(HOMEDIR_LOCK_FILE);
+    let mut options: OpenOptions = OpenOptions::new();
+    options.create(true).truncate(false).write(true);
+    // like everything else in the homedir, only the owner should have access to it
+    #[cfg(unix)]
+    options.mode(0o600);
+    let file: File = match options.open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::HomedirLockError(format!("failed to open {}: {}", path.display(), e)),
+                None,
+            ))
+        }
+    };
+    let started: Instant = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(e)) => {
+                return Err(GPGError::new(
+                    GPGErrorType::HomedirLockError(format!("failed to lock {}: {}", path.display(), e)),
+                    None,
+                ))
+            }
+        }
+        if started.elapsed() >= timeout {
+            return Err(GPGError::new(
+                GPGErrorType::HomedirLockError(format!(
+                    "timed out after {:?} waiting for the lock of {}",
+                    timeout,
+                    homedir.display()
+                )),
+                None,
+            ));
+        }
+        thread::sleep(HOMEDIR_LOCK_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())));
+    }
+}
+
+// list the lock file(s) gpg created in the homedir ( ex. pubring.kbx.lock, trustdb.gpg.lock ), with whether it was stale
+// nothing will be listed if the homedir could not be read
+pub fn find_gpg_locks(homedir: &Path) -> Vec<GpgLock> {
+    let mut locks: Vec<GpgLock> = Vec::new();
+    // gpg lock with LockFileEx on windows, nothing was left behind
+    if cfg!(not(unix)) {
+        return locks;
+    }
+    let entries = match std::fs::read_dir(homedir) {
+        Ok(entries) => entries,
+        Err(_) => return locks,
+    };
+    for entry in entries.flatten() {
+        let path: PathBuf = entry.path();
+        let name: String = entry.file_name().to_string_lossy().to_string();
+        // <file>.lock was the lock, .#lk<address>.<host>.<pid> the file it was created from
+        let is_gpg_lock: bool = (name.ends_with(".lock") && name != HOMEDIR_LOCK_FILE) || name.starts_with(".#lk");
+        if is_gpg_lock && path.is_file() {
+            locks.push(get_gpg_lock(path));
+        }
+    }
+    return locks;
+}
+
+fn get_gpg_lock(path: PathBuf) -> GpgLock {
+    // ex) "     12345\nhostname\n", the pid and the host of the process holding the lock
+    let content: String = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut lines = content.lines();
+    let pid: Option<u32> = lines.next().and_then(|line| line.trim().parse::<u32>().ok());
+    let host: Option<String> = lines.next().map(|line| line.trim().to_string());
+    // a lock created on another host sharing the homedir ( ex. over NFS ) could not be checked
+    let hostname: String = hostname::get().map(|h| h.to_string_lossy().to_string()).unwrap_or_default();
+    let is_this_host: bool = match &host {
+        Some(host) => *host == hostname,
+        None => true,
+    };
+    let stale: bool = pid.is_some_and(|pid| is_this_host && !is_process_running(pid));
+    return GpgLock {
+        path: path,
+        pid: pid,
+        host: host,
+        stale: stale,
+    };
+}
+
+// remove the stale lock file(s) gpg left in the homedir when it was killed, return the path of the removed lock file(s)
+pub fn remove_stale_gpg_locks(homedir: &Path) -> Result<Vec<PathBuf>, GPGError> {
+    let mut removed: Vec<PathBuf> = Vec::new();
+    for lock in find_gpg_locks(homedir).into_iter().filter(|lock| lock.stale) {
+        if let Err(e) = std::fs::remove_file(&lock.path) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to remove {}: {}", lock.path.display(), e)),
+                None,
+            ));
+        }
+        removed.push(lock.path);
+    }
+    return Ok(removed);
+}
+
+// list the output(s) the crate created with a timestamped name directly in the directories, the oldest first
+// nothing will be listed for a directory that could not be read
+pub fn find_managed_outputs(dirs: &[PathBuf]) -> Vec<ManagedOutput> {
+    let re: Regex = Regex::new(MANAGED_OUTPUT_REGEX).unwrap();
+    let mut outputs: Vec<ManagedOutput> = Vec::new();
+    for dir in dirs {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let name: String = entry.file_name().to_string_lossy().to_string();
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if !re.is_match(&name) || !metadata.is_file() {
+                continue;
+            }
+            outputs.push(ManagedOutput {
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::now()),
+            });
+        }
+    }
+    outputs.sort_by_key(|o| o.modified);
+    return outputs;
+}
+
+// remove the output(s) older than max_age, then the oldest one(s) until the rest was within max_total_size
+pub fn remove_managed_outputs(
+    outputs: Vec<ManagedOutput>,
+    max_age: Option<Duration>,
+    max_total_size: Option<u64>,
+) -> Result<OutputCleanupResult, GPGError> {
+    // outputs: the output(s) to apply the policy to, the oldest first ( check find_managed_outputs )
+
+    let now: SystemTime = SystemTime::now();
+    let mut kept_bytes: u64 = outputs.iter().map(|o| o.size).sum();
+    let mut result: OutputCleanupResult = OutputCleanupResult {
+        removed: Vec::new(),
+        freed_bytes: 0,
+        kept: outputs.len(),
+        kept_bytes: kept_bytes,
+    };
+    for output in outputs {
+        let expired: bool = max_age.is_some_and(|max_age| {
+            now.duration_since(output.modified).unwrap_or_default() > max_age
+        });
+        let oversized: bool = max_total_size.is_some_and(|max_total_size| kept_bytes > max_total_size);
+        if !expired && !oversized {
+            continue;
+        }
+        if let Err(e) = std::fs::remove_file(&output.path) {
+            // removed by someone else in the meantime
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(GPGError::new(
+                    GPGErrorType::WriteFailError(format!("failed to remove {}: {}", output.path.display(), e)),
+                    None,
+                ));
+            }
+        }
+        kept_bytes -= output.size;
+        result.removed.push(output.path);
+        result.freed_bytes += output.size;
+        result.kept -= 1;
+    }
+    result.kept_bytes = kept_bytes;
+    return Ok(result);
+}
+
+// wait up to timeout for the lock file(s) gpg held in the homedir to be released, as gpg would wait for them forever
+// a HomedirLocked will be returned if one was stale ( without waiting ) or still held after the timeout
+pub fn wait_for_gpg_locks(homedir: &Path, timeout: Duration) -> Result<(), GPGError> {
+    let started: Instant = Instant::now();
+    loop {
+        let locks: Vec<GpgLock> = find_gpg_locks(homedir);
+        let lock: &GpgLock = match locks.iter().find(|lock| lock.stale).or(locks.first()) {
+            Some(lock) => lock,
+            None => return Ok(()),
+        };
+        let holder: String = match (&lock.pid, &lock.host) {
+            (Some(pid), Some(host)) => format!("process {} on {}", pid, host),
+            (Some(pid), None) => format!("process {}", pid),
+            _ => "an unknown process".to_string(),
+        };
+        if lock.stale {
+            return Err(GPGError::new(
+                GPGErrorType::HomedirLocked(format!(
+                    "{} was left by {} which is no longer running, remove it with recover_locks",
+                    lock.path.display(),
+                    holder
+                )),
+                None,
+            ));
+        }
+        if started.elapsed() >= timeout {
+            return Err(GPGError::new(
+                GPGErrorType::HomedirLocked(format!(
+                    "timed out after {:?} waiting for {} held by {}",
+                    timeout,
+                    lock.path.display(),
+                    holder
+                )),
+                None,
+            ));
+        }
+        thread::sleep(HOMEDIR_LOCK_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())));
+    }
+}
+
+fn is_process_running(pid: u32) -> bool {
+    if Path::new("/proc/self").exists() {
+        return Path::new("/proc").join(pid.to_string()).exists();
+    }
+    // when it could not be checked, the process was treated as running so the lock was kept
+    match Command::new("kill").args(["-0", &pid.to_string()]).output() {
+        Ok(output) => return output.status.success(),
+        Err(_) => return true,
+    }
+}
+
+// retrieve the directories used by gpg from gpgconf, for the homedir if provided, else for the default homedir
+pub fn get_gpg_dirs(homedir: Option<PathBuf>) -> Result<GpgDirs, GPGError> {
+    let mut command = Command::new("gpgconf");
+    if let Some(homedir) = homedir {
+        command.arg("--homedir").arg(homedir);
+    }
+    let output = command.arg("--list-dirs").output();
+    match output {
+        Ok(output) => {
+            if !output.status.success() {
+                return Err(GPGError::new(
+                    GPGErrorType::GPGProcessError(
+                        String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                    ),
+                    None,
+                ));
+            }
+            return Ok(GpgDirs::new(&String::from_utf8_lossy(&output.stdout)));
+        }
+        Err(e) => {
+            return Err(GPGError::new(
+                GPGErrorType::GPGNotFoundError(e.to_string()),
+                None,
+            ));
+        }
+    }
+}
+
+// send a single Assuan command to the gpg-agent of the homedir through gpg-connect-agent,
+// the agent will be started if it was not running
+pub fn run_agent_command(homedir: &Path, command: &str) -> Result<AgentResponse, GPGError> {
+    let output = Command::new("gpg-connect-agent")
+        .arg("--homedir")
+        .arg(homedir)
+        .args([command, "/bye"])
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => return Err(GPGError::new(GPGErrorType::GPGNotFoundError(e.to_string()), None)),
+    };
+    // gpg-connect-agent exit with 0 even if the agent answered ERR
+    match AgentResponse::from_output(&String::from_utf8_lossy(&output.stdout)) {
+        Some(response) => return Ok(response),
+        None => {
+            let stderr: String = String::from_utf8_lossy(&output.stderr).to_string();
+            let message: &str = stderr.lines().last().unwrap_or("gpg-agent did not respond");
+            return Err(GPGError::new(GPGErrorType::GPGProcessError(message.trim().to_string()), None));
+        }
+    }
+}
+
+// ask the gpg-agent of the homedir for its version ( getinfo version )
+pub fn get_agent_version(homedir: &Path) -> Result<String, GPGError> {
+    let response: AgentResponse = run_agent_command(homedir, "getinfo version")?;
+    if !response.ok {
+        return Err(GPGError::new(
+            GPGErrorType::GPGProcessError(response.error.unwrap_or_default()),
+            None,
+        ));
+    }
+    return Ok(response.data.trim().to_string());
+}
+
+// scan PATH and the common install locations for every gpg binary ( gpg, gpg2, gpg1 ) and retrieve its version
+// the same binary found through several path ( ex. /bin and /usr/bin ) was only reported once,
+// binary that could not be run were skipped, the newest version came first
+pub fn find_gpg_installations() -> Vec<GpgInstallation> {
+    let mut dirs: Vec<PathBuf> = match std::env::var_os("PATH") {
+        Some(path) => std::env::split_paths(&path).collect(),
+        None => Vec::new(),
+    };
+    dirs.extend(COMMON_GPG_DIRS.iter().map(PathBuf::from));
+
+    let mut installations: Vec<GpgInstallation> = Vec::new();
+    let mut seen: Vec<PathBuf> = Vec::new();
+    for dir in dirs {
+        for name in GPG_PROGRAM_NAMES {
+            let path: PathBuf = dir.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+            if !path.is_file() {
+                continue;
+            }
+            let resolved: PathBuf = path.canonicalize().unwrap_or(path.clone());
+            if seen.contains(&resolved) {
+                continue;
+            }
+            seen.push(resolved);
+            if let Some(installation) = get_gpg_installation(path) {
+                installations.push(installation);
+            }
+        }
+    }
+    // sort is stable, so of the same version the one found first on PATH came first
+    installations.sort_by(|a, b| {
+        let a: Vec<u32> = parse_version(&a.full_version).unwrap_or_default();
+        let b: Vec<u32> = parse_version(&b.full_version).unwrap_or_default();
+        return b.cmp(&a);
+    });
+    return installations;
+}
+
+// run the binary with --version, None if it could not be run or was not gpg
+fn get_gpg_installation(path: PathBuf) -> Option<GpgInstallation> {
+    let output = match Command::new(&path).arg("--version").output() {
+        Ok(output) => output,
+        Err(_) => return None,
+    };
+    let stdout: String = String::from_utf8_lossy(&output.stdout).to_string();
+    let re = Regex::new(BINARY_VERSION_REGEX).unwrap();
+    let full_version: String = match stdout.lines().next().and_then(|line| re.captures(line)) {
+        Some(captures) => captures.get(1).unwrap().as_str().to_string(),
+        None => return None,
+    };
+    let v: Vec<&str> = full_version.split(".").collect();
+    let version: f32 = match v.get(1) {
+        Some(minor) => format!("{}.{}", v[0], minor).parse::<f32>().unwrap_or(0.0),
+        None => v[0].parse::<f32>().unwrap_or(0.0),
+    };
+    return Some(GpgInstallation {
+        path: path,
+        version: version,
+        full_version: full_version,
+    });
+}
+
+// choose the newest of the installations satisfying the requirement ( ex. =2 for the newest 2.x ),
+// an UnsupportedVersion will be returned if none of them did
+pub fn select_gpg_installation(
+    installations: &[GpgInstallation],
+    requirement: &str,
+) -> Result<GpgInstallation, GPGError> {
+    // installations: ordered from the newest, as returned by find_gpg_installations
+    // requirement: check check_version_requirement
+
+    parse_version_requirement(requirement)?;
+    let selected: Option<&GpgInstallation> = installations
+        .iter()
+        .find(|installation| check_version_requirement(&installation.full_version, requirement).is_ok());
+    match selected {
+        Some(installation) => return Ok(installation.clone()),
+        None => {
+            let found: Vec<String> = installations
+                .iter()
+                .map(|installation| format!("{} ( {} )", installation.full_version, installation.path.display()))
+                .collect();
+            return Err(GPGError::new(
+                GPGErrorType::UnsupportedVersion(format!(
+                    "gpg {} is required, gpg found: {}",
+                    requirement.trim(),
+                    if found.is_empty() { "none".to_string() } else { found.join(", ") }
+                )),
+                None,
+            ));
+        }
+    }
+}
+
 // retrieve gpg version from result raw data
 pub fn get_gpg_version(result: &CmdResult) -> (f32, String) {
     let data: Option<String> = result.get_raw_data();
     let re = Regex::new(VERSION_REGEX).unwrap();
 
-    if data.is_some() {
-        let data = data.as_ref().unwrap();
+    if let Some(data) = &data {
         let version = re.captures(data);
 
-        if version.is_some() {
-            let version_string = version.unwrap().get(1).unwrap().as_str().to_string();
+        if let Some(version) = version {
+            let version_string = version.get(1).unwrap().as_str().to_string();
             let version_clone: String = version_string.clone();
             let v: Vec<&str> = version_clone.split(".").collect();
             let major_minor_v = format!("{}.{}", v[0], v[1]);
@@ -128,34 +553,203 @@ pub fn get_gpg_version(result: &CmdResult) -> (f32, String) {
     return (0.0, "0.0.0".to_string());
 }
 
-pub fn get_file_obj(file: Option<File>, file_path: Option<String>) -> Result<File, GPGError> {
-    if file.is_some() {
-        let mut file = file.unwrap();
-        file.rewind().unwrap();
-        return Ok(file);
-    } else if file_path.is_some() {
-        let file_path = file_path.unwrap();
-        let file = File::open(file_path);
-
-        if file.is_err() {
+// check the full version of gpg ( ex. 2.4.6 ) against a requirement ( ex. >=2.2 )
+// operator can be one of >=, >, <=, <, =, a requirement without operator will be treated as >=
+pub fn check_version_requirement(full_version: &str, requirement: &str) -> Result<(), GPGError> {
+    let (operator, required) = parse_version_requirement(requirement)?;
+    let found: Vec<u32> = match parse_version(full_version) {
+        // 0.0.0 is what get_gpg_version report when the version could not be retrieved
+        Some(found) if found.iter().any(|v| *v != 0) => found,
+        _ => {
             return Err(GPGError::new(
-                GPGErrorType::FileNotFoundError("File do not exist".to_string()),
+                GPGErrorType::UnsupportedVersion(format!(
+                    "gpg {} is required, but the version of gpg could not be retrieved",
+                    requirement.trim()
+                )),
                 None,
             ));
         }
-        let mut file = file.unwrap();
-        file.rewind().unwrap();
-        return Ok(file);
-    }
+    };
 
+    // compare only the components given in the requirement, so 2.2.40 satisfy =2.2
+    let found: Vec<u32> = (0..required.len())
+        .map(|i| *found.get(i).unwrap_or(&0))
+        .collect();
+    let ordering: std::cmp::Ordering = found.cmp(&required);
+    let satisfied: bool = match operator.as_str() {
+        ">=" => ordering.is_ge(),
+        ">" => ordering.is_gt(),
+        "<=" => ordering.is_le(),
+        "<" => ordering.is_lt(),
+        _ => ordering.is_eq(),
+    };
+    if satisfied {
+        return Ok(());
+    }
     return Err(GPGError::new(
-        GPGErrorType::FileNotProvidedError("File or file path not provided".to_string()),
+        GPGErrorType::UnsupportedVersion(format!(
+            "gpg {} is required, gpg {} was found",
+            requirement.trim(),
+            full_version
+        )),
         None,
     ));
 }
 
+// split a requirement into its operator and version, ex) >=2.2 -> (">=", [2, 2])
+pub fn parse_version_requirement(requirement: &str) -> Result<(String, Vec<u32>), GPGError> {
+    let requirement: &str = requirement.trim();
+    let operator: &str = [">=", "<=", "==", ">", "<", "="]
+        .into_iter()
+        .find(|op| requirement.starts_with(op))
+        .unwrap_or("");
+    let version: Option<Vec<u32>> = parse_version(requirement[operator.len()..].trim());
+    if version.is_none() {
+        return Err(GPGError::new(
+            GPGErrorType::InvalidArgumentError(format!(
+                "version requirement invalid: {}",
+                requirement
+            )),
+            None,
+        ));
+    }
+    let operator: String = match operator {
+        "" => ">=".to_string(),
+        "==" => "=".to_string(),
+        op => op.to_string(),
+    };
+    return Ok((operator, version.unwrap()));
+}
+
+fn parse_version(version: &str) -> Option<Vec<u32>> {
+    if version.is_empty() {
+        return None;
+    }
+    let mut components: Vec<u32> = Vec::new();
+    for v in version.split(".") {
+        match v.parse::<u32>() {
+            Ok(v) => components.push(v),
+            Err(_) => return None,
+        }
+    }
+    return Some(components);
+}
+
+// retrieve the key gpg asked the passphrase for from the status line of the result
+// ex) [GNUPG:] NEED_PASSPHRASE 535F8AD3664C3DFC 535F8AD3664C3DFC 1 0 -> 535F8AD3664C3DFC
+// an empty string for the passphrase of a symmetric encrypted file, None if gpg did not ask for any passphrase
+pub fn get_passphrase_hint(result: &CmdResult) -> Option<String> {
+    for status in result.status_lines() {
+        match status {
+            StatusLine::NeedPassphraseSym { .. } => return Some("".to_string()),
+            // the first keyid was the subkey gpg used ( ex. the encryption subkey for decrypt ), report the primary key
+            StatusLine::NeedPassphrase { main_keyid, .. } => return Some(main_keyid),
+            _ => {}
+        }
+    }
+    return None;
+}
+
+// check if gpg failed as the passphrase provided was wrong
+pub fn is_bad_passphrase(result: &CmdResult) -> bool {
+    let data: String = result.get_raw_data().unwrap_or("".to_string());
+    return result.status_lines().iter().any(|status| matches!(status, StatusLine::BadPassphrase { .. }))
+        || data.lines().any(|l| l.starts_with("gpg: ") && l.contains("Bad passphrase"));
+}
+
+// retrieve supported algorithms from result raw data of --list-config
+pub fn get_gpg_capabilities(result: &CmdResult) -> GPGCapabilities {
+    // ex) cfg:ciphername:IDEA;3DES;CAST5;BLOWFISH;AES;AES192;AES256
+    let mut capabilities: GPGCapabilities = GPGCapabilities::init();
+    let data: String = result.get_raw_data().unwrap_or("".to_string());
+    for line in data.lines() {
+        let fields: Vec<&str> = line.trim().splitn(3, ":").collect();
+        if fields.len() != 3 || fields[0] != "cfg" {
+            continue;
+        }
+        let values: Vec<String> = fields[2]
+            .split(";")
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string())
+            .collect();
+        match fields[1] {
+            "pubkeyname" => capabilities.pubkey = values,
+            "ciphername" => capabilities.cipher = values,
+            "digestname" => capabilities.digest = values,
+            "compressname" => capabilities.compress = values,
+            "curve" => capabilities.curve = values,
+            _ => {}
+        }
+    }
+    return capabilities;
+}
+
+// decode every line of the result raw data of --list-config, including the group(s) defined in gpg.conf
+pub fn decode_list_config_result(result: &CmdResult) -> GpgConfig {
+    // ex) cfg:cipher:1;2;3;4;7;8;9;10;11;12;13
+    //     cfg:group:team:D3A4E5F6A7B8C9D0;A1B2C3D4E5F6A7B8
+    let mut config: GpgConfig = GpgConfig::init();
+    config.version = get_gpg_version(result).1;
+    config.capabilities = get_gpg_capabilities(result);
+    let data: String = result.get_raw_data().unwrap_or("".to_string());
+    for line in data.lines() {
+        let fields: Vec<&str> = line.trim().splitn(3, ":").collect();
+        if fields.len() != 3 || fields[0] != "cfg" {
+            continue;
+        }
+        let ids: Vec<u8> = fields[2]
+            .split(";")
+            .filter_map(|v| v.parse::<u8>().ok())
+            .collect();
+        match fields[1] {
+            "version" | "pubkeyname" | "ciphername" | "digestname" | "compressname" | "curve" => {}
+            "pubkey" => config.pubkey_ids = ids,
+            "cipher" => config.cipher_ids = ids,
+            "digest" => config.digest_ids = ids,
+            "compress" => config.compress_ids = ids,
+            "group" => {
+                // the value was <name>:<key>;<key>...
+                let group: Vec<&str> = fields[2].splitn(2, ":").collect();
+                if group.len() != 2 || group[0].is_empty() {
+                    continue;
+                }
+                let members: Vec<String> = group[1]
+                    .split(";")
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.to_string())
+                    .collect();
+                config.groups.insert(group[0].to_string(), members);
+            }
+            other => {
+                config.other.insert(other.to_string(), fields[2].to_string());
+            }
+        }
+    }
+    return config;
+}
+
+// read and remove the file holding the output of gpg, if it was spilled as it was larger than the memory limit of the runner
+pub fn take_spilled_output(result: &CmdResult) -> Option<String> {
+    match &result.output_file {
+        Some(output_file) => {
+            let output: String = std::fs::read_to_string(output_file).unwrap_or_default();
+            let _ = std::fs::remove_file(output_file);
+            return Some(output);
+        }
+        None => return None,
+    }
+}
+
 pub fn decode_list_key_result(result: CmdResult) -> Vec<ListKeyResult> {
-    let output_lines = result.get_raw_data().unwrap();
+    let output_lines: String = match take_spilled_output(&result) {
+        Some(output) => output,
+        None => result.get_raw_data().unwrap(),
+    };
+    return decode_list_key_listing(&output_lines);
+}
+
+// turn a --with-colons key listing ( ex. a keyring snapshot saved earlier ) into the keys
+pub fn decode_list_key_listing(output_lines: &str) -> Vec<ListKeyResult> {
     let mut processed_keyword: Vec<String> = Vec::new();
     let mut r: ListKey = ListKey::init();
     for output in output_lines.split("\n") {
@@ -176,7 +770,7 @@ pub fn decode_list_key_result(result: CmdResult) -> Vec<ListKeyResult> {
 
         // process if keyword found
         if LIST_KEY_KEYWORDS.to_vec().contains(&l_key_pair[0]) {
-            r.call_method(&l_key_pair[0], l_key_pair);
+            r.call_method(l_key_pair[0], l_key_pair);
             processed_keyword.push(k_w.to_string());
         }
     }
@@ -184,24 +778,306 @@ pub fn decode_list_key_result(result: CmdResult) -> Vec<ListKeyResult> {
     return r.get_list_key_result();
 }
 
+// turn the result of a verification into a VerifyResult
+// a bad signature was checked successfully, so it will be a VerifyResult that is not valid rather than an error
+pub fn decode_verify_result(result: Result<CmdResult, GPGError>) -> Result<VerifyResult, GPGError> {
+    match result {
+        Ok(result) => return Ok(VerifyResult::from_cmd_result(result)),
+        Err(e) => {
+            let bad_signature: bool = e
+                .cmd_result
+                .as_ref()
+                .is_some_and(|r| r.exit_status() == GpgExitStatus::BadSignature);
+            if bad_signature {
+                return Ok(VerifyResult::from_cmd_result(e.cmd_result.unwrap()));
+            }
+            return Err(e);
+        }
+    }
+}
+
+pub fn decode_attribute_result(result: &CmdResult, attribute_data: &[u8]) -> Vec<KeyAttribute> {
+    // each attribute was reported with a ATTRIBUTE status line
+    // and its data was written in the same order to the attribute file
+    let mut attributes: Vec<KeyAttribute> = Vec::new();
+    let mut offset: usize = 0;
+    let raw_data: String = result.get_raw_data().unwrap_or("".to_string());
+    for line in raw_data.lines() {
+        let args: Vec<&str> = match split_status_line(line) {
+            Some(("ATTRIBUTE", args)) => args.split_whitespace().collect(),
+            _ => continue,
+        };
+        let octets: usize = args.get(1).unwrap_or(&"0").parse::<usize>().unwrap_or(0);
+        let end: usize = (offset + octets).min(attribute_data.len());
+        let data: Vec<u8> = attribute_data[offset..end].to_vec();
+        offset = end;
+        attributes.push(KeyAttribute::new(args, data));
+    }
+    return attributes;
+}
+
+// turn the output of --export-options export-dane into the DaneRecord(s), one for each uid with an email
+// ex)  $ORIGIN _openpgpkey.example.com.
+//      ; <fingerprint>
+//      ; Joe <joe@example.com>
+//      <hash> TYPE61 \# 401 (
+//          9833046ad2783516...
+//          )
+pub fn decode_dane_records(output: &str) -> Vec<DaneRecord> {
+    let mut records: Vec<DaneRecord> = Vec::new();
+    let mut domain: String = String::new();
+    let mut comments: Vec<String> = Vec::new();
+    let mut current: Option<DaneRecord> = None;
+    let mut hex: String = String::new();
+    for line in output.lines() {
+        let line: &str = line.trim();
+        if let Some(mut record) = current.take() {
+            let (part, end): (&str, bool) = match line.strip_suffix(")") {
+                Some(part) => (part.trim(), true),
+                None => (line, false),
+            };
+            hex.push_str(part);
+            if !end {
+                current = Some(record);
+                continue;
+            }
+            record.data = (0..hex.len() / 2)
+                .filter_map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+                .collect();
+            records.push(record);
+            hex.clear();
+            comments.clear();
+        } else if let Some(origin) = line.strip_prefix("$ORIGIN ") {
+            domain = origin.trim_start_matches("_openpgpkey.").trim_end_matches(".").to_string();
+            comments.clear();
+        } else if let Some(comment) = line.strip_prefix(";") {
+            comments.push(comment.trim().to_string());
+        } else if line.contains(" TYPE61 ") {
+            let hash: &str = line.split_whitespace().next().unwrap_or("");
+            current = Some(DaneRecord {
+                fingerprint: comments.first().cloned().unwrap_or_default(),
+                uid: comments.get(1).cloned().unwrap_or_default(),
+                domain: domain.clone(),
+                name: format!("{}._openpgpkey.{}.", hash, domain),
+                data: Vec::new(),
+            });
+        }
+    }
+    return records;
+}
+
+pub fn filter_ownertrust(ownertrust: &str, fingerprints: Option<&[String]>) -> String {
+    // keep only the "<fingerprint>:<trust value>:" lines ( of the given fingerprints if provided ),
+    // comments and any other lines will be dropped
+    let mut filtered: String = String::new();
+    for line in ownertrust.lines() {
+        let line: &str = line.trim();
+        let fields: Vec<&str> = line.split(":").collect();
+        if fields.len() != 3
+            || fields[0].is_empty()
+            || !fields[0].chars().all(|c| c.is_ascii_hexdigit())
+            || fields[1].parse::<u8>().is_err()
+        {
+            continue;
+        }
+        if fingerprints.is_none()
+            || fingerprints.unwrap().iter().any(|f| f.eq_ignore_ascii_case(fields[0]))
+        {
+            filtered.push_str(&format!("{}\n", line));
+        }
+    }
+    return filtered;
+}
+
+// the fingerprint and reason ( a bit field, 1 = new key, 16 = contains secret key ) of every key reported by IMPORT_OK
+// a key imported more than once was only listed once with the reasons combined
+pub fn get_imported_keys(result: &CmdResult) -> Vec<(String, u32)> {
+    let mut imported: Vec<(String, u32)> = Vec::new();
+    for status in result.status_lines() {
+        let (reason, fingerprint): (u32, String) = match status {
+            StatusLine::ImportOk { reason, fingerprint: Some(fingerprint) } => (reason, fingerprint),
+            _ => continue,
+        };
+        match imported.iter_mut().find(|(f, _)| *f == fingerprint) {
+            Some((_, reasons)) => *reasons |= reason,
+            None => imported.push((fingerprint, reason)),
+        }
+    }
+    return imported;
+}
+
+// fail with a WeakCryptoError naming every legacy or weak construct gpg reported for the result:
+// data without integrity protection ( MDC ), a legacy cipher or a signature with a weak digest
+pub fn reject_weak_crypto(result: CmdResult) -> Result<(), GPGError> {
+    let weak: Vec<String> = result
+        .warnings
+        .iter()
+        .filter(|w| matches!(w, GpgWarning::MissingMdc | GpgWarning::LegacyCipher(_) | GpgWarning::WeakDigest(_)))
+        .map(|w| w.to_string())
+        .collect();
+    if weak.is_empty() {
+        return Ok(());
+    }
+    return Err(GPGError::new(
+        GPGErrorType::WeakCryptoError(format!("rejected by strict mode: {}", weak.join(", "))),
+        Some(result),
+    ));
+}
+
+// remove the file as the mode, its content will be overwritten first for ShredMode::Overwrite
+pub fn shred_file(path: &Path, mode: &ShredMode) -> Result<(), GPGError> {
+    if let ShredMode::Overwrite(passes) = mode {
+        if let Err(e) = overwrite_file(path, *passes) {
+            return Err(GPGError::new(
+                GPGErrorType::WriteFailError(format!("failed to overwrite {}: {}", path.display(), e)),
+                None,
+            ));
+        }
+    }
+    if let Err(e) = std::fs::remove_file(path) {
+        return Err(GPGError::new(
+            GPGErrorType::WriteFailError(format!("failed to remove {}: {}", path.display(), e)),
+            None,
+        ));
+    }
+    return Ok(());
+}
+
+fn overwrite_file(path: &Path, passes: u32) -> Result<(), std::io::Error> {
+    let mut file: File = OpenOptions::new().write(true).open(path)?;
+    let len: u64 = match file.metadata() {
+        Ok(metadata) => metadata.len(),
+        Err(e) => return Err(e),
+    };
+    for pass in 0..passes {
+        let buffer: [u8; 8192] = if pass % 2 == 0 { [0x00; 8192] } else { [0xFF; 8192] };
+        file.rewind()?;
+        let mut remaining: u64 = len;
+        while remaining > 0 {
+            let n: usize = remaining.min(buffer.len() as u64) as usize;
+            file.write_all(&buffer[..n])?;
+            remaining -= n as u64;
+        }
+        // each pass should reach the disk instead of being merged in the page cache
+        file.sync_all()?;
+    }
+    return Ok(());
+}
+
 pub fn is_passphrase_valid(passhrase: &str) -> bool {
     return !passhrase.contains("\n") && !passhrase.contains("\r") && !passhrase.contains("\x00");
 }
 
-pub fn set_output_without_confirmation(args: &mut Vec<String>, output: &str) {
+pub fn set_output_without_confirmation(
+    args: &mut Vec<String>,
+    output: &Path,
+    create_parent: bool,
+) -> Result<(), GPGError> {
+    // create_parent: whether to create the parent directory of the output if it does not exist
+
+    let output_arg: String = path_to_arg(output)?;
+    check_output_parent(output, create_parent)?;
     // prevent a confimation prompt when output provided exist
-    if Path::new(output).exists() {
+    if output.exists() {
         args.push("--yes".to_string()); // assume yes on most question
     }
-    args.append(&mut vec!["--output".to_string(), output.to_string()]);
+    args.append(&mut vec!["--output".to_string(), output_arg]);
+    return Ok(());
+}
+
+// make sure the directory the output will be written to exists, as gpg only fail with an opaque error otherwise
+pub fn check_output_parent(output: &Path, create_parent: bool) -> Result<(), GPGError> {
+    // output: path that the output will be written to
+    // create_parent: whether to create the parent directory if it does not exist
+
+    let parent: &Path = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        // a file name only will be written to the current directory
+        _ => return Ok(()),
+    };
+    if parent.is_dir() {
+        return Ok(());
+    }
+    if parent.exists() {
+        return Err(GPGError::new(
+            GPGErrorType::OutputDirError(format!("{} is not a directory", parent.display())),
+            None,
+        ));
+    }
+    if !create_parent {
+        return Err(GPGError::new(
+            GPGErrorType::OutputDirError(format!("directory does not exist: {}", parent.display())),
+            None,
+        ));
+    }
+    if let Err(e) = std::fs::create_dir_all(parent) {
+        return Err(GPGError::new(
+            GPGErrorType::OutputDirError(format!("failed to create {}: {}", parent.display(), e)),
+            None,
+        ));
+    }
+    return Ok(());
+}
+
+// convert a path into an argument to be passed to gpg
+// gpg arguments are passed as string, so a path that is not valid unicode will be rejected instead of being mangled
+pub fn path_to_arg(path: &Path) -> Result<String, GPGError> {
+    match path.to_str() {
+        Some(path) => return Ok(path.to_string()),
+        None => {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError(format!(
+                    "path is not valid unicode: {}",
+                    path.display()
+                )),
+                None,
+            ));
+        }
+    }
+}
+
+// check if the file was an ascii armored file ( ex. an exported key with armor )
+pub fn is_armored_file(path: &Path) -> bool {
+    let mut buffer: [u8; 64] = [0; 64];
+    let read: usize = match File::open(path) {
+        Ok(mut file) => file.read(&mut buffer).unwrap_or(0),
+        Err(_) => return false,
+    };
+    return String::from_utf8_lossy(&buffer[..read])
+        .trim_start()
+        .starts_with("-----BEGIN PGP");
+}
+
+// extensions of detached signature by convention, in the order they will be looked up
+pub const DETACHED_SIG_EXTENSIONS: [&str; 3] = ["sig", "asc", "sign"];
+
+// the conventional name of the detached signature of a file ( ex. foo.tar.gz.asc )
+pub fn get_conventional_sig_path(file_path: &Path, armor: bool) -> PathBuf {
+    let extension: &str = if armor { "asc" } else { "sig" };
+    return append_extension(file_path, extension);
+}
+
+// look for the detached signature next to a file named by convention
+pub fn find_conventional_sig(file_path: &Path) -> Option<PathBuf> {
+    return DETACHED_SIG_EXTENSIONS
+        .iter()
+        .map(|ext| append_extension(file_path, ext))
+        .find(|path| path.is_file());
+}
+
+// append an extension to the full file name ( ex. foo.tar.gz -> foo.tar.gz.asc ), unlike Path::with_extension
+pub fn append_extension(file_path: &Path, extension: &str) -> PathBuf {
+    let mut path = file_path.as_os_str().to_os_string();
+    path.push(".");
+    path.push(extension);
+    return PathBuf::from(path);
 }
 
-pub fn get_file_extension(file_path: Option<String>) -> String {
+pub fn get_file_extension(file_path: Option<&Path>) -> String {
     let mut ext: String = "gpg".to_string();
 
-    if file_path.is_some() {
-        let p = file_path.unwrap();
-        let path = Path::new(p.as_str());
+    if let Some(file_path) = file_path {
+        let path: &Path = file_path;
         ext = path
             .extension()
             .map(|ext| ext.to_string_lossy().into_owned())
@@ -209,3 +1085,282 @@ pub fn get_file_extension(file_path: Option<String>) -> String {
     }
     return ext;
 }
+
+// the file name of the input, or the fallback if it was not a file ( ex. InputSource::Bytes )
+pub fn get_input_file_name(file_path: Option<&Path>, fallback: &str) -> String {
+    return file_path
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or(fallback.to_string());
+}
+
+// the name of the encrypted output of the file ( ex. file.txt -> file.txt.gpg, or file.txt.asc if armored )
+pub fn get_encrypted_file_name(file_path: Option<&Path>, armor: bool) -> String {
+    let extension: &str = if armor { "asc" } else { "gpg" };
+    return format!("{}.{}", get_input_file_name(file_path, "encrypted_file"), extension);
+}
+
+// the name of the decrypted output of the encrypted file ( ex. file.txt.gpg -> file.txt ),
+// .out will be appended if it does not have the extension of an encrypted file
+pub fn get_decrypted_file_name(name: &str) -> String {
+    for ext in [".gpg", ".pgp", ".asc"] {
+        match name.strip_suffix(ext) {
+            Some(stem) if !stem.is_empty() => return stem.to_string(),
+            _ => {}
+        }
+    }
+    return format!("{}.out", name);
+}
+
+// the path of the name in the dir, with -1, -2 ... appended before the extension if it was already taken
+// ( ex. file.txt.gpg -> file.txt-1.gpg )
+pub fn get_unique_path(dir: &Path, name: &str) -> PathBuf {
+    let path: PathBuf = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, ext): (&str, &str) = match name.rfind('.') {
+        Some(i) if i > 0 => (&name[..i], &name[i..]),
+        _ => (name, ""),
+    };
+    let mut count: u32 = 1;
+    loop {
+        let path: PathBuf = dir.join(format!("{}-{}{}", stem, count, ext));
+        if !path.exists() {
+            return path;
+        }
+        count += 1;
+    }
+}
+
+// split a raw git commit or tag object ( ex. output of `git cat-file commit <sha>` ) into
+// the payload that was signed and the armored signature
+pub fn split_git_signature(raw_object: &[u8]) -> Result<(Vec<u8>, String), GPGError> {
+    let mut raw_object: &[u8] = raw_object;
+    // strip the "<type> <size>\0" header of a loose object if it was included
+    if raw_object.starts_with(b"commit ") || raw_object.starts_with(b"tag ") {
+        if let Some(nul) = raw_object.iter().take(32).position(|b| *b == 0) {
+            raw_object = &raw_object[nul + 1..];
+        }
+    }
+    let lines: Vec<&[u8]> = raw_object.split_inclusive(|b| *b == b'\n').collect();
+
+    let mut payload: Vec<u8> = Vec::new();
+    let mut signature: Vec<String> = Vec::new();
+    let mut in_header: bool = true;
+    // in_signature: whether the current header was a signature header
+    // collecting: whether the current signature header was the one to be verified
+    let mut in_signature: bool = false;
+    let mut collecting: bool = false;
+    for line in lines.iter() {
+        if in_header && in_signature && line.starts_with(b" ") {
+            // continuation line of the signature header
+            if collecting {
+                signature.push(String::from_utf8_lossy(&line[1..]).trim_end().to_string());
+            }
+            continue;
+        }
+        in_signature = false;
+        if in_header && *line == b"\n" {
+            in_header = false;
+        } else if in_header && (line.starts_with(b"gpgsig ") || line.starts_with(b"gpgsig-sha256 ")) {
+            // a commit keep its signature in the header, every signature header was excluded
+            // from the payload but only the first one will be verified
+            in_signature = true;
+            collecting = signature.is_empty();
+            if collecting {
+                let start: usize = line.iter().position(|b| *b == b' ').unwrap() + 1;
+                signature.push(String::from_utf8_lossy(&line[start..]).trim_end().to_string());
+            }
+            continue;
+        }
+        payload.extend_from_slice(line);
+    }
+
+    if signature.is_empty() {
+        // a tag keep its signature at the end of the message, the last one will be used as git does
+        let mut offset: usize = 0;
+        let mut signature_start: Option<usize> = None;
+        for line in lines.iter() {
+            if line.starts_with(b"-----BEGIN PGP SIGNATURE-----")
+                || line.starts_with(b"-----BEGIN SSH SIGNATURE-----")
+                || line.starts_with(b"-----BEGIN SIGNED MESSAGE-----")
+            {
+                signature_start = Some(offset);
+            }
+            offset += line.len();
+        }
+        if signature_start.is_none() {
+            return Err(GPGError::new(
+                GPGErrorType::InvalidArgumentError("git object was not signed".to_string()),
+                None,
+            ));
+        }
+        payload = raw_object[..signature_start.unwrap()].to_vec();
+        signature = String::from_utf8_lossy(&raw_object[signature_start.unwrap()..])
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+    }
+
+    let signature: String = format!("{}\n", signature.join("\n").trim_end());
+    if !signature.starts_with("-----BEGIN PGP SIGNATURE-----") {
+        return Err(GPGError::new(
+            GPGErrorType::InvalidArgumentError("git object was not signed with an OpenPGP signature".to_string()),
+            None,
+        ));
+    }
+    return Ok((payload, signature));
+}
+
+// long options that would override what the crate pass to gpg ( ex. where the output was written to )
+pub const OVERRIDING_ARGS: [&str; 21] = [
+    "output",
+    "homedir",
+    "status-fd",
+    "status-file",
+    "logger-fd",
+    "logger-file",
+    "command-fd",
+    "command-file",
+    "attribute-fd",
+    "attribute-file",
+    "passphrase",
+    "passphrase-fd",
+    "passphrase-file",
+    "pinentry-mode",
+    "no-batch",
+    "options",
+    "keyring",
+    "secret-keyring",
+    "primary-keyring",
+    "no-default-keyring",
+    "trustdb-name",
+];
+
+// short options taking a value, the rest of the flag cluster is the value ( ex. -ubob )
+const SHORT_ARGS_WITH_VALUE: [char; 7] = ['r', 'R', 'u', 'z', 'N', 'f', 'F'];
+
+// find the first argument that would override what the crate pass to gpg, None if all are safe
+// gpg accept any unambiguous abbreviation of long option ( ex. --ou for --output ),
+// so every prefix of an overriding option was rejected, even the one gpg will find ambiguous
+pub fn find_overriding_arg(args: &[String]) -> Option<String> {
+    for arg in args.iter() {
+        if arg == "--" {
+            // everything after -- was file name
+            return None;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            let name: &str = long.split('=').next().unwrap_or("");
+            let overriding: bool = OVERRIDING_ARGS
+                .iter()
+                .any(|o| !name.is_empty() && o.starts_with(name));
+            if overriding {
+                return Some(arg.clone());
+            }
+        } else if let Some(short) = arg.strip_prefix("-") {
+            for c in short.chars() {
+                if c == 'o' {
+                    return Some(arg.clone());
+                }
+                if SHORT_ARGS_WITH_VALUE.contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    return None;
+}
+
+// check the permission of the homedir and its gpg.conf, gpg warn about unsafe permission
+// if any of them was accessible by group or other
+pub fn check_homedir_permissions(homedir: &Path) -> PermissionCheckResult {
+    let mut insecure: Vec<InsecurePermission> = Vec::new();
+    #[cfg(unix)]
+    for path in [homedir.to_path_buf(), homedir.join("gpg.conf")] {
+        collect_insecure_permissions(&path, false, &mut insecure);
+    }
+    return PermissionCheckResult {
+        homedir: homedir.to_path_buf(),
+        insecure: insecure,
+        fixed: false,
+    };
+}
+
+// remove the group and other permission of the homedir and everything in it ( ex. keyring, private keys )
+// directory will be set to at least 700 so it can still be used by the owner
+pub fn fix_homedir_permissions(homedir: &Path) -> Result<PermissionCheckResult, GPGError> {
+    let mut insecure: Vec<InsecurePermission> = Vec::new();
+    #[cfg(unix)]
+    collect_insecure_permissions(homedir, true, &mut insecure);
+    #[cfg(unix)]
+    for path in insecure.iter() {
+        let mode: u32 = if path.path.is_dir() {
+            (path.mode & 0o700) | 0o700
+        } else {
+            path.mode & 0o700
+        };
+        if let Err(e) = set_permissions(&path.path, std::fs::Permissions::from_mode(mode)) {
+            return Err(GPGError::new(
+                GPGErrorType::HomedirError(format!(
+                    "failed to fix the permission of {}: {}",
+                    path.path.display(),
+                    e
+                )),
+                None,
+            ));
+        }
+    }
+    return Ok(PermissionCheckResult {
+        homedir: homedir.to_path_buf(),
+        insecure: insecure,
+        fixed: true,
+    });
+}
+
+#[cfg(unix)]
+fn collect_insecure_permissions(path: &Path, recursive: bool, insecure: &mut Vec<InsecurePermission>) {
+    // symlink was not followed, and socket ( ex. S.gpg-agent ) was not checked as gpg does not check it either
+    let metadata: std::fs::Metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return,
+    };
+    if !metadata.is_dir() && !metadata.is_file() {
+        return;
+    }
+    let mode: u32 = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        insecure.push(InsecurePermission {
+            path: path.to_path_buf(),
+            mode: mode,
+        });
+    }
+    if recursive && metadata.is_dir() {
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collect_insecure_permissions(&entry.path(), recursive, insecure);
+            }
+        }
+    }
+}
+
+// the arguments with the value of every secret argument ( ex. --passphrase ) replaced with REDACTED
+pub fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted: Vec<String> = vec![];
+    let mut redact_next: bool = false;
+    for arg in args {
+        if redact_next {
+            redacted.push(REDACTED.to_string());
+            redact_next = false;
+            continue;
+        }
+        match SECRET_ARGS.iter().find(|secret| arg.starts_with(&format!("{}=", secret))) {
+            Some(secret) => redacted.push(format!("{}={}", secret, REDACTED)),
+            None => {
+                redact_next = SECRET_ARGS.contains(&arg.as_str());
+                redacted.push(arg.clone());
+            }
+        }
+    }
+    return redacted;
+}
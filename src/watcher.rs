@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use notify::{event::ModifyKind, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::gnupg::{invalidate_key_cache, GPG};
+use crate::utils::errors::{GPGError, GPGErrorType};
+
+//*******************************************************
+
+//              RELATED TO HOMEDIR WATCHER
+
+//*******************************************************
+// what was changed in the homedir
+#[derive(Debug, Clone, PartialEq)]
+pub enum HomedirChange {
+    // the public keyring ( pubring.kbx, or pubring.gpg of gpg 1.x )
+    Keyring,
+    // the secret keys ( private-keys-v1.d, or secring.gpg of gpg 1.x )
+    SecretKeys,
+    // the trustdb ( trustdb.gpg )
+    TrustDb,
+}
+
+#[derive(Debug, Clone)]
+pub struct HomedirEvent {
+    pub homedir: PathBuf,
+    pub change: HomedirChange,
+    // the file changed
+    pub path: PathBuf,
+}
+
+pub type HomedirCallback = Box<dyn Fn(&HomedirEvent) + Send + Sync>;
+
+// watch the homedir of a GPG for keyring and trustdb changed by other process ( ex. the gpg command line ),
+// the listing cached by list_keys for the homedir will be invalidated on every change
+// the homedir was watched until the HomedirWatcher was dropped
+#[derive(Debug)]
+pub struct HomedirWatcher {
+    pub homedir: PathBuf,
+    // keep the watcher alive
+    _watcher: RecommendedWatcher,
+}
+
+impl HomedirWatcher {
+    pub fn watch(gpg: &GPG, callback: Option<HomedirCallback>) -> Result<HomedirWatcher, GPGError> {
+        // gpg: the GPG whose homedir to be watched
+        // callback: called with every change after the cache was invalidated, None to only invalidate the cache
+
+        let homedir: PathBuf = gpg.homedir.clone();
+        let watched: PathBuf = homedir.clone();
+        let homedirs: Vec<PathBuf> = vec![homedir.clone(), homedir.canonicalize().unwrap_or(homedir.clone())];
+        let handler = move |event: notify::Result<Event>| {
+            let event: Event = match event {
+                Ok(event) => event,
+                Err(_) => return,
+            };
+            // reading the keyring ( ex. list_keys ) will also be reported as access or metadata change
+            let changed: bool = match event.kind {
+                EventKind::Create(_) | EventKind::Remove(_) => true,
+                EventKind::Modify(ModifyKind::Metadata(_)) => false,
+                EventKind::Modify(_) => true,
+                _ => false,
+            };
+            if !changed {
+                return;
+            }
+            for path in event.paths {
+                let change: HomedirChange = match get_homedir_change(&homedirs, &path) {
+                    Some(change) => change,
+                    None => continue,
+                };
+                invalidate_key_cache(Some(&watched));
+                if let Some(callback) = &callback {
+                    callback(&HomedirEvent {
+                        homedir: watched.clone(),
+                        change: change,
+                        path: path,
+                    });
+                }
+            }
+        };
+        let mut watcher: RecommendedWatcher = match notify::recommended_watcher(handler) {
+            Ok(watcher) => watcher,
+            Err(e) => return Err(watcher_error(&homedir, e)),
+        };
+        if let Err(e) = watcher.watch(&homedir, RecursiveMode::Recursive) {
+            return Err(watcher_error(&homedir, e));
+        }
+        return Ok(HomedirWatcher {
+            homedir: homedir,
+            _watcher: watcher,
+        });
+    }
+}
+
+// which part of the homedir the path belong to, None for the other file ( ex. the socket of the agent, random_seed )
+fn get_homedir_change(homedirs: &[PathBuf], path: &Path) -> Option<HomedirChange> {
+    // homedirs: the homedir and its canonicalized path, as the watcher may report either
+
+    let relative: &Path = homedirs.iter().find_map(|homedir| path.strip_prefix(homedir).ok())?;
+    if relative.starts_with("private-keys-v1.d") {
+        return Some(HomedirChange::SecretKeys);
+    }
+    match relative.to_str() {
+        Some("pubring.kbx") | Some("pubring.gpg") => return Some(HomedirChange::Keyring),
+        Some("secring.gpg") => return Some(HomedirChange::SecretKeys),
+        Some("trustdb.gpg") => return Some(HomedirChange::TrustDb),
+        _ => return None,
+    }
+}
+
+fn watcher_error(homedir: &Path, error: notify::Error) -> GPGError {
+    return GPGError::new(
+        GPGErrorType::HomedirError(format!("failed to watch {}: {}", homedir.display(), error)),
+        None,
+    );
+}